@@ -0,0 +1,274 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct AlertArgs {
+    /// Urls to post critical events to as json
+    #[clap(long, env, value_delimiter = ',')]
+    pub alert_webhook_urls: Vec<String>,
+    /// Whether to print critical events to stdout as json lines
+    #[clap(long, env, default_value_t = false)]
+    pub alert_stdout_json: bool,
+    /// File to append critical events to as json lines
+    #[clap(long, env)]
+    pub alert_file: Option<PathBuf>,
+    /// Routing key of a PagerDuty Events API v2 integration to trigger incidents on
+    #[clap(long, env)]
+    pub alert_pagerduty_routing_key: Option<String>,
+}
+
+/// Endpoint of the PagerDuty Events API v2
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+/// Maximum length of a PagerDuty incident summary
+const PAGERDUTY_SUMMARY_LIMIT: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+/// A critical event raised by the proposer or validator
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    pub severity: AlertSeverity,
+    /// Short machine-readable name of the event
+    pub event: String,
+    pub message: String,
+    pub timestamp: u64,
+    /// Identifies the incident this alert belongs to across repeated alerts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_key: Option<String>,
+}
+
+/// A destination for alerts
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()>;
+
+    /// Marks the incident raised under the given key as resolved
+    async fn resolve(&self, _dedup_key: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Posts alerts as json to a url
+pub struct WebhookSink {
+    pub client: reqwest::Client,
+    pub url: String,
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        self.client
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .context("send webhook")?
+            .error_for_status()
+            .context("webhook status")?;
+        Ok(())
+    }
+}
+
+/// Prints alerts to stdout as json lines
+pub struct StdoutJsonSink;
+
+#[async_trait]
+impl AlertSink for StdoutJsonSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        println!("{}", serde_json::to_string(alert)?);
+        Ok(())
+    }
+}
+
+/// Appends alerts to a file as json lines
+pub struct FileSink {
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl AlertSink for FileSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .context("open alert file")?;
+        let mut line = serde_json::to_vec(alert)?;
+        line.push(b'\n');
+        file.write_all(&line).await.context("write alert file")?;
+        file.flush().await.context("flush alert file")
+    }
+}
+
+/// Triggers PagerDuty incidents for critical alerts
+pub struct PagerDutySink {
+    pub client: reqwest::Client,
+    pub routing_key: String,
+}
+
+impl PagerDutySink {
+    async fn enqueue(&self, event: serde_json::Value) -> anyhow::Result<()> {
+        self.client
+            .post(PAGERDUTY_EVENTS_URL)
+            .json(&event)
+            .send()
+            .await
+            .context("send pagerduty event")?
+            .error_for_status()
+            .context("pagerduty status")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for PagerDutySink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        if alert.severity != AlertSeverity::Critical {
+            return Ok(());
+        }
+        let summary = alert
+            .message
+            .chars()
+            .take(PAGERDUTY_SUMMARY_LIMIT)
+            .collect::<String>();
+        let mut event = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "payload": {
+                "summary": summary,
+                "source": "kailua",
+                "severity": "critical",
+                "component": alert.event,
+            },
+        });
+        if let Some(dedup_key) = &alert.dedup_key {
+            event["dedup_key"] = json!(dedup_key);
+        }
+        self.enqueue(event).await
+    }
+
+    async fn resolve(&self, dedup_key: &str) -> anyhow::Result<()> {
+        self.enqueue(json!({
+            "routing_key": self.routing_key,
+            "event_action": "resolve",
+            "dedup_key": dedup_key,
+        }))
+        .await
+    }
+}
+
+/// Dispatches alerts to every configured sink
+#[derive(Clone, Default)]
+pub struct Alerts {
+    pub sinks: Vec<Arc<dyn AlertSink>>,
+}
+
+impl Alerts {
+    pub fn from_args(args: &AlertArgs) -> Self {
+        let mut alerts = Self::default();
+        let client = reqwest::Client::new();
+        for url in &args.alert_webhook_urls {
+            alerts.add_sink(WebhookSink {
+                client: client.clone(),
+                url: url.clone(),
+            });
+        }
+        if args.alert_stdout_json {
+            alerts.add_sink(StdoutJsonSink);
+        }
+        if let Some(path) = &args.alert_file {
+            alerts.add_sink(FileSink { path: path.clone() });
+        }
+        if let Some(routing_key) = &args.alert_pagerduty_routing_key {
+            alerts.add_sink(PagerDutySink {
+                client,
+                routing_key: routing_key.clone(),
+            });
+        }
+        alerts
+    }
+
+    pub fn add_sink<S: AlertSink + 'static>(&mut self, sink: S) {
+        self.sinks.push(Arc::new(sink));
+    }
+
+    /// Sends the alert to all sinks, logging any sink failures
+    pub async fn raise(&self, severity: AlertSeverity, event: &str, message: String) {
+        self.dispatch(severity, event, None, message).await
+    }
+
+    /// Sends an alert that repeated alerts with the same key escalate instead of duplicating
+    pub async fn raise_incident(
+        &self,
+        severity: AlertSeverity,
+        event: &str,
+        dedup_key: String,
+        message: String,
+    ) {
+        self.dispatch(severity, event, Some(dedup_key), message)
+            .await
+    }
+
+    /// Resolves the incident raised under the given key in all sinks that track incidents
+    pub async fn resolve_incident(&self, dedup_key: &str) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.resolve(dedup_key).await {
+                warn!("Failed to resolve incident {dedup_key}: {e:?}");
+            }
+        }
+    }
+
+    async fn dispatch(
+        &self,
+        severity: AlertSeverity,
+        event: &str,
+        dedup_key: Option<String>,
+        message: String,
+    ) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let alert = Alert {
+            severity,
+            event: event.to_string(),
+            message,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            dedup_key,
+        };
+        for sink in &self.sinks {
+            if let Err(e) = sink.send(&alert).await {
+                warn!("Failed to deliver alert {}: {e:?}", alert.event);
+            }
+        }
+    }
+}
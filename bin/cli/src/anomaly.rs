@@ -0,0 +1,186 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::alert::{AlertSeverity, Alerts};
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::primitives::{BlockTransactionsKind, HeaderResponse};
+use alloy::network::{BlockResponse, Network};
+use alloy::primitives::B256;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use metrics::{counter, gauge};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AnomalyArgs {
+    /// Number of L1 reorgs within the anomaly window that raises an alert (0 to disable)
+    #[clap(long, env, default_value_t = 3)]
+    pub l1_reorg_alert_threshold: usize,
+    /// Number of op-node output mismatches within the anomaly window that raises an alert
+    /// (0 to disable)
+    #[clap(long, env, default_value_t = 3)]
+    pub op_node_mismatch_alert_threshold: usize,
+    /// Number of inconsistent beacon blobs within the anomaly window that raises an alert
+    /// (0 to disable)
+    #[clap(long, env, default_value_t = 1)]
+    pub beacon_anomaly_alert_threshold: usize,
+    /// Seconds over which chain anomalies are counted against their alert thresholds
+    #[clap(long, env, default_value_t = 3600)]
+    pub anomaly_window: u64,
+}
+
+impl Default for AnomalyArgs {
+    fn default() -> Self {
+        Self {
+            l1_reorg_alert_threshold: 3,
+            op_node_mismatch_alert_threshold: 3,
+            beacon_anomaly_alert_threshold: 1,
+            anomaly_window: 3600,
+        }
+    }
+}
+
+/// An observation that indicates the chain data relied upon may be untrustworthy
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChainAnomaly {
+    /// A previously observed L1 block was replaced
+    L1Reorg,
+    /// The op-node reported an output that differs from the one derived by the prover
+    OpNodeMismatch,
+    /// The beacon node served blob data that does not match its commitment
+    BeaconInconsistency,
+}
+
+impl Display for ChainAnomaly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainAnomaly::L1Reorg => write!(f, "l1_reorg"),
+            ChainAnomaly::OpNodeMismatch => write!(f, "op_node_mismatch"),
+            ChainAnomaly::BeaconInconsistency => write!(f, "beacon_inconsistency"),
+        }
+    }
+}
+
+impl ChainAnomaly {
+    fn severity(&self) -> AlertSeverity {
+        match self {
+            ChainAnomaly::L1Reorg => AlertSeverity::Warning,
+            ChainAnomaly::OpNodeMismatch | ChainAnomaly::BeaconInconsistency => {
+                AlertSeverity::Critical
+            }
+        }
+    }
+}
+
+/// Counts chain anomalies and raises alerts once they occur too frequently
+#[derive(Debug, Default)]
+pub struct ChainAnomalies {
+    pub args: AnomalyArgs,
+    /// Timestamps of the anomalies observed within the window
+    pub recent: BTreeMap<ChainAnomaly, VecDeque<u64>>,
+    /// Anomalies whose threshold was crossed since the last alert
+    pub pending_alerts: Vec<(ChainAnomaly, usize)>,
+    /// The last L1 block checked for reorgs
+    pub l1_head: Option<(u64, B256)>,
+}
+
+impl ChainAnomalies {
+    pub fn new(args: AnomalyArgs) -> Self {
+        Self {
+            args,
+            ..Default::default()
+        }
+    }
+
+    fn threshold(&self, anomaly: ChainAnomaly) -> usize {
+        match anomaly {
+            ChainAnomaly::L1Reorg => self.args.l1_reorg_alert_threshold,
+            ChainAnomaly::OpNodeMismatch => self.args.op_node_mismatch_alert_threshold,
+            ChainAnomaly::BeaconInconsistency => self.args.beacon_anomaly_alert_threshold,
+        }
+    }
+
+    /// Counts an occurrence of the anomaly and queues an alert if its threshold is crossed
+    pub fn record(&mut self, anomaly: ChainAnomaly) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let window_start = now.saturating_sub(self.args.anomaly_window);
+        let threshold = self.threshold(anomaly);
+        let recent = self.recent.entry(anomaly).or_default();
+        while recent.front().is_some_and(|t| *t < window_start) {
+            recent.pop_front();
+        }
+        recent.push_back(now);
+        counter!("kailua_chain_anomalies_total", "kind" => anomaly.to_string()).increment(1);
+        gauge!("kailua_chain_anomalies_recent", "kind" => anomaly.to_string())
+            .set(recent.len() as f64);
+        // alert only once per crossing of the threshold
+        if threshold > 0 && recent.len() == threshold {
+            self.pending_alerts.push((anomaly, recent.len()));
+        }
+    }
+
+    /// Raises the alerts queued since the last call
+    pub async fn raise_alerts(&mut self, alerts: &Alerts) {
+        for (anomaly, count) in std::mem::take(&mut self.pending_alerts) {
+            let message = format!(
+                "Observed {count} {anomaly} anomalies within {} seconds.",
+                self.args.anomaly_window
+            );
+            warn!("{message}");
+            alerts
+                .raise(anomaly.severity(), "chain_anomaly", message)
+                .await;
+        }
+    }
+
+    /// Records a reorg if the last checked L1 block is no longer part of the chain
+    pub async fn check_l1_reorg<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        latest_block: u64,
+    ) -> anyhow::Result<()> {
+        if let Some((number, hash)) = self.l1_head {
+            let current_hash = l1_block_hash(&provider, number).await?;
+            if current_hash != Some(hash) {
+                warn!("L1 reorg detected at block {number} ({hash} was replaced).");
+                self.record(ChainAnomaly::L1Reorg);
+            }
+        }
+        self.l1_head = l1_block_hash(&provider, latest_block)
+            .await?
+            .map(|hash| (latest_block, hash));
+        Ok(())
+    }
+}
+
+async fn l1_block_hash<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    number: u64,
+) -> anyhow::Result<Option<B256>> {
+    Ok(provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await
+        .context("get_block_by_number")?
+        .map(|block| block.header().hash()))
+}
@@ -0,0 +1,342 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::reload::Reloader;
+use alloy::primitives::{Address, B256};
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, RwLock};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{info, warn};
+
+/// Number of proof submissions reported by the api
+const RECENT_SUBMISSIONS_LIMIT: usize = 64;
+/// Number of events buffered for each subscriber of the event stream
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+pub type SharedValidatorStatus = Arc<RwLock<ValidatorStatus>>;
+
+/// Snapshot of the validator's state exposed by the status api
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ValidatorStatus {
+    pub health: Health,
+    pub proposals: BTreeMap<u64, ProposalSummary>,
+    pub proof_queue: Vec<QueuedProof>,
+    pub recent_submissions: VecDeque<ProofSubmission>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Health {
+    /// Timestamp of the last update of this snapshot
+    pub updated_at: u64,
+    pub next_factory_index: u64,
+    pub canonical_tip: Option<u64>,
+    /// Whether proof submissions are halted due to a local op-node divergence
+    pub halted: bool,
+    pub withheld_proofs: usize,
+    pub quarantined_proposals: usize,
+    pub eliminated_proposers: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalSummary {
+    pub index: u64,
+    pub contract: Address,
+    pub parent: u64,
+    pub proposer: Address,
+    pub created_at: u64,
+    pub output_block_number: u64,
+    pub output_root: B256,
+    pub children: Vec<u64>,
+    pub survivor: Option<u64>,
+    pub contender: Option<u64>,
+    pub duplicate_of: Option<u64>,
+    pub correct: Option<bool>,
+    pub canonical: Option<bool>,
+}
+
+impl From<&Proposal> for ProposalSummary {
+    fn from(proposal: &Proposal) -> Self {
+        Self {
+            index: proposal.index,
+            contract: proposal.contract,
+            parent: proposal.parent,
+            proposer: proposal.proposer,
+            created_at: proposal.created_at,
+            output_block_number: proposal.output_block_number,
+            output_root: proposal.output_root,
+            children: proposal.children.clone(),
+            survivor: proposal.survivor,
+            contender: proposal.contender,
+            duplicate_of: proposal.duplicate_of,
+            correct: proposal.is_correct(),
+            canonical: proposal.canonical,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueuedProof {
+    pub contender: u64,
+    pub proposal: u64,
+    pub deadline: u64,
+    pub alert_level: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofSubmission {
+    pub contender: u64,
+    pub proposal: u64,
+    pub tx_hash: B256,
+    pub proof_status: u8,
+    pub submitted_at: u64,
+}
+
+/// A decision taken by the validator, published to the event stream
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ValidatorEvent {
+    ProposalIngested {
+        index: u64,
+        contract: Address,
+        parent: u64,
+        output_block_number: u64,
+        correct: Option<bool>,
+        canonical: Option<bool>,
+    },
+    FaultDetected {
+        index: u64,
+        contract: Address,
+        proposer: Address,
+        output_block_number: u64,
+    },
+    ProofStarted {
+        proposal_index: u64,
+        correlation_id: String,
+    },
+    ProofCompleted {
+        proposal_index: u64,
+        correlation_id: String,
+        success: bool,
+    },
+    TransactionSubmitted {
+        intent: String,
+        to: Address,
+        tx_hash: B256,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimestampedEvent {
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: ValidatorEvent,
+}
+
+/// Publishes validator events to the subscribers of the event stream
+#[derive(Clone, Debug)]
+pub struct ValidatorEvents {
+    sender: broadcast::Sender<TimestampedEvent>,
+}
+
+impl Default for ValidatorEvents {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_BUFFER_SIZE).0,
+        }
+    }
+}
+
+impl ValidatorEvents {
+    /// Publishes the event, which is dropped if nobody is subscribed
+    pub fn emit(&self, event: ValidatorEvent) {
+        let _ = self.sender.send(TimestampedEvent {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            event,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TimestampedEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl ValidatorStatus {
+    /// Refreshes the summaries of the given proposals and their parents
+    pub fn update_proposals(&mut self, kailua_db: &KailuaDB, proposal_indices: &[u64]) {
+        for proposal_index in proposal_indices {
+            let Some(proposal) = kailua_db.get_local_proposal(proposal_index) else {
+                continue;
+            };
+            if let Some(parent) = kailua_db.get_local_proposal(&proposal.parent) {
+                self.proposals
+                    .insert(parent.index, ProposalSummary::from(&parent));
+            }
+            self.proposals
+                .insert(proposal.index, ProposalSummary::from(&proposal));
+        }
+    }
+
+    /// Refreshes the health data and proof queue
+    pub fn update_health(
+        &mut self,
+        kailua_db: &KailuaDB,
+        halted: bool,
+        withheld_proofs: usize,
+        quarantined_proposals: usize,
+    ) {
+        self.health = Health {
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            next_factory_index: kailua_db.state.next_factory_index,
+            canonical_tip: kailua_db.state.canonical_tip_index,
+            halted,
+            withheld_proofs,
+            quarantined_proposals,
+            eliminated_proposers: kailua_db.state.eliminations.len(),
+        };
+        self.proof_queue = kailua_db
+            .state
+            .match_deadlines
+            .iter()
+            .map(|((contender, proposal), match_deadline)| QueuedProof {
+                contender: *contender,
+                proposal: *proposal,
+                deadline: match_deadline.deadline,
+                alert_level: match_deadline.alert_level,
+            })
+            .collect();
+        self.proof_queue.sort_by_key(|p| p.deadline);
+    }
+
+    pub fn record_submission(&mut self, submission: ProofSubmission) {
+        if self.recent_submissions.len() == RECENT_SUBMISSIONS_LIMIT {
+            self.recent_submissions.pop_front();
+        }
+        self.recent_submissions.push_back(submission);
+    }
+}
+
+/// Serves the validator status as json until the listener fails
+pub async fn serve(
+    addr: SocketAddr,
+    status: SharedValidatorStatus,
+    events: ValidatorEvents,
+    reloader: Reloader,
+) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/proposals", get(proposals))
+        .route("/proposals/:index", get(proposal))
+        .route("/proofs/queue", get(proof_queue))
+        .route("/proofs/submissions", get(recent_submissions))
+        .route("/events", get(event_stream).with_state(events))
+        .route("/admin/reload", post(reload).with_state(reloader))
+        .with_state(status);
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .context("TcpListener::bind")?;
+    info!("Serving status api on {addr}.");
+    axum::serve(listener, app).await.context("axum::serve")
+}
+
+async fn reload(State(reloader): State<Reloader>) -> Result<StatusCode, (StatusCode, String)> {
+    match reloader.reload() {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{err:#}"))),
+    }
+}
+
+async fn health(State(status): State<SharedValidatorStatus>) -> Json<Health> {
+    Json(status.read().await.health.clone())
+}
+
+async fn proposals(
+    State(status): State<SharedValidatorStatus>,
+) -> Json<BTreeMap<u64, ProposalSummary>> {
+    Json(status.read().await.proposals.clone())
+}
+
+async fn proposal(
+    State(status): State<SharedValidatorStatus>,
+    Path(index): Path<u64>,
+) -> Result<Json<ProposalSummary>, StatusCode> {
+    status
+        .read()
+        .await
+        .proposals
+        .get(&index)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn proof_queue(State(status): State<SharedValidatorStatus>) -> Json<Vec<QueuedProof>> {
+    Json(status.read().await.proof_queue.clone())
+}
+
+async fn recent_submissions(
+    State(status): State<SharedValidatorStatus>,
+) -> Json<VecDeque<ProofSubmission>> {
+    Json(status.read().await.recent_submissions.clone())
+}
+
+/// Streams validator events to the client as server-sent events
+async fn event_stream(
+    State(events): State<ValidatorEvents>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(|event| match event {
+        Ok(event) => {
+            let name = match &event.event {
+                ValidatorEvent::ProposalIngested { .. } => "proposal_ingested",
+                ValidatorEvent::FaultDetected { .. } => "fault_detected",
+                ValidatorEvent::ProofStarted { .. } => "proof_started",
+                ValidatorEvent::ProofCompleted { .. } => "proof_completed",
+                ValidatorEvent::TransactionSubmitted { .. } => "transaction_submitted",
+            };
+            match Event::default().event(name).json_data(&event) {
+                Ok(event) => Some(Ok(event)),
+                Err(err) => {
+                    warn!("Failed to serialize validator event: {err:?}");
+                    None
+                }
+            }
+        }
+        // slow subscribers miss the events that were overwritten in their buffer
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            warn!("Event stream subscriber skipped {skipped} events.");
+            None
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
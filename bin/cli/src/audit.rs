@@ -0,0 +1,122 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::{keccak256, Address, B256};
+use anyhow::Context;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+/// Name of the file in the data directory that every sent transaction is appended to
+pub const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum AuditOutcome {
+    /// The transaction was included in a block
+    Confirmed {
+        block_number: Option<u64>,
+        success: bool,
+    },
+    /// The transaction was sent but its receipt could not be fetched
+    Unconfirmed { error: String },
+    /// The transaction could not be sent
+    Failed { error: String },
+}
+
+impl AuditOutcome {
+    pub fn confirmed<R: ReceiptResponse>(receipt: &R) -> Self {
+        Self::Confirmed {
+            block_number: receipt.block_number(),
+            success: receipt.status(),
+        }
+    }
+
+    pub fn unconfirmed(error: &anyhow::Error) -> Self {
+        Self::Unconfirmed {
+            error: format!("{error:?}"),
+        }
+    }
+
+    pub fn failed(error: &anyhow::Error) -> Self {
+        Self::Failed {
+            error: format!("{error:?}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    /// What the transaction was sent to achieve
+    pub intent: String,
+    pub to: Address,
+    pub calldata_hash: B256,
+    pub tx_hash: Option<B256>,
+    pub outcome: AuditOutcome,
+}
+
+/// Append-only record of every transaction sent by this agent
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    pub path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(AUDIT_LOG_FILE),
+        }
+    }
+
+    /// Appends an entry for the transaction, logging an error if it cannot be written
+    pub fn record(
+        &self,
+        intent: String,
+        to: Address,
+        calldata: &[u8],
+        tx_hash: Option<B256>,
+        outcome: AuditOutcome,
+    ) {
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            intent,
+            to,
+            calldata_hash: keccak256(calldata),
+            tx_hash,
+            outcome,
+        };
+        if let Err(e) = self.append(&entry) {
+            error!("Failed to write audit log entry {entry:?}: {e:?}");
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("open audit log")?;
+        file.write_all(&line).context("write audit log")?;
+        file.sync_data().context("sync audit log")
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::channel::DuplexChannel;
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::layout::{BACKFILL_DIR, DB_DIR, RECEIPTS_DIR};
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::stall::Stall;
+use crate::validate::{
+    load_fpvm_registry, prepare_proving_job, request_proof, run_kailua_host, ValidateArgs,
+};
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{ProviderBuilder, ReqwestProvider};
+use anyhow::{ensure, Context};
+use kailua_client::EXIT_CODE_OUTPUT_DIVERGENCE;
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use kailua_host::registry::FpvmRegistry;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct BackfillArgs {
+    /// Factory index of the first game of a historical range to audit instead of validating the
+    /// chain
+    #[clap(long, env)]
+    pub from_index: Option<u64>,
+    /// Factory index of the last game of the historical range to audit (Defaults to the latest)
+    #[clap(long, env, requires = "from_index")]
+    pub to_index: Option<u64>,
+    /// Whether to re-prove the matches of the audited games that were decided by a proof
+    #[clap(long, env, default_value_t = false, requires = "from_index")]
+    pub backfill_reprove: bool,
+    /// File to write the json report of the audit to
+    #[clap(long, env, requires = "from_index")]
+    pub backfill_report: Option<PathBuf>,
+}
+
+/// The outcome of a historical audit of a range of games
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BackfillReport {
+    pub from_index: u64,
+    pub to_index: u64,
+    pub games: Vec<GameAudit>,
+    /// Factory indices in the range of games that were not ingested, e.g. of other game types
+    pub skipped: Vec<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameAudit {
+    pub factory_index: u64,
+    pub proposer: Address,
+    pub parent: u64,
+    pub output_block_number: u64,
+    pub output_root: B256,
+    /// Whether the outputs of the game agree with the op-node
+    pub correct: Option<bool>,
+    pub canonical: Option<bool>,
+    /// Whether the game was resolved in favor of its proposer, if resolved
+    pub resolved: Option<bool>,
+    pub contender: Option<u64>,
+    /// Status of the match against the contender recorded on chain
+    pub proof_status: Option<u8>,
+    /// Status of the match against the contender according to the op-node
+    pub expected_proof_status: Option<u8>,
+    pub reproved: Option<ReproveOutcome>,
+    /// Disagreements between the chain and the op-node
+    pub findings: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReproveOutcome {
+    Proven,
+    OutputDivergence,
+    Failed,
+    Skipped,
+}
+
+/// Audits the games in a historical range against the op-node and writes a report
+pub async fn backfill(args: ValidateArgs, data_dir: PathBuf) -> anyhow::Result<()> {
+    let from_index = args
+        .backfill_args
+        .from_index
+        .context("Missing from-index")?;
+    // initialize blockchain connections
+    let op_node_provider =
+        OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
+    let eth_rpc_provider =
+        ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
+    let op_geth_provider =
+        ProviderBuilder::new().on_http(args.core.op_geth_url.as_str().try_into()?);
+    let cl_node_provider = BlobProvider::new(
+        args.core.beacon_rpc_url.as_str(),
+        args.core.blob_archive_url.as_deref(),
+    )
+    .await?;
+
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
+    let l2_chain_id = config.l2_chain_id.to_string();
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    let game_count: u64 = dispute_game_factory
+        .gameCount()
+        .stall()
+        .await
+        .gameCount_
+        .to();
+    let to_index = args
+        .backfill_args
+        .to_index
+        .unwrap_or(game_count.saturating_sub(1));
+    ensure!(
+        from_index <= to_index && to_index < game_count,
+        "Invalid range of games {from_index} to {to_index} ({game_count} games created)."
+    );
+
+    // Replay the history up to the end of the range apart from the database of the live validator
+    let backfill_dir = data_dir.join(BACKFILL_DIR);
+    std::fs::create_dir_all(backfill_dir.join(DB_DIR)).context("create_dir_all")?;
+    let mut kailua_db = KailuaDB::init(backfill_dir, &dispute_game_factory).await?;
+    kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+    info!("Ingesting games up to factory index {to_index}.");
+    while kailua_db.state.next_factory_index <= to_index {
+        let next_factory_index = kailua_db.state.next_factory_index;
+        kailua_db
+            .load_proposals_until(
+                &dispute_game_factory,
+                &op_node_provider,
+                &cl_node_provider,
+                to_index + 1,
+            )
+            .await
+            .context("load_proposals_until")?;
+        ensure!(
+            kailua_db.state.next_factory_index > next_factory_index,
+            "Failed to ingest game at factory index {next_factory_index}."
+        );
+    }
+
+    let fpvm_registry = load_fpvm_registry(&args)?;
+    let mut report = BackfillReport {
+        from_index,
+        to_index,
+        ..Default::default()
+    };
+    for factory_index in from_index..=to_index {
+        let Some(proposal) = kailua_db.get_local_proposal(&factory_index) else {
+            report.skipped.push(factory_index);
+            continue;
+        };
+        let mut audit = GameAudit {
+            factory_index,
+            proposer: proposal.proposer,
+            parent: proposal.parent,
+            output_block_number: proposal.output_block_number,
+            output_root: proposal.output_root,
+            correct: proposal.is_correct(),
+            canonical: proposal.canonical,
+            resolved: proposal.fetch_finality(&eth_rpc_provider).await?,
+            contender: proposal.contender,
+            proof_status: None,
+            expected_proof_status: None,
+            reproved: None,
+            findings: vec![],
+        };
+        match (audit.correct, audit.resolved) {
+            (Some(true), Some(false)) => audit
+                .findings
+                .push(String::from("correct proposal was resolved as faulty")),
+            (Some(false), Some(true)) => audit
+                .findings
+                .push(String::from("faulty proposal was resolved as correct")),
+            _ => {}
+        }
+        // audit the match against the contender
+        if let Some(contender) = proposal
+            .contender
+            .and_then(|contender| kailua_db.get_local_proposal(&contender))
+        {
+            let parent = kailua_db
+                .get_local_proposal(&proposal.parent)
+                .context("parent missing")?;
+            let (Some(u_index), Some(v_index)) = (
+                parent.child_index(contender.index),
+                parent.child_index(proposal.index),
+            ) else {
+                report.games.push(audit);
+                continue;
+            };
+            let parent_contract = parent.tournament_contract_instance(&eth_rpc_provider);
+            let proof_status = parent_contract
+                .proofStatus(U256::from(u_index), U256::from(v_index))
+                .stall()
+                .await
+                ._0;
+            audit.proof_status = Some(proof_status);
+            audit.expected_proof_status = expected_proof_status(&contender, &proposal);
+            if proof_status != 0 {
+                if audit.expected_proof_status != Some(proof_status) {
+                    audit.findings.push(format!(
+                        "match against contender {} was proven as {proof_status} instead of {:?}",
+                        contender.index, audit.expected_proof_status
+                    ));
+                }
+                // the match was decided by a proof
+                if args.backfill_args.backfill_reprove {
+                    let fpvm_image_id = parent_contract.imageId().stall().await.imageId_;
+                    let outcome = reprove(
+                        &args,
+                        &l2_chain_id,
+                        &data_dir,
+                        &fpvm_registry,
+                        fpvm_image_id,
+                        kailua_db.config.precondition_version(),
+                        &contender,
+                        &proposal,
+                        &eth_rpc_provider,
+                        &op_geth_provider,
+                        &op_node_provider,
+                    )
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!("Failed to re-prove proposal {factory_index}: {e:?}");
+                        ReproveOutcome::Failed
+                    });
+                    if matches!(outcome, ReproveOutcome::OutputDivergence) {
+                        audit.findings.push(String::from(
+                            "op-node output diverges from the derived output",
+                        ));
+                    }
+                    audit.reproved = Some(outcome);
+                }
+            }
+        }
+        for finding in &audit.findings {
+            warn!("Game {factory_index}: {finding}.");
+        }
+        report.games.push(audit);
+    }
+
+    let findings = report
+        .games
+        .iter()
+        .map(|audit| audit.findings.len())
+        .sum::<usize>();
+    info!(
+        "Audited {} games from factory index {from_index} to {to_index} with {findings} findings.",
+        report.games.len()
+    );
+    let report_file = args
+        .backfill_args
+        .backfill_report
+        .clone()
+        .unwrap_or_else(|| data_dir.join(format!("backfill-{from_index}-{to_index}.json")));
+    std::fs::write(&report_file, serde_json::to_vec_pretty(&report)?)
+        .context("write backfill report")?;
+    info!("Wrote backfill report to {report_file:?}.");
+    Ok(())
+}
+
+/// Returns the proof status that a match between the two proposals should be proven with given
+/// the outputs of the op-node
+fn expected_proof_status(contender: &Proposal, proposal: &Proposal) -> Option<u8> {
+    let point = contender.divergence_point(proposal)?;
+    let is_correct_at = |player: &Proposal| {
+        if point < player.io_field_elements.len() {
+            player.correct_io[point]
+        } else {
+            player.correct_claim
+        }
+    };
+    match (is_correct_at(contender)?, is_correct_at(proposal)?) {
+        (false, false) => Some(1), // U_LOSE_V_LOSE
+        (false, true) => Some(2),  // U_LOSE_V_WIN
+        (true, false) => Some(3),  // U_WIN_V_LOSE
+        (true, true) => None,
+    }
+}
+
+/// Proves the match between the two proposals again with the outputs of the op-node
+#[allow(clippy::too_many_arguments)]
+async fn reprove(
+    args: &ValidateArgs,
+    l2_chain_id: &str,
+    data_dir: &Path,
+    fpvm_registry: &FpvmRegistry,
+    fpvm_image_id: B256,
+    precondition_version: u8,
+    contender: &Proposal,
+    proposal: &Proposal,
+    eth_rpc_provider: &ReqwestProvider,
+    op_geth_provider: &ReqwestProvider,
+    op_node_provider: &OpNodeProvider,
+) -> anyhow::Result<ReproveOutcome> {
+    let (mut proposals_channel, mut proofs_channel) = DuplexChannel::new_pair(1);
+    request_proof(
+        &mut proposals_channel,
+        fpvm_image_id,
+        precondition_version,
+        contender,
+        proposal,
+        eth_rpc_provider,
+        op_geth_provider,
+        op_node_provider,
+    )
+    .await?;
+    let Ok(message) = proofs_channel.receiver.try_recv() else {
+        return Ok(ReproveOutcome::Skipped);
+    };
+    let Some(job) = prepare_proving_job(args, l2_chain_id, data_dir, fpvm_registry, message)?
+    else {
+        return Ok(ReproveOutcome::Skipped);
+    };
+    info!(correlation_id = %job.correlation_id, "Re-proving match of proposal {}.", proposal.index);
+    let status = run_kailua_host(
+        args.kailua_host.as_deref(),
+        &job.proving_args,
+        false,
+        Some(&data_dir.join(RECEIPTS_DIR)),
+    )
+    .await?;
+    Ok(if status.success() {
+        ReproveOutcome::Proven
+    } else if status.code() == Some(EXIT_CODE_OUTPUT_DIVERGENCE) {
+        ReproveOutcome::OutputDivergence
+    } else {
+        ReproveOutcome::Failed
+    })
+}
@@ -0,0 +1,334 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bucket::{Bucket, BucketConfig};
+use crate::db::KailuaDB;
+use crate::layout::RECEIPTS_DIR;
+use anyhow::Context;
+use rocksdb::checkpoint::Checkpoint;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct BackupArgs {
+    /// Bucket and prefix to back up the database and receipts of this validator to, and restore
+    /// them from on startup (e.g. s3://bucket/prefix or gs://bucket/prefix)
+    #[clap(long, env)]
+    pub backup_url: Option<String>,
+    /// Endpoint of an S3-compatible service to use instead of AWS (Defaults to Google Cloud
+    /// Storage for gs:// urls)
+    #[clap(long, env)]
+    pub backup_endpoint: Option<String>,
+    /// Region of the backup bucket
+    #[clap(long, env, default_value = "us-east-1")]
+    pub backup_region: String,
+    /// Access key of the backup bucket (HMAC access id for Google Cloud Storage)
+    #[clap(long, env, requires = "backup_url")]
+    pub backup_access_key: Option<String>,
+    /// Secret key of the backup bucket (HMAC secret for Google Cloud Storage)
+    #[clap(long, env, requires = "backup_url")]
+    pub backup_secret_key: Option<String>,
+    /// Seconds between database snapshots
+    #[clap(long, env, default_value_t = 600)]
+    pub backup_interval: u64,
+    /// Number of most recent database snapshots to keep in the bucket
+    #[clap(long, env, default_value_t = 6)]
+    pub backup_snapshots_kept: usize,
+    /// Seconds to keep backed up receipts in the bucket after they are collected locally
+    #[clap(long, env, default_value_t = 604800)]
+    pub backup_receipt_retention_secs: u64,
+}
+
+/// Periodically uploads snapshots of the data directory and the produced receipts to a bucket so
+/// that a rescheduled validator without persistent volumes can resume from them
+#[derive(Debug)]
+pub struct Backup {
+    args: BackupArgs,
+    bucket: Bucket,
+    last_snapshot: Option<Instant>,
+    upload_task: Option<JoinHandle<()>>,
+}
+
+impl Backup {
+    /// Returns None if no backup bucket was configured
+    pub fn from_args(args: &BackupArgs) -> anyhow::Result<Option<Self>> {
+        let Some(url) = &args.backup_url else {
+            return Ok(None);
+        };
+        let bucket = Bucket::connect(BucketConfig {
+            url,
+            endpoint: args.backup_endpoint.as_deref(),
+            region: &args.backup_region,
+            access_key: args.backup_access_key.as_deref(),
+            secret_key: args.backup_secret_key.as_deref(),
+        })
+        .context("backup bucket")?;
+        Ok(Some(Self {
+            args: args.clone(),
+            bucket,
+            last_snapshot: None,
+            upload_task: None,
+        }))
+    }
+
+    /// Downloads the latest snapshot into the data directory, skipping any of its files or
+    /// database directories that already exist locally, along with any missing receipts
+    pub async fn restore(&self, data_dir: &Path) -> anyhow::Result<()> {
+        let snapshots = snapshots(&self.bucket).await?;
+        match snapshots.last_key_value() {
+            Some((timestamp, files)) => {
+                info!("Restoring snapshot {timestamp} into {data_dir:?}.");
+                let mut restored = 0;
+                for file in files {
+                    // database files are only restored together
+                    let entry = match file.rsplit_once('/') {
+                        Some((dir, _)) => dir,
+                        None => file.as_str(),
+                    };
+                    if data_dir.join(entry).exists() {
+                        continue;
+                    }
+                    let data = self
+                        .bucket
+                        .get(&format!("{SNAPSHOTS_DIR}/{timestamp}/{file}"))
+                        .await?
+                        .context("snapshot file missing")?;
+                    let path = data_dir.join(file);
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent).context("create_dir_all")?;
+                    }
+                    std::fs::write(&path, data).context("write snapshot file")?;
+                    restored += 1;
+                }
+                info!("Restored {restored}/{} snapshot files.", files.len());
+            }
+            None => info!("No snapshots to restore."),
+        }
+        let receipts_dir = data_dir.join(RECEIPTS_DIR);
+        let mut restored = 0;
+        for (receipt_file_name, _) in self.bucket.list(RECEIPTS_DIR).await? {
+            let receipt_path = receipts_dir.join(&receipt_file_name);
+            if receipt_path.exists() {
+                continue;
+            }
+            let Some(data) = self
+                .bucket
+                .get(&format!("{RECEIPTS_DIR}/{receipt_file_name}"))
+                .await?
+            else {
+                continue;
+            };
+            std::fs::write(&receipt_path, data).context("write receipt")?;
+            restored += 1;
+        }
+        info!("Restored {restored} receipts.");
+        Ok(())
+    }
+
+    /// Starts uploading a snapshot of the database and data directory in the background if one
+    /// is due and the last one has completed
+    pub fn snapshot_if_due(&mut self, kailua_db: &KailuaDB, data_dir: &Path) {
+        if self
+            .last_snapshot
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(self.args.backup_interval))
+        {
+            return;
+        }
+        if self
+            .upload_task
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+        {
+            warn!("Previous backup still in progress.");
+            return;
+        }
+        self.last_snapshot = Some(Instant::now());
+        let checkpoint = match checkpoint(kailua_db, data_dir) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                error!("Failed to checkpoint database: {e:?}");
+                return;
+            }
+        };
+        let args = self.args.clone();
+        let bucket = self.bucket.clone();
+        let data_dir = data_dir.to_path_buf();
+        self.upload_task = Some(tokio::spawn(async move {
+            if let Err(e) = upload_snapshot(&bucket, &args, checkpoint, &data_dir).await {
+                error!("Failed to upload backup: {e:?}");
+            }
+        }));
+    }
+}
+
+async fn upload_snapshot(
+    bucket: &Bucket,
+    args: &BackupArgs,
+    checkpoint: DbCheckpoint,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let timestamp = format!(
+        "{:020}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    );
+    // top-level files hold the manifest and ledgers of the validator
+    let mut files = vec![];
+    for entry in std::fs::read_dir(data_dir).context("read_dir")?.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_file()) {
+            files.push((
+                entry.file_name().to_string_lossy().to_string(),
+                entry.path(),
+            ));
+        }
+    }
+    for entry in std::fs::read_dir(checkpoint.path())
+        .context("read_dir")?
+        .flatten()
+    {
+        files.push((
+            format!(
+                "{}/{}",
+                checkpoint.db_dir.to_string_lossy(),
+                entry.file_name().to_string_lossy()
+            ),
+            entry.path(),
+        ));
+    }
+    for (file, path) in &files {
+        let data = std::fs::read(path).context("read snapshot file")?;
+        bucket
+            .put(&format!("{SNAPSHOTS_DIR}/{timestamp}/{file}"), data)
+            .await?;
+    }
+    info!("Uploaded snapshot {timestamp} ({} files).", files.len());
+    // delete the oldest snapshots
+    let snapshots = snapshots(bucket).await?;
+    let excess = snapshots
+        .len()
+        .saturating_sub(args.backup_snapshots_kept.max(1));
+    for (timestamp, files) in snapshots.into_iter().take(excess) {
+        for file in files {
+            bucket
+                .delete(&format!("{SNAPSHOTS_DIR}/{timestamp}/{file}"))
+                .await?;
+        }
+        info!("Deleted snapshot {timestamp}.");
+    }
+    upload_receipts(bucket, args, data_dir).await
+}
+
+/// Uploads new receipts from the data directory, and deletes those collected locally longer ago
+/// than the retention period
+async fn upload_receipts(
+    bucket: &Bucket,
+    args: &BackupArgs,
+    data_dir: &Path,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let backed_up = bucket.list(RECEIPTS_DIR).await?;
+    let backed_up_names = backed_up
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<HashSet<_>>();
+    let mut local_names = HashSet::new();
+    for entry in std::fs::read_dir(data_dir.join(RECEIPTS_DIR))
+        .context("read_dir")?
+        .flatten()
+    {
+        let receipt_file_name = entry.file_name().to_string_lossy().to_string();
+        if !receipt_file_name.starts_with("risc0-") || !entry.file_type().is_ok_and(|t| t.is_file())
+        {
+            continue;
+        }
+        if !backed_up_names.contains(&receipt_file_name) {
+            let data = std::fs::read(entry.path()).context("read receipt")?;
+            bucket
+                .put(&format!("{RECEIPTS_DIR}/{receipt_file_name}"), data)
+                .await?;
+            info!("Backed up receipt {receipt_file_name}.");
+        }
+        local_names.insert(receipt_file_name);
+    }
+    for (receipt_file_name, modified) in backed_up {
+        if local_names.contains(&receipt_file_name)
+            || modified + args.backup_receipt_retention_secs > now
+        {
+            continue;
+        }
+        bucket
+            .delete(&format!("{RECEIPTS_DIR}/{receipt_file_name}"))
+            .await?;
+        info!("Deleted backed up receipt {receipt_file_name}.");
+    }
+    Ok(())
+}
+
+/// Returns the files of each snapshot in the bucket, ordered from oldest to newest
+async fn snapshots(bucket: &Bucket) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+    let mut snapshots = BTreeMap::<String, Vec<String>>::new();
+    for (path, _) in bucket.list(SNAPSHOTS_DIR).await? {
+        if let Some((timestamp, file)) = path.split_once('/') {
+            snapshots
+                .entry(timestamp.to_string())
+                .or_default()
+                .push(file.to_string());
+        }
+    }
+    Ok(snapshots)
+}
+
+/// A consistent copy of the database, deleted once dropped
+struct DbCheckpoint {
+    dir: TempDir,
+    /// Path of the database relative to the data directory
+    db_dir: PathBuf,
+}
+
+impl DbCheckpoint {
+    fn path(&self) -> PathBuf {
+        self.dir.path().join(&self.db_dir)
+    }
+}
+
+fn checkpoint(kailua_db: &KailuaDB, data_dir: &Path) -> anyhow::Result<DbCheckpoint> {
+    let db_dir = kailua_db
+        .db
+        .path()
+        .strip_prefix(data_dir)
+        .context("database outside data directory")?
+        .to_path_buf();
+    // checkpoints hard link the database files, which requires the same file system
+    let dir = tempfile::tempdir_in(data_dir).context("tempdir_in")?;
+    let checkpoint = DbCheckpoint { dir, db_dir };
+    if let Some(parent) = checkpoint.path().parent() {
+        std::fs::create_dir_all(parent).context("create_dir_all")?;
+    }
+    Checkpoint::new(&kailua_db.db)
+        .context("Checkpoint::new")?
+        .create_checkpoint(checkpoint.path())
+        .context("create_checkpoint")?;
+    Ok(checkpoint)
+}
@@ -0,0 +1,159 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{bail, ensure, Context};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+
+/// Endpoint of the S3-compatible XML API of Google Cloud Storage
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Connection parameters of an S3 or Google Cloud Storage bucket
+#[derive(Clone, Debug)]
+pub struct BucketConfig<'a> {
+    /// Bucket and prefix to store objects under (e.g. s3://bucket/prefix or gs://bucket/prefix)
+    pub url: &'a str,
+    /// Endpoint of an S3-compatible service to use instead of AWS
+    pub endpoint: Option<&'a str>,
+    pub region: &'a str,
+    pub access_key: Option<&'a str>,
+    pub secret_key: Option<&'a str>,
+}
+
+/// Objects stored under a prefix of an S3 or Google Cloud Storage bucket
+#[derive(Clone, Debug)]
+pub struct Bucket {
+    client: Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl Bucket {
+    pub fn connect(config: BucketConfig) -> anyhow::Result<Self> {
+        let (scheme, path) = config
+            .url
+            .split_once("://")
+            .context("bucket url has no scheme")?;
+        let default_endpoint = match scheme {
+            "s3" => None,
+            "gs" => Some(GCS_ENDPOINT),
+            _ => bail!("Unsupported bucket url scheme {scheme}."),
+        };
+        let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+        ensure!(!bucket.is_empty(), "Bucket url has no bucket.");
+        let credentials = Credentials::new(
+            config.access_key.context("access key required")?,
+            config.secret_key.context("secret key required")?,
+            None,
+            None,
+            "kailua",
+        );
+        let s3_config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.to_string()))
+            .credentials_provider(credentials)
+            .set_endpoint_url(config.endpoint.or(default_endpoint).map(String::from))
+            .force_path_style(true)
+            .build();
+        Ok(Self {
+            client: Client::from_conf(s3_config),
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    fn key(&self, path: &str) -> String {
+        if self.prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{path}", self.prefix)
+        }
+    }
+
+    /// Returns the object at the path, or None if it does not exist
+    pub async fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let output = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.key(path))
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e).context("get_object"),
+        };
+        let data = output.body.collect().await.context("collect")?.into_bytes();
+        Ok(Some(data.to_vec()))
+    }
+
+    pub async fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.key(path))
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .context("put_object")?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, path: &str) -> anyhow::Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.key(path))
+            .send()
+            .await
+            .context("delete_object")?;
+        Ok(())
+    }
+
+    /// Returns the paths of all objects under the directory along with their last modification
+    /// timestamps
+    pub async fn list(&self, dir: &str) -> anyhow::Result<Vec<(String, u64)>> {
+        let dir_key = format!("{}/", self.key(dir.trim_end_matches('/')));
+        let mut objects = vec![];
+        let mut continuation_token = None;
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&dir_key)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .context("list_objects_v2")?;
+            for object in output.contents() {
+                let Some(path) = object.key().and_then(|k| k.strip_prefix(&dir_key)) else {
+                    continue;
+                };
+                let modified = object
+                    .last_modified()
+                    .map(|t| t.secs().max(0) as u64)
+                    .unwrap_or_default();
+                objects.push((path.to_string(), modified));
+            }
+            continuation_token = output.next_continuation_token().map(String::from);
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        Ok(objects)
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::consensus::BlockHeader;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::{BlockResponse, Network};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// Seconds between L1 blocks assumed until some are observed
+const DEFAULT_L1_BLOCK_TIME: f64 = 12.0;
+/// Seconds after its timestamp by which a new L1 block is expected to reach the rpc endpoint
+const L1_BLOCK_PROPAGATION_DELAY: f64 = 1.0;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CadenceArgs {
+    /// Minimum number of seconds between iterations of the main loop
+    #[clap(long, env, default_value_t = 1.0)]
+    pub poll_interval_min: f64,
+    /// Maximum number of seconds between iterations of the main loop while waiting for a new L1
+    /// block
+    #[clap(long, env, default_value_t = 12.0)]
+    pub poll_interval_max: f64,
+}
+
+/// Schedules the iterations of a main loop shortly after each new L1 block is expected, as the
+/// state that the loop reacts to only changes with new L1 blocks
+#[derive(Clone, Debug)]
+pub struct PollCadence {
+    min_interval: f64,
+    max_interval: f64,
+    /// Number and timestamp of the latest observed L1 block
+    latest_block: Option<(u64, u64)>,
+    /// Estimated seconds between L1 blocks
+    block_time: f64,
+}
+
+impl PollCadence {
+    pub fn new(args: &CadenceArgs) -> Self {
+        let min_interval = args.poll_interval_min.max(0.0);
+        Self {
+            min_interval,
+            max_interval: args.poll_interval_max.max(min_interval),
+            latest_block: None,
+            block_time: DEFAULT_L1_BLOCK_TIME,
+        }
+    }
+
+    /// Sleeps until the next L1 block is expected and observes the latest one
+    pub async fn wait<T: Transport + Clone, P: Provider<T, N>, N: Network>(&mut self, provider: P) {
+        sleep(self.next_delay()).await;
+        if let Err(e) = self.observe(provider).await {
+            warn!("Failed to observe latest L1 block: {e:?}");
+        }
+    }
+
+    /// Returns the delay until the next L1 block is expected, or the minimum interval if it is
+    /// already overdue
+    fn next_delay(&self) -> Duration {
+        let Some((_, timestamp)) = self.latest_block else {
+            return Duration::from_secs_f64(self.min_interval);
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        let expected_at = timestamp as f64 + self.block_time + L1_BLOCK_PROPAGATION_DELAY;
+        Duration::from_secs_f64((expected_at - now).clamp(self.min_interval, self.max_interval))
+    }
+
+    async fn observe<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+    ) -> anyhow::Result<()> {
+        let block = provider
+            .get_block(
+                BlockId::Number(BlockNumberOrTag::Latest),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .context("get_block")?
+            .context("latest block not found")?;
+        let number = block.header().number();
+        let timestamp = block.header().timestamp();
+        if let Some((latest_number, latest_timestamp)) = self.latest_block {
+            if number <= latest_number {
+                return Ok(());
+            }
+            // average out missed slots
+            let observed_block_time =
+                timestamp.saturating_sub(latest_timestamp) as f64 / (number - latest_number) as f64;
+            if observed_block_time > 0.0 {
+                self.block_time = 0.8 * self.block_time + 0.2 * observed_block_time;
+            }
+        }
+        debug!(
+            "Observed L1 block {number} at {timestamp} ({:.1}s block time).",
+            self.block_time
+        );
+        self.latest_block = Some((number, timestamp));
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::CoreArgs;
+use anyhow::{ensure, Context};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+/// Parameters of the faults injected into the traffic to all endpoints, e.g. to exercise the
+/// retry logic of the agents in integration tests
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ChaosArgs {
+    /// Probability in [0, 1] that a request to an endpoint fails with a server error
+    #[clap(long, env, default_value_t = 0.0)]
+    pub chaos_error_rate: f64,
+    /// Maximum number of milliseconds randomly added to the latency of each request
+    #[clap(long, env, default_value_t = 0)]
+    pub chaos_latency: u64,
+    /// Probability in [0, 1] that a response from an endpoint is cut short
+    #[clap(long, env, default_value_t = 0.0)]
+    pub chaos_truncation_rate: f64,
+}
+
+impl ChaosArgs {
+    pub fn is_enabled(&self) -> bool {
+        self.chaos_error_rate > 0.0 || self.chaos_latency > 0 || self.chaos_truncation_rate > 0.0
+    }
+}
+
+/// The local servers injecting faults into the traffic to the configured endpoints
+#[derive(Debug)]
+pub struct ChaosSession {
+    servers: Vec<JoinHandle<()>>,
+}
+
+impl Drop for ChaosSession {
+    fn drop(&mut self) {
+        for server in &self.servers {
+            server.abort();
+        }
+    }
+}
+
+impl CoreArgs {
+    /// Redirects all endpoints to local servers injecting faults into their traffic, if any fault
+    /// is configured
+    pub async fn start_chaos(&mut self) -> anyhow::Result<Option<ChaosSession>> {
+        let args = self.chaos_args.clone();
+        if !args.is_enabled() {
+            return Ok(None);
+        }
+        for (name, rate) in [
+            ("error", args.chaos_error_rate),
+            ("truncation", args.chaos_truncation_rate),
+        ] {
+            ensure!(
+                (0.0..=1.0).contains(&rate),
+                "Chaos {name} rate {rate} is not a probability."
+            );
+        }
+        warn!(
+            "Injecting faults into rpc traffic: {:.1}% errors, {:.1}% truncations, up to {}ms latency.",
+            100.0 * args.chaos_error_rate,
+            100.0 * args.chaos_truncation_rate,
+            args.chaos_latency
+        );
+
+        let client = reqwest::Client::new();
+        let mut servers = vec![];
+        for (name, url) in self.endpoints_mut() {
+            let endpoint = ChaosEndpoint {
+                name,
+                upstream: url.trim_end_matches('/').to_string(),
+                client: client.clone(),
+                args: args.clone(),
+            };
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .context("TcpListener::bind")?;
+            let local_url = format!("http://{}", listener.local_addr()?);
+            warn!("Serving faulty {name} endpoint at {local_url}.");
+            let app = Router::new()
+                .fallback(handle)
+                .with_state(Arc::new(endpoint));
+            servers.push(tokio::spawn(async move {
+                if let Err(err) = axum::serve(listener, app).await {
+                    error!("Faulty {name} endpoint stopped: {err:?}");
+                }
+            }));
+            *url = local_url;
+        }
+        Ok(Some(ChaosSession { servers }))
+    }
+}
+
+struct ChaosEndpoint {
+    name: &'static str,
+    upstream: String,
+    client: reqwest::Client,
+    args: ChaosArgs,
+}
+
+impl ChaosEndpoint {
+    async fn forward(&self, method: Method, uri: Uri, body: Bytes) -> anyhow::Result<Response> {
+        let path = uri.path_and_query().map_or("/", |p| p.as_str());
+        let url = if path == "/" {
+            self.upstream.clone()
+        } else {
+            format!("{}{path}", self.upstream)
+        };
+        let response = self
+            .client
+            .request(method, url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .context("send")?;
+        let status = StatusCode::from_u16(response.status().as_u16())?;
+        let mut response = response.bytes().await.context("bytes")?;
+
+        if random_unit() < self.args.chaos_truncation_rate {
+            let len = (random_unit() * response.len() as f64) as usize;
+            debug!(
+                "Truncating {} response to {len}/{} bytes.",
+                self.name,
+                response.len()
+            );
+            response.truncate(len);
+        }
+        Ok((
+            status,
+            [(axum::http::header::CONTENT_TYPE, "application/json")],
+            response,
+        )
+            .into_response())
+    }
+}
+
+/// Returns a uniformly random number in [0, 1)
+fn random_unit() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+async fn handle(
+    State(endpoint): State<Arc<ChaosEndpoint>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let latency = (random_unit() * (endpoint.args.chaos_latency + 1) as f64) as u64;
+    tokio::time::sleep(Duration::from_millis(latency)).await;
+
+    if random_unit() < endpoint.args.chaos_error_rate {
+        debug!("Failing {} request with an injected error.", endpoint.name);
+        return (StatusCode::SERVICE_UNAVAILABLE, "Injected fault").into_response();
+    }
+
+    match endpoint.forward(method, uri, body).await {
+        Ok(response) => response,
+        Err(err) => {
+            error!("Faulty {} request failed: {err:?}", endpoint.name);
+            (StatusCode::BAD_GATEWAY, format!("{err:?}")).into_response()
+        }
+    }
+}
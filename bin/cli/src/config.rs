@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::proxy::ProxiedContract;
 use crate::stall::Stall;
 use crate::{BN254_CONTROL_ID, CONTROL_ROOT, KAILUA_GAME_TYPE, SET_BUILDER_ID};
 use alloy::primitives::address;
@@ -20,7 +21,7 @@ use anyhow::Context;
 use kailua_build::KAILUA_FPVM_ID;
 use kailua_common::client::config_hash;
 use kailua_contracts::SystemConfig;
-use kailua_host::fetch_rollup_config;
+use kailua_host::{fetch_rollup_config, HardforkArgs};
 use risc0_zkvm::sha::Digest;
 
 #[derive(clap::Args, Debug, Clone)]
@@ -37,12 +38,20 @@ pub struct ConfigArgs {
     /// Address of the ethereum rpc endpoint to use (eth namespace required)
     #[clap(long, env)]
     pub eth_rpc_url: String,
+
+    #[clap(flatten)]
+    pub hardfork_args: HardforkArgs,
 }
 
 pub async fn config(args: ConfigArgs) -> anyhow::Result<()> {
-    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
-        .await
-        .context("fetch_rollup_config")?;
+    let config = fetch_rollup_config(
+        &args.op_node_url,
+        &args.op_geth_url,
+        None,
+        &args.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
     let eth_rpc_provider = ProviderBuilder::new().on_http(args.eth_rpc_url.as_str().try_into()?);
     // load system config
     let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
@@ -121,6 +130,20 @@ pub async fn config(args: ConfigArgs) -> anyhow::Result<()> {
         "OPTIMISM_PORTAL: 0x{}",
         hex::encode_upper(portal_address.as_slice())
     );
+    // report implementations of proxied contracts
+    for (name, address) in [
+        ("SYSTEM_CONFIG", config.l1_system_config_address),
+        ("DISPUTE_GAME_FACTORY", dgf_address),
+        ("OPTIMISM_PORTAL", portal_address),
+    ] {
+        let proxied_contract = ProxiedContract::fetch(&eth_rpc_provider, address).await?;
+        if let Some(implementation) = proxied_contract.implementation {
+            println!(
+                "{name}_IMPLEMENTATION: 0x{}",
+                hex::encode_upper(implementation.as_slice())
+            );
+        }
+    }
     // report game type
     println!("KAILUA_GAME_TYPE: {}", KAILUA_GAME_TYPE);
 
@@ -0,0 +1,97 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use kailua_client::stats::ProvingStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Name of the file in the data directory that accumulates proving costs across restarts
+pub const PROVING_COSTS_FILE: &str = "proving-costs.json";
+
+/// Compute spent on proofs, keyed by the index of the disputed proposal they were computed for
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProvingCostLedger {
+    pub disputes: BTreeMap<u64, DisputeCost>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DisputeCost {
+    /// Number of proving jobs completed for the dispute
+    pub jobs: u64,
+    /// Cycles proven, excluding jobs whose backend does not report them
+    pub total_cycles: u64,
+    /// Seconds spent computing proofs
+    pub proving_secs: f64,
+    /// Estimated spend in USD, excluding jobs proven without a configured price
+    pub estimated_cost: f64,
+    /// Number of jobs per prover backend
+    pub backends: BTreeMap<String, u64>,
+    /// Identifiers of the proving jobs, to look up their logs
+    #[serde(default)]
+    pub correlation_ids: Vec<String>,
+}
+
+impl DisputeCost {
+    pub fn add(&mut self, stats: &ProvingStats) {
+        self.jobs += 1;
+        self.total_cycles += stats.total_cycles.unwrap_or_default();
+        self.proving_secs += stats.proving_secs;
+        self.estimated_cost += stats.estimated_cost.unwrap_or_default();
+        *self.backends.entry(stats.backend.clone()).or_default() += 1;
+        self.correlation_ids.extend(stats.correlation_id.clone());
+    }
+
+    pub fn merge(&mut self, other: &DisputeCost) {
+        self.jobs += other.jobs;
+        self.total_cycles += other.total_cycles;
+        self.proving_secs += other.proving_secs;
+        self.estimated_cost += other.estimated_cost;
+        for (backend, jobs) in &other.backends {
+            *self.backends.entry(backend.clone()).or_default() += jobs;
+        }
+        self.correlation_ids
+            .extend(other.correlation_ids.iter().cloned());
+    }
+}
+
+impl ProvingCostLedger {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path).context("read proving costs file")?;
+        serde_json::from_slice(&data).context("parse proving costs file")
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?).context("write proving costs file")
+    }
+
+    /// Adds the measurements of a completed proving job to the dispute it served
+    pub fn record(&mut self, proposal_index: u64, stats: &ProvingStats) -> &DisputeCost {
+        let dispute = self.disputes.entry(proposal_index).or_default();
+        dispute.add(stats);
+        dispute
+    }
+
+    pub fn total(&self) -> DisputeCost {
+        let mut total = DisputeCost::default();
+        for dispute in self.disputes.values() {
+            total.merge(dispute);
+        }
+        total
+    }
+}
@@ -17,7 +17,11 @@ use alloy::network::Network;
 use alloy::primitives::{Address, B256};
 use alloy::providers::Provider;
 use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use kailua_common::precondition::{LEGACY_PRECONDITION_VERSION, PRECONDITION_VERSION};
+use kailua_contracts::revision::ContractRevision;
 use kailua_contracts::KailuaGame::KailuaGameInstance;
+use tracing::{info, warn};
 
 #[derive(Clone, Debug, Default)]
 pub struct Config {
@@ -34,6 +38,7 @@ pub struct Config {
     pub genesis_time: u64,
     pub block_time: u64,
     pub proposal_gap: u64,
+    pub revision: ContractRevision,
 }
 
 impl Config {
@@ -102,6 +107,7 @@ impl Config {
             .await
             .proposalTimeGap_
             .to();
+        let revision = fetch_revision(kailua_game_implementation, treasury).await?;
         Ok(Self {
             treasury,
             game,
@@ -116,9 +122,19 @@ impl Config {
             genesis_time,
             block_time,
             proposal_gap,
+            revision,
         })
     }
 
+    /// The precondition encoding version expected by the deployed contracts
+    pub fn precondition_version(&self) -> u8 {
+        if self.revision.versioned_preconditions {
+            PRECONDITION_VERSION
+        } else {
+            LEGACY_PRECONDITION_VERSION
+        }
+    }
+
     pub fn allows_proposal(&self, proposal_block_number: u64, proposal_time: u64) -> bool {
         proposal_time >= self.min_proposal_time(proposal_block_number)
     }
@@ -127,3 +143,58 @@ impl Config {
         self.genesis_time + proposal_block_number * self.block_time + self.proposal_gap + 1
     }
 }
+
+/// Detects the features supported by the deployed KailuaGame and KailuaTreasury contracts
+pub async fn fetch_revision<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    kailua_game_implementation: &KailuaGameInstance<T, P, N>,
+    treasury: Address,
+) -> anyhow::Result<ContractRevision> {
+    let version = match kailua_game_implementation.version().call().await {
+        Ok(version) => match semver::Version::parse(&version._0) {
+            Ok(version) => Some(version),
+            Err(e) => {
+                warn!("Could not parse KailuaGame version {}: {e:?}", version._0);
+                None
+            }
+        },
+        Err(e) => {
+            warn!("Could not fetch KailuaGame version: {e:?}");
+            None
+        }
+    };
+    let revision = match version.clone().and_then(ContractRevision::from_version) {
+        Some(revision) => revision,
+        None => {
+            let provider = kailua_game_implementation.provider();
+            let game_code = provider
+                .get_code_at(*kailua_game_implementation.address())
+                .await
+                .context("get_code_at")?;
+            let treasury_code = provider
+                .get_code_at(treasury)
+                .await
+                .context("get_code_at")?;
+            let Some(revision) =
+                ContractRevision::from_bytecode(version, &game_code, &treasury_code)
+            else {
+                bail!(
+                    "KailuaGame({}) does not match any supported contract revision.",
+                    kailua_game_implementation.address()
+                );
+            };
+            revision
+        }
+    };
+    info!(
+        "Detected contract revision {} (validity proofs: {}, bond withdrawals: {}, versioned preconditions: {}).",
+        revision
+            .version
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or(String::from("unknown")),
+        revision.validity_proofs,
+        revision.bond_withdrawals,
+        revision.versioned_preconditions
+    );
+    Ok(revision)
+}
@@ -17,16 +17,22 @@ pub mod proposal;
 pub mod state;
 pub mod treasury;
 
-use crate::providers::beacon::BlobProvider;
+use crate::anomaly::{ChainAnomalies, ChainAnomaly};
+use crate::layout::DB_DIR;
+use crate::providers::beacon::{BlobCommitmentMismatch, BlobProvider};
 use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
 use crate::KAILUA_GAME_TYPE;
-use alloy::network::Network;
+use alloy::consensus::BlockHeader;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::primitives::BlockTransactionsKind;
+use alloy::network::{BlockResponse, Network};
 use alloy::primitives::{Address, U256};
 use alloy::providers::Provider;
 use alloy::transports::Transport;
-use anyhow::{bail, Context};
+use anyhow::{anyhow, bail, Context};
 use config::Config;
+use futures::{stream, StreamExt};
 use kailua_contracts::{
     IDisputeGameFactory::{gameAtIndexReturn, IDisputeGameFactoryInstance},
     *,
@@ -35,6 +41,7 @@ use proposal::Proposal;
 use state::State;
 use std::collections::hash_map::Entry;
 use std::path::PathBuf;
+use std::pin::pin;
 use tracing::{error, info, warn};
 use treasury::Treasury;
 
@@ -47,12 +54,37 @@ pub enum ProofStatus {
     UWinVLose,
 }
 
+/// The L1 blocks whose proposals may be ingested
+#[derive(Clone, Copy, Debug, Default)]
+pub enum L1Confirmation {
+    /// Proposals are ingested as soon as they are created
+    #[default]
+    Latest,
+    /// Proposals are ingested once their block has this many confirmations
+    Confirmations(u64),
+    /// Proposals are ingested once their block is finalized
+    Finalized,
+}
+
 #[derive(Debug)]
 pub struct KailuaDB {
     pub config: Config,
     pub treasury: Treasury,
     pub db: rocksdb::DB,
     pub state: State,
+    pub l1_confirmation: L1Confirmation,
+    pub chain_anomalies: ChainAnomalies,
+    /// Maximum number of games whose data is fetched concurrently during ingestion
+    pub ingestion_concurrency: usize,
+}
+
+/// The data of a game fetched ahead of its ingestion
+pub struct FetchedGame {
+    pub index: u64,
+    pub game_type: u32,
+    pub created_at: u64,
+    /// The proposal of a kailua game and whether the treasury attributes it to its proposer
+    pub proposal: anyhow::Result<Option<(Proposal, bool)>>,
 }
 
 impl Drop for KailuaDB {
@@ -85,6 +117,7 @@ impl KailuaDB {
             KailuaTreasury::new(config.treasury, dispute_game_factory.provider());
         let treasury = Treasury::init(&treasury_implementation).await?;
 
+        data_dir.push(DB_DIR);
         data_dir.push(config.cfg_hash.to_string());
         let db = rocksdb::DB::open(&Self::options(), &data_dir)?;
         Ok(Self {
@@ -92,6 +125,9 @@ impl KailuaDB {
             treasury,
             db,
             state: Default::default(),
+            l1_confirmation: Default::default(),
+            chain_anomalies: Default::default(),
+            ingestion_concurrency: 1,
         })
     }
 
@@ -101,28 +137,66 @@ impl KailuaDB {
         op_node_provider: &OpNodeProvider,
         blob_provider: &BlobProvider,
     ) -> anyhow::Result<Vec<u64>> {
-        let canonical_start = self.state.canonical_tip_index;
         let game_count: u64 = dispute_game_factory
             .gameCount()
             .stall()
             .await
             .gameCount_
             .to();
+        self.load_proposals_until(
+            dispute_game_factory,
+            op_node_provider,
+            blob_provider,
+            game_count,
+        )
+        .await
+    }
+
+    /// Ingests the games created before the factory index, returning the processed proposals
+    pub async fn load_proposals_until<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
+        op_node_provider: &OpNodeProvider,
+        blob_provider: &BlobProvider,
+        game_count: u64,
+    ) -> anyhow::Result<Vec<u64>> {
+        let canonical_start = self.state.canonical_tip_index;
         let mut proposals =
-            Vec::with_capacity((game_count - self.state.next_factory_index) as usize);
-        while self.state.next_factory_index < game_count {
-            let proposal = match self.get_local_proposal(&self.state.next_factory_index) {
+            Vec::with_capacity(game_count.saturating_sub(self.state.next_factory_index) as usize);
+        let ingestion_cutoff = self
+            .l1_ingestion_cutoff(dispute_game_factory.provider())
+            .await
+            .context("l1_ingestion_cutoff")?;
+        // Fetch the data of upcoming games concurrently while ingesting them in order
+        let config = self.config.clone();
+        let treasury = self.treasury.clone();
+        let mut games = pin!(stream::iter(self.state.next_factory_index..game_count)
+            .map(|index| {
+                Self::fetch_game_at_index(
+                    &config,
+                    &treasury,
+                    dispute_game_factory,
+                    op_node_provider,
+                    blob_provider,
+                    index,
+                )
+            })
+            .buffered(self.ingestion_concurrency.max(1)));
+        while let Some(game) = games.next().await {
+            let proposal = match self.get_local_proposal(&game.index) {
                 Some(proposal) => Some(proposal),
                 None => {
-                    match self
-                        .load_game_at_index(
-                            dispute_game_factory,
-                            op_node_provider,
-                            blob_provider,
-                            self.state.next_factory_index,
-                        )
-                        .await
-                    {
+                    // wait for the game's creation to be sufficiently confirmed on l1
+                    if let Some(cutoff) = ingestion_cutoff {
+                        if game.created_at > cutoff {
+                            info!(
+                                "Waiting for L1 confirmation of game at factory index {}.",
+                                game.index
+                            );
+                            break;
+                        }
+                    }
+                    match self.ingest_game(game) {
                         Ok(processed) => {
                             if processed {
                                 proposals.push(self.state.next_factory_index);
@@ -135,10 +209,19 @@ impl KailuaDB {
                             }
                         }
                         Err(err) => {
-                            error!(
-                                "Error loading game at index {}: {err:?}",
-                                self.state.next_factory_index
-                            );
+                            if let Some(mismatch) = err.downcast_ref::<BlobCommitmentMismatch>() {
+                                error!(
+                                    "UNTRUSTED BEACON DATA: {mismatch} (game at index {}).",
+                                    self.state.next_factory_index
+                                );
+                                self.chain_anomalies
+                                    .record(ChainAnomaly::BeaconInconsistency);
+                            } else {
+                                error!(
+                                    "Error loading game at index {}: {err:?}",
+                                    self.state.next_factory_index
+                                );
+                            }
                             break;
                         }
                     }
@@ -172,36 +255,118 @@ impl KailuaDB {
         Ok(proposals)
     }
 
-    pub async fn load_game_at_index<T: Transport + Clone, P: Provider<T, N>, N: Network>(
-        &mut self,
+    /// Returns the latest timestamp of games that may be ingested, if restricted
+    pub async fn l1_ingestion_cutoff<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        provider: P,
+    ) -> anyhow::Result<Option<u64>> {
+        let block_id = match self.l1_confirmation {
+            L1Confirmation::Latest => return Ok(None),
+            L1Confirmation::Confirmations(confirmations) => {
+                let latest = provider
+                    .get_block_number()
+                    .await
+                    .context("get_block_number")?;
+                BlockNumberOrTag::Number(latest.saturating_sub(confirmations))
+            }
+            L1Confirmation::Finalized => BlockNumberOrTag::Finalized,
+        };
+        let block = provider
+            .get_block(BlockId::Number(block_id), BlockTransactionsKind::Hashes)
+            .await
+            .context("get_block")?
+            .ok_or_else(|| anyhow!("Could not fetch L1 block {block_id}"))?;
+        Ok(Some(block.header().timestamp()))
+    }
+
+    /// Fetches the data needed to ingest the game at the index, which does not depend on the games
+    /// before it so that several games can be fetched concurrently
+    pub async fn fetch_game_at_index<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        config: &Config,
+        treasury: &Treasury,
         dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
         op_node_provider: &OpNodeProvider,
         blob_provider: &BlobProvider,
         index: u64,
-    ) -> anyhow::Result<bool> {
-        // process game
+    ) -> FetchedGame {
         let gameAtIndexReturn {
             gameType_: game_type,
+            timestamp_: created_at,
             proxy_: game_address,
-            ..
         } = dispute_game_factory
             .gameAtIndex(U256::from(index))
             .stall()
             .await;
         // skip entries for other game types
-        if game_type != KAILUA_GAME_TYPE {
-            info!("Skipping proposal of different game type {game_type} at factory index {index}");
+        let proposal = if game_type == KAILUA_GAME_TYPE {
+            Self::fetch_proposal(
+                config,
+                treasury,
+                dispute_game_factory.provider(),
+                op_node_provider,
+                blob_provider,
+                game_address,
+            )
+            .await
+            .map(Some)
+        } else {
+            Ok(None)
+        };
+        FetchedGame {
+            index,
+            game_type,
+            created_at,
+            proposal,
+        }
+    }
+
+    async fn fetch_proposal<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        config: &Config,
+        treasury: &Treasury,
+        provider: &P,
+        op_node_provider: &OpNodeProvider,
+        blob_provider: &BlobProvider,
+        game_address: Address,
+    ) -> anyhow::Result<(Proposal, bool)> {
+        let tournament_instance = KailuaTournament::new(game_address, provider);
+        let mut proposal = Proposal::load(config, blob_provider, &tournament_instance).await?;
+        // Accept correctness of treasury instance data
+        if !proposal.has_parent() {
+            return Ok((proposal, true));
+        }
+        let attributed = Self::verify_proposer_attribution(treasury, &proposal, provider)
+            .await
+            .context("Failed to verify proposer attribution")?;
+        // The correctness of the parent is inherited once it is ingested
+        proposal
+            .assess_correctness(config, op_node_provider, true)
+            .await
+            .context("Failed to assess proposal correctness")?;
+        Ok((proposal, attributed))
+    }
+
+    /// Ingests a fetched game after all the games before it, returning whether it was processed
+    pub fn ingest_game(&mut self, game: FetchedGame) -> anyhow::Result<bool> {
+        let Some((mut proposal, attributed)) = game.proposal? else {
+            info!(
+                "Skipping proposal of different game type {} at factory index {}",
+                game.game_type, game.index
+            );
+            return Ok(false);
+        };
+        info!(
+            "Processing tournament {} at {}",
+            proposal.index, proposal.contract
+        );
+
+        // Exclude proposals that were not created through the treasury
+        if !attributed {
+            self.state.unattributed_proposals.insert(proposal.index);
             return Ok(false);
         }
-        info!("Processing tournament {index} at {game_address}");
-        let tournament_instance =
-            KailuaTournament::new(game_address, dispute_game_factory.provider());
-        let mut proposal =
-            Proposal::load(&self.config, blob_provider, &tournament_instance).await?;
 
         // Determine inherited correctness
-        self.determine_correctness(&mut proposal, op_node_provider)
-            .await
+        self.determine_correctness(&mut proposal)
             .context("Failed to determine proposal correctness")?;
 
         // Determine whether to follow or eliminate proposer
@@ -230,28 +395,55 @@ impl KailuaDB {
         }
     }
 
-    pub async fn determine_correctness(
-        &mut self,
-        proposal: &mut Proposal,
-        op_node_provider: &OpNodeProvider,
+    /// Returns true if the treasury records the game's creator and proposer as expected
+    pub async fn verify_proposer_attribution<
+        T: Transport + Clone,
+        P: Provider<T, N>,
+        N: Network,
+    >(
+        treasury: &Treasury,
+        proposal: &Proposal,
+        provider: P,
     ) -> anyhow::Result<bool> {
+        let treasury_proposer = treasury
+            .treasury_contract_instance(&provider)
+            .proposerOf(proposal.contract)
+            .stall()
+            .await
+            ._0;
+        let game_creator = KailuaGame::new(proposal.contract, &provider)
+            .gameCreator()
+            .stall()
+            .await
+            .creator_;
+        if game_creator != treasury.address
+            || treasury_proposer.is_zero()
+            || treasury_proposer != proposal.proposer
+        {
+            warn!(
+                "UNATTRIBUTED PROPOSAL: Proposal {} by {} was created by {game_creator} with treasury proposer {treasury_proposer}.",
+                proposal.index, proposal.proposer
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    pub fn determine_correctness(&mut self, proposal: &mut Proposal) -> anyhow::Result<bool> {
         // Accept correctness of treasury instance data
         if !proposal.has_parent() {
             info!("Accepting initial treasury proposal as true.");
             return Ok(true);
         }
 
-        // Validate game instance data
-        info!("Assessing proposal correctness..");
+        // Inherit the correctness of the parent
         let is_parent_correct = self
             .get_local_proposal(&proposal.parent)
             .expect("Attempted to process child before registering parent.")
             .is_correct()
             .expect("Attempted to process child before deciding parent correctness");
-        let is_correct_proposal = match proposal
-            .assess_correctness(&self.config, op_node_provider, is_parent_correct)
-            .await?
-        {
+        proposal.correct_parent = Some(is_parent_correct);
+        let is_correct_proposal = match proposal.is_correct() {
             None => {
                 bail!("Failed to assess correctness. Is op-node synced far enough?");
             }
@@ -285,6 +477,58 @@ impl KailuaDB {
         proposal.canonical
     }
 
+    /// Removes a proposal found faulty after its ingestion and its descendants from the canonical
+    /// chain, which then continues from a correct sibling of the same height or from its parent
+    pub fn revoke_canonical(
+        &mut self,
+        mut proposal: Proposal,
+        descendants: &[u64],
+    ) -> anyhow::Result<()> {
+        proposal.canonical = Some(false);
+        self.set_local_proposal(proposal.index, &proposal)?;
+        if let Entry::Vacant(entry) = self.state.eliminations.entry(proposal.proposer) {
+            entry.insert(proposal.index);
+        }
+        for index in descendants {
+            let mut descendant = self
+                .get_local_proposal(index)
+                .context("descendant missing")?;
+            descendant.correct_parent = Some(false);
+            descendant.canonical = Some(false);
+            self.set_local_proposal(*index, &descendant)?;
+        }
+        let parent = self
+            .get_local_proposal(&proposal.parent)
+            .context("parent missing")?;
+        let sibling = parent
+            .children
+            .iter()
+            .filter_map(|i| self.get_local_proposal(i))
+            .find(|sibling| {
+                sibling.index != proposal.index
+                    && sibling.output_block_number == proposal.output_block_number
+                    && sibling.is_correct() == Some(true)
+            });
+        let canonical_tip_index = match sibling {
+            Some(mut sibling) => {
+                // the sibling may have been assessed against the same faulty outputs
+                if self.state.eliminations.get(&sibling.proposer) == Some(&sibling.index) {
+                    self.state.eliminations.remove(&sibling.proposer);
+                }
+                sibling.canonical = Some(true);
+                self.set_local_proposal(sibling.index, &sibling)?;
+                sibling.index
+            }
+            None => parent.index,
+        };
+        warn!(
+            "Revoked proposal {} from the canonical chain, which continues from proposal {canonical_tip_index}.",
+            proposal.index
+        );
+        self.state.canonical_tip_index = Some(canonical_tip_index);
+        Ok(())
+    }
+
     pub fn determine_tournament_participation(
         &mut self,
         proposal: &mut Proposal,
@@ -312,8 +556,23 @@ impl KailuaDB {
         if !parent.canonical.unwrap_or_default() {
             return Ok(false);
         }
-        // Update the contender
-        proposal.contender = parent.survivor;
+        // Record the earliest identical sibling as the canonical representative of duplicates
+        for child in &parent.children {
+            let sibling = self.get_local_proposal(child).unwrap();
+            if proposal.is_duplicate_of(&sibling) {
+                let representative = sibling.duplicate_of.unwrap_or(sibling.index);
+                info!(
+                    "Proposal {} is a duplicate of proposal {representative}.",
+                    proposal.index
+                );
+                proposal.duplicate_of = Some(representative);
+                break;
+            }
+        }
+        // Update the contender unless it is an identical proposal
+        proposal.contender = parent.survivor.filter(|contender| {
+            !proposal.is_duplicate_of(&self.get_local_proposal(contender).unwrap())
+        });
         // Append child to parent
         if !parent.append_child(proposal.index) {
             warn!(
@@ -366,6 +625,14 @@ impl KailuaDB {
             .unwrap_or_default()
     }
 
+    /// Returns the locally known proposals submitted by the proposer that are not canonical
+    pub fn non_canonical_proposals_by(&self, proposer: Address) -> Vec<Proposal> {
+        (0..self.state.next_factory_index)
+            .filter_map(|index| self.get_local_proposal(&index))
+            .filter(|proposal| proposal.proposer == proposer && proposal.canonical != Some(true))
+            .collect()
+    }
+
     pub fn canonical_tip(&self) -> Option<Proposal> {
         self.state
             .canonical_tip_index
@@ -1,10 +1,11 @@
+use crate::audit::{AuditLog, AuditOutcome};
 use crate::db::config::Config;
-use crate::providers::beacon::blob_fe_proof;
+use crate::providers::beacon::{blob_fe_proof, verify_blob_fe_proof};
 use crate::providers::beacon::{blob_sidecar, BlobProvider};
 use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
-use alloy::consensus::{Blob, BlobTransactionSidecar, BlockHeader};
-use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
+use alloy::consensus::{BlobTransactionSidecar, BlockHeader};
+use alloy::eips::eip4844::{kzg_to_versioned_hash, FIELD_ELEMENTS_PER_BLOB};
 use alloy::eips::{BlockId, BlockNumberOrTag};
 use alloy::network::primitives::BlockTransactionsKind;
 use alloy::network::{BlockResponse, Network};
@@ -13,7 +14,9 @@ use alloy::providers::Provider;
 use alloy::transports::Transport;
 use alloy_rpc_types_beacon::sidecar::BlobData;
 use anyhow::{bail, Context};
-use kailua_common::blobs::{hash_to_fe, intermediate_outputs};
+use kailua_common::blobs::{decode_intermediate_outputs, encode_intermediate_outputs, hash_to_fe};
+use kailua_common::precondition::validity_precondition_hash;
+use kailua_contracts::extra_data::GameExtraData;
 use kailua_contracts::{
     KailuaGame::KailuaGameInstance, KailuaTournament::KailuaTournamentInstance,
     KailuaTreasury::KailuaTreasuryInstance, *,
@@ -40,6 +43,7 @@ pub struct Proposal {
     pub children: Vec<u64>,
     pub survivor: Option<u64>,
     pub contender: Option<u64>,
+    pub duplicate_of: Option<u64>,
     // correctness
     pub correct_io: Vec<Option<bool>>,
     pub correct_claim: Option<bool>,
@@ -108,6 +112,7 @@ impl Proposal {
             children: Default::default(),
             survivor: None,
             contender: None,
+            duplicate_of: None,
             correct_io: vec![],
             correct_claim: Some(true),
             correct_parent: Some(true),
@@ -121,16 +126,13 @@ impl Proposal {
         game_instance: &KailuaGameInstance<T, P, N>,
     ) -> anyhow::Result<Self> {
         let index = game_instance.gameIndex().stall().await._0.to();
-        let parent = game_instance
-            .parentGameIndex()
-            .stall()
-            .await
-            .parentGameIndex_;
+        let extra_data = GameExtraData::decode(&game_instance.extraData().stall().await.extraData_)
+            .context("GameExtraData::decode")?;
+        let parent = extra_data.parent_game_index;
         let proposer = game_instance.proposer().stall().await.proposer_;
         let created_at = game_instance.createdAt().stall().await._0;
         // fetch blob data
         let mut io_blobs = Vec::new();
-        let mut io_field_elements = Vec::new();
         for _ in 0..config.proposal_blobs {
             let blob_kzg_hash = game_instance
                 .proposalBlobHashes(U256::from(io_blobs.len()))
@@ -141,20 +143,18 @@ impl Proposal {
                 .get_blob(created_at, blob_kzg_hash)
                 .await
                 .context("get_blob")?;
-            // save data
-            let io_remaining = config.proposal_block_count - (io_field_elements.len() as u64) - 1;
-            let io_in_blob = io_remaining.min(FIELD_ELEMENTS_PER_BLOB);
-            io_field_elements.extend(intermediate_outputs(&blob_data, io_in_blob as usize)?);
             io_blobs.push((blob_kzg_hash, blob_data));
         }
+        let io_field_elements = decode_intermediate_outputs(
+            io_blobs
+                .iter()
+                .map(|(_, blob_data)| blob_data.blob.as_ref()),
+            config.proposal_block_count,
+        )
+        .context("decode_intermediate_outputs")?;
         // claim data
         let output_root = game_instance.rootClaim().stall().await.rootClaim_.0.into();
-        let output_block_number: u64 = game_instance
-            .l2BlockNumber()
-            .stall()
-            .await
-            .l2BlockNumber_
-            .to();
+        let output_block_number = extra_data.l2_block_number;
         let l1_head = game_instance.l1Head().stall().await.l1Head_.0.into();
         Ok(Self {
             contract: *game_instance.address(),
@@ -170,6 +170,7 @@ impl Proposal {
             children: Default::default(),
             survivor: None,
             contender: None,
+            duplicate_of: None,
             correct_io: repeat(None)
                 .take((config.proposal_block_count - 1) as usize)
                 .collect(),
@@ -269,6 +270,11 @@ impl Proposal {
             .duration_)
     }
 
+    /// Returns the timestamp at which the challenge window of this proposal elapses
+    pub fn challenge_deadline(&self, timeout: u64) -> u64 {
+        self.created_at + timeout
+    }
+
     pub fn parse_finality(game_status: u8) -> anyhow::Result<Option<bool>> {
         match game_status {
             0u8 => Ok(None),        // IN_PROGRESS
@@ -342,21 +348,52 @@ impl Proposal {
     pub async fn resolve<T: Transport + Clone, P: Provider<T, N>, N: Network>(
         &self,
         provider: P,
+        audit_log: &AuditLog,
     ) -> anyhow::Result<N::ReceiptResponse> {
-        self.tournament_contract_instance(provider)
-            .resolve()
-            .send()
-            .await
-            .context("KailuaTreasury::resolve (send)")?
+        let intent = format!("resolve proposal {}", self.index);
+        let contract = self.tournament_contract_instance(provider);
+        let call = contract.resolve();
+        let txn = match call.send().await.context("KailuaTreasury::resolve (send)") {
+            Ok(txn) => txn,
+            Err(e) => {
+                audit_log.record(
+                    intent,
+                    self.contract,
+                    call.calldata(),
+                    None,
+                    AuditOutcome::failed(&e),
+                );
+                return Err(e);
+            }
+        };
+        let tx_hash = *txn.tx_hash();
+        let receipt = txn
             .get_receipt()
             .await
-            .context("KailuaTreasury::resolve (get_receipt)")
+            .context("KailuaTreasury::resolve (get_receipt)");
+        let outcome = match &receipt {
+            Ok(receipt) => AuditOutcome::confirmed(receipt),
+            Err(e) => AuditOutcome::unconfirmed(e),
+        };
+        audit_log.record(
+            intent,
+            self.contract,
+            call.calldata(),
+            Some(tx_hash),
+            outcome,
+        );
+        receipt
     }
 
     pub fn has_parent(&self) -> bool {
         self.index != self.parent
     }
 
+    pub fn is_duplicate_of(&self, proposal: &Proposal) -> bool {
+        self.output_block_number == proposal.output_block_number
+            && self.divergence_point(proposal).is_none()
+    }
+
     pub fn divergence_point(&self, proposal: &Proposal) -> Option<usize> {
         // Check divergence in IO
         for i in 0..self.io_field_elements.len() {
@@ -430,6 +467,37 @@ impl Proposal {
         Ok(Bytes::from(proof.to_vec()))
     }
 
+    /// Verifies locally that the output at the position is committed to by the blob, mirroring
+    /// `KailuaGame.verifyIntermediateOutput` without the rpc round trip
+    pub fn verify_intermediate_output(
+        &self,
+        position: u64,
+        output: B256,
+        commitment: &Bytes,
+        proof: &Bytes,
+    ) -> anyhow::Result<bool> {
+        let (blob_hash, _) = self.io_blob_for(position);
+        if kzg_to_versioned_hash(commitment) != blob_hash {
+            return Ok(false);
+        }
+        verify_blob_fe_proof(
+            commitment,
+            (position % FIELD_ELEMENTS_PER_BLOB) as usize,
+            output,
+            proof,
+        )
+    }
+
+    pub fn validity_precondition_hash(&self, precondition_version: u8) -> B256 {
+        let output_count = self.io_field_elements.len() as u64 + 1;
+        validity_precondition_hash(
+            self.output_block_number - output_count,
+            output_count,
+            &self.io_blobs.iter().map(|(h, _)| *h).collect::<Vec<_>>(),
+            precondition_version,
+        )
+    }
+
     pub fn output_at(&self, position: u64) -> B256 {
         self.io_field_elements
             .get(position as usize)
@@ -438,18 +506,6 @@ impl Proposal {
     }
 
     pub fn create_sidecar(io_field_elements: &[B256]) -> anyhow::Result<BlobTransactionSidecar> {
-        let mut io_blobs = vec![];
-        loop {
-            let start = io_blobs.len() * FIELD_ELEMENTS_PER_BLOB as usize;
-            if start >= io_field_elements.len() {
-                break;
-            }
-            let end = (start + FIELD_ELEMENTS_PER_BLOB as usize).min(io_field_elements.len());
-            let io_bytes = io_field_elements[start..end].concat();
-            // Encode as blob sidecar
-            let blob = Blob::right_padding_from(io_bytes.as_slice());
-            io_blobs.push(blob);
-        }
-        blob_sidecar(io_blobs)
+        blob_sidecar(encode_intermediate_outputs(io_field_elements))
     }
 }
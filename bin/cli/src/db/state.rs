@@ -13,11 +13,37 @@
 // limitations under the License.
 
 use alloy::primitives::Address;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Debug, Default)]
 pub struct State {
     pub eliminations: HashMap<Address, u64>,
     pub next_factory_index: u64,
     pub canonical_tip_index: Option<u64>,
+    /// Proposals whose proposer is not attributed by the treasury
+    pub unattributed_proposals: HashSet<u64>,
+    /// Deadlines of unresolved matches keyed by (contender, proposal) index
+    pub match_deadlines: HashMap<(u64, u64), MatchDeadline>,
+    /// Receipts of matches proven on chain keyed by file name
+    pub proven_receipts: HashMap<String, ProvenReceipt>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MatchDeadline {
+    /// Timestamp after which the proposal's challenge window has elapsed
+    pub deadline: u64,
+    /// Highest alert level fired so far
+    pub alert_level: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProvenReceipt {
+    /// Index of the contender in the proven match
+    pub contender: u64,
+    /// Index of the proposal in the proven match
+    pub proposal: u64,
+    /// Timestamp at which the match was observed as proven
+    pub proven_at: u64,
+    /// Timestamp at which the match was observed as resolved
+    pub resolved_at: Option<u64>,
 }
@@ -12,14 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::audit::{AuditLog, AuditOutcome};
 use crate::stall::Stall;
 use alloy::network::Network;
 use alloy::primitives::{Address, U256};
 use alloy::providers::Provider;
 use alloy::transports::Transport;
+use anyhow::Context;
 use kailua_contracts::{KailuaTreasury::KailuaTreasuryInstance, *};
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone, Debug, Default)]
 pub struct Treasury {
@@ -28,6 +30,7 @@ pub struct Treasury {
     pub claim_proposer: HashMap<Address, Address>,
     pub participation_bond: U256,
     pub paid_bond: HashMap<Address, U256>,
+    pub released_proposals: HashSet<Address>,
 }
 
 impl Treasury {
@@ -42,6 +45,7 @@ impl Treasury {
             claim_proposer: Default::default(),
             participation_bond,
             paid_bond: Default::default(),
+            released_proposals: Default::default(),
         })
     }
 
@@ -96,19 +100,159 @@ impl Treasury {
         Ok(proposer)
     }
 
+    /// Returns true if the proposer had been eliminated as of the proposal at the given game index
+    pub async fn is_proposer_eliminated<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        proposer: Address,
+        game_index: u64,
+    ) -> anyhow::Result<bool> {
+        let round = self.fetch_elimination_round(provider, proposer).await?;
+        Ok(round != 0 && round <= game_index)
+    }
+
     pub async fn fetch_elimination_round<T: Transport + Clone, P: Provider<T, N>, N: Network>(
         &mut self,
         provider: P,
         address: Address,
     ) -> anyhow::Result<u64> {
         let instance = self.treasury_contract_instance(provider);
-        let round = match self.elimination_round.entry(address) {
-            Entry::Vacant(entry) => {
+        // eliminations are permanent, so only a zero round needs to be re-fetched
+        let round = match self.elimination_round.get(&address) {
+            Some(round) if *round != 0 => *round,
+            _ => {
                 let round = instance.eliminationRound(address).stall().await._0.to();
-                *entry.insert(round)
+                self.elimination_round.insert(address, round);
+                round
             }
-            Entry::Occupied(entry) => *entry.get(),
         };
         Ok(round)
     }
+
+    /// Returns the bond paid by the proposer if it can be withdrawn, or zero otherwise
+    pub async fn fetch_withdrawable_bond<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        proposer: Address,
+    ) -> anyhow::Result<U256> {
+        let paid_bond = self.fetch_balance(&provider, proposer).await?;
+        if paid_bond.is_zero() || self.fetch_elimination_round(&provider, proposer).await? != 0 {
+            return Ok(U256::ZERO);
+        }
+        // the bond remains locked until all proposals are resolved
+        let unresolved_proposals = self
+            .treasury_contract_instance(&provider)
+            .unresolvedProposals(proposer)
+            .stall()
+            .await
+            ._0;
+        if !unresolved_proposals.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        Ok(paid_bond)
+    }
+
+    /// Releases the bond lock of a proposal that can no longer be resolved, if any
+    pub async fn release_proposal<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        proposal: Address,
+        audit_log: &AuditLog,
+    ) -> anyhow::Result<Option<N::ReceiptResponse>> {
+        if self.released_proposals.contains(&proposal) {
+            return Ok(None);
+        }
+        let contract = self.treasury_contract_instance(provider);
+        if contract.isReleased(proposal).stall().await._0 {
+            self.released_proposals.insert(proposal);
+            return Ok(None);
+        }
+        // the proposal is released only once it is outlived by a sibling or orphaned by its parent
+        let call = contract.releaseProposal(proposal);
+        if call.call().await.is_err() {
+            return Ok(None);
+        }
+        let intent = format!("release proposal {proposal}");
+        let txn = match call
+            .send()
+            .await
+            .context("KailuaTreasury::releaseProposal (send)")
+        {
+            Ok(txn) => txn,
+            Err(e) => {
+                audit_log.record(
+                    intent,
+                    self.address,
+                    call.calldata(),
+                    None,
+                    AuditOutcome::failed(&e),
+                );
+                return Err(e);
+            }
+        };
+        let tx_hash = *txn.tx_hash();
+        let receipt = txn
+            .get_receipt()
+            .await
+            .context("KailuaTreasury::releaseProposal (get_receipt)");
+        let outcome = match &receipt {
+            Ok(receipt) => AuditOutcome::confirmed(receipt),
+            Err(e) => AuditOutcome::unconfirmed(e),
+        };
+        audit_log.record(
+            intent,
+            self.address,
+            call.calldata(),
+            Some(tx_hash),
+            outcome,
+        );
+        let receipt = receipt?;
+        self.released_proposals.insert(proposal);
+        Ok(Some(receipt))
+    }
+
+    pub async fn withdraw_bond<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &self,
+        provider: P,
+        recipient: Address,
+        audit_log: &AuditLog,
+    ) -> anyhow::Result<N::ReceiptResponse> {
+        let intent = format!("withdraw bond to {recipient}");
+        let contract = self.treasury_contract_instance(provider);
+        let call = contract.withdrawBond(recipient);
+        let txn = match call
+            .send()
+            .await
+            .context("KailuaTreasury::withdrawBond (send)")
+        {
+            Ok(txn) => txn,
+            Err(e) => {
+                audit_log.record(
+                    intent,
+                    self.address,
+                    call.calldata(),
+                    None,
+                    AuditOutcome::failed(&e),
+                );
+                return Err(e);
+            }
+        };
+        let tx_hash = *txn.tx_hash();
+        let receipt = txn
+            .get_receipt()
+            .await
+            .context("KailuaTreasury::withdrawBond (get_receipt)");
+        let outcome = match &receipt {
+            Ok(receipt) => AuditOutcome::confirmed(receipt),
+            Err(e) => AuditOutcome::unconfirmed(e),
+        };
+        audit_log.record(
+            intent,
+            self.address,
+            call.calldata(),
+            Some(tx_hash),
+            outcome,
+        );
+        receipt
+    }
 }
@@ -0,0 +1,149 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::{debug, info};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ExportArgs {
+    /// Directory to write unsigned transactions to instead of signing and sending them
+    /// ("-" for stdout)
+    #[clap(long, env)]
+    pub unsigned_tx_dir: Option<PathBuf>,
+    /// Address of the offline signer, which replaces the secret key when exporting transactions
+    #[clap(long, env, requires = "unsigned_tx_dir")]
+    pub unsigned_tx_sender: Option<Address>,
+}
+
+/// Returns the address and wallet of the agent, which holds no signer if only the address of
+/// the offline signer is known
+pub fn agent_wallet(
+    secret_key: Option<&str>,
+    export_args: &ExportArgs,
+) -> anyhow::Result<(Address, EthereumWallet)> {
+    match (secret_key, export_args.unsigned_tx_sender) {
+        (Some(secret_key), _) => {
+            let signer = LocalSigner::from_str(secret_key)?;
+            Ok((signer.address(), EthereumWallet::from(signer)))
+        }
+        (None, Some(sender)) => Ok((sender, EthereumWallet::default())),
+        (None, None) => bail!("Either a secret key or an unsigned transaction sender is required."),
+    }
+}
+
+#[derive(Serialize)]
+struct UnsignedTransaction<'a> {
+    intent: &'a str,
+    transaction: &'a TransactionRequest,
+}
+
+/// Writes fully populated unsigned transactions out for offline signing and broadcasting
+pub struct TxExporter {
+    dir: PathBuf,
+    sender: Address,
+    /// The nonce after the last exported transaction, as those are not yet seen by the node
+    next_nonce: u64,
+    /// Intents already exported, which are not exported again until the agent restarts
+    exported: HashSet<String>,
+}
+
+impl TxExporter {
+    pub fn new(args: &ExportArgs, sender: Address) -> anyhow::Result<Option<Self>> {
+        let Some(dir) = args.unsigned_tx_dir.clone() else {
+            return Ok(None);
+        };
+        if dir.as_os_str() != "-" {
+            std::fs::create_dir_all(&dir).context("create unsigned transaction directory")?;
+        }
+        info!(
+            "Exporting unsigned transactions from {sender} to {}.",
+            dir.display()
+        );
+        Ok(Some(Self {
+            dir,
+            sender,
+            next_nonce: 0,
+            exported: HashSet::new(),
+        }))
+    }
+
+    /// Populates the nonce, gas and fee fields of the transaction and writes it out unsigned
+    pub async fn export<T: Transport + Clone, P: Provider<T>>(
+        &mut self,
+        provider: P,
+        intent: &str,
+        request: TransactionRequest,
+    ) -> anyhow::Result<()> {
+        if self.exported.contains(intent) {
+            debug!("Skipping previously exported transaction to {intent}.");
+            return Ok(());
+        }
+        let mut request = request.with_from(self.sender);
+        let chain_id = provider.get_chain_id().await.context("get_chain_id")?;
+        let pending_nonce = provider
+            .get_transaction_count(self.sender)
+            .pending()
+            .await
+            .context("get_transaction_count")?;
+        let nonce = pending_nonce.max(self.next_nonce);
+        let gas_limit = provider
+            .estimate_gas(&request)
+            .await
+            .context("estimate_gas")?;
+        let fees = provider
+            .estimate_eip1559_fees(None)
+            .await
+            .context("estimate_eip1559_fees")?;
+        request = request
+            .with_chain_id(chain_id)
+            .with_nonce(nonce)
+            .with_gas_limit(gas_limit)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        if request.sidecar.is_some() {
+            request.max_fee_per_blob_gas = Some(
+                provider
+                    .get_blob_base_fee()
+                    .await
+                    .context("get_blob_base_fee")?,
+            );
+        }
+
+        let data = serde_json::to_string_pretty(&UnsignedTransaction {
+            intent,
+            transaction: &request,
+        })?;
+        if self.dir.as_os_str() == "-" {
+            println!("{data}");
+        } else {
+            let file_name = format!("{nonce:08}-{}.json", intent.replace([' ', '/'], "_"));
+            std::fs::write(self.dir.join(&file_name), data)
+                .context("write unsigned transaction file")?;
+            info!("Exported unsigned transaction to {intent} as {file_name}.");
+        }
+        self.next_nonce = nonce + 1;
+        self.exported.insert(intent.to_string());
+        Ok(())
+    }
+}
@@ -13,19 +13,21 @@
 // limitations under the License.
 
 use crate::providers::optimism::OpNodeProvider;
+use crate::safe::{exec_or_bundle_safe_txn, SafeBundle};
 use crate::stall::Stall;
 use crate::{BN254_CONTROL_ID, CONTROL_ROOT, KAILUA_GAME_TYPE, SET_BUILDER_ID};
 use alloy::network::{EthereumWallet, Network, TxSigner};
-use alloy::primitives::{Address, Bytes, Uint, U256};
+use alloy::primitives::{Address, Uint, U256};
 use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::LocalSigner;
-use alloy::sol_types::SolValue;
 use alloy::transports::Transport;
 use anyhow::{bail, Context};
 use kailua_build::KAILUA_FPVM_ID;
 use kailua_common::client::config_hash;
+use kailua_contracts::extra_data::TreasuryExtraData;
 use kailua_contracts::*;
-use kailua_host::fetch_rollup_config;
+use kailua_host::{fetch_rollup_config, HardforkArgs};
+use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
 use tracing::{error, info};
@@ -45,6 +47,9 @@ pub struct FastTrackArgs {
     #[clap(long, env)]
     pub eth_rpc_url: String,
 
+    #[clap(flatten)]
+    pub hardfork_args: HardforkArgs,
+
     /// The l2 block number to start sequencing since
     #[clap(long, env)]
     pub starting_block_number: u64,
@@ -69,8 +74,8 @@ pub struct FastTrackArgs {
     #[clap(long, env)]
     pub deployer_key: String,
     /// Secret key of L1 wallet that (indirectly) owns `DisputeGameFactory`
-    #[clap(long, env)]
-    pub owner_key: String,
+    #[clap(long, env, required_unless_present = "safe")]
+    pub owner_key: Option<String>,
     /// Secret key of L1 guardian wallet
     #[clap(long, env, required_if_eq("respect_kailua_proposals", "true"))]
     pub guardian_key: Option<String>,
@@ -78,6 +83,14 @@ pub struct FastTrackArgs {
     /// Whether to set Kailua as the OptimismPortal's respected game type
     #[clap(long, env)]
     pub respect_kailua_proposals: bool,
+
+    /// Whether to write the transactions of the `DisputeGameFactory` owner Safe to a bundle file
+    /// for its owners to sign instead of executing them with the owner key
+    #[clap(long, env)]
+    pub safe: bool,
+    /// File to write the Safe Transaction Builder bundle to
+    #[clap(long, env, default_value = "kailua-safe-bundle.json")]
+    pub safe_bundle_file: PathBuf,
 }
 
 pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
@@ -87,9 +100,14 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
-    let config = fetch_rollup_config(&args.op_node_url, &args.op_geth_url, None)
-        .await
-        .context("fetch_rollup_config")?;
+    let config = fetch_rollup_config(
+        &args.op_node_url,
+        &args.op_geth_url,
+        None,
+        &args.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
     let rollup_config_hash = config_hash(&config).expect("Configuration hash derivation error");
     info!("RollupConfigHash({})", hex::encode(rollup_config_hash));
 
@@ -100,8 +118,11 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
 
     // initialize owner wallet
     info!("Initializing owner wallet.");
-    let owner_signer = LocalSigner::from_str(&args.owner_key)?;
-    let owner_wallet = EthereumWallet::from(owner_signer);
+    let owner_wallet = match &args.owner_key {
+        Some(owner_key) => EthereumWallet::from(LocalSigner::from_str(owner_key)?),
+        // the owner provider is only used for reads in safe mode
+        None => EthereumWallet::default(),
+    };
     let owner_provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(&owner_wallet)
@@ -118,14 +139,32 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
     info!("Safe({:?})", factory_owner_safe.address());
     let safe_owners = factory_owner_safe.getOwners().stall().await._0;
     info!("Safe::owners({:?})", &safe_owners);
-    let owner_address = owner_wallet.default_signer().address();
-    if safe_owners.first().unwrap() != &owner_address {
-        error!("Incorrect owner key.");
-        exit(2);
-    } else if safe_owners.len() != 1 {
-        error!("Expected exactly one owner of safe account.");
-        exit(1);
-    }
+    let (owner_address, mut safe_bundle) = if args.safe {
+        let chain_id = eth_rpc_provider.get_chain_id().await?;
+        let threshold = factory_owner_safe.getThreshold().stall().await._0;
+        info!(
+            "Building Safe bundle requiring {threshold} of {} owner signatures.",
+            safe_owners.len()
+        );
+        (
+            factory_owner_address,
+            Some(SafeBundle::new(
+                "Kailua fast-track upgrade",
+                chain_id,
+                factory_owner_address,
+            )),
+        )
+    } else {
+        let owner_address = owner_wallet.default_signer().address();
+        if safe_owners.first().unwrap() != &owner_address {
+            error!("Incorrect owner key.");
+            exit(2);
+        } else if safe_owners.len() != 1 {
+            error!("Expected exactly one owner of safe account.");
+            exit(1);
+        }
+        (owner_address, None)
+    };
 
     // initialize deployment wallet
     info!("Initializing deployer wallet.");
@@ -138,9 +177,14 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
 
     // Deploy or reuse existing RISCZeroVerifier contracts
     let verifier_contract_address = match &args.verifier_contract {
-        None => deploy_verifier(&deployer_provider, &owner_provider, owner_address)
-            .await
-            .context("deploy_verifier")?,
+        None => deploy_verifier(
+            &deployer_provider,
+            &owner_provider,
+            owner_address,
+            safe_bundle.as_mut(),
+        )
+        .await
+        .context("deploy_verifier")?,
         Some(address) => Address::from_str(address)?,
     };
 
@@ -159,126 +203,162 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
     .context("KailuaTreasury implementation contract deployment error")?;
     info!("{:?}", &kailua_treasury_implementation);
 
+    // Deploy KailuaGame contract
+    info!("Deploying KailuaGame contract to L1 rpc.");
+    let kailua_game_contract = KailuaGame::deploy(
+        &deployer_provider,
+        *kailua_treasury_implementation.address(),
+        verifier_contract_address,
+        bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID).into(),
+        rollup_config_hash.into(),
+        Uint::from(args.proposal_block_span),
+        KAILUA_GAME_TYPE,
+        dgf_address,
+        U256::from(config.genesis.l2_time),
+        U256::from(config.block_time),
+        U256::from(args.proposal_time_gap),
+        args.challenge_timeout,
+    )
+    .await
+    .context("KailuaGame contract deployment error")?;
+    info!("{:?}", &kailua_game_contract);
+
     // Update dispute factory implementation to KailuaTreasury
     info!("Setting KailuaTreasury initialization bond value in DisputeGameFactory to zero.");
-    crate::exec_safe_txn(
+    exec_or_bundle_safe_txn(
         dispute_game_factory.setInitBond(KAILUA_GAME_TYPE, U256::ZERO),
         &factory_owner_safe,
         owner_address,
+        safe_bundle.as_mut(),
+        "setInitBond 0 wei",
     )
-    .await
-    .context("setInitBond 0 wei")?;
-    assert_eq!(
-        dispute_game_factory
-            .initBonds(KAILUA_GAME_TYPE)
-            .stall()
-            .await
-            .bond_,
-        U256::ZERO
-    );
+    .await?;
+    if safe_bundle.is_none() {
+        assert_eq!(
+            dispute_game_factory
+                .initBonds(KAILUA_GAME_TYPE)
+                .stall()
+                .await
+                .bond_,
+            U256::ZERO
+        );
+    }
     info!("Setting KailuaTreasury participation bond value to 1 wei.");
     let bond_value = U256::from(1);
-    crate::exec_safe_txn(
+    exec_or_bundle_safe_txn(
         kailua_treasury_implementation.setParticipationBond(bond_value),
         &factory_owner_safe,
         owner_address,
+        safe_bundle.as_mut(),
+        "setParticipationBond 1 wei",
     )
-    .await
-    .context("setParticipationBond 1 wei")?;
-    assert_eq!(
-        kailua_treasury_implementation
-            .participationBond()
-            .stall()
-            .await
-            ._0,
-        bond_value
-    );
+    .await?;
+    if safe_bundle.is_none() {
+        assert_eq!(
+            kailua_treasury_implementation
+                .participationBond()
+                .stall()
+                .await
+                ._0,
+            bond_value
+        );
+    }
 
     info!("Setting KailuaTreasury implementation address in DisputeGameFactory.");
-    crate::exec_safe_txn(
+    exec_or_bundle_safe_txn(
         dispute_game_factory
             .setImplementation(KAILUA_GAME_TYPE, *kailua_treasury_implementation.address()),
         &factory_owner_safe,
         owner_address,
+        safe_bundle.as_mut(),
+        "setImplementation KailuaTreasury",
     )
-    .await
-    .context("setImplementation KailuaTreasury")?;
-    assert_eq!(
-        dispute_game_factory
-            .gameImpls(KAILUA_GAME_TYPE)
-            .stall()
-            .await
-            .impl_,
-        *kailua_treasury_implementation.address()
-    );
+    .await?;
+    if safe_bundle.is_none() {
+        assert_eq!(
+            dispute_game_factory
+                .gameImpls(KAILUA_GAME_TYPE)
+                .stall()
+                .await
+                .impl_,
+            *kailua_treasury_implementation.address()
+        );
+    }
 
     // Create new treasury instance from target block number
     let root_claim = op_node_provider
         .output_at_block(args.starting_block_number)
         .await?;
-    let extra_data = Bytes::from(args.starting_block_number.abi_encode_packed());
+    let extra_data = TreasuryExtraData::new(args.starting_block_number).encode();
     info!(
         "Creating new KailuaTreasury game instance from {} ({}).",
         args.starting_block_number, root_claim
     );
-    crate::exec_safe_txn(
+    // the factory deploys game instances using CREATE, so the address of the instance created
+    // by the bundle is known in advance if no other game is created before it executes
+    let factory_nonce = eth_rpc_provider
+        .get_transaction_count(dgf_address)
+        .await
+        .context("get_transaction_count")?;
+    exec_or_bundle_safe_txn(
         dispute_game_factory.create(KAILUA_GAME_TYPE, root_claim, extra_data.clone()),
         &factory_owner_safe,
         owner_address,
+        safe_bundle.as_mut(),
+        "create KailuaTreasury",
     )
-    .await
-    .context("create KailuaTreasury")?;
-    let kailua_treasury_instance_address = dispute_game_factory
-        .games(KAILUA_GAME_TYPE, root_claim, extra_data)
-        .stall()
-        .await
-        .proxy_;
+    .await?;
+    let kailua_treasury_instance_address = if safe_bundle.is_some() {
+        dgf_address.create(factory_nonce)
+    } else {
+        dispute_game_factory
+            .games(KAILUA_GAME_TYPE, root_claim, extra_data)
+            .stall()
+            .await
+            .proxy_
+    };
     let kailua_treasury_instance =
         KailuaTreasury::new(kailua_treasury_instance_address, &owner_provider);
     info!("{:?}", &kailua_treasury_instance);
-    let status = kailua_treasury_instance.status().stall().await._0;
+    let status = if safe_bundle.is_some() {
+        0
+    } else {
+        kailua_treasury_instance.status().stall().await._0
+    };
     if status == 0 {
         info!("Resolving KailuaTreasury instance");
-        crate::exec_safe_txn(
+        exec_or_bundle_safe_txn(
             kailua_treasury_instance.resolve(),
             &factory_owner_safe,
             owner_address,
+            safe_bundle.as_mut(),
+            "resolve KailuaTreasury",
         )
-        .await
-        .context("resolve KailuaTreasury")?;
+        .await?;
     } else {
         info!("Game instance is not ongoing ({status})");
     }
 
-    // Deploy KailuaGame contract
-    info!("Deploying KailuaGame contract to L1 rpc.");
-    let kailua_game_contract = KailuaGame::deploy(
-        &deployer_provider,
-        *kailua_treasury_implementation.address(),
-        verifier_contract_address,
-        bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID).into(),
-        rollup_config_hash.into(),
-        Uint::from(args.proposal_block_span),
-        KAILUA_GAME_TYPE,
-        dgf_address,
-        U256::from(config.genesis.l2_time),
-        U256::from(config.block_time),
-        U256::from(args.proposal_time_gap),
-        args.challenge_timeout,
-    )
-    .await
-    .context("KailuaGame contract deployment error")?;
-    info!("{:?}", &kailua_game_contract);
-
     // Update implementation to KailuaGame
     info!("Setting KailuaGame implementation address in DisputeGameFactory.");
-    crate::exec_safe_txn(
+    exec_or_bundle_safe_txn(
         dispute_game_factory.setImplementation(KAILUA_GAME_TYPE, *kailua_game_contract.address()),
         &factory_owner_safe,
         owner_address,
+        safe_bundle.as_mut(),
+        "setImplementation KailuaGame",
     )
-    .await
-    .context("setImplementation KailuaGame")?;
+    .await?;
+
+    // Write the bundle for the safe owners to sign
+    if let Some(safe_bundle) = &safe_bundle {
+        safe_bundle.save(&args.safe_bundle_file)?;
+        info!(
+            "Wrote {} Safe transactions to {}.",
+            safe_bundle.transactions.len(),
+            args.safe_bundle_file.display()
+        );
+    }
 
     // Update the respectedGameType as the guardian
     if args.respect_kailua_proposals {
@@ -307,7 +387,11 @@ pub async fn fast_track(args: FastTrackArgs) -> anyhow::Result<()> {
             .await?;
     }
 
-    info!("Kailua upgrade complete.");
+    if safe_bundle.is_some() {
+        info!("Kailua upgrade ready for execution through Safe {factory_owner_address}.");
+    } else {
+        info!("Kailua upgrade complete.");
+    }
     Ok(())
 }
 
@@ -320,6 +404,7 @@ pub async fn deploy_verifier<
     deployer_provider: P1,
     owner_provider: P2,
     owner_address: Address,
+    mut safe_bundle: Option<&mut SafeBundle>,
 ) -> anyhow::Result<Address> {
     // Deploy verifier router contract
     info!("Deploying RiscZeroVerifierRouter contract to L1 under ownership of {owner_address}.");
@@ -338,14 +423,20 @@ pub async fn deploy_verifier<
     info!("{:?}", &groth16_verifier_contract);
     let selector = groth16_verifier_contract.SELECTOR().stall().await._0;
     info!("Adding RiscZeroGroth16Verifier contract to RiscZeroVerifierRouter.");
-    verifier_contract
-        .addVerifier(selector, *groth16_verifier_contract.address())
-        .send()
-        .await
-        .context("addVerifier RiscZeroGroth16Verifier (send)")?
-        .get_receipt()
-        .await
-        .context("addVerifier RiscZeroGroth16Verifier (get_receipt)")?;
+    let add_verifier =
+        verifier_contract.addVerifier(selector, *groth16_verifier_contract.address());
+    match safe_bundle.as_deref_mut() {
+        Some(bundle) => bundle.push("addVerifier RiscZeroGroth16Verifier", add_verifier),
+        None => {
+            add_verifier
+                .send()
+                .await
+                .context("addVerifier RiscZeroGroth16Verifier (send)")?
+                .get_receipt()
+                .await
+                .context("addVerifier RiscZeroGroth16Verifier (get_receipt)")?;
+        }
+    }
 
     // Deploy RiscZeroSetVerifier contract
     info!("Deploying RiscZeroSetVerifier contract to L1.");
@@ -360,14 +451,19 @@ pub async fn deploy_verifier<
     info!("{:?}", &set_verifier_contract);
     let selector = set_verifier_contract.SELECTOR().stall().await._0;
     info!("Adding RiscZeroSetVerifier contract to RiscZeroVerifierRouter.");
-    verifier_contract
-        .addVerifier(selector, *set_verifier_contract.address())
-        .send()
-        .await
-        .context("addVerifier RiscZeroSetVerifier (send)")?
-        .get_receipt()
-        .await
-        .context("addVerifier RiscZeroSetVerifier (get_receipt)")?;
+    let add_verifier = verifier_contract.addVerifier(selector, *set_verifier_contract.address());
+    match safe_bundle.as_deref_mut() {
+        Some(bundle) => bundle.push("addVerifier RiscZeroSetVerifier", add_verifier),
+        None => {
+            add_verifier
+                .send()
+                .await
+                .context("addVerifier RiscZeroSetVerifier (send)")?
+                .get_receipt()
+                .await
+                .context("addVerifier RiscZeroSetVerifier (get_receipt)")?;
+        }
+    }
 
     // Deploy mock verifier
     #[cfg(feature = "devnet")]
@@ -380,14 +476,20 @@ pub async fn deploy_verifier<
                 .context("RiscZeroMockVerifier contract deployment error")?;
         tracing::warn!("{:?}", &mock_verifier_contract);
         tracing::warn!("Adding RiscZeroMockVerifier contract to RiscZeroVerifierRouter.");
-        verifier_contract
-            .addVerifier([0u8; 4].into(), *mock_verifier_contract.address())
-            .send()
-            .await
-            .context("addVerifier RiscZeroMockVerifier (send)")?
-            .get_receipt()
-            .await
-            .context("addVerifier RiscZeroMockVerifier (get_receipt)")?;
+        let add_verifier =
+            verifier_contract.addVerifier([0u8; 4].into(), *mock_verifier_contract.address());
+        match safe_bundle.as_deref_mut() {
+            Some(bundle) => bundle.push("addVerifier RiscZeroMockVerifier", add_verifier),
+            None => {
+                add_verifier
+                    .send()
+                    .await
+                    .context("addVerifier RiscZeroMockVerifier (send)")?
+                    .get_receipt()
+                    .await
+                    .context("addVerifier RiscZeroMockVerifier (get_receipt)")?;
+            }
+        }
     }
 
     Ok(verifier_contract_address)
@@ -18,17 +18,17 @@ use crate::providers::optimism::OpNodeProvider;
 use crate::stall::Stall;
 use crate::KAILUA_GAME_TYPE;
 use alloy::network::EthereumWallet;
-use alloy::primitives::{Bytes, B256, U256};
+use alloy::primitives::{keccak256, B256, U256};
 use alloy::providers::ProviderBuilder;
-use alloy::signers::local::LocalSigner;
-use alloy::sol_types::SolValue;
-use anyhow::Context;
+use alloy::signers::local::{LocalSigner, PrivateKeySigner};
+use anyhow::{ensure, Context};
 use kailua_common::blobs::hash_to_fe;
 use kailua_common::client::config_hash;
+use kailua_contracts::extra_data::GameExtraData;
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
 use std::str::FromStr;
-use tracing::{error, info};
+use tracing::info;
 
 #[derive(clap::Args, Debug, Clone)]
 pub struct FaultArgs {
@@ -44,19 +44,118 @@ pub struct FaultArgs {
     pub fault_parent: u64,
 }
 
+#[derive(clap::Args, Debug, Clone)]
+pub struct BlobCorruptionArgs {
+    #[clap(flatten)]
+    pub propose_args: ProposeArgs,
+
+    /// Offsets of the intermediate blocks within the proposal whose field elements to corrupt
+    #[clap(long, value_delimiter = ',', required = true)]
+    pub corrupt_offsets: Vec<u64>,
+
+    /// Index of the parent of the corrupted proposal
+    #[clap(long)]
+    pub corrupt_parent: u64,
+
+    /// The kind of field element to publish at the corrupted offsets
+    #[clap(long, value_enum, default_value_t = Corruption::Unencodable)]
+    pub corruption: Corruption,
+}
+
+/// A field element published in place of the encoding of an output root
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// A field element that no output root encodes to
+    Unencodable,
+    /// The encoding of an arbitrary hash
+    Random,
+    /// The zero field element
+    Zero,
+}
+
+impl Corruption {
+    /// Returns the field element to publish at the given offset instead of the honest one
+    pub fn field_element(self, seed: U256, offset: u64) -> B256 {
+        let hash = keccak256([seed.as_le_slice(), &offset.to_be_bytes()].concat());
+        match self {
+            Self::Unencodable => {
+                // Set the bits cleared by the encoding while staying below the BLS modulus
+                let mut fe = hash;
+                fe.0[0] = 0x40 | (fe.0[0] & 0x1f);
+                fe
+            }
+            Self::Random => hash_to_fe(hash),
+            Self::Zero => B256::ZERO,
+        }
+    }
+}
+
+/// The deviation of a test proposal from the canonical chain
+pub(crate) enum Deviation {
+    /// Proposes an invalid output root at the offset
+    Output { offset: u64 },
+    /// Publishes corrupted field elements at the intermediate offsets
+    FieldElements {
+        offsets: Vec<u64>,
+        corruption: Corruption,
+    },
+}
+
+/// Parses the signer of test proposals
+fn tester_signer(propose_args: &ProposeArgs) -> anyhow::Result<PrivateKeySigner> {
+    Ok(LocalSigner::from_str(
+        propose_args
+            .proposer_key
+            .as_deref()
+            .context("proposer key required")?,
+    )?)
+}
+
 pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
+    submit_deviating_proposal(
+        &args.propose_args,
+        tester_signer(&args.propose_args)?,
+        args.fault_parent,
+        Deviation::Output {
+            offset: args.fault_offset,
+        },
+    )
+    .await
+}
+
+pub async fn corrupt_blob(args: BlobCorruptionArgs) -> anyhow::Result<()> {
+    submit_deviating_proposal(
+        &args.propose_args,
+        tester_signer(&args.propose_args)?,
+        args.corrupt_parent,
+        Deviation::FieldElements {
+            offsets: args.corrupt_offsets,
+            corruption: args.corruption,
+        },
+    )
+    .await
+}
+
+/// Submits a proposal deviating from the canonical chain as the child of the given parent
+pub(crate) async fn submit_deviating_proposal(
+    propose_args: &ProposeArgs,
+    tester_signer: PrivateKeySigner,
+    parent_index: u64,
+    deviation: Deviation,
+) -> anyhow::Result<()> {
     let op_node_provider = OpNodeProvider(
-        ProviderBuilder::new().on_http(args.propose_args.core.op_node_url.as_str().try_into()?),
+        ProviderBuilder::new().on_http(propose_args.core.op_node_url.as_str().try_into()?),
     );
     let eth_rpc_provider =
-        ProviderBuilder::new().on_http(args.propose_args.core.eth_rpc_url.as_str().try_into()?);
+        ProviderBuilder::new().on_http(propose_args.core.eth_rpc_url.as_str().try_into()?);
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
     let config = fetch_rollup_config(
-        &args.propose_args.core.op_node_url,
-        &args.propose_args.core.op_geth_url,
+        &propose_args.core.op_node_url,
+        &propose_args.core.op_geth_url,
         None,
+        &propose_args.core.hardfork_args.overrides(),
     )
     .await
     .context("fetch_rollup_config")?;
@@ -68,13 +167,12 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
     let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
 
     // init l1 stuff
-    let tester_signer = LocalSigner::from_str(&args.propose_args.proposer_key)?;
     let tester_address = tester_signer.address();
     let tester_wallet = EthereumWallet::from(tester_signer);
     let tester_provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(tester_wallet)
-        .on_http(args.propose_args.core.eth_rpc_url.as_str().try_into()?);
+        .on_http(propose_args.core.eth_rpc_url.as_str().try_into()?);
 
     let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &tester_provider);
     let kailua_game_implementation = kailua_contracts::KailuaGame::new(
@@ -103,7 +201,7 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
     // get proposal parent
     let games_count = dispute_game_factory.gameCount().stall().await.gameCount_;
     let parent_game_address = dispute_game_factory
-        .gameAtIndex(U256::from(args.fault_parent))
+        .gameAtIndex(U256::from(parent_index))
         .stall()
         .await
         .proxy_;
@@ -115,11 +213,24 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
         .l2BlockNumber_
         .to();
     // Prepare faulty proposal
-    let faulty_block_number = parent_block_number + args.fault_offset;
-    let faulty_root_claim = B256::from(games_count.to_be_bytes());
+    let faulty_block_number = match &deviation {
+        Deviation::Output { offset } => Some(parent_block_number + offset),
+        Deviation::FieldElements { offsets, .. } => {
+            for offset in offsets {
+                ensure!(
+                    (1..proposal_block_count).contains(offset),
+                    "Corrupted offset {offset} is not an intermediate block (1..{proposal_block_count})."
+                );
+            }
+            None
+        }
+    };
+    // Distinguish the claims of testers proposing concurrently under the same parent
+    let faulty_root_claim =
+        keccak256([games_count.as_le_slice(), tester_address.as_slice()].concat());
     // Prepare remainder of proposal
     let proposed_block_number = parent_block_number + proposal_block_count;
-    let proposed_output_root = if proposed_block_number == faulty_block_number {
+    let proposed_output_root = if Some(proposed_block_number) == faulty_block_number {
         faulty_root_claim
     } else {
         op_node_provider
@@ -131,32 +242,33 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
     let mut io_field_elements = vec![];
     let first_io_number = parent_block_number + 1;
     for i in first_io_number..proposed_block_number {
-        let output = if i == faulty_block_number {
+        let output = if Some(i) == faulty_block_number {
             faulty_root_claim
         } else {
             op_node_provider.output_at_block(i).await?
         };
         io_field_elements.push(hash_to_fe(output));
     }
+    // Corrupt intermediate field elements
+    if let Deviation::FieldElements {
+        offsets,
+        corruption,
+    } = &deviation
+    {
+        for offset in offsets {
+            let fe = corruption.field_element(games_count, *offset);
+            info!("Corrupting field element at offset {offset} with {corruption:?} value {fe}.");
+            io_field_elements[*offset as usize - 1] = fe;
+        }
+    }
     let sidecar = Proposal::create_sidecar(&io_field_elements)?;
 
     // Calculate required duplication counter
-    let mut dupe_counter = 0u64;
+    let mut extra_data = GameExtraData::new(proposed_block_number, parent_index);
     let extra_data = loop {
-        // compute extra data with block number, parent factory index, and blob hash
-        let extra_data = [
-            proposed_block_number.abi_encode_packed(),
-            args.fault_parent.abi_encode_packed(),
-            dupe_counter.abi_encode_packed(),
-        ]
-        .concat();
         // check if proposal exists
         let dupe_game_address = dispute_game_factory
-            .games(
-                KAILUA_GAME_TYPE,
-                proposed_output_root,
-                Bytes::from(extra_data.clone()),
-            )
+            .games(KAILUA_GAME_TYPE, proposed_output_root, extra_data.encode())
             .stall()
             .await
             .proxy_;
@@ -165,7 +277,7 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
             break extra_data;
         }
         // increment counter
-        dupe_counter += 1;
+        extra_data = extra_data.next_duplicate();
     };
 
     let bond_value = kailua_treasury_instance
@@ -180,25 +292,16 @@ pub async fn fault(args: FaultArgs) -> anyhow::Result<()> {
         ._0;
     let owed_collateral = bond_value.saturating_sub(paid_in);
 
-    match kailua_treasury_instance
-        .propose(proposed_output_root, Bytes::from(extra_data))
+    let receipt = kailua_treasury_instance
+        .propose(proposed_output_root, extra_data.encode())
         .value(owed_collateral)
         .sidecar(sidecar)
         .send()
         .await
-        .context("propose (send)")
-    {
-        Ok(txn) => match txn.get_receipt().await.context("propose (get_receipt)") {
-            Ok(receipt) => {
-                info!("Faulty proposal submitted at index {games_count}: {receipt:?}")
-            }
-            Err(e) => {
-                error!("Failed to confirm faulty proposal txn: {e:?}");
-            }
-        },
-        Err(e) => {
-            error!("Failed to send faulty proposal txn: {e:?}");
-        }
-    }
+        .context("propose (send)")?
+        .get_receipt()
+        .await
+        .context("propose (get_receipt)")?;
+    info!("Deviating proposal submitted by {tester_address}: {receipt:?}");
     Ok(())
 }
@@ -0,0 +1,114 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::utils::format_ether;
+use alloy::primitives::U256;
+use metrics::counter;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// The purpose of a submitted transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TxCategory {
+    Propose,
+    Prove,
+    Resolve,
+    Withdraw,
+}
+
+impl Display for TxCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxCategory::Propose => write!(f, "propose"),
+            TxCategory::Prove => write!(f, "prove"),
+            TxCategory::Resolve => write!(f, "resolve"),
+            TxCategory::Withdraw => write!(f, "withdraw"),
+        }
+    }
+}
+
+/// Cumulative gas spent by transactions of a single category
+#[derive(Clone, Debug, Default)]
+pub struct GasSpend {
+    pub transactions: u64,
+    pub gas_used: u128,
+    pub blob_gas_used: u128,
+    /// Total fees paid for execution and blob gas in wei
+    pub fees: U256,
+}
+
+/// Tracks the gas spent by confirmed transactions and periodically reports a summary
+#[derive(Clone, Debug)]
+pub struct GasAccountant {
+    pub spend: BTreeMap<TxCategory, GasSpend>,
+    report_interval: Duration,
+    last_report: Instant,
+}
+
+impl GasAccountant {
+    pub fn new(report_interval_secs: u64) -> Self {
+        Self {
+            spend: Default::default(),
+            report_interval: Duration::from_secs(report_interval_secs),
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn record<R: ReceiptResponse>(&mut self, category: TxCategory, receipt: &R) {
+        let gas_used = receipt.gas_used() as u128;
+        let blob_gas_used = receipt.blob_gas_used().unwrap_or_default() as u128;
+        let fees = U256::from(gas_used * receipt.effective_gas_price())
+            + U256::from(blob_gas_used * receipt.blob_gas_price().unwrap_or_default());
+        let spend = self.spend.entry(category).or_default();
+        spend.transactions += 1;
+        spend.gas_used += gas_used;
+        spend.blob_gas_used += blob_gas_used;
+        spend.fees += fees;
+        // export cumulative counters
+        let category = category.to_string();
+        counter!("kailua_transactions_total", "category" => category.clone()).increment(1);
+        counter!("kailua_gas_used_total", "category" => category.clone())
+            .increment(gas_used as u64);
+        counter!("kailua_blob_gas_used_total", "category" => category.clone())
+            .increment(blob_gas_used as u64);
+        counter!("kailua_fees_gwei_total", "category" => category)
+            .increment((fees / U256::from(1_000_000_000u64)).saturating_to());
+    }
+
+    /// Logs the summary if the report interval has elapsed since the last one
+    pub fn report_if_due(&mut self) {
+        if self.last_report.elapsed() < self.report_interval {
+            return;
+        }
+        self.last_report = Instant::now();
+        if self.spend.is_empty() {
+            return;
+        }
+        let mut total_fees = U256::ZERO;
+        for (category, spend) in &self.spend {
+            info!(
+                "Gas spent on {category}: {} transactions, {} gas, {} blob gas, {} ETH in fees.",
+                spend.transactions,
+                spend.gas_used,
+                spend.blob_gas_used,
+                format_ether(spend.fees)
+            );
+            total_fees += spend.fees;
+        }
+        info!("Total fees spent: {} ETH.", format_ether(total_fees));
+    }
+}
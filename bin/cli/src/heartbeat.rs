@@ -0,0 +1,97 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::KailuaDB;
+use anyhow::Context;
+use serde::Serialize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct HeartbeatArgs {
+    /// Url to periodically post a heartbeat to, such as a healthchecks.io check
+    #[clap(long, env)]
+    pub heartbeat_url: Option<String>,
+    /// Seconds between heartbeats
+    #[clap(long, env, default_value_t = 60)]
+    pub heartbeat_interval: u64,
+}
+
+/// The payload of each heartbeat
+#[derive(Clone, Debug, Serialize)]
+pub struct HeartbeatReport {
+    pub timestamp: u64,
+    pub next_factory_index: u64,
+    pub canonical_block_number: Option<u64>,
+    /// Number of matches awaiting a proof
+    pub proof_backlog: usize,
+    pub last_action: Option<String>,
+}
+
+/// Posts heartbeats from the main loop, so that a wedged loop stops reporting
+pub struct Heartbeat {
+    client: reqwest::Client,
+    url: Option<String>,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    /// Description of the most recent action taken by the agent
+    pub last_action: Option<String>,
+}
+
+impl Heartbeat {
+    pub fn new(args: &HeartbeatArgs) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: args.heartbeat_url.clone(),
+            interval: Duration::from_secs(args.heartbeat_interval),
+            last_sent: None,
+            last_action: None,
+        }
+    }
+
+    /// Posts a heartbeat if the interval has elapsed since the last one
+    pub async fn beat_if_due(&mut self, kailua_db: &KailuaDB) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        if self.last_sent.is_some_and(|t| t.elapsed() < self.interval) {
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+        let report = HeartbeatReport {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            next_factory_index: kailua_db.state.next_factory_index,
+            canonical_block_number: kailua_db.canonical_tip_height(),
+            proof_backlog: kailua_db.state.match_deadlines.len(),
+            last_action: self.last_action.clone(),
+        };
+        // a slow endpoint must not stall the caller beyond one interval
+        let result = self
+            .client
+            .post(url)
+            .timeout(self.interval)
+            .json(&report)
+            .send()
+            .await
+            .context("send heartbeat")
+            .and_then(|r| r.error_for_status().context("heartbeat status"));
+        match result {
+            Ok(_) => debug!("Heartbeat sent: {report:?}"),
+            Err(e) => warn!("Failed to send heartbeat: {e:?}"),
+        }
+    }
+}
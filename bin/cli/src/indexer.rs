@@ -0,0 +1,526 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cadence::PollCadence;
+use crate::db::proposal::Proposal;
+use crate::db::KailuaDB;
+use crate::layout::chain_data_dir;
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::proxy::ProxiedContract;
+use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
+use alloy::eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
+use alloy::network::Network;
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::{Filter, Log};
+use alloy::sol_types::SolEvent;
+use alloy::transports::Transport;
+use anyhow::{anyhow, Context};
+use kailua_common::client::config_hash;
+use kailua_contracts::events::KailuaEvent;
+use kailua_contracts::{IDisputeGameFactory::IDisputeGameFactoryInstance, *};
+use kailua_host::fetch_rollup_config;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::sleep;
+use tokio_postgres::NoTls;
+use tracing::{error, info, warn};
+
+/// Tables populated by the indexer, see the book for their documentation
+pub const INDEXER_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS kailua_proposals (
+    factory_index BIGINT PRIMARY KEY,
+    contract BYTEA NOT NULL,
+    parent_index BIGINT NOT NULL,
+    proposer BYTEA NOT NULL,
+    created_at BIGINT NOT NULL,
+    l2_block_number BIGINT NOT NULL,
+    output_root BYTEA NOT NULL,
+    l1_head BYTEA NOT NULL,
+    canonical BOOLEAN,
+    duplicate_of BIGINT
+);
+CREATE TABLE IF NOT EXISTS kailua_matches (
+    tournament_index BIGINT NOT NULL,
+    contender_index BIGINT NOT NULL,
+    proposal_index BIGINT NOT NULL,
+    u_index BIGINT NOT NULL,
+    v_index BIGINT NOT NULL,
+    PRIMARY KEY (contender_index, proposal_index)
+);
+CREATE TABLE IF NOT EXISTS kailua_divergences (
+    factory_index BIGINT PRIMARY KEY,
+    tournament_index BIGINT NOT NULL,
+    proposer BYTEA NOT NULL,
+    contender_index BIGINT,
+    fault TEXT NOT NULL,
+    divergence_index BIGINT,
+    l2_block_number BIGINT,
+    blob_index BIGINT,
+    field_element_index BIGINT,
+    proof_status SMALLINT
+);
+CREATE TABLE IF NOT EXISTS kailua_proofs (
+    tournament_index BIGINT NOT NULL,
+    u_index BIGINT,
+    v_index BIGINT NOT NULL,
+    status SMALLINT,
+    l1_block_number BIGINT NOT NULL,
+    l1_tx_hash BYTEA NOT NULL,
+    l1_log_index BIGINT NOT NULL,
+    PRIMARY KEY (l1_tx_hash, l1_log_index)
+);
+CREATE TABLE IF NOT EXISTS kailua_resolutions (
+    factory_index BIGINT PRIMARY KEY,
+    status SMALLINT NOT NULL,
+    l1_block_number BIGINT NOT NULL,
+    l1_tx_hash BYTEA NOT NULL
+);
+CREATE TABLE IF NOT EXISTS kailua_bond_updates (
+    amount TEXT NOT NULL,
+    l1_block_number BIGINT NOT NULL,
+    l1_tx_hash BYTEA NOT NULL,
+    l1_log_index BIGINT NOT NULL,
+    PRIMARY KEY (l1_tx_hash, l1_log_index)
+);
+CREATE TABLE IF NOT EXISTS kailua_bond_withdrawals (
+    proposer BYTEA NOT NULL,
+    recipient BYTEA NOT NULL,
+    amount TEXT NOT NULL,
+    l1_block_number BIGINT NOT NULL,
+    l1_tx_hash BYTEA NOT NULL,
+    l1_log_index BIGINT NOT NULL,
+    PRIMARY KEY (l1_tx_hash, l1_log_index)
+);
+CREATE TABLE IF NOT EXISTS kailua_indexer_cursor (
+    id SMALLINT PRIMARY KEY,
+    next_l1_block BIGINT NOT NULL
+);
+"#;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct IndexerArgs {
+    #[clap(flatten)]
+    pub core: CoreArgs,
+
+    /// Postgres connection string of the database to write into
+    #[clap(long, env)]
+    pub postgres_url: String,
+
+    /// L1 block from which to start scanning for events on first launch
+    #[clap(long, env, default_value_t = 0)]
+    pub index_from_block: u64,
+    /// Maximum number of L1 blocks to query for events at once
+    #[clap(long, env, default_value_t = 1000)]
+    pub log_batch_size: u64,
+}
+
+pub async fn index(args: IndexerArgs, data_dir: PathBuf) -> anyhow::Result<()> {
+    // initialize database connection
+    info!("Connecting to postgres.");
+    let (client, connection) = tokio_postgres::connect(&args.postgres_url, NoTls)
+        .await
+        .context("tokio_postgres::connect")?;
+    tokio::spawn(async move {
+        if let Err(err) = connection.await {
+            error!("Postgres connection error: {err:?}");
+        }
+    });
+    client
+        .batch_execute(INDEXER_SCHEMA)
+        .await
+        .context("create schema")?;
+    let mut next_l1_block = match client
+        .query_opt(
+            "SELECT next_l1_block FROM kailua_indexer_cursor WHERE id = 0",
+            &[],
+        )
+        .await?
+    {
+        Some(row) => row.get::<_, i64>(0) as u64,
+        None => args.index_from_block,
+    };
+
+    // initialize blockchain connections
+    let op_node_provider =
+        OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
+    let cl_node_provider = BlobProvider::new(
+        args.core.beacon_rpc_url.as_str(),
+        args.core.blob_archive_url.as_deref(),
+    )
+    .await?;
+    let eth_rpc_provider =
+        ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
+    let rollup_config_hash = config_hash(&config).context("config_hash")?;
+    let data_dir = chain_data_dir(
+        &data_dir,
+        config.l2_chain_id,
+        B256::from(rollup_config_hash),
+    )
+    .context("chain_data_dir")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    info!("DisputeGameFactory({dgf_address:?})");
+    ProxiedContract::fetch(&eth_rpc_provider, dgf_address)
+        .await?
+        .check("DisputeGameFactory", None)?;
+    // wait for the kailua game to be installed
+    while dispute_game_factory
+        .gameImpls(KAILUA_GAME_TYPE)
+        .stall()
+        .await
+        .impl_
+        .is_zero()
+    {
+        info!("Waiting for KailuaGame installation.");
+        sleep(Duration::from_secs(30)).await;
+    }
+
+    let mut kailua_db = KailuaDB::init(data_dir, &dispute_game_factory).await?;
+    kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+    info!("KailuaTreasury({:?})", kailua_db.treasury.address);
+    let mut game_indices = HashMap::new();
+    let mut poll_cadence = PollCadence::new(&args.core.cadence_args);
+    loop {
+        poll_cadence.wait(&eth_rpc_provider).await;
+        // index newly ingested proposals and their matches
+        let loaded_proposals = kailua_db
+            .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
+            .await
+            .context("load_proposals")?;
+        for proposal_index in loaded_proposals {
+            let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
+                continue;
+            };
+            game_indices.insert(proposal.contract, proposal.index);
+            index_proposal(&client, &kailua_db, &proposal).await?;
+        }
+
+        // index events emitted by games and the treasury
+        let latest_l1_block = eth_rpc_provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        while next_l1_block <= latest_l1_block {
+            let to_block = latest_l1_block.min(next_l1_block + args.log_batch_size.max(1) - 1);
+            let filter = Filter::new()
+                .from_block(next_l1_block)
+                .to_block(to_block)
+                .event_signature(vec![
+                    KailuaTournament::Proven::SIGNATURE_HASH,
+                    KailuaTournament::ValidityProven::SIGNATURE_HASH,
+                    KailuaGame::Resolved::SIGNATURE_HASH,
+                    KailuaTreasury::BondUpdated::SIGNATURE_HASH,
+                    KailuaTreasury::BondWithdrawn::SIGNATURE_HASH,
+                ]);
+            let logs = eth_rpc_provider
+                .get_logs(&filter)
+                .await
+                .context("get_logs")?;
+            for log in logs {
+                let address = log.address();
+                let game_index = match game_indices.get(&address) {
+                    Some(index) => Some(*index),
+                    None => fetch_game_index(&dispute_game_factory, address).await,
+                };
+                if let Some(game_index) = game_index {
+                    game_indices.insert(address, game_index);
+                }
+                index_event(&client, &kailua_db, game_index, &log).await?;
+            }
+            next_l1_block = to_block + 1;
+            client
+                .execute(
+                    "INSERT INTO kailua_indexer_cursor (id, next_l1_block) VALUES (0, $1) \
+                    ON CONFLICT (id) DO UPDATE SET next_l1_block = EXCLUDED.next_l1_block",
+                    &[&(next_l1_block as i64)],
+                )
+                .await
+                .context("update cursor")?;
+        }
+    }
+}
+
+/// Returns the factory index of the game at the given address, if it was created by the factory
+async fn fetch_game_index<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    dispute_game_factory: &IDisputeGameFactoryInstance<T, P, N>,
+    address: Address,
+) -> Option<u64> {
+    let game_index: u64 = KailuaTournament::new(address, dispute_game_factory.provider())
+        .gameIndex()
+        .call()
+        .await
+        .ok()?
+        ._0
+        .to();
+    let game = dispute_game_factory
+        .gameAtIndex(U256::from(game_index))
+        .call()
+        .await
+        .ok()?;
+    (game.proxy_ == address).then_some(game_index)
+}
+
+async fn index_proposal(
+    client: &tokio_postgres::Client,
+    kailua_db: &KailuaDB,
+    proposal: &Proposal,
+) -> anyhow::Result<()> {
+    client
+        .execute(
+            "INSERT INTO kailua_proposals (factory_index, contract, parent_index, proposer, \
+            created_at, l2_block_number, output_root, l1_head, canonical, duplicate_of) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) \
+            ON CONFLICT (factory_index) DO UPDATE SET canonical = EXCLUDED.canonical, \
+            duplicate_of = EXCLUDED.duplicate_of",
+            &[
+                &(proposal.index as i64),
+                &proposal.contract.as_slice(),
+                &(proposal.parent as i64),
+                &proposal.proposer.as_slice(),
+                &(proposal.created_at as i64),
+                &(proposal.output_block_number as i64),
+                &proposal.output_root.as_slice(),
+                &proposal.l1_head.as_slice(),
+                &proposal.canonical,
+                &proposal.duplicate_of.map(|i| i as i64),
+            ],
+        )
+        .await
+        .context("insert proposal")?;
+    if proposal.is_correct() == Some(false) {
+        index_divergence(client, kailua_db, proposal).await?;
+    }
+    // index the match against the contender
+    let Some(contender) = proposal.contender else {
+        return Ok(());
+    };
+    let Some(parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+        warn!(
+            "Parent of proposal {} missing from database.",
+            proposal.index
+        );
+        return Ok(());
+    };
+    let (Some(u_index), Some(v_index)) = (
+        parent.child_index(contender),
+        parent.child_index(proposal.index),
+    ) else {
+        warn!(
+            "Match of proposal {} missing from tournament.",
+            proposal.index
+        );
+        return Ok(());
+    };
+    client
+        .execute(
+            "INSERT INTO kailua_matches (tournament_index, contender_index, proposal_index, \
+            u_index, v_index) VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+            &[
+                &(parent.index as i64),
+                &(contender as i64),
+                &(proposal.index as i64),
+                &(u_index as i64),
+                &(v_index as i64),
+            ],
+        )
+        .await
+        .context("insert match")?;
+    Ok(())
+}
+
+/// Records where a faulty proposal first diverges from the outputs reported by the op-node
+async fn index_divergence(
+    client: &tokio_postgres::Client,
+    kailua_db: &KailuaDB,
+    proposal: &Proposal,
+) -> anyhow::Result<()> {
+    let io_count = proposal.io_field_elements.len();
+    let (fault, divergence_index) = if proposal.correct_parent == Some(false) {
+        ("parent", None)
+    } else if let Some(i) = proposal.correct_io.iter().position(|c| c == &Some(false)) {
+        ("output", Some(i))
+    } else {
+        ("claim", Some(io_count))
+    };
+    let l2_block_number = divergence_index.map(|i| {
+        proposal
+            .output_block_number
+            .saturating_sub(kailua_db.config.proposal_block_count)
+            + i as u64
+            + 1
+    });
+    // the final claim is not published in a blob
+    let blob_position = divergence_index.filter(|i| *i < io_count).map(|i| i as u64);
+    client
+        .execute(
+            "INSERT INTO kailua_divergences (factory_index, tournament_index, proposer,             contender_index, fault, divergence_index, l2_block_number, blob_index,             field_element_index) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)             ON CONFLICT (factory_index) DO UPDATE SET contender_index = EXCLUDED.contender_index",
+            &[
+                &(proposal.index as i64),
+                &(proposal.parent as i64),
+                &proposal.proposer.as_slice(),
+                &proposal.contender.map(|i| i as i64),
+                &fault,
+                &divergence_index.map(|i| i as i64),
+                &l2_block_number.map(|n| n as i64),
+                &blob_position.map(|p| (p / FIELD_ELEMENTS_PER_BLOB) as i64),
+                &blob_position.map(|p| (p % FIELD_ELEMENTS_PER_BLOB) as i64),
+            ],
+        )
+        .await
+        .context("insert divergence")?;
+    Ok(())
+}
+
+async fn index_event(
+    client: &tokio_postgres::Client,
+    kailua_db: &KailuaDB,
+    game_index: Option<u64>,
+    log: &Log,
+) -> anyhow::Result<()> {
+    let l1_block_number = log
+        .block_number
+        .ok_or_else(|| anyhow!("Missing log block number"))? as i64;
+    let l1_tx_hash = log
+        .transaction_hash
+        .ok_or_else(|| anyhow!("Missing log transaction hash"))?;
+    let l1_log_index = log.log_index.ok_or_else(|| anyhow!("Missing log index"))? as i64;
+    let Some(event) = KailuaEvent::decode(&log.inner)? else {
+        return Ok(());
+    };
+    match event {
+        // only the treasury's bond events are relevant
+        KailuaEvent::BondUpdated { amount } if log.address() == kailua_db.treasury.address => {
+            client
+                .execute(
+                    "INSERT INTO kailua_bond_updates (amount, l1_block_number, l1_tx_hash, \
+                    l1_log_index) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                    &[
+                        &amount.to_string(),
+                        &l1_block_number,
+                        &l1_tx_hash.as_slice(),
+                        &l1_log_index,
+                    ],
+                )
+                .await
+                .context("insert bond update")?;
+            return Ok(());
+        }
+        KailuaEvent::BondWithdrawn {
+            proposer,
+            recipient,
+            amount,
+        } if log.address() == kailua_db.treasury.address => {
+            client
+                .execute(
+                    "INSERT INTO kailua_bond_withdrawals (proposer, recipient, amount, \
+                    l1_block_number, l1_tx_hash, l1_log_index) VALUES ($1, $2, $3, $4, $5, $6) \
+                    ON CONFLICT DO NOTHING",
+                    &[
+                        &proposer.as_slice(),
+                        &recipient.as_slice(),
+                        &amount.to_string(),
+                        &l1_block_number,
+                        &l1_tx_hash.as_slice(),
+                        &l1_log_index,
+                    ],
+                )
+                .await
+                .context("insert bond withdrawal")?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    // the remaining events must originate from kailua games
+    let Some(game_index) = game_index else {
+        return Ok(());
+    };
+    let game_index = game_index as i64;
+    match event {
+        KailuaEvent::Proven { u, v, status } => {
+            client
+                .execute(
+                    "INSERT INTO kailua_proofs (tournament_index, u_index, v_index, status, \
+                    l1_block_number, l1_tx_hash, l1_log_index) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                    ON CONFLICT DO NOTHING",
+                    &[
+                        &game_index,
+                        &Some(u as i64),
+                        &(v as i64),
+                        &(status as i16),
+                        &l1_block_number,
+                        &l1_tx_hash.as_slice(),
+                        &l1_log_index,
+                    ],
+                )
+                .await
+                .context("insert proof")?;
+            // record the outcome for the faulty side of the match
+            client
+                .execute(
+                    "UPDATE kailua_divergences AS d SET proof_status = $4 FROM kailua_matches AS m                     WHERE m.tournament_index = $1 AND m.u_index = $2 AND m.v_index = $3                     AND d.factory_index IN (m.contender_index, m.proposal_index)",
+                    &[&game_index, &(u as i64), &(v as i64), &(status as i16)],
+                )
+                .await
+                .context("update divergence")?;
+        }
+        KailuaEvent::ValidityProven { child } => {
+            client
+                .execute(
+                    "INSERT INTO kailua_proofs (tournament_index, u_index, v_index, status, \
+                    l1_block_number, l1_tx_hash, l1_log_index) VALUES ($1, NULL, $2, NULL, $3, $4, $5) \
+                    ON CONFLICT DO NOTHING",
+                    &[
+                        &game_index,
+                        &(child as i64),
+                        &l1_block_number,
+                        &l1_tx_hash.as_slice(),
+                        &l1_log_index,
+                    ],
+                )
+                .await
+                .context("insert validity proof")?;
+        }
+        KailuaEvent::Resolved { status } => {
+            client
+                .execute(
+                    "INSERT INTO kailua_resolutions (factory_index, status, l1_block_number, \
+                    l1_tx_hash) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                    &[
+                        &game_index,
+                        &(status as i16),
+                        &l1_block_number,
+                        &l1_tx_hash.as_slice(),
+                    ],
+                )
+                .await
+                .context("insert resolution")?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::B256;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Name of the file describing the chain whose data a chain directory holds
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// Directory of the databases of each deployment of the game contracts
+pub const DB_DIR: &str = "db";
+/// Directory that kailua-host writes proof files to
+pub const RECEIPTS_DIR: &str = "receipts";
+/// Directory of the preimages fetched for each proof
+pub const PREIMAGES_DIR: &str = "preimages";
+/// Directory of the database replayed by historical audits, apart from the live one
+pub const BACKFILL_DIR: &str = "backfill";
+
+/// The current version of the chain directory layout
+pub const LAYOUT_VERSION: u8 = 1;
+
+/// Describes the chain whose data a chain directory holds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChainManifest {
+    pub layout_version: u8,
+    pub l2_chain_id: u64,
+    /// Hash of the rollup configuration last used with the directory
+    pub rollup_config_hash: B256,
+    pub created_at: u64,
+}
+
+impl ChainManifest {
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read(path).context("read manifest file")?;
+        serde_json::from_slice(&data)
+            .context("parse manifest file")
+            .map(Some)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?).context("write manifest file")
+    }
+}
+
+/// Returns the absolute path of the directory within the data directory that holds the data of
+/// the chain, creating it along with its manifest if necessary
+pub fn chain_data_dir(
+    data_dir: &Path,
+    l2_chain_id: u64,
+    rollup_config_hash: B256,
+) -> anyhow::Result<PathBuf> {
+    let chain_dir =
+        std::path::absolute(data_dir.join(l2_chain_id.to_string())).context("absolute")?;
+    for dir in [DB_DIR, RECEIPTS_DIR, PREIMAGES_DIR] {
+        std::fs::create_dir_all(chain_dir.join(dir)).context("create_dir_all")?;
+    }
+    let manifest_file = chain_dir.join(MANIFEST_FILE);
+    let manifest = match ChainManifest::load(&manifest_file)? {
+        Some(manifest) => {
+            if manifest.layout_version != LAYOUT_VERSION {
+                bail!(
+                    "Unsupported layout version {} of data directory {chain_dir:?}.",
+                    manifest.layout_version
+                );
+            }
+            if manifest.l2_chain_id != l2_chain_id {
+                bail!(
+                    "Data directory {chain_dir:?} holds data of chain {} instead of {l2_chain_id}.",
+                    manifest.l2_chain_id
+                );
+            }
+            if manifest.rollup_config_hash != rollup_config_hash {
+                warn!(
+                    "Rollup configuration of chain {l2_chain_id} changed from {} to {rollup_config_hash}.",
+                    manifest.rollup_config_hash
+                );
+            }
+            ChainManifest {
+                rollup_config_hash,
+                ..manifest
+            }
+        }
+        None => {
+            info!("Creating data directory {chain_dir:?} for chain {l2_chain_id}.");
+            ChainManifest {
+                layout_version: LAYOUT_VERSION,
+                l2_chain_id,
+                rollup_config_hash,
+                created_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            }
+        }
+    };
+    manifest.save(&manifest_file)?;
+    Ok(chain_dir)
+}
@@ -18,18 +18,52 @@ use alloy::primitives::{b256, Address, Uint, B256, U256};
 use alloy::providers::Provider;
 use alloy::transports::Transport;
 use kailua_contracts::Safe::SafeInstance;
+use kailua_host::HardforkArgs;
 use std::path::PathBuf;
 
 // pub mod bench;
+pub mod alert;
+pub mod anomaly;
+pub mod api;
+pub mod audit;
+pub mod backfill;
+pub mod backup;
+pub mod bucket;
+pub mod cadence;
 pub mod channel;
+pub mod chaos;
 pub mod config;
+pub mod costs;
 pub mod db;
+pub mod export;
 pub mod fast_track;
 pub mod fault;
+pub mod gas;
+pub mod heartbeat;
+pub mod indexer;
+pub mod layout;
+pub mod load;
+pub mod logging;
+pub mod monitor;
+pub mod multicall;
 pub mod propose;
 pub mod providers;
+pub mod proxy;
+pub mod receipts;
+pub mod recover;
+pub mod reload;
+pub mod respected;
+pub mod retention;
+pub mod rewards;
+pub mod safe;
+pub mod self_audit;
 pub mod stall;
+pub mod status;
+pub mod telemetry;
+pub mod trace;
 pub mod validate;
+pub mod wallet;
+pub mod workers;
 
 pub const KAILUA_GAME_TYPE: u32 = 1337;
 
@@ -50,7 +84,18 @@ pub enum Cli {
     FastTrack(fast_track::FastTrackArgs),
     Propose(propose::ProposeArgs),
     Validate(validate::ValidateArgs),
+    Status(status::StatusArgs),
+    Index(indexer::IndexerArgs),
+    Monitor(monitor::MonitorArgs),
+    Recover(recover::RecoverArgs),
     TestFault(fault::FaultArgs),
+    TestBlobCorruption(fault::BlobCorruptionArgs),
+    TestLoad(load::LoadArgs),
+    ProveWorker(workers::WorkerArgs),
+    /// Runs the embedded kailua-host in place of a separate binary
+    #[cfg(feature = "embedded-host")]
+    #[command(hide = true)]
+    Host(kailua_host::KailuaHostCli),
     // Benchmark(bench::BenchArgs),
 }
 
@@ -71,10 +116,48 @@ pub struct CoreArgs {
     /// Address of the L1 Beacon API endpoint to use.
     #[clap(long, env)]
     pub beacon_rpc_url: String,
+    /// Address of a blob archiver to fetch blobs pruned by the L1 Beacon API from
+    #[clap(long, env)]
+    pub blob_archive_url: Option<String>,
 
     /// Directory to use for caching data
     #[clap(long, env)]
     pub data_dir: Option<PathBuf>,
+
+    /// Maximum number of games whose data is fetched concurrently while ingesting proposals
+    #[clap(long, env, default_value_t = 16)]
+    pub ingestion_concurrency: usize,
+
+    #[clap(flatten)]
+    pub hardfork_args: HardforkArgs,
+
+    #[clap(flatten)]
+    pub logging_args: logging::LoggingArgs,
+
+    #[clap(flatten)]
+    pub trace_args: trace::TraceArgs,
+
+    #[clap(flatten)]
+    pub chaos_args: chaos::ChaosArgs,
+
+    #[clap(flatten)]
+    pub cadence_args: cadence::CadenceArgs,
+}
+
+impl CoreArgs {
+    /// Returns the names and urls of all configured endpoints
+    pub fn endpoints_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        let mut endpoints = vec![
+            ("op-node", &mut self.op_node_url),
+            ("op-geth", &mut self.op_geth_url),
+            ("eth-rpc", &mut self.eth_rpc_url),
+            ("beacon", &mut self.beacon_rpc_url),
+        ];
+        if let Some(blob_archive_url) = self.blob_archive_url.as_mut() {
+            endpoints.push(("blob-archive", blob_archive_url));
+        }
+        endpoints
+    }
 }
 
 impl Cli {
@@ -84,7 +167,16 @@ impl Cli {
             Cli::FastTrack(args) => args.v,
             Cli::Propose(args) => args.core.v,
             Cli::Validate(args) => args.core.v,
+            Cli::Status(args) => args.core.v,
+            Cli::Index(args) => args.core.v,
+            Cli::Monitor(args) => args.v,
+            Cli::Recover(args) => args.v,
+            Cli::ProveWorker(args) => args.v,
+            #[cfg(feature = "embedded-host")]
+            Cli::Host(args) => args.kona.v,
             Cli::TestFault(args) => args.propose_args.core.v,
+            Cli::TestBlobCorruption(args) => args.propose_args.core.v,
+            Cli::TestLoad(args) => args.propose_args.core.v,
             // Cli::Benchmark(args) => args.v,
         }
     }
@@ -93,6 +185,35 @@ impl Cli {
         match self {
             Cli::Propose(args) => args.core.data_dir.clone(),
             Cli::Validate(args) => args.core.data_dir.clone(),
+            Cli::Status(args) => args.core.data_dir.clone(),
+            Cli::Index(args) => args.core.data_dir.clone(),
+            Cli::ProveWorker(args) => args.data_dir.clone(),
+            _ => None,
+        }
+    }
+
+    pub fn core_args_mut(&mut self) -> Option<&mut CoreArgs> {
+        match self {
+            Cli::Propose(args) => Some(&mut args.core),
+            Cli::Validate(args) => Some(&mut args.core),
+            Cli::Status(args) => Some(&mut args.core),
+            Cli::Index(args) => Some(&mut args.core),
+            Cli::TestFault(args) => Some(&mut args.propose_args.core),
+            Cli::TestBlobCorruption(args) => Some(&mut args.propose_args.core),
+            Cli::TestLoad(args) => Some(&mut args.propose_args.core),
+            _ => None,
+        }
+    }
+
+    pub fn logging_args(&self) -> Option<&logging::LoggingArgs> {
+        match self {
+            Cli::Propose(args) => Some(&args.core.logging_args),
+            Cli::Validate(args) => Some(&args.core.logging_args),
+            Cli::Status(args) => Some(&args.core.logging_args),
+            Cli::Index(args) => Some(&args.core.logging_args),
+            Cli::TestFault(args) => Some(&args.propose_args.core.logging_args),
+            Cli::TestBlobCorruption(args) => Some(&args.propose_args.core.logging_args),
+            Cli::TestLoad(args) => Some(&args.propose_args.core.logging_args),
             _ => None,
         }
     }
@@ -0,0 +1,264 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::fault::{submit_deviating_proposal, Deviation};
+use crate::propose::ProposeArgs;
+use crate::stall::Stall;
+use crate::KAILUA_GAME_TYPE;
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::U256;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::{LocalSigner, PrivateKeySigner};
+use anyhow::{ensure, Context};
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct LoadArgs {
+    /// The proposer key funds the load generating proposers
+    #[clap(flatten)]
+    pub propose_args: ProposeArgs,
+
+    /// Number of freshly funded proposers to concurrently submit faulty proposals with
+    #[clap(long, default_value_t = 8)]
+    pub load_proposers: usize,
+
+    /// Index of the parent of the faulty proposals
+    #[clap(long)]
+    pub load_parent: u64,
+
+    /// Offsets of the faulty blocks within the proposals, assigned to the proposers in turn
+    #[clap(long, value_delimiter = ',', default_value = "1")]
+    pub load_fault_offsets: Vec<u64>,
+
+    /// Wei to fund each proposer with in addition to the participation bond to pay for gas
+    #[clap(long, default_value_t = 1_000_000_000_000_000_000)]
+    pub load_gas_funding: u128,
+
+    /// Seconds to wait for the validator to eliminate all faulty proposals
+    #[clap(long, default_value_t = 3600)]
+    pub load_timeout: u64,
+
+    /// Seconds between checks of the elimination progress
+    #[clap(long, default_value_t = 12)]
+    pub load_poll_interval: u64,
+}
+
+/// Submits concurrent faulty proposals from many proposers and measures how quickly the validator
+/// eliminates them
+pub async fn load(args: LoadArgs) -> anyhow::Result<()> {
+    ensure!(
+        args.load_proposers > 0,
+        "At least one proposer is required."
+    );
+    ensure!(
+        !args.load_fault_offsets.is_empty(),
+        "At least one fault offset is required."
+    );
+    let eth_rpc_url = args.propose_args.core.eth_rpc_url.as_str();
+    let funder_signer = LocalSigner::from_str(
+        args.propose_args
+            .proposer_key
+            .as_deref()
+            .context("proposer key required")?,
+    )?;
+    let funder_address = funder_signer.address();
+    let funder_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(funder_signer))
+        .on_http(eth_rpc_url.try_into()?);
+
+    // load treasury
+    let config = fetch_rollup_config(
+        &args.propose_args.core.op_node_url,
+        &args.propose_args.core.op_geth_url,
+        None,
+        &args.propose_args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
+    let system_config = SystemConfig::new(config.l1_system_config_address, &funder_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &funder_provider);
+    let kailua_game_implementation = KailuaGame::new(
+        dispute_game_factory
+            .gameImpls(KAILUA_GAME_TYPE)
+            .stall()
+            .await
+            .impl_,
+        &funder_provider,
+    );
+    let kailua_treasury_address = kailua_game_implementation
+        .treasury()
+        .stall()
+        .await
+        .treasury_;
+    let kailua_treasury_instance = KailuaTreasury::new(kailua_treasury_address, &funder_provider);
+    let bond_value = kailua_treasury_instance
+        .participationBond()
+        .stall()
+        .await
+        ._0;
+
+    // fund proposers
+    let funding = bond_value + U256::from(args.load_gas_funding);
+    let mut proposers = Vec::with_capacity(args.load_proposers);
+    for _ in 0..args.load_proposers {
+        let signer = PrivateKeySigner::random();
+        let transaction = TransactionRequest::default()
+            .with_to(signer.address())
+            .with_value(funding);
+        funder_provider
+            .send_transaction(transaction)
+            .await
+            .context("fund (send)")?
+            .get_receipt()
+            .await
+            .context("fund (get_receipt)")?;
+        info!(
+            "Funded proposer {} with {funding} wei from {funder_address}.",
+            signer.address()
+        );
+        proposers.push(signer);
+    }
+
+    // submit faulty proposals concurrently
+    let start = Instant::now();
+    let mut submissions = JoinSet::new();
+    for (i, signer) in proposers.iter().enumerate() {
+        let propose_args = args.propose_args.clone();
+        let signer = signer.clone();
+        let offset = args.load_fault_offsets[i % args.load_fault_offsets.len()];
+        let parent = args.load_parent;
+        submissions.spawn(async move {
+            let address = signer.address();
+            let submitted = Instant::now();
+            let result = submit_deviating_proposal(
+                &propose_args,
+                signer,
+                parent,
+                Deviation::Output { offset },
+            )
+            .await;
+            (address, submitted.elapsed(), result)
+        });
+    }
+    let mut pending = vec![];
+    let mut latencies = vec![];
+    while let Some(submission) = submissions.join_next().await {
+        let (address, latency, result) = submission?;
+        match result {
+            Ok(()) => {
+                pending.push((address, Instant::now()));
+                latencies.push(latency);
+            }
+            Err(err) => error!("Proposer {address} failed to submit: {err:?}"),
+        }
+    }
+    let submission_time = start.elapsed();
+    info!(
+        "Submitted {}/{} faulty proposals in {:.1}s ({:.2}/s), latency mean {:.1}s max {:.1}s.",
+        pending.len(),
+        proposers.len(),
+        submission_time.as_secs_f64(),
+        pending.len() as f64 / submission_time.as_secs_f64(),
+        mean_secs(&latencies),
+        latencies
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default()
+            .as_secs_f64()
+    );
+
+    // wait for eliminations
+    let submitted_count = pending.len();
+    let mut elimination_times = vec![];
+    let deadline = start + Duration::from_secs(args.load_timeout);
+    while !pending.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_secs(args.load_poll_interval)).await;
+        let mut remaining = vec![];
+        for (address, submitted) in pending {
+            let round: u64 = kailua_treasury_instance
+                .eliminationRound(address)
+                .stall()
+                .await
+                ._0
+                .to();
+            if round > 0 {
+                elimination_times.push(submitted.elapsed());
+            } else {
+                remaining.push((address, submitted));
+            }
+        }
+        pending = remaining;
+        let elapsed = start.elapsed().as_secs_f64();
+        info!(
+            "LOAD {elapsed:.0}s: {} eliminated, {} pending ({:.2}/min).",
+            elimination_times.len(),
+            pending.len(),
+            60.0 * elimination_times.len() as f64 / elapsed
+        );
+    }
+    if !pending.is_empty() {
+        warn!(
+            "{} faulty proposals were not eliminated within {}s.",
+            pending.len(),
+            args.load_timeout
+        );
+    }
+
+    println!("PROPOSALS_SUBMITTED: {submitted_count}/{}", proposers.len());
+    println!(
+        "SUBMISSION_LATENCY: mean {:.1}s, max {:.1}s",
+        mean_secs(&latencies),
+        latencies
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default()
+            .as_secs_f64()
+    );
+    println!(
+        "PROPOSALS_ELIMINATED: {}/{submitted_count}",
+        elimination_times.len()
+    );
+    println!(
+        "ELIMINATION_LATENCY: mean {:.1}s, max {:.1}s",
+        mean_secs(&elimination_times),
+        elimination_times
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or_default()
+            .as_secs_f64()
+    );
+    println!(
+        "ELIMINATION_THROUGHPUT: {:.2}/min",
+        60.0 * elimination_times.len() as f64 / start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn mean_secs(durations: &[Duration]) -> f64 {
+    if durations.is_empty() {
+        return 0.0;
+    }
+    durations.iter().map(Duration::as_secs_f64).sum::<f64>() / durations.len() as f64
+}
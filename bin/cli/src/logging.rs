@@ -0,0 +1,191 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Context};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct LoggingArgs {
+    /// File to write logs to in addition to the console
+    #[clap(long, env)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level (0-4) of the log file
+    #[clap(long, env, default_value_t = 2)]
+    pub log_file_verbosity: u8,
+    /// Size in bytes after which the log file is rotated
+    #[clap(long, env)]
+    pub log_max_bytes: Option<u64>,
+    /// Seconds after which the log file is rotated
+    #[clap(long, env)]
+    pub log_rotation_secs: Option<u64>,
+    /// Number of rotated log files to keep
+    #[clap(long, env, default_value_t = 10)]
+    pub log_max_files: usize,
+}
+
+fn level_filter(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::ERROR,
+        1 => LevelFilter::WARN,
+        2 => LevelFilter::INFO,
+        3 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Initializes console logging at the given verbosity, and file logging if configured
+pub fn init_tracing(verbosity: u8, logging_args: Option<&LoggingArgs>) -> anyhow::Result<()> {
+    let Some((args, log_file)) =
+        logging_args.and_then(|args| args.log_file.as_ref().map(|f| (args, f)))
+    else {
+        return kona_host::init_tracing_subscriber(verbosity);
+    };
+    let file_writer = RotatingFile::open(
+        log_file.clone(),
+        args.log_max_bytes,
+        args.log_rotation_secs,
+        args.log_max_files,
+    )?;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(level_filter(verbosity)))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file_writer))
+                .with_filter(level_filter(args.log_file_verbosity)),
+        )
+        .try_init()
+        .map_err(|e| anyhow!(e))
+}
+
+/// A log file that is renamed with a timestamp suffix once it grows too large or too old
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened_at: u64,
+    max_bytes: Option<u64>,
+    rotation_secs: Option<u64>,
+    max_files: usize,
+}
+
+fn unix_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: PathBuf,
+        max_bytes: Option<u64>,
+        rotation_secs: Option<u64>,
+        max_files: usize,
+    ) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).context("create log directory")?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .context("open log file")?;
+        let size = file.metadata().context("log file metadata")?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened_at: (unix_millis() / 1000) as u64,
+            max_bytes,
+            rotation_secs,
+            max_files,
+        })
+    }
+
+    fn is_due(&self, incoming: usize) -> bool {
+        let too_large = self
+            .max_bytes
+            .is_some_and(|max| self.size > 0 && self.size + incoming as u64 > max);
+        let too_old = self
+            .rotation_secs
+            .is_some_and(|secs| self.opened_at + secs <= (unix_millis() / 1000) as u64);
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", unix_millis()));
+        std::fs::rename(&self.path, rotated)?;
+        *self = Self::open(
+            self.path.clone(),
+            self.max_bytes,
+            self.rotation_secs,
+            self.max_files,
+        )
+        .map_err(std::io::Error::other)?;
+        self.prune()
+    }
+
+    /// Deletes the oldest rotated files beyond the retention limit
+    fn prune(&self) -> std::io::Result<()> {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return Ok(());
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            std::path::Path::new(".")
+        } else {
+            dir
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let mut rotated = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let stamp = file_name.strip_prefix(&prefix)?.parse::<u128>().ok()?;
+                Some((stamp, entry.path()))
+            })
+            .collect::<Vec<_>>();
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for (_, path) in rotated.into_iter().take(excess) {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.is_due(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -13,14 +13,25 @@
 // limitations under the License.
 
 use clap::Parser;
+use kailua_cli::logging::init_tracing;
 use kailua_cli::Cli;
-use kona_host::init_tracing_subscriber;
 use tempfile::tempdir;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-    init_tracing_subscriber(cli.verbosity())?;
+    let mut cli = Cli::parse();
+    init_tracing(cli.verbosity(), cli.logging_args())?;
+
+    // redirect rpc traffic through the trace recorder if requested
+    let _rpc_trace = match cli.core_args_mut() {
+        Some(core) => core.start_rpc_trace().await?,
+        None => None,
+    };
+    // faults are injected in front of the trace, such that recordings remain faultless
+    let _chaos = match cli.core_args_mut() {
+        Some(core) => core.start_chaos().await?,
+        None => None,
+    };
 
     let tmp_dir = tempdir()?;
     let data_dir = cli.data_dir().unwrap_or(tmp_dir.path().to_path_buf());
@@ -30,10 +41,27 @@ async fn main() -> anyhow::Result<()> {
         Cli::FastTrack(args) => kailua_cli::fast_track::fast_track(args).await?,
         Cli::Propose(args) => kailua_cli::propose::propose(args, data_dir).await?,
         Cli::Validate(args) => kailua_cli::validate::validate(args, data_dir).await?,
+        Cli::Status(args) => kailua_cli::status::status(args, data_dir).await?,
+        Cli::Index(args) => kailua_cli::indexer::index(args, data_dir).await?,
+        Cli::Monitor(args) => kailua_cli::monitor::monitor(args).await?,
+        Cli::Recover(args) => kailua_cli::recover::recover(args).await?,
+        Cli::ProveWorker(args) => kailua_cli::workers::prove_worker(args, data_dir).await?,
+        #[cfg(feature = "embedded-host")]
+        Cli::Host(args) => kailua_host::prove::run(args).await?,
         Cli::TestFault(_args) =>
         {
             #[cfg(feature = "devnet")]
             kailua_cli::fault::fault(_args).await?
+        }
+        Cli::TestBlobCorruption(_args) =>
+        {
+            #[cfg(feature = "devnet")]
+            kailua_cli::fault::corrupt_blob(_args).await?
+        }
+        Cli::TestLoad(_args) =>
+        {
+            #[cfg(feature = "devnet")]
+            kailua_cli::load::load(_args).await?
         } // Cli::Benchmark(bench_args) => kailua_cli::bench::benchmark(bench_args).await?,
     }
     Ok(())
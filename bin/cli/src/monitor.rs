@@ -0,0 +1,245 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api::{Health, ProofSubmission, ProposalSummary, QueuedProof};
+use anyhow::Context;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct MonitorArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Url of the status api served by the validator
+    #[clap(long, env, default_value = "http://127.0.0.1:8080")]
+    pub status_api_url: String,
+    /// Seconds between refreshes of the displayed state
+    #[clap(long, env, default_value_t = 2)]
+    pub refresh_interval: u64,
+}
+
+/// The latest state fetched from the status api
+#[derive(Default)]
+struct Snapshot {
+    health: Health,
+    proposals: BTreeMap<u64, ProposalSummary>,
+    proof_queue: Vec<QueuedProof>,
+    recent_submissions: VecDeque<ProofSubmission>,
+    error: Option<String>,
+}
+
+pub async fn monitor(args: MonitorArgs) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run(&args, &mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+async fn run(args: &MonitorArgs, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let refresh_interval = Duration::from_secs(args.refresh_interval);
+    let mut snapshot = Snapshot::default();
+    let mut last_refresh: Option<Instant> = None;
+    loop {
+        if last_refresh.map_or(true, |t| t.elapsed() >= refresh_interval) {
+            refresh(&client, &args.status_api_url, &mut snapshot).await;
+            last_refresh = Some(Instant::now());
+        }
+        terminal
+            .draw(|frame| draw(frame, &args.status_api_url, &snapshot))
+            .context("draw")?;
+        // Handle key presses between refreshes
+        if event::poll(Duration::from_millis(250)).context("poll")? {
+            if let Event::Key(key) = event::read().context("read")? {
+                if key.kind == KeyEventKind::Press {
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('r') => last_refresh = None,
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn fetch<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    path: &str,
+) -> anyhow::Result<T> {
+    client
+        .get(format!("{}{path}", url.trim_end_matches('/')))
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .with_context(|| format!("GET {path}"))?
+        .error_for_status()
+        .with_context(|| format!("GET {path}"))?
+        .json()
+        .await
+        .with_context(|| format!("parse {path}"))
+}
+
+/// Updates the snapshot in place, keeping the previous state if the api is unreachable
+async fn refresh(client: &reqwest::Client, url: &str, snapshot: &mut Snapshot) {
+    let result = async {
+        snapshot.health = fetch(client, url, "/health").await?;
+        snapshot.proposals = fetch(client, url, "/proposals").await?;
+        snapshot.proof_queue = fetch(client, url, "/proofs/queue").await?;
+        snapshot.recent_submissions = fetch(client, url, "/proofs/submissions").await?;
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    snapshot.error = result.err().map(|e| format!("{e:#}"));
+}
+
+fn draw(frame: &mut Frame, url: &str, snapshot: &Snapshot) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let [header_area, body_area, events_area] = Layout::vertical([
+        Constraint::Length(5),
+        Constraint::Min(8),
+        Constraint::Length(10),
+    ])
+    .areas(frame.area());
+    let [tournaments_area, queue_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .areas(body_area);
+
+    // Validator health
+    let health = &snapshot.health;
+    let status = match &snapshot.error {
+        Some(error) => Line::styled(
+            format!("UNREACHABLE: {error}"),
+            Style::default().fg(Color::Red),
+        ),
+        None if health.halted => Line::styled(
+            "HALTED: local op-node divergence",
+            Style::default().fg(Color::Red),
+        ),
+        None => Line::styled("OK", Style::default().fg(Color::Green)),
+    };
+    let header = Paragraph::new(vec![
+        status,
+        Line::from(format!(
+            "Factory index {} | Canonical tip {} | Updated {}s ago",
+            health.next_factory_index,
+            health
+                .canonical_tip
+                .map_or(String::from("none"), |i| i.to_string()),
+            now.saturating_sub(health.updated_at)
+        )),
+        Line::from(format!(
+            "Withheld proofs {} | Quarantined proposals {} | Eliminated proposers {}",
+            health.withheld_proofs, health.quarantined_proposals, health.eliminated_proposers
+        )),
+    ])
+    .block(Block::bordered().title(format!(" kailua monitor ({url}) - q to quit ")));
+    frame.render_widget(header, header_area);
+
+    // Tournaments with at least one child, most recent first
+    let rows = snapshot
+        .proposals
+        .values()
+        .rev()
+        .filter(|p| !p.children.is_empty())
+        .take(tournaments_area.height as usize)
+        .map(|p| {
+            let correctness = match p.correct {
+                Some(true) => "correct",
+                Some(false) => "faulty",
+                None => "unknown",
+            };
+            let style = match (p.canonical, p.correct) {
+                (_, Some(false)) => Style::default().fg(Color::Red),
+                (Some(true), _) => Style::default().fg(Color::Green),
+                _ => Style::default(),
+            };
+            Row::new(vec![
+                p.index.to_string(),
+                p.output_block_number.to_string(),
+                p.children.len().to_string(),
+                p.survivor.map_or(String::from("-"), |s| s.to_string()),
+                correctness.to_string(),
+            ])
+            .style(style)
+        });
+    let tournaments = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Min(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["Index", "Block", "Children", "Survivor", "Status"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(" Tournaments "));
+    frame.render_widget(tournaments, tournaments_area);
+
+    // Matches awaiting proofs
+    let rows = snapshot.proof_queue.iter().map(|p| {
+        let style = match p.alert_level {
+            0 => Style::default(),
+            1 => Style::default().fg(Color::Yellow),
+            _ => Style::default().fg(Color::Red),
+        };
+        Row::new(vec![
+            format!("{} vs {}", p.contender, p.proposal),
+            format!("{}s", p.deadline.saturating_sub(now)),
+        ])
+        .style(style)
+    });
+    let queue = Table::new(rows, [Constraint::Min(12), Constraint::Length(12)])
+        .header(
+            Row::new(vec!["Match", "Remaining"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(format!(" Proof queue ({}) ", snapshot.proof_queue.len())));
+    frame.render_widget(queue, queue_area);
+
+    // Most recent proof submissions
+    let events = snapshot
+        .recent_submissions
+        .iter()
+        .rev()
+        .map(|s| {
+            Line::from(format!(
+                "{}s ago: proved {} vs {} (status {}) in {}",
+                now.saturating_sub(s.submitted_at),
+                s.contender,
+                s.proposal,
+                s.proof_status,
+                s.tx_hash
+            ))
+        })
+        .collect::<Vec<_>>();
+    let events = Paragraph::new(events).block(Block::bordered().title(" Recent events "));
+    frame.render_widget(events, events_area);
+}
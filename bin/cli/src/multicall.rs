@@ -0,0 +1,120 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::audit::{AuditLog, AuditOutcome};
+use crate::db::proposal::Proposal;
+use alloy::network::{Network, TransactionBuilder};
+use alloy::primitives::{address, Address};
+use alloy::providers::Provider;
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use alloy::transports::Transport;
+use anyhow::Context;
+use kailua_contracts::KailuaTournament;
+
+/// The address at which `Multicall3` is deployed on most chains
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+    }
+}
+
+fn resolve_calls(proposals: &[Proposal]) -> Vec<IMulticall3::Call3> {
+    proposals
+        .iter()
+        .map(|proposal| IMulticall3::Call3 {
+            target: proposal.contract,
+            allowFailure: true,
+            callData: KailuaTournament::resolveCall {}.abi_encode().into(),
+        })
+        .collect()
+}
+
+/// Returns the transaction that [resolve_batch] would send
+pub fn resolve_batch_request(
+    multicall_address: Address,
+    proposals: &[Proposal],
+) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_to(multicall_address)
+        .with_input(
+            IMulticall3::aggregate3Call {
+                calls: resolve_calls(proposals),
+            }
+            .abi_encode(),
+        )
+}
+
+/// Resolves the proposals in the given order within a single transaction
+///
+/// Each resolution is allowed to fail without reverting the others, so the finality of the
+/// proposals has to be fetched again afterward to learn which ones were resolved.
+pub async fn resolve_batch<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    multicall_address: Address,
+    proposals: &[Proposal],
+    audit_log: &AuditLog,
+) -> anyhow::Result<N::ReceiptResponse> {
+    let intent = format!(
+        "resolve proposals {:?}",
+        proposals.iter().map(|p| p.index).collect::<Vec<_>>()
+    );
+    let multicall = IMulticall3::new(multicall_address, provider);
+    let call = multicall.aggregate3(resolve_calls(proposals));
+    let txn = match call.send().await.context("Multicall3::aggregate3 (send)") {
+        Ok(txn) => txn,
+        Err(e) => {
+            audit_log.record(
+                intent,
+                multicall_address,
+                call.calldata(),
+                None,
+                AuditOutcome::failed(&e),
+            );
+            return Err(e);
+        }
+    };
+    let tx_hash = *txn.tx_hash();
+    let receipt = txn
+        .get_receipt()
+        .await
+        .context("Multicall3::aggregate3 (get_receipt)");
+    let outcome = match &receipt {
+        Ok(receipt) => AuditOutcome::confirmed(receipt),
+        Err(e) => AuditOutcome::unconfirmed(e),
+    };
+    audit_log.record(
+        intent,
+        multicall_address,
+        call.calldata(),
+        Some(tx_hash),
+        outcome,
+    );
+    receipt
+}
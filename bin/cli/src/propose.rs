@@ -12,29 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::alert::{AlertArgs, AlertSeverity, Alerts};
+use crate::anomaly::{AnomalyArgs, ChainAnomalies};
+use crate::audit::{AuditLog, AuditOutcome};
+use crate::cadence::PollCadence;
 use crate::db::proposal::Proposal;
 use crate::db::KailuaDB;
+use crate::export::{agent_wallet, ExportArgs, TxExporter};
+use crate::gas::{GasAccountant, TxCategory};
+use crate::layout::chain_data_dir;
+use crate::multicall::{resolve_batch, resolve_batch_request, MULTICALL3_ADDRESS};
 use crate::providers::beacon::BlobProvider;
 use crate::providers::optimism::OpNodeProvider;
+use crate::proxy::ProxiedContract;
+use crate::respected::{RespectedGameTypeArgs, RespectedGameTypeMonitor};
+use crate::self_audit::{SelfAudit, SelfAuditArgs};
+use crate::wallet::{WalletArgs, WalletMonitor};
 use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
 use alloy::consensus::BlockHeader;
 use alloy::eips::{BlockId, BlockNumberOrTag};
 use alloy::network::primitives::BlockTransactionsKind;
-use alloy::network::{BlockResponse, EthereumWallet};
-use alloy::primitives::Bytes;
+use alloy::network::BlockResponse;
+use alloy::primitives::{Address, B256};
 use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::LocalSigner;
-use alloy::sol_types::SolValue;
+use alloy::transports::Transport;
 use anyhow::Context;
 use kailua_common::blobs::hash_to_fe;
 use kailua_common::client::config_hash;
+use kailua_contracts::extra_data::GameExtraData;
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
 use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
-use std::time::Duration;
-use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
 #[derive(clap::Args, Debug, Clone)]
@@ -43,35 +54,105 @@ pub struct ProposeArgs {
     pub core: CoreArgs,
 
     /// Secret key of L1 wallet to use for proposing outputs
-    #[clap(long, env)]
-    pub proposer_key: String,
+    #[clap(long, env, required_unless_present = "unsigned_tx_sender")]
+    pub proposer_key: Option<String>,
+
+    /// Seconds between summaries of the gas spent by submitted transactions
+    #[clap(long, env, default_value_t = 3600)]
+    pub gas_report_interval: u64,
+
+    /// Address to withdraw the proposer's bond to once all of its proposals are resolved
+    /// while proposals are paused
+    #[clap(long, env, conflicts_with = "unsigned_tx_dir")]
+    pub bond_withdrawal_address: Option<Address>,
+    /// Whether to also withdraw the bond whenever all proposals are resolved, even if the next
+    /// proposal will lock it in again
+    #[clap(
+        long,
+        env,
+        default_value_t = false,
+        requires = "bond_withdrawal_address"
+    )]
+    pub withdraw_when_idle: bool,
+
+    /// Maximum number of proposals to resolve in a single transaction (1 to disable batching)
+    #[clap(long, env, default_value_t = 16)]
+    pub resolution_batch_size: usize,
+    /// Address of the `Multicall3` contract used to batch resolutions
+    #[clap(long, env, default_value_t = MULTICALL3_ADDRESS)]
+    pub multicall_address: Address,
+
+    #[clap(flatten)]
+    pub alert_args: AlertArgs,
+
+    #[clap(flatten)]
+    pub anomaly_args: AnomalyArgs,
+
+    #[clap(flatten)]
+    pub wallet_args: WalletArgs,
+
+    #[clap(flatten)]
+    pub respected_game_type_args: RespectedGameTypeArgs,
+
+    #[clap(flatten)]
+    pub self_audit_args: SelfAuditArgs,
+
+    #[clap(flatten)]
+    pub export_args: ExportArgs,
 }
 
 pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()> {
+    let alerts = Alerts::from_args(&args.alert_args);
     // initialize blockchain connections
     let op_node_provider =
         OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
-    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str()).await?;
+    let cl_node_provider = BlobProvider::new(
+        args.core.beacon_rpc_url.as_str(),
+        args.core.blob_archive_url.as_deref(),
+    )
+    .await?;
     let eth_rpc_provider =
         ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
-    let config = fetch_rollup_config(&args.core.op_node_url, &args.core.op_geth_url, None)
-        .await
-        .context("fetch_rollup_config")?;
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
     let rollup_config_hash = config_hash(&config).expect("Configuration hash derivation error");
     info!("RollupConfigHash({})", hex::encode(rollup_config_hash));
+    let data_dir = chain_data_dir(
+        &data_dir,
+        config.l2_chain_id,
+        B256::from(rollup_config_hash),
+    )
+    .context("chain_data_dir")?;
 
     // load system config
     let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
     let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let portal_address = system_config.optimismPortal().stall().await.addr_;
 
     // initialize proposer wallet
     info!("Initializing proposer wallet.");
-    let proposer_signer = LocalSigner::from_str(&args.proposer_key)?;
-    let proposer_address = proposer_signer.address();
-    let proposer_wallet = EthereumWallet::from(proposer_signer);
+    let (proposer_address, mut proposer_wallet) =
+        agent_wallet(args.proposer_key.as_deref(), &args.export_args)?;
+    // Propose corrected outputs from a second wallet once this proposer is found faulty
+    let corrective_address = match &args.self_audit_args.corrective_proposer_key {
+        Some(corrective_proposer_key) => {
+            let signer = LocalSigner::from_str(corrective_proposer_key)?;
+            let address = signer.address();
+            proposer_wallet.register_signer(signer);
+            info!("Corrective proposer address: {address}");
+            Some(address)
+        }
+        None => None,
+    };
     let proposer_provider = ProviderBuilder::new()
         .with_recommended_fillers()
         .wallet(&proposer_wallet)
@@ -82,6 +163,9 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
     let dispute_game_factory =
         kailua_contracts::IDisputeGameFactory::new(dgf_address, &proposer_provider);
     info!("DisputeGameFactory({:?})", dispute_game_factory.address());
+    ProxiedContract::fetch(&eth_rpc_provider, dgf_address)
+        .await?
+        .check("DisputeGameFactory", Some(proposer_address))?;
     let game_count: u64 = dispute_game_factory
         .gameCount()
         .stall()
@@ -100,26 +184,125 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
     info!("KailuaGame({:?})", kailua_game_implementation.address());
     if kailua_game_implementation.address().is_zero() {
         error!("Fault proof game is not installed!");
+        alerts
+            .raise(
+                AlertSeverity::Critical,
+                "game_not_installed",
+                String::from("Fault proof game is not installed."),
+            )
+            .await;
         exit(1);
     }
     // Initialize empty DB
     info!("Initializing..");
+    let audit_log = AuditLog::new(&data_dir);
     let mut kailua_db = KailuaDB::init(data_dir, &dispute_game_factory).await?;
+    kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+    kailua_db.chain_anomalies = ChainAnomalies::new(args.anomaly_args.clone());
     info!("KailuaTreasury({:?})", kailua_db.treasury.address);
+    let bond_withdrawal_address = args
+        .bond_withdrawal_address
+        .filter(|_| kailua_db.config.revision.bond_withdrawals);
+    if bond_withdrawal_address.is_none() && args.bond_withdrawal_address.is_some() {
+        warn!("Bond withdrawals disabled as the deployed treasury does not support them.");
+    }
+    let mut gas_accountant = GasAccountant::new(args.gas_report_interval);
+    let mut insufficient_balance_alerted = false;
+    let mut bond_withdrawal_alerted = false;
+    let mut wallet_monitor =
+        WalletMonitor::new(&args.wallet_args, proposer_address, &args.core.eth_rpc_url)?;
+    let mut respected_game_type_monitor =
+        RespectedGameTypeMonitor::new(&args.respected_game_type_args, portal_address);
+    // Batch resolutions only if the multicall contract is deployed
+    let multicall_address = if args.resolution_batch_size > 1 {
+        let code = eth_rpc_provider
+            .get_code_at(args.multicall_address)
+            .await
+            .context("get_code_at")?;
+        if code.is_empty() {
+            warn!(
+                "Multicall3 not found at {}. Resolving proposals individually.",
+                args.multicall_address
+            );
+            None
+        } else {
+            Some(args.multicall_address)
+        }
+    } else {
+        None
+    };
+    let mut tx_exporter = TxExporter::new(&args.export_args, proposer_address)?;
+    let mut self_audit = SelfAudit::new(&args.self_audit_args);
+    let resolution_batch_size = if multicall_address.is_some() {
+        args.resolution_batch_size
+    } else {
+        1
+    };
     // Run the proposer loop to sync and post
     info!(
         "Starting from proposal at factory index {}",
         kailua_db.state.next_factory_index
     );
 
+    let mut poll_cadence = PollCadence::new(&args.core.cadence_args);
     loop {
         // Wait for new data on every iteration
-        sleep(Duration::from_secs(1)).await;
+        poll_cadence.wait(&eth_rpc_provider).await;
+        gas_accountant.report_if_due();
+        if let Err(e) = wallet_monitor
+            .check_if_due(&proposer_provider, &alerts, &audit_log)
+            .await
+        {
+            warn!("Failed to check wallet balance: {e:?}");
+        }
+        if let Err(e) = respected_game_type_monitor
+            .check_if_due(&proposer_provider, &alerts)
+            .await
+        {
+            warn!("Failed to check respected game type: {e:?}");
+        }
         // fetch latest games
         kailua_db
             .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
             .await
             .context("load_proposals")?;
+        kailua_db.chain_anomalies.raise_alerts(&alerts).await;
+        // Stop defending own proposals that the op-node no longer agrees with
+        if let Err(e) = self_audit
+            .audit_if_due(
+                &mut kailua_db,
+                proposer_address,
+                &op_node_provider,
+                &proposer_provider,
+                &alerts,
+            )
+            .await
+        {
+            warn!("Failed to audit own proposals: {e:?}");
+        }
+
+        // Detect changes to the bond required for making proposals
+        let previous_bond = kailua_db.treasury.participation_bond;
+        let bond_value = kailua_db.treasury.fetch_bond(&proposer_provider).await?;
+        if bond_value != previous_bond {
+            let paid_in = kailua_db
+                .treasury
+                .fetch_balance(&proposer_provider, proposer_address)
+                .await?;
+            warn!("Participation bond changed from {previous_bond} to {bond_value} wei ({paid_in} wei deposited).");
+            let message = if paid_in < bond_value {
+                format!("Participation bond changed from {previous_bond} to {bond_value} wei. The deposited bond of {paid_in} wei is below the new requirement, and the difference will be locked with the next proposal.")
+            } else {
+                format!("Participation bond changed from {previous_bond} to {bond_value} wei.")
+            };
+            alerts
+                .raise(
+                    AlertSeverity::Warning,
+                    "participation_bond_changed",
+                    message,
+                )
+                .await;
+        }
 
         // Stack unresolved ancestors
         let mut unresolved_proposal_indices = kailua_db
@@ -132,6 +315,7 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 unresolved_proposal_indices.len()
             );
         }
+        let mut resolution_batch: Vec<Proposal> = Vec::new();
         while let Some(proposal_index) = unresolved_proposal_indices.pop() {
             let proposal = kailua_db.get_local_proposal(&proposal_index).unwrap();
             let parent = kailua_db.get_local_proposal(&proposal.parent).unwrap();
@@ -160,8 +344,10 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 continue;
             }
 
-            // Check if claim won in tournament
+            // Check if claim won in tournament, unless its parent is yet to be resolved in this batch
+            let is_parent_batched = resolution_batch.iter().any(|p| p.index == proposal.parent);
             if proposal.has_parent()
+                && !is_parent_batched
                 && !proposal
                     .fetch_parent_tournament_survivor_status(&proposer_provider)
                     .await
@@ -186,12 +372,103 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 "Resolving game at index {} and height {}.",
                 proposal.index, proposal.output_block_number
             );
+            resolution_batch.push(proposal);
+            if resolution_batch.len() >= resolution_batch_size {
+                resolve_proposals(
+                    &proposer_provider,
+                    multicall_address,
+                    std::mem::take(&mut resolution_batch),
+                    &audit_log,
+                    &alerts,
+                    &mut gas_accountant,
+                    tx_exporter.as_mut(),
+                )
+                .await;
+            }
+        }
+        if !resolution_batch.is_empty() {
+            resolve_proposals(
+                &proposer_provider,
+                multicall_address,
+                resolution_batch,
+                &audit_log,
+                &alerts,
+                &mut gas_accountant,
+                tx_exporter.as_mut(),
+            )
+            .await;
+        }
 
-            if let Err(e) = proposal.resolve(&proposer_provider).await {
-                error!("Failed to resolve proposal: {e:?}");
+        // Withdraw the bond once it no longer backs any unresolved proposal, unless it would be
+        // locked in again by the next proposal
+        let is_paused = !respected_game_type_monitor.is_respected();
+        if let Some(recipient) =
+            bond_withdrawal_address.filter(|_| is_paused || args.withdraw_when_idle)
+        {
+            // Release proposals that were outlived by a sibling or orphaned by their parent
+            for proposal in kailua_db.non_canonical_proposals_by(proposer_address) {
+                match kailua_db
+                    .treasury
+                    .release_proposal(&proposer_provider, proposal.contract, &audit_log)
+                    .await
+                {
+                    Ok(Some(receipt)) => {
+                        info!("Released proposal {}: {receipt:?}", proposal.index);
+                        gas_accountant.record(TxCategory::Withdraw, &receipt);
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to release proposal {}: {e:?}", proposal.index),
+                }
             }
+            let withdrawable_bond = kailua_db
+                .treasury
+                .fetch_withdrawable_bond(&proposer_provider, proposer_address)
+                .await?;
+            if !withdrawable_bond.is_zero() {
+                info!("Withdrawing bond of {withdrawable_bond} wei to {recipient}.");
+                match kailua_db
+                    .treasury
+                    .withdraw_bond(&proposer_provider, recipient, &audit_log)
+                    .await
+                {
+                    Ok(receipt) => {
+                        info!("Bond withdrawn: {receipt:?}");
+                        gas_accountant.record(TxCategory::Withdraw, &receipt);
+                        bond_withdrawal_alerted = false;
+                    }
+                    Err(e) => {
+                        error!("Failed to withdraw bond: {e:?}");
+                        if !bond_withdrawal_alerted {
+                            alerts
+                                .raise(
+                                    AlertSeverity::Warning,
+                                    "bond_withdrawal_failed",
+                                    format!("Failed to withdraw bond of {withdrawable_bond} wei to {recipient}: {e:?}"),
+                                )
+                                .await;
+                            bond_withdrawal_alerted = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Proposals made under a game type that is not respected only put bonds at risk
+        if is_paused {
+            warn!("Pausing proposals while Kailua is not the respected game type.");
+            continue;
         }
 
+        // A faulty proposer can only continue through its corrective wallet
+        let Some(active_proposer) = [Some(proposer_address), corrective_address]
+            .into_iter()
+            .flatten()
+            .find(|address| !kailua_db.is_proposer_eliminated(*address))
+        else {
+            warn!("Pausing proposals as all proposer wallets are eliminated.");
+            continue;
+        };
+
         // Submit proposal to extend canonical chain
         let Some(canonical_tip) = kailua_db.canonical_tip() else {
             warn!("No canonical proposal chain to extend!");
@@ -254,22 +531,11 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
         let sidecar = Proposal::create_sidecar(&io_field_elements)?;
 
         // Calculate required duplication counter
-        let mut dupe_counter = 0u64;
+        let mut extra_data = GameExtraData::new(proposed_block_number, canonical_tip.index);
         let unique_extra_data = loop {
-            // compute extra data with block number, parent factory index, and blob hash
-            let extra_data = [
-                proposed_block_number.abi_encode_packed(),
-                canonical_tip.index.abi_encode_packed(),
-                dupe_counter.abi_encode_packed(),
-            ]
-            .concat();
             // check if proposal exists
             let dupe_game_address = dispute_game_factory
-                .games(
-                    KAILUA_GAME_TYPE,
-                    proposed_output_root,
-                    Bytes::from(extra_data.clone()),
-                )
+                .games(KAILUA_GAME_TYPE, proposed_output_root, extra_data.encode())
                 .stall()
                 .await
                 .proxy_;
@@ -295,7 +561,7 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
                 break None;
             }
             // increment counter
-            dupe_counter += 1;
+            extra_data = extra_data.next_duplicate();
         };
 
         let Some(extra_data) = unique_extra_data else {
@@ -303,40 +569,181 @@ pub async fn propose(args: ProposeArgs, data_dir: PathBuf) -> anyhow::Result<()>
             continue;
         };
         // Check collateral requirements
-        let bond_value = kailua_db.treasury.fetch_bond(&proposer_provider).await?;
         let paid_in = kailua_db
             .treasury
-            .fetch_balance(&proposer_provider, proposer_address)
+            .fetch_balance(&proposer_provider, active_proposer)
             .await?;
-        let balance = proposer_provider.get_balance(proposer_address).await?;
+        let balance = proposer_provider.get_balance(active_proposer).await?;
         let owed_collateral = bond_value.saturating_sub(paid_in);
         if balance < owed_collateral {
-            error!("INSUFFICIENT BALANCE! Need to lock in at least {owed_collateral}.");
+            error!("INSUFFICIENT BALANCE! Need to lock in at least {owed_collateral} to meet the participation bond of {bond_value}. Pausing proposals until the wallet is topped up.");
+            if !insufficient_balance_alerted {
+                alerts
+                    .raise(
+                        AlertSeverity::Critical,
+                        "insufficient_balance",
+                        format!("Proposer balance {balance} is below the owed collateral {owed_collateral}."),
+                    )
+                    .await;
+                insufficient_balance_alerted = true;
+            }
             continue;
         }
+        insufficient_balance_alerted = false;
         // Submit proposal
-        info!("Proposing output {proposed_output_root} at l2 block number {proposed_block_number} with {owed_collateral} additional collateral and duplication counter {dupe_counter}.");
-        match kailua_db
+        info!("Proposing output {proposed_output_root} at l2 block number {proposed_block_number} with {owed_collateral} additional collateral and duplication counter {}.", extra_data.duplication_counter);
+        let intent =
+            format!("propose output {proposed_output_root} at block {proposed_block_number}");
+        let treasury_contract = kailua_db
             .treasury
-            .treasury_contract_instance(&proposer_provider)
-            .propose(proposed_output_root, Bytes::from(extra_data))
+            .treasury_contract_instance(&proposer_provider);
+        let propose_call = treasury_contract
+            .propose(proposed_output_root, extra_data.encode())
+            .from(active_proposer)
             .value(owed_collateral)
-            .sidecar(sidecar)
-            .send()
-            .await
-            .context("propose (send)")
-        {
-            Ok(txn) => match txn.get_receipt().await.context("propose (get_receipt)") {
-                Ok(receipt) => {
-                    info!("Proposal submitted: {receipt:?}")
-                }
-                Err(e) => {
-                    error!("Failed to confirm proposal txn: {e:?}");
+            .sidecar(sidecar);
+        if let Some(tx_exporter) = tx_exporter.as_mut() {
+            if let Err(e) = tx_exporter
+                .export(
+                    &proposer_provider,
+                    &intent,
+                    propose_call.into_transaction_request(),
+                )
+                .await
+            {
+                error!("Failed to export proposal txn: {e:?}");
+            }
+            continue;
+        }
+        match propose_call.send().await.context("propose (send)") {
+            Ok(txn) => {
+                let tx_hash = *txn.tx_hash();
+                match txn.get_receipt().await.context("propose (get_receipt)") {
+                    Ok(receipt) => {
+                        info!("Proposal submitted: {receipt:?}");
+                        audit_log.record(
+                            intent,
+                            kailua_db.treasury.address,
+                            propose_call.calldata(),
+                            Some(tx_hash),
+                            AuditOutcome::confirmed(&receipt),
+                        );
+                        gas_accountant.record(TxCategory::Propose, &receipt);
+                    }
+                    Err(e) => {
+                        error!("Failed to confirm proposal txn: {e:?}");
+                        audit_log.record(
+                            intent,
+                            kailua_db.treasury.address,
+                            propose_call.calldata(),
+                            Some(tx_hash),
+                            AuditOutcome::unconfirmed(&e),
+                        );
+                        alerts
+                            .raise(
+                                AlertSeverity::Critical,
+                                "proposal_failed",
+                                format!("Failed to confirm proposal txn: {e:?}"),
+                            )
+                            .await;
+                    }
                 }
-            },
+            }
             Err(e) => {
                 error!("Failed to send proposal txn: {e:?}");
+                audit_log.record(
+                    intent,
+                    kailua_db.treasury.address,
+                    propose_call.calldata(),
+                    None,
+                    AuditOutcome::failed(&e),
+                );
+                alerts
+                    .raise(
+                        AlertSeverity::Critical,
+                        "proposal_failed",
+                        format!("Failed to send proposal txn: {e:?}"),
+                    )
+                    .await;
             }
         }
     }
 }
+
+/// Resolves the proposals in order, in a single multicall transaction if there are several
+async fn resolve_proposals<T: Transport + Clone, P: Provider<T>>(
+    provider: P,
+    multicall_address: Option<Address>,
+    proposals: Vec<Proposal>,
+    audit_log: &AuditLog,
+    alerts: &Alerts,
+    gas_accountant: &mut GasAccountant,
+    tx_exporter: Option<&mut TxExporter>,
+) {
+    let is_batched = multicall_address.is_some() && proposals.len() > 1;
+    if let Some(tx_exporter) = tx_exporter {
+        let (intent, request) = match multicall_address.filter(|_| is_batched) {
+            Some(multicall_address) => (
+                format!(
+                    "resolve proposals {:?}",
+                    proposals.iter().map(|p| p.index).collect::<Vec<_>>()
+                ),
+                resolve_batch_request(multicall_address, &proposals),
+            ),
+            None => (
+                format!("resolve proposal {}", proposals[0].index),
+                proposals[0]
+                    .tournament_contract_instance(&provider)
+                    .resolve()
+                    .into_transaction_request(),
+            ),
+        };
+        if let Err(e) = tx_exporter.export(&provider, &intent, request).await {
+            error!("Failed to export resolution txn: {e:?}");
+        }
+        return;
+    }
+    let result = match multicall_address.filter(|_| is_batched) {
+        Some(multicall_address) => {
+            info!("Resolving {} games in one transaction.", proposals.len());
+            resolve_batch(&provider, multicall_address, &proposals, audit_log).await
+        }
+        None => proposals[0].resolve(&provider, audit_log).await,
+    };
+    match result {
+        Ok(receipt) => gas_accountant.record(TxCategory::Resolve, &receipt),
+        Err(e) => {
+            error!("Failed to resolve proposal: {e:?}");
+            alerts
+                .raise(
+                    AlertSeverity::Warning,
+                    "resolve_failed",
+                    format!("Failed to resolve proposal {}: {e:?}", proposals[0].index),
+                )
+                .await;
+            return;
+        }
+    }
+    if !is_batched {
+        return;
+    }
+    // Resolutions within a batch may fail individually without reverting the transaction
+    for proposal in &proposals {
+        if !proposal
+            .fetch_finality(&provider)
+            .await
+            .unwrap_or_default()
+            .unwrap_or_default()
+        {
+            warn!("Batched resolution of proposal {} failed.", proposal.index);
+            alerts
+                .raise(
+                    AlertSeverity::Warning,
+                    "resolve_failed",
+                    format!("Failed to resolve proposal {} in batch.", proposal.index),
+                )
+                .await;
+            break;
+        }
+    }
+}
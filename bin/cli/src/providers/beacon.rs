@@ -18,21 +18,29 @@ use alloy::primitives::{B256, U256};
 use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
 use alloy_rpc_types_beacon::sidecar::{BeaconBlobBundle, BlobData};
 use anyhow::{bail, Context};
+use kailua_common::blobs::hash_to_fe;
+use metrics::counter;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::ops::{Div, Sub};
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(Clone, Debug)]
 pub struct BlobProvider {
     pub cl_node_provider: ReqwestProvider,
+    /// Blob archiver serving the beacon blob sidecar api for blobs pruned by the beacon node
+    pub archive_provider: Option<ReqwestProvider>,
     pub genesis_time: u64,
     pub seconds_per_slot: u64,
 }
 
 impl BlobProvider {
-    pub async fn new(url: &str) -> anyhow::Result<Self> {
+    pub async fn new(url: &str, archive_url: Option<&str>) -> anyhow::Result<Self> {
         let cl_node_provider = ProviderBuilder::new().on_http(url.try_into()?);
+        let archive_provider = match archive_url {
+            Some(archive_url) => Some(ProviderBuilder::new().on_http(archive_url.try_into()?)),
+            None => None,
+        };
         let genesis =
             Self::provider_get::<Value>(&cl_node_provider, "eth/v1/beacon/genesis").await?;
         debug!("genesis {:?}", &genesis);
@@ -48,6 +56,7 @@ impl BlobProvider {
             .parse::<u64>()?;
         Ok(Self {
             cl_node_provider,
+            archive_provider,
             genesis_time,
             seconds_per_slot,
         })
@@ -92,23 +101,101 @@ impl BlobProvider {
 
     pub async fn get_blob(&self, timestamp: u64, blob_hash: B256) -> anyhow::Result<BlobData> {
         let slot = self.slot(timestamp);
-        let blobs = self
-            .get::<BeaconBlobBundle>(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
-            .await
-            .context(format!("blob_sidecars {slot}"))?;
+        let err = match Self::provider_get_blob(&self.cl_node_provider, slot, blob_hash).await {
+            Ok(blob) => return Ok(blob),
+            Err(err) => err,
+        };
+        Self::record_failure("beacon", &err);
+        let Some(archive_provider) = &self.archive_provider else {
+            return Err(err);
+        };
+        warn!("Falling back to blob archive for blob {blob_hash} @ {timestamp}: {err:?}");
+        counter!("kailua_blob_archive_fallbacks_total").increment(1);
+        match Self::provider_get_blob(archive_provider, slot, blob_hash).await {
+            Ok(blob) => Ok(blob),
+            Err(archive_err) => {
+                Self::record_failure("archive", &archive_err);
+                // report the beacon node error unless it failed for a reason the archive did not
+                Err(if err.is::<BlobCommitmentMismatch>() {
+                    err
+                } else {
+                    archive_err
+                })
+            }
+        }
+    }
+
+    async fn provider_get_blob(
+        provider: &ReqwestProvider,
+        slot: u64,
+        blob_hash: B256,
+    ) -> anyhow::Result<BlobData> {
+        let blobs = Self::provider_get::<BeaconBlobBundle>(
+            provider,
+            &format!("eth/v1/beacon/blob_sidecars/{slot}"),
+        )
+        .await
+        .context(format!("blob_sidecars {slot}"))?;
 
         let blob_count = blobs.len();
+        let mut mismatched = false;
         for blob in blobs {
             let versioned_hash = kzg_to_versioned_hash(blob.kzg_commitment.as_slice());
-            if versioned_hash == blob_hash {
+            if versioned_hash != blob_hash {
+                continue;
+            }
+            // do not trust the beacon node to serve data matching the commitment
+            if verify_blob_commitment(&blob)? {
                 return Ok(blob);
             }
+            mismatched = true;
         }
 
-        bail!("Blob {blob_hash} @ {timestamp} not found in slot ({blob_count} blobs found)!");
+        if mismatched {
+            return Err(BlobCommitmentMismatch { blob_hash, slot }.into());
+        }
+        bail!("Blob {blob_hash} not found in slot {slot} ({blob_count} blobs found)!");
+    }
+
+    /// Reports a failure to retrieve a blob from the given source
+    fn record_failure(source: &'static str, err: &anyhow::Error) {
+        if err.is::<BlobCommitmentMismatch>() {
+            warn!("KZG verification of blob from {source} failed: {err:?}");
+            counter!("kailua_blob_kzg_failures_total", "source" => source).increment(1);
+        } else {
+            warn!("Failed to fetch blob sidecar from {source}: {err:?}");
+            counter!("kailua_blob_fetch_failures_total", "source" => source).increment(1);
+        }
     }
 }
 
+/// The beacon node served blob data that does not match the blob's kzg commitment
+#[derive(Clone, Copy, Debug)]
+pub struct BlobCommitmentMismatch {
+    pub blob_hash: B256,
+    pub slot: u64,
+}
+
+impl std::fmt::Display for BlobCommitmentMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Blob {} in slot {} does not match its kzg commitment",
+            self.blob_hash, self.slot
+        )
+    }
+}
+
+impl std::error::Error for BlobCommitmentMismatch {}
+
+/// Returns true if the blob data matches its kzg commitment
+pub fn verify_blob_commitment(blob: &BlobData) -> anyhow::Result<bool> {
+    let c_kzg_blob = c_kzg::Blob::from_bytes(blob.blob.as_slice())?;
+    let settings = alloy::consensus::EnvKzgSettings::default();
+    let commitment = c_kzg::KzgCommitment::blob_to_kzg_commitment(&c_kzg_blob, settings.get())?;
+    Ok(commitment.to_bytes().as_slice() == blob.kzg_commitment.as_slice())
+}
+
 pub fn blob_sidecar(blob_data: Vec<Blob>) -> anyhow::Result<BlobTransactionSidecar> {
     let mut blobs = Vec::with_capacity(blob_data.len());
     let mut commitments = Vec::with_capacity(blob_data.len());
@@ -175,3 +262,23 @@ pub fn blob_fe_proof(
         bail!("Generated invalid kzg proof.")
     }
 }
+
+/// Returns true if the proof shows that the blob element at the index holds the hash, mirroring
+/// the point evaluation performed by `KailuaLib.verifyKZGBlobProof`
+pub fn verify_blob_fe_proof(
+    commitment: &[u8],
+    index: usize,
+    hash: B256,
+    proof: &[u8],
+) -> anyhow::Result<bool> {
+    let z = c_kzg::Bytes32::new(root_of_unity(index).to_be_bytes());
+    let y = c_kzg::Bytes32::new(hash_to_fe(hash).0);
+    let settings = alloy::consensus::EnvKzgSettings::default();
+    Ok(c_kzg::KzgProof::verify_kzg_proof(
+        &c_kzg::Bytes48::from_bytes(commitment)?,
+        &z,
+        &y,
+        &c_kzg::Bytes48::from_bytes(proof)?,
+        settings.get(),
+    )?)
+}
@@ -14,15 +14,30 @@
 
 use alloy::primitives::B256;
 use alloy::providers::{Provider, ReqwestProvider};
-use anyhow::Context;
+use anyhow::{bail, Context};
+use kailua_common::output::OutputResponse;
 use serde_json::Value;
-use std::str::FromStr;
 use tracing::debug;
 
 pub struct OpNodeProvider(pub ReqwestProvider);
 
 impl OpNodeProvider {
+    /// Returns the output root at the given block after checking it against its components
     pub async fn output_at_block(&self, output_block_number: u64) -> anyhow::Result<B256> {
+        let output_at_block = self.output_response_at_block(output_block_number).await?;
+        if !output_at_block.is_consistent() {
+            bail!(
+                "Output root {} at block {output_block_number} does not match its components",
+                output_at_block.output_root
+            );
+        }
+        Ok(output_at_block.output_root)
+    }
+
+    pub async fn output_response_at_block(
+        &self,
+        output_block_number: u64,
+    ) -> anyhow::Result<OutputResponse> {
         let output_at_block: serde_json::Value = self
             .0
             .client()
@@ -33,9 +48,7 @@ impl OpNodeProvider {
             .await
             .context(format!("optimism_outputAtBlock {output_block_number}"))?;
         debug!("optimism_outputAtBlock {:?}", &output_at_block);
-        Ok(B256::from_str(
-            output_at_block["outputRoot"].as_str().unwrap(),
-        )?)
+        serde_json::from_value(output_at_block).context("OutputResponse")
     }
 
     pub async fn sync_status(&self) -> anyhow::Result<Value> {
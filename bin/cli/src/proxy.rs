@@ -0,0 +1,103 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::network::Network;
+use alloy::primitives::{b256, Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use tracing::info;
+
+/// Storage slot of the implementation address of an EIP-1967 proxy
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// Storage slot of the admin address of an EIP-1967 proxy
+pub const EIP1967_ADMIN_SLOT: B256 =
+    b256!("b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103");
+
+/// Maximum number of proxies to follow when resolving an implementation
+const MAX_PROXY_DEPTH: usize = 4;
+
+/// A contract address along with the implementation it delegates calls to, if it is a proxy
+#[derive(Clone, Copy, Debug)]
+pub struct ProxiedContract {
+    pub address: Address,
+    pub admin: Option<Address>,
+    pub implementation: Option<Address>,
+}
+
+impl ProxiedContract {
+    /// Reads the EIP-1967 slots of the contract, following nested proxies to the implementation
+    pub async fn fetch<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        provider: P,
+        address: Address,
+    ) -> anyhow::Result<Self> {
+        let admin = read_address_slot(&provider, address, EIP1967_ADMIN_SLOT).await?;
+        let mut implementation = None;
+        let mut current = address;
+        for _ in 0..MAX_PROXY_DEPTH {
+            match read_address_slot(&provider, current, EIP1967_IMPLEMENTATION_SLOT).await? {
+                Some(next) => {
+                    implementation = Some(next);
+                    current = next;
+                }
+                None => break,
+            }
+        }
+        Ok(Self {
+            address,
+            admin,
+            implementation,
+        })
+    }
+
+    /// Logs the proxy configuration, and fails if calls made by the caller would not reach the
+    /// implementation
+    pub fn check(&self, name: &str, caller: Option<Address>) -> anyhow::Result<()> {
+        if self.admin.is_some() && self.implementation.is_none() {
+            bail!(
+                "{name}({}) is a proxy without an implementation.",
+                self.address
+            );
+        }
+        if let Some(implementation) = self.implementation {
+            info!(
+                "{name}({}) is a proxy for implementation {implementation}.",
+                self.address
+            );
+        }
+        // transparent proxies route calls made by their admin to the proxy itself
+        if caller.is_some() && caller == self.admin {
+            bail!(
+                "{name}({}) is administered by {}, whose calls do not reach its implementation.",
+                self.address,
+                self.admin.unwrap()
+            );
+        }
+        Ok(())
+    }
+}
+
+async fn read_address_slot<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    provider: P,
+    address: Address,
+    slot: B256,
+) -> anyhow::Result<Option<Address>> {
+    let value = provider
+        .get_storage_at(address, U256::from_be_bytes(slot.0))
+        .await
+        .context("get_storage_at")?;
+    let value = Address::from_word(B256::from(value));
+    Ok((!value.is_zero()).then_some(value))
+}
@@ -0,0 +1,127 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bucket::{Bucket, BucketConfig};
+use crate::validate::ProvingJob;
+use anyhow::{ensure, Context};
+use kailua_client::proof::{fpvm_proof_file_name_from_journal, Proof};
+use kailua_common::journal::ProofJournal;
+use metrics::counter;
+use tracing::{debug, error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ReceiptStoreArgs {
+    /// Bucket and prefix under which validators of the same chain share receipts
+    /// (e.g. s3://bucket/prefix or gs://bucket/prefix)
+    #[clap(long, env)]
+    pub receipt_store_url: Option<String>,
+    /// Endpoint of an S3-compatible service to use instead of AWS (Defaults to Google Cloud
+    /// Storage for gs:// urls)
+    #[clap(long, env)]
+    pub receipt_store_endpoint: Option<String>,
+    /// Region of the receipt store bucket
+    #[clap(long, env, default_value = "us-east-1")]
+    pub receipt_store_region: String,
+    /// Access key of the receipt store (HMAC access id for Google Cloud Storage)
+    #[clap(long, env, requires = "receipt_store_url")]
+    pub receipt_store_access_key: Option<String>,
+    /// Secret key of the receipt store (HMAC secret for Google Cloud Storage)
+    #[clap(long, env, requires = "receipt_store_url")]
+    pub receipt_store_secret_key: Option<String>,
+}
+
+/// A bucket of proof files shared by a fleet of validators, keyed by the proof file name derived
+/// from the image id and journal they commit to
+#[derive(Clone, Debug)]
+pub(crate) struct ReceiptStore {
+    bucket: Bucket,
+}
+
+impl ReceiptStore {
+    /// Returns None if no receipt store was configured
+    pub fn from_args(args: &ReceiptStoreArgs) -> anyhow::Result<Option<Self>> {
+        let Some(url) = &args.receipt_store_url else {
+            return Ok(None);
+        };
+        let bucket = Bucket::connect(BucketConfig {
+            url,
+            endpoint: args.receipt_store_endpoint.as_deref(),
+            region: &args.receipt_store_region,
+            access_key: args.receipt_store_access_key.as_deref(),
+            secret_key: args.receipt_store_secret_key.as_deref(),
+        })
+        .context("receipt store")?;
+        info!("Sharing receipts through {url}.");
+        Ok(Some(Self { bucket }))
+    }
+
+    /// Downloads the proof of the job into its proof file if a peer already published it.
+    /// Returns whether the job can be skipped.
+    pub async fn fetch(&self, job: &ProvingJob) -> bool {
+        match self.download(job).await {
+            Ok(true) => {
+                counter!("kailua_receipt_store_lookups_total", "result" => "hit").increment(1);
+                info!(correlation_id = %job.correlation_id, "Reusing proof for local index {} from receipt store.", job.proposal_index);
+                true
+            }
+            Ok(false) => {
+                counter!("kailua_receipt_store_lookups_total", "result" => "miss").increment(1);
+                debug!(correlation_id = %job.correlation_id, "Proof {} not found in receipt store.", job.proof_file_name);
+                false
+            }
+            Err(e) => {
+                counter!("kailua_receipt_store_lookups_total", "result" => "error").increment(1);
+                warn!(correlation_id = %job.correlation_id, "Failed to fetch proof {} from receipt store: {e:?}", job.proof_file_name);
+                false
+            }
+        }
+    }
+
+    async fn download(&self, job: &ProvingJob) -> anyhow::Result<bool> {
+        let Some(data) = self.bucket.get(&job.proof_file_name).await? else {
+            return Ok(false);
+        };
+        // Only accept proofs of the exact claim under this key
+        let proof = Proof::from_file_bytes(&data, job.fpvm_image_id)?;
+        let journal = ProofJournal::decode_packed(proof.journal().as_ref())?;
+        ensure!(
+            fpvm_proof_file_name_from_journal(job.fpvm_image_id, &journal) == job.proof_file_name,
+            "Stored proof commits to a different journal."
+        );
+        tokio::fs::write(&job.proof_file, &data)
+            .await
+            .context("write proof file")?;
+        Ok(true)
+    }
+
+    /// Uploads the proof file of a completed job for peers to reuse
+    pub async fn publish(&self, job: &ProvingJob) {
+        match self.upload(job).await {
+            Ok(()) => {
+                info!(correlation_id = %job.correlation_id, "Published proof {} to receipt store.", job.proof_file_name)
+            }
+            Err(e) => {
+                error!(correlation_id = %job.correlation_id, "Failed to publish proof {} to receipt store: {e:?}", job.proof_file_name)
+            }
+        }
+    }
+
+    async fn upload(&self, job: &ProvingJob) -> anyhow::Result<()> {
+        let data = tokio::fs::read(&job.proof_file)
+            .await
+            .context("read proof file")?;
+        Proof::from_file_bytes(&data, job.fpvm_image_id)?;
+        self.bucket.put(&job.proof_file_name, data).await
+    }
+}
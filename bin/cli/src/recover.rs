@@ -0,0 +1,246 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::providers::beacon::blob_sidecar;
+use alloy::consensus::{Blob, Transaction as _};
+use alloy::network::{EthereumWallet, TransactionBuilder, TransactionBuilder4844};
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::{Transaction, TransactionRequest};
+use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+/// Minimum fee increase in percent required by nodes to replace a blob transaction
+const BLOB_FEE_BUMP_PERCENT: u128 = 100;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RecoverArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Address of the ethereum rpc endpoint to use (eth namespace required)
+    #[clap(long, env)]
+    pub eth_rpc_url: String,
+
+    /// Secret key of the agent wallet whose pending transactions to recover
+    #[clap(long, env)]
+    pub agent_key: String,
+    /// Percentage by which the fees of replacement transactions exceed those of the stuck ones
+    #[clap(long, env, default_value_t = 25)]
+    pub fee_bump_percent: u128,
+    /// Whether to replace stuck transactions with empty self-transfers instead of resubmitting
+    /// them with higher fees
+    #[clap(long, env)]
+    pub cancel: bool,
+    /// Only report the state of the account without sending any transactions
+    #[clap(long, env)]
+    pub dry_run: bool,
+}
+
+/// The transactions of an account in the node's transaction pool
+#[derive(Debug, Default)]
+struct AccountPool {
+    /// Transactions that are executable in sequence from the account nonce
+    pending: BTreeMap<u64, Transaction>,
+    /// Transactions that are not executable due to a nonce gap
+    queued: BTreeMap<u64, Transaction>,
+}
+
+pub async fn recover(args: RecoverArgs) -> anyhow::Result<()> {
+    let agent_signer = LocalSigner::from_str(&args.agent_key)?;
+    let agent_address = agent_signer.address();
+    let agent_wallet = EthereumWallet::from(agent_signer);
+    let agent_provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(&agent_wallet)
+        .on_http(args.eth_rpc_url.as_str().try_into()?);
+
+    // Inspect the account nonces
+    let confirmed_nonce = agent_provider
+        .get_transaction_count(agent_address)
+        .latest()
+        .await
+        .context("get_transaction_count")?;
+    let pending_nonce = agent_provider
+        .get_transaction_count(agent_address)
+        .pending()
+        .await
+        .context("get_transaction_count")?;
+    info!("Account {agent_address} has confirmed nonce {confirmed_nonce} and pending nonce {pending_nonce}.");
+    let account_pool = match fetch_account_pool(&agent_provider, agent_address).await {
+        Ok(account_pool) => account_pool,
+        Err(e) => {
+            warn!("Could not inspect the transaction pool, stuck transactions can only be cancelled: {e:?}");
+            AccountPool::default()
+        }
+    };
+
+    // Transactions that are pending but not included
+    let stuck_nonces = (confirmed_nonce..pending_nonce).collect::<Vec<_>>();
+    for nonce in &stuck_nonces {
+        match account_pool.pending.get(nonce) {
+            Some(tx) => info!(
+                "STUCK: Nonce {nonce} to {:?} with max fee {} and priority fee {:?}.",
+                tx.to(),
+                tx.max_fee_per_gas(),
+                tx.max_priority_fee_per_gas()
+            ),
+            None => info!("STUCK: Nonce {nonce}."),
+        }
+    }
+    // Missing nonces that prevent queued transactions from executing
+    let gap_nonces = match account_pool.queued.keys().last() {
+        Some(last_queued) => (pending_nonce..*last_queued)
+            .filter(|nonce| !account_pool.queued.contains_key(nonce))
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    for nonce in &gap_nonces {
+        info!("GAP: Nonce {nonce} is missing.");
+    }
+    if stuck_nonces.is_empty() && gap_nonces.is_empty() {
+        info!("No stuck transactions or nonce gaps found.");
+        return Ok(());
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+
+    // Replace stuck transactions and fill nonce gaps
+    let fees = agent_provider
+        .estimate_eip1559_fees(None)
+        .await
+        .context("estimate_eip1559_fees")?;
+    let mut transactions = vec![];
+    for nonce in stuck_nonces {
+        let stuck_tx = account_pool.pending.get(&nonce);
+        let is_blob_tx = stuck_tx.is_some_and(|tx| tx.blob_versioned_hashes().is_some());
+        let fee_bump_percent = if is_blob_tx {
+            args.fee_bump_percent.max(BLOB_FEE_BUMP_PERCENT)
+        } else {
+            args.fee_bump_percent
+        };
+        let bump = |fee: u128| fee + fee * fee_bump_percent / 100;
+        // blob transactions can not be resubmitted without their sidecar
+        let mut request = match stuck_tx.filter(|_| !args.cancel && !is_blob_tx) {
+            Some(tx) => TransactionRequest::default()
+                .with_to(tx.to().unwrap_or_default())
+                .with_value(tx.value())
+                .with_input(tx.input().clone())
+                .with_gas_limit(tx.gas_limit()),
+            None => cancellation(agent_address),
+        };
+        request = request
+            .with_nonce(nonce)
+            .with_max_fee_per_gas(
+                stuck_tx
+                    .map(|tx| bump(tx.max_fee_per_gas()))
+                    .unwrap_or_default()
+                    .max(bump(fees.max_fee_per_gas)),
+            )
+            .with_max_priority_fee_per_gas(
+                stuck_tx
+                    .and_then(|tx| tx.max_priority_fee_per_gas())
+                    .map(bump)
+                    .unwrap_or_default()
+                    .max(bump(fees.max_priority_fee_per_gas)),
+            );
+        if is_blob_tx {
+            let blob_base_fee = agent_provider
+                .get_blob_base_fee()
+                .await
+                .context("get_blob_base_fee")?;
+            request = request
+                .with_blob_sidecar(blob_sidecar(vec![Blob::default()])?)
+                .with_max_fee_per_blob_gas(
+                    stuck_tx
+                        .and_then(|tx| tx.max_fee_per_blob_gas())
+                        .map(bump)
+                        .unwrap_or_default()
+                        .max(bump(blob_base_fee)),
+                );
+        }
+        transactions.push(("replace", nonce, request));
+    }
+    for nonce in gap_nonces {
+        let request = cancellation(agent_address)
+            .with_nonce(nonce)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        transactions.push(("fill", nonce, request));
+    }
+
+    let mut failures = 0;
+    for (action, nonce, request) in transactions {
+        match send(&agent_provider, request).await {
+            Ok(tx_hash) => info!("Transaction to {action} nonce {nonce} confirmed: {tx_hash}"),
+            Err(e) => {
+                error!("Failed to {action} nonce {nonce}: {e:?}");
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        anyhow::bail!("Failed to recover {failures} nonce(s).");
+    }
+    info!("Account {agent_address} recovered.");
+    Ok(())
+}
+
+/// An empty self-transfer that only consumes a nonce
+fn cancellation(address: Address) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_to(address)
+        .with_gas_limit(21_000)
+}
+
+async fn send<T: Transport + Clone, P: Provider<T>>(
+    provider: P,
+    request: TransactionRequest,
+) -> anyhow::Result<String> {
+    let receipt = provider
+        .send_transaction(request)
+        .await
+        .context("send_transaction")?
+        .get_receipt()
+        .await
+        .context("get_receipt")?;
+    Ok(receipt.transaction_hash.to_string())
+}
+
+async fn fetch_account_pool<T: Transport + Clone, P: Provider<T>>(
+    provider: P,
+    address: Address,
+) -> anyhow::Result<AccountPool> {
+    let account_pool: BTreeMap<String, BTreeMap<String, Transaction>> = provider
+        .raw_request("txpool_contentFrom".into(), (address,))
+        .await
+        .context("txpool_contentFrom")?;
+    let parse = |section: &str| -> anyhow::Result<BTreeMap<u64, Transaction>> {
+        account_pool
+            .get(section)
+            .into_iter()
+            .flatten()
+            .map(|(nonce, tx)| Ok((nonce.parse::<u64>()?, tx.clone())))
+            .collect()
+    };
+    Ok(AccountPool {
+        pending: parse("pending")?,
+        queued: parse("queued")?,
+    })
+}
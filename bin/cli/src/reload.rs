@@ -0,0 +1,182 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::validate::ValidateArgs;
+use anyhow::{ensure, Context};
+use metrics::counter;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ReloadArgs {
+    /// Json file of settings that override the command line, re-read on SIGHUP or through the
+    /// status api without restarting
+    #[clap(long, env)]
+    pub runtime_config_file: Option<PathBuf>,
+}
+
+/// Settings that can be changed without restarting the validator
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RuntimeConfig {
+    pub op_node_url: Option<String>,
+    pub op_geth_url: Option<String>,
+    pub eth_rpc_url: Option<String>,
+    pub beacon_rpc_url: Option<String>,
+    pub blob_archive_url: Option<String>,
+    pub alert_webhook_urls: Option<Vec<String>>,
+    pub alert_stdout_json: Option<bool>,
+    pub alert_file: Option<PathBuf>,
+    pub alert_pagerduty_routing_key: Option<String>,
+    pub expected_proving_time: Option<u64>,
+    pub proposal_timeout: Option<u64>,
+    pub ingestion_concurrency: Option<usize>,
+    pub proving_cost_per_mcycle: Option<f64>,
+    pub proving_cost_ceiling: Option<f64>,
+}
+
+impl RuntimeConfig {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read(path).context("read runtime config file")?;
+        serde_json::from_slice(&data).context("parse runtime config file")
+    }
+
+    /// Overrides the settings present in this configuration
+    pub fn apply(self, args: &mut ValidateArgs) -> anyhow::Result<()> {
+        let core = &mut args.core;
+        for (url, setting) in [
+            (self.op_node_url, &mut core.op_node_url),
+            (self.op_geth_url, &mut core.op_geth_url),
+            (self.eth_rpc_url, &mut core.eth_rpc_url),
+            (self.beacon_rpc_url, &mut core.beacon_rpc_url),
+        ] {
+            if let Some(url) = url {
+                *setting = url;
+            }
+        }
+        if let Some(url) = self.blob_archive_url {
+            core.blob_archive_url = Some(url);
+        }
+        // reject endpoints the connections cannot be rebuilt with
+        for (name, url) in core.endpoints_mut() {
+            reqwest::Url::parse(url).with_context(|| format!("invalid {name} url"))?;
+        }
+        let alert_args = &mut args.alert_args;
+        if let Some(urls) = self.alert_webhook_urls {
+            alert_args.alert_webhook_urls = urls;
+        }
+        if let Some(stdout_json) = self.alert_stdout_json {
+            alert_args.alert_stdout_json = stdout_json;
+        }
+        if let Some(path) = self.alert_file {
+            alert_args.alert_file = Some(path);
+        }
+        if let Some(routing_key) = self.alert_pagerduty_routing_key {
+            alert_args.alert_pagerduty_routing_key = Some(routing_key);
+        }
+        if let Some(expected_proving_time) = self.expected_proving_time {
+            args.expected_proving_time = expected_proving_time;
+        }
+        if let Some(proposal_timeout) = self.proposal_timeout {
+            args.proposal_timeout = proposal_timeout;
+        }
+        if let Some(ingestion_concurrency) = self.ingestion_concurrency {
+            args.core.ingestion_concurrency = ingestion_concurrency;
+        }
+        let proving_cost_args = &mut args.proving_cost_args;
+        if let Some(price) = self.proving_cost_per_mcycle {
+            proving_cost_args.proving_cost_per_mcycle = Some(price);
+        }
+        if let Some(ceiling) = self.proving_cost_ceiling {
+            proving_cost_args.proving_cost_ceiling = Some(ceiling);
+        }
+        ensure!(
+            proving_cost_args.proving_cost_ceiling.is_none()
+                || proving_cost_args.proving_cost_per_mcycle.is_some(),
+            "proving-cost-ceiling requires proving-cost-per-mcycle"
+        );
+        Ok(())
+    }
+}
+
+/// Publishes the settings of the validator to its tasks each time the runtime configuration is
+/// reloaded
+#[derive(Clone, Debug)]
+pub struct Reloader {
+    /// The settings given on the command line
+    args: ValidateArgs,
+    sender: Arc<watch::Sender<ValidateArgs>>,
+}
+
+impl Reloader {
+    pub fn new(args: ValidateArgs) -> anyhow::Result<Self> {
+        let (sender, _) = watch::channel(runtime_args(&args)?);
+        Ok(Self {
+            args,
+            sender: Arc::new(sender),
+        })
+    }
+
+    /// Returns a receiver of the current settings that is notified of every reload
+    pub fn subscribe(&self) -> watch::Receiver<ValidateArgs> {
+        self.sender.subscribe()
+    }
+
+    /// Re-reads the runtime configuration file, keeping the current settings if it is invalid
+    pub fn reload(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.args.reload_args.runtime_config_file.is_some(),
+            "No runtime config file given."
+        );
+        let result = runtime_args(&self.args);
+        let label = if result.is_ok() { "success" } else { "error" };
+        counter!("kailua_config_reloads_total", "result" => label).increment(1);
+        self.sender.send_replace(result?);
+        info!("Reloaded runtime configuration.");
+        Ok(())
+    }
+
+    /// Reloads the runtime configuration on every SIGHUP
+    pub async fn reload_on_sighup(self) -> anyhow::Result<()> {
+        let mut hangups = signal(SignalKind::hangup()).context("signal")?;
+        while hangups.recv().await.is_some() {
+            info!("Received SIGHUP.");
+            if let Err(e) = self.reload() {
+                error!("Failed to reload runtime configuration: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the settings of the receiver if they were reloaded since they were last seen
+pub fn take_reloaded(receiver: &mut watch::Receiver<ValidateArgs>) -> Option<ValidateArgs> {
+    if !receiver.has_changed().unwrap_or_default() {
+        return None;
+    }
+    Some(receiver.borrow_and_update().clone())
+}
+
+/// Returns the command line settings overridden by the runtime configuration file, if any
+fn runtime_args(args: &ValidateArgs) -> anyhow::Result<ValidateArgs> {
+    let mut runtime_args = args.clone();
+    if let Some(path) = &args.reload_args.runtime_config_file {
+        RuntimeConfig::load(path)?.apply(&mut runtime_args)?;
+    }
+    Ok(runtime_args)
+}
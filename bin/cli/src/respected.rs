@@ -0,0 +1,136 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::alert::{AlertSeverity, Alerts};
+use crate::KAILUA_GAME_TYPE;
+use alloy::network::Network;
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::transports::Transport;
+use anyhow::Context;
+use kailua_contracts::OptimismPortal2;
+use metrics::gauge;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+sol! {
+    #[sol(rpc)]
+    interface IAnchorStateRegistry {
+        function respectedGameType() external view returns (uint32);
+    }
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct RespectedGameTypeArgs {
+    /// Address of the AnchorStateRegistry that holds the respected game type on deployments where
+    /// the OptimismPortal does not
+    #[clap(long, env)]
+    pub anchor_state_registry: Option<Address>,
+    /// Seconds between checks of the respected game type
+    #[clap(long, env, default_value_t = 60)]
+    pub respected_game_type_check_interval: u64,
+}
+
+/// Periodically checks whether Kailua is the game type respected for withdrawals
+pub struct RespectedGameTypeMonitor {
+    /// The contract holding the respected game type
+    source: Address,
+    /// Whether the source is an AnchorStateRegistry instead of the OptimismPortal
+    anchor_state_registry: bool,
+    interval: Duration,
+    last_check: Option<Instant>,
+    /// The last respected game type read, assumed to be Kailua until the first check
+    respected_game_type: u32,
+}
+
+impl RespectedGameTypeMonitor {
+    pub fn new(args: &RespectedGameTypeArgs, portal_address: Address) -> Self {
+        let (source, anchor_state_registry) = match args.anchor_state_registry {
+            Some(registry) => (registry, true),
+            None => (portal_address, false),
+        };
+        Self {
+            source,
+            anchor_state_registry,
+            interval: Duration::from_secs(args.respected_game_type_check_interval),
+            last_check: None,
+            respected_game_type: KAILUA_GAME_TYPE,
+        }
+    }
+
+    /// Whether Kailua was the respected game type as of the last check
+    pub fn is_respected(&self) -> bool {
+        self.respected_game_type == KAILUA_GAME_TYPE
+    }
+
+    /// Reads the respected game type if the interval has elapsed since the last check, and alerts
+    /// whenever Kailua ceases or resumes to be respected
+    pub async fn check_if_due<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        alerts: &Alerts,
+    ) -> anyhow::Result<()> {
+        if self.last_check.is_some_and(|t| t.elapsed() < self.interval) {
+            return Ok(());
+        }
+        self.last_check = Some(Instant::now());
+        let respected_game_type = if self.anchor_state_registry {
+            IAnchorStateRegistry::new(self.source, &provider)
+                .respectedGameType()
+                .call()
+                .await
+                .context("AnchorStateRegistry::respectedGameType")?
+                ._0
+        } else {
+            OptimismPortal2::new(self.source, &provider)
+                .respectedGameType()
+                .call()
+                .await
+                .context("OptimismPortal2::respectedGameType")?
+                ._0
+        };
+        gauge!("kailua_respected_game_type").set(respected_game_type as f64);
+        if respected_game_type == self.respected_game_type {
+            return Ok(());
+        }
+        let was_respected = self.is_respected();
+        self.respected_game_type = respected_game_type;
+        if was_respected {
+            error!("DISRESPECTED: The respected game type changed from {KAILUA_GAME_TYPE} to {respected_game_type}.");
+            alerts
+                .raise(
+                    AlertSeverity::Critical,
+                    "game_type_disrespected",
+                    format!("Kailua ({KAILUA_GAME_TYPE}) is no longer the respected game type of {} ({respected_game_type}).", self.source),
+                )
+                .await;
+        } else if self.is_respected() {
+            info!("Kailua is the respected game type again.");
+            alerts
+                .raise(
+                    AlertSeverity::Warning,
+                    "game_type_respected",
+                    format!(
+                        "Kailua ({KAILUA_GAME_TYPE}) is the respected game type of {} again.",
+                        self.source
+                    ),
+                )
+                .await;
+        } else {
+            error!("DISRESPECTED: The respected game type changed to {respected_game_type}.");
+        }
+        Ok(())
+    }
+}
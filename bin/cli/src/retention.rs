@@ -0,0 +1,173 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::state::ProvenReceipt;
+use crate::db::KailuaDB;
+use crate::layout::{PREIMAGES_DIR, RECEIPTS_DIR};
+use crate::validate::settled_match_reason;
+use alloy::network::Network;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RetentionArgs {
+    /// Seconds to keep the receipt of a proven match after the match was resolved
+    #[clap(long, env, default_value_t = 86400)]
+    pub receipt_retention_secs: u64,
+    /// Maximum total size in bytes of the receipts kept for proven matches
+    #[clap(long, env)]
+    pub receipt_retention_max_bytes: Option<u64>,
+    /// Directory to move collected receipts into instead of deleting them
+    #[clap(long, env)]
+    pub receipt_archive_dir: Option<PathBuf>,
+}
+
+impl RetentionArgs {
+    /// Deletes or archives a receipt file along with its preimage store
+    pub fn collect(&self, receipt_file_name: &str, data_dir: &Path) -> anyhow::Result<()> {
+        let receipt_path = data_dir.join(RECEIPTS_DIR).join(receipt_file_name);
+        if receipt_path.exists() {
+            match &self.receipt_archive_dir {
+                Some(archive_dir) => {
+                    std::fs::create_dir_all(archive_dir).context("create_dir_all")?;
+                    let archive_path = archive_dir.join(receipt_file_name);
+                    // renaming fails across file systems
+                    if std::fs::rename(&receipt_path, &archive_path).is_err() {
+                        std::fs::copy(&receipt_path, &archive_path).context("copy receipt")?;
+                        std::fs::remove_file(&receipt_path).context("remove receipt")?;
+                    }
+                    info!("Archived receipt {receipt_file_name}.");
+                }
+                None => {
+                    std::fs::remove_file(&receipt_path).context("remove receipt")?;
+                    info!("Deleted receipt {receipt_file_name}.");
+                }
+            }
+        }
+        let preimage_dir = data_dir.join(PREIMAGES_DIR).join(receipt_file_name);
+        if preimage_dir.exists() {
+            std::fs::remove_dir_all(&preimage_dir).context("remove preimages")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the receipts of proven matches that exceed the configured retention limits
+pub async fn collect_receipts<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    args: &RetentionArgs,
+    kailua_db: &mut KailuaDB,
+    data_dir: &Path,
+    provider: P,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // mark receipts of newly resolved matches
+    let pending = kailua_db
+        .state
+        .proven_receipts
+        .iter()
+        .filter(|(_, r)| r.resolved_at.is_none())
+        .map(|(name, r)| (name.clone(), *r))
+        .collect::<Vec<_>>();
+    for (receipt_file_name, proven_receipt) in pending {
+        let (Some(contender), Some(proposal)) = (
+            kailua_db.get_local_proposal(&proven_receipt.contender),
+            kailua_db.get_local_proposal(&proven_receipt.proposal),
+        ) else {
+            warn!("Match of receipt {receipt_file_name} missing from database.");
+            continue;
+        };
+        let Some(parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+            warn!("Tournament of receipt {receipt_file_name} missing from database.");
+            continue;
+        };
+        if settled_match_reason(&parent, &contender, &proposal, &provider)
+            .await?
+            .is_some()
+        {
+            if let Some(r) = kailua_db.state.proven_receipts.get_mut(&receipt_file_name) {
+                r.resolved_at = Some(now);
+            }
+        }
+    }
+    // collect receipts of matches resolved long enough ago
+    let mut expired = kailua_db
+        .state
+        .proven_receipts
+        .iter()
+        .filter(|(_, r)| {
+            r.resolved_at
+                .is_some_and(|t| t + args.receipt_retention_secs <= now)
+        })
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    // collect the oldest receipts that exceed the size limit
+    if let Some(max_bytes) = args.receipt_retention_max_bytes {
+        let mut retained = kailua_db
+            .state
+            .proven_receipts
+            .iter()
+            .filter(|(name, _)| !expired.contains(name))
+            .map(|(name, r)| {
+                let size = std::fs::metadata(data_dir.join(RECEIPTS_DIR).join(name))
+                    .map(|m| m.len())
+                    .unwrap_or_default();
+                (r.proven_at, name.clone(), size)
+            })
+            .collect::<Vec<_>>();
+        retained.sort();
+        let mut total_size = retained.iter().map(|(_, _, size)| size).sum::<u64>();
+        for (_, name, size) in retained {
+            if total_size <= max_bytes {
+                break;
+            }
+            total_size -= size;
+            expired.push(name);
+        }
+    }
+    for receipt_file_name in expired {
+        args.collect(&receipt_file_name, data_dir)?;
+        kailua_db.state.proven_receipts.remove(&receipt_file_name);
+    }
+    Ok(())
+}
+
+/// Registers the receipt of a match that was proven on chain for collection
+pub fn track_proven_receipt(
+    kailua_db: &mut KailuaDB,
+    receipt_file_name: String,
+    contender: u64,
+    proposal: u64,
+) {
+    let proven_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    kailua_db
+        .state
+        .proven_receipts
+        .entry(receipt_file_name)
+        .or_insert(ProvenReceipt {
+            contender,
+            proposal,
+            proven_at,
+            resolved_at: None,
+        });
+}
@@ -0,0 +1,143 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::db::proposal::Proposal;
+use crate::db::treasury::Treasury;
+use crate::stall::Stall;
+use alloy::network::Network;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use kailua_contracts::KailuaTournament;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Name of the file in the data directory that accumulates rewards across restarts
+pub const REWARDS_FILE: &str = "rewards.json";
+
+/// Proof status of a match that the contender lost (`U_LOSE_V_WIN`)
+const PROOF_STATUS_U_LOSE_V_WIN: u8 = 2;
+
+/// Bonds of eliminated proposers paid out to the validator as the prover of their matches
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RewardLedger {
+    /// Matches proven by the validator whose loser has not been eliminated yet
+    pub pending: Vec<ProvenMatch>,
+    pub received: Vec<Reward>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProvenMatch {
+    pub tournament: u64,
+    pub loser_index: u64,
+    pub loser_contract: Address,
+    pub loser_proposer: Address,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Reward {
+    pub tournament: u64,
+    /// Index of the proposal whose proposer was eliminated
+    pub proposal_index: u64,
+    pub proposer: Address,
+    /// The bond paid to the validator in wei
+    pub amount: U256,
+}
+
+impl ProvenMatch {
+    pub fn new(
+        tournament: &Proposal,
+        contender: &Proposal,
+        proposal: &Proposal,
+        proof_status: u8,
+    ) -> Self {
+        // the proposal is eliminated unless the contender lost alone
+        let loser = if proof_status == PROOF_STATUS_U_LOSE_V_WIN {
+            contender
+        } else {
+            proposal
+        };
+        Self {
+            tournament: tournament.index,
+            loser_index: loser.index,
+            loser_contract: loser.contract,
+            loser_proposer: loser.proposer,
+        }
+    }
+}
+
+impl RewardLedger {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path).context("read rewards file")?;
+        serde_json::from_slice(&data).context("parse rewards file")
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?).context("write rewards file")
+    }
+
+    pub fn total(&self) -> U256 {
+        self.received.iter().map(|r| r.amount).sum()
+    }
+
+    /// Moves the proven matches whose loser was eliminated to the received rewards
+    ///
+    /// Bonds are transferred to the prover by the treasury when the tournament is pruned, so no
+    /// claim transaction is necessary. Matches whose loser is resolved without being eliminated,
+    /// or that was eliminated by another match, are discarded.
+    pub async fn collect<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        treasury: &mut Treasury,
+    ) -> anyhow::Result<Vec<Reward>> {
+        let mut collected = Vec::new();
+        let mut pending = Vec::new();
+        for proven_match in std::mem::take(&mut self.pending) {
+            let round = treasury
+                .fetch_elimination_round(&provider, proven_match.loser_proposer)
+                .await?;
+            if round == proven_match.loser_index {
+                // the bond is not deducted from the paid amount upon elimination
+                let amount = treasury
+                    .fetch_balance(&provider, proven_match.loser_proposer)
+                    .await?;
+                counter!("kailua_rewards_gwei_total")
+                    .increment((amount / U256::from(1_000_000_000u64)).saturating_to());
+                collected.push(Reward {
+                    tournament: proven_match.tournament,
+                    proposal_index: proven_match.loser_index,
+                    proposer: proven_match.loser_proposer,
+                    amount,
+                });
+            } else if round == 0 {
+                let status = KailuaTournament::new(proven_match.loser_contract, &provider)
+                    .status()
+                    .stall()
+                    .await
+                    ._0;
+                if Proposal::parse_finality(status)?.is_none() {
+                    pending.push(proven_match);
+                }
+            }
+        }
+        self.pending = pending;
+        self.received.extend(collected.iter().cloned());
+        Ok(collected)
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::contract::SolCallBuilder;
+use alloy::network::{Network, TransactionBuilder};
+use alloy::primitives::{Address, Bytes};
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use kailua_contracts::Safe::SafeInstance;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// A batch of transactions in the format imported by the Safe{Wallet} Transaction Builder
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeBundle {
+    pub version: String,
+    pub chain_id: String,
+    /// Creation time in milliseconds
+    pub created_at: u128,
+    pub meta: SafeBundleMeta,
+    pub transactions: Vec<SafeBundleTransaction>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeBundleMeta {
+    pub name: String,
+    pub description: String,
+    pub created_from_safe_address: Address,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SafeBundleTransaction {
+    pub to: Address,
+    /// Value in wei as a decimal string
+    pub value: String,
+    pub data: Bytes,
+}
+
+impl SafeBundle {
+    pub fn new(name: &str, chain_id: u64, safe: Address) -> Self {
+        Self {
+            version: String::from("1.0"),
+            chain_id: chain_id.to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+            meta: SafeBundleMeta {
+                name: name.to_string(),
+                description: String::new(),
+                created_from_safe_address: safe,
+            },
+            transactions: vec![],
+        }
+    }
+
+    /// Appends the call to the bundle instead of sending it
+    pub fn push<T: Transport + Clone, P: Provider<T, N>, C, N: Network>(
+        &mut self,
+        description: &str,
+        txn: SolCallBuilder<T, P, C, N>,
+    ) {
+        info!("Adding {description} to Safe bundle.");
+        let req = txn.into_transaction_request();
+        self.transactions.push(SafeBundleTransaction {
+            to: req.to().unwrap(),
+            value: req.value().unwrap_or_default().to_string(),
+            data: req.input().cloned().unwrap_or_default(),
+        });
+        // list the steps in the order they are executed for the signers to review
+        if !self.meta.description.is_empty() {
+            self.meta.description.push_str("; ");
+        }
+        self.meta.description.push_str(description);
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?).context("write safe bundle file")
+    }
+}
+
+/// Executes the call through the safe, or appends it to the bundle if one is being built
+pub async fn exec_or_bundle_safe_txn<
+    T: Transport + Clone,
+    P1: Provider<T, N>,
+    P2: Provider<T, N>,
+    C,
+    N: Network,
+>(
+    txn: SolCallBuilder<T, P1, C, N>,
+    safe: &SafeInstance<T, P2, N>,
+    from: Address,
+    bundle: Option<&mut SafeBundle>,
+    description: &str,
+) -> anyhow::Result<()> {
+    match bundle {
+        Some(bundle) => {
+            bundle.push(description, txn);
+            Ok(())
+        }
+        None => crate::exec_safe_txn(txn, safe, from)
+            .await
+            .context(description.to_string()),
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::alert::{AlertSeverity, Alerts};
+use crate::db::KailuaDB;
+use crate::providers::optimism::OpNodeProvider;
+use alloy::network::Network;
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::transports::Transport;
+use anyhow::Context;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct SelfAuditArgs {
+    /// Seconds between re-derivations of the outputs of this proposer's unresolved proposals
+    /// (0 disables the self-audit)
+    #[clap(long, env, default_value_t = 600)]
+    pub self_audit_interval: u64,
+    /// Secret key of a second L1 wallet to propose corrected outputs from once a proposal of this
+    /// proposer is found faulty, as the later proposals of a faulty proposer are ignored
+    #[clap(long, env)]
+    pub corrective_proposer_key: Option<String>,
+}
+
+/// Periodically checks the unresolved proposals made by this proposer against the op-node, which
+/// may have since been fixed to report different outputs
+pub struct SelfAudit {
+    interval: u64,
+    last_audit: Option<Instant>,
+}
+
+impl SelfAudit {
+    pub fn new(args: &SelfAuditArgs) -> Self {
+        Self {
+            interval: args.self_audit_interval,
+            last_audit: None,
+        }
+    }
+
+    /// Re-derives the outputs of the proposer's unresolved canonical proposals if due, and
+    /// revokes the earliest one found faulty from the canonical chain, returning its index
+    pub async fn audit_if_due<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        kailua_db: &mut KailuaDB,
+        proposer: Address,
+        op_node_provider: &OpNodeProvider,
+        provider: P,
+        alerts: &Alerts,
+    ) -> anyhow::Result<Option<u64>> {
+        if self.interval == 0
+            || self
+                .last_audit
+                .is_some_and(|t| t.elapsed() < Duration::from_secs(self.interval))
+        {
+            return Ok(None);
+        }
+        self.last_audit = Some(Instant::now());
+        // ordered from the canonical tip up to the earliest unresolved proposal
+        let unresolved = kailua_db
+            .unresolved_canonical_proposals(&provider)
+            .await
+            .context("unresolved_canonical_proposals")?;
+        for (position, index) in unresolved.iter().enumerate().rev() {
+            let mut proposal = kailua_db
+                .get_local_proposal(index)
+                .context("proposal missing")?;
+            if proposal.proposer != proposer || !proposal.has_parent() {
+                continue;
+            }
+            // the ancestors of this proposal were found correct in earlier iterations
+            let correct = proposal
+                .assess_correctness(&kailua_db.config, op_node_provider, true)
+                .await
+                .context("assess_correctness")?;
+            if correct != Some(false) {
+                continue;
+            }
+            error!(
+                "SELF-AUDIT FAILURE: Proposal {index} of this proposer at l2 block {} contradicts the op-node.",
+                proposal.output_block_number
+            );
+            // reassess siblings that may have been rejected because of the same faulty outputs
+            let parent = kailua_db
+                .get_local_proposal(&proposal.parent)
+                .context("parent missing")?;
+            for child in &parent.children {
+                let Some(mut sibling) = kailua_db.get_local_proposal(child) else {
+                    continue;
+                };
+                if sibling.index == proposal.index
+                    || sibling.output_block_number != proposal.output_block_number
+                {
+                    continue;
+                }
+                sibling
+                    .assess_correctness(&kailua_db.config, op_node_provider, true)
+                    .await
+                    .context("assess_correctness")?;
+                kailua_db.set_local_proposal(sibling.index, &sibling)?;
+            }
+            let output_block_number = proposal.output_block_number;
+            kailua_db.revoke_canonical(proposal, &unresolved[..position])?;
+            alerts
+                .raise_incident(
+                    AlertSeverity::Critical,
+                    "own_proposal_faulty",
+                    format!("own_proposal_faulty-{index}"),
+                    format!("Proposal {index} of proposer {proposer} at l2 block {output_block_number} contradicts the op-node and will not be defended."),
+                )
+                .await;
+            return Ok(Some(*index));
+        }
+        info!(
+            "Self-audit of {} unresolved canonical proposals passed.",
+            unresolved.len()
+        );
+        Ok(None)
+    }
+}
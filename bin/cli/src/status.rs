@@ -0,0 +1,285 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::costs::{ProvingCostLedger, PROVING_COSTS_FILE};
+use crate::db::KailuaDB;
+use crate::layout::chain_data_dir;
+use crate::providers::beacon::BlobProvider;
+use crate::providers::optimism::OpNodeProvider;
+use crate::proxy::ProxiedContract;
+use crate::rewards::{RewardLedger, REWARDS_FILE};
+use crate::validate::{ProposalQuarantine, PROPOSAL_QUARANTINE_FILE};
+use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::ProviderBuilder;
+use anyhow::Context;
+use kailua_common::client::config_hash;
+use kailua_contracts::*;
+use kailua_host::fetch_rollup_config;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use tracing::info;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct StatusArgs {
+    #[clap(flatten)]
+    pub core: CoreArgs,
+
+    /// Additional addresses whose treasury bond balances should be reported
+    #[clap(long, env, value_delimiter = ',')]
+    pub bond_addresses: Vec<Address>,
+}
+
+pub async fn status(args: StatusArgs, data_dir: PathBuf) -> anyhow::Result<()> {
+    // initialize blockchain connections
+    let op_node_provider =
+        OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
+    let cl_node_provider = BlobProvider::new(
+        args.core.beacon_rpc_url.as_str(),
+        args.core.blob_archive_url.as_deref(),
+    )
+    .await?;
+    let eth_rpc_provider =
+        ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
+
+    info!("Fetching rollup configuration from rpc endpoints.");
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
+    let rollup_config_hash = config_hash(&config).context("config_hash")?;
+    let data_dir = chain_data_dir(
+        &data_dir,
+        config.l2_chain_id,
+        B256::from(rollup_config_hash),
+    )
+    .context("chain_data_dir")?;
+
+    // load system config
+    let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
+    let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &eth_rpc_provider);
+    ProxiedContract::fetch(&eth_rpc_provider, dgf_address)
+        .await?
+        .check("DisputeGameFactory", None)?;
+    println!(
+        "DISPUTE_GAME_FACTORY: 0x{}",
+        hex::encode_upper(dgf_address.as_slice())
+    );
+    let kailua_game_implementation = dispute_game_factory
+        .gameImpls(KAILUA_GAME_TYPE)
+        .stall()
+        .await
+        .impl_;
+    if kailua_game_implementation.is_zero() {
+        println!("KAILUA_GAME: NOT INSTALLED");
+        return Ok(());
+    }
+    println!(
+        "KAILUA_GAME: 0x{}",
+        hex::encode_upper(kailua_game_implementation.as_slice())
+    );
+
+    // Sync the local database with the chain
+    info!("Loading proposals..");
+    let proposal_quarantine = ProposalQuarantine::load(&data_dir.join(PROPOSAL_QUARANTINE_FILE))
+        .context("ProposalQuarantine::load")?;
+    let proving_costs = ProvingCostLedger::load(&data_dir.join(PROVING_COSTS_FILE))
+        .context("ProvingCostLedger::load")?;
+    let reward_ledger =
+        RewardLedger::load(&data_dir.join(REWARDS_FILE)).context("RewardLedger::load")?;
+    // The local database is scratch space that is destroyed on drop, and a running validator
+    // holds the lock on its own, so status syncs into a private directory.
+    let db_dir = tempfile::tempdir().context("tempdir")?;
+    let mut kailua_db = KailuaDB::init(db_dir.path().to_path_buf(), &dispute_game_factory).await?;
+    kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+    kailua_db
+        .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
+        .await
+        .context("load_proposals")?;
+    println!(
+        "KAILUA_TREASURY: 0x{}",
+        hex::encode_upper(kailua_db.treasury.address.as_slice())
+    );
+    println!("FACTORY_INDEX: {}", kailua_db.state.next_factory_index);
+    println!(
+        "ELIMINATED_PROPOSERS: {}",
+        kailua_db.state.eliminations.len()
+    );
+    println!(
+        "UNATTRIBUTED_PROPOSALS: {}",
+        kailua_db.state.unattributed_proposals.len()
+    );
+    let quarantined = proposal_quarantine
+        .failures
+        .iter()
+        .filter(|(_, failure)| failure.is_quarantined())
+        .collect::<Vec<_>>();
+    println!("QUARANTINED_PROPOSALS: {}", quarantined.len());
+    for (proposal_index, failure) in quarantined {
+        println!(
+            "QUARANTINED_PROPOSAL: {proposal_index} ({} failures, last error: {})",
+            failure.count, failure.error
+        );
+    }
+    let total_cost = proving_costs.total();
+    println!(
+        "PROVING_COSTS: {} disputes, {} jobs, {} cycles, {:.0}s, ${:.2}",
+        proving_costs.disputes.len(),
+        total_cost.jobs,
+        total_cost.total_cycles,
+        total_cost.proving_secs,
+        total_cost.estimated_cost
+    );
+    for (proposal_index, cost) in &proving_costs.disputes {
+        println!(
+            "PROVING_COST {proposal_index}: {} jobs, {} cycles, {:.0}s, ${:.2}, backends {:?}",
+            cost.jobs, cost.total_cycles, cost.proving_secs, cost.estimated_cost, cost.backends
+        );
+    }
+    println!(
+        "REWARDS: {} wei from {} eliminations, {} pending matches",
+        reward_ledger.total(),
+        reward_ledger.received.len(),
+        reward_ledger.pending.len()
+    );
+    for reward in &reward_ledger.received {
+        println!(
+            "REWARD {}: {} wei from proposer {} in tournament {}",
+            reward.proposal_index, reward.amount, reward.proposer, reward.tournament
+        );
+    }
+
+    // Report the canonical chain tip
+    let Some(canonical_tip) = kailua_db.canonical_tip() else {
+        println!("CANONICAL_PROPOSAL: NONE");
+        return Ok(());
+    };
+    println!(
+        "CANONICAL_PROPOSAL: {} (block {}, output {}, contract {})",
+        canonical_tip.index,
+        canonical_tip.output_block_number,
+        canonical_tip.output_root,
+        canonical_tip.contract
+    );
+
+    // Collect the canonical tournaments that may still be in progress
+    let mut tournaments = vec![];
+    let mut cursor = Some(canonical_tip);
+    while let Some(tournament) = cursor {
+        let is_resolved = tournament
+            .fetch_finality(&eth_rpc_provider)
+            .await?
+            .is_some();
+        cursor = tournament
+            .has_parent()
+            .then(|| kailua_db.get_local_proposal(&tournament.parent))
+            .flatten();
+        tournaments.push((tournament, is_resolved));
+        // Tournaments above a resolved proposal are decided
+        if is_resolved {
+            break;
+        }
+    }
+
+    // Report tournament and match states
+    let mut proposers = BTreeSet::from_iter(args.bond_addresses.iter().copied());
+    let mut unproven_matches = 0;
+    let mut next_proof_deadline = None;
+    println!("TOURNAMENTS: {}", tournaments.len());
+    for (tournament, is_resolved) in &tournaments {
+        let resolution = if *is_resolved {
+            String::from("resolved")
+        } else {
+            let duration = tournament
+                .fetch_current_challenger_duration(&eth_rpc_provider)
+                .await?;
+            format!("resolvable in {duration}s")
+        };
+        println!(
+            "TOURNAMENT {}: block {}, {} children, survivor {:?}, {resolution}",
+            tournament.index,
+            tournament.output_block_number,
+            tournament.children.len(),
+            tournament.survivor
+        );
+        let tournament_contract = tournament.tournament_contract_instance(&eth_rpc_provider);
+        for child_index in &tournament.children {
+            let Some(child) = kailua_db.get_local_proposal(child_index) else {
+                continue;
+            };
+            proposers.insert(child.proposer);
+            // Only children with a contender are involved in a match
+            let Some(contender) = child
+                .contender
+                .and_then(|c| kailua_db.get_local_proposal(&c))
+            else {
+                continue;
+            };
+            let (Some(u_index), Some(v_index)) = (
+                tournament.child_index(contender.index),
+                tournament.child_index(child.index),
+            ) else {
+                continue;
+            };
+            let proof_status = tournament_contract
+                .proofStatus(U256::from(u_index), U256::from(v_index))
+                .stall()
+                .await
+                ._0;
+            let contender_duration = contender
+                .fetch_current_challenger_duration(&eth_rpc_provider)
+                .await?;
+            let proposal_duration = child
+                .fetch_current_challenger_duration(&eth_rpc_provider)
+                .await?;
+            if proof_status == 0 {
+                unproven_matches += 1;
+                next_proof_deadline = Some(
+                    next_proof_deadline
+                        .map_or(proposal_duration, |d: u64| d.min(proposal_duration)),
+                );
+            }
+            println!(
+                "MATCH {}: {} vs {}, proof status {proof_status}, contender deadline {contender_duration}s, proposal deadline {proposal_duration}s",
+                tournament.index, contender.index, child.index
+            );
+        }
+    }
+    println!("UNPROVEN_MATCHES: {unproven_matches}");
+    match next_proof_deadline {
+        Some(duration) => println!("NEXT_PROOF_DEADLINE: {duration}s"),
+        None => println!("NEXT_PROOF_DEADLINE: NONE"),
+    }
+
+    // Report bond balances
+    let participation_bond = kailua_db.treasury.fetch_bond(&eth_rpc_provider).await?;
+    println!("PARTICIPATION_BOND: {participation_bond}");
+    for proposer in proposers {
+        let paid_bond = kailua_db
+            .treasury
+            .fetch_balance(&eth_rpc_provider, proposer)
+            .await?;
+        println!(
+            "BOND 0x{}: {paid_bond}",
+            hex::encode_upper(proposer.as_slice())
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use kailua_client::bonsai::is_bonsai_enabled;
+use kailua_client::stats::ProvingStats;
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use std::net::SocketAddr;
+use tracing::info;
+
+const DURATION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0, 7200.0, 14400.0,
+];
+const CYCLE_BUCKETS: &[f64] = &[1e6, 1e7, 5e7, 1e8, 5e8, 1e9, 5e9, 1e10, 5e10];
+const SIZE_BUCKETS: &[f64] = &[1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9];
+
+/// Serves the recorded metrics for prometheus to scrape
+pub fn install_prometheus_exporter(addr: SocketAddr) -> anyhow::Result<()> {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .set_buckets_for_metric(Matcher::Suffix("seconds".to_string()), DURATION_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("cycles".to_string()), CYCLE_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("bytes".to_string()), SIZE_BUCKETS)?
+        .install()
+        .context("PrometheusBuilder::install")?;
+    info!("Serving metrics on {addr}.");
+    Ok(())
+}
+
+/// Returns the name of the backend that proofs are requested from
+pub fn proving_backend(boundless: bool) -> &'static str {
+    if boundless {
+        "boundless"
+    } else if is_bonsai_enabled() {
+        "bonsai"
+    } else {
+        "local"
+    }
+}
+
+/// Counts the failed journal checks of proofs submitted regardless under the permissive policy
+pub fn record_permissive_journal_mismatches(count: u64) {
+    counter!("kailua_permissive_journal_mismatches_total").increment(count);
+}
+
+/// Labels attached to the metrics of a proving job
+#[derive(Clone, Debug)]
+pub struct ProvingLabels {
+    pub chain: String,
+    pub backend: String,
+}
+
+impl ProvingLabels {
+    pub fn record_preflight_duration(&self, secs: f64) {
+        histogram!("kailua_preflight_duration_seconds", "chain" => self.chain.clone(), "backend" => self.backend.clone())
+            .record(secs);
+    }
+
+    /// Records the measurements reported by the client that computed the proof
+    pub fn record_proving_stats(&self, stats: &ProvingStats) {
+        histogram!("kailua_proving_duration_seconds", "chain" => self.chain.clone(), "backend" => stats.backend.clone())
+            .record(stats.proving_secs);
+        if let Some(total_cycles) = stats.total_cycles {
+            histogram!("kailua_executor_cycles", "chain" => self.chain.clone(), "backend" => stats.backend.clone())
+                .record(total_cycles as f64);
+        }
+    }
+
+    pub fn record_wrapping_duration(&self, secs: f64) {
+        histogram!("kailua_wrapping_duration_seconds", "chain" => self.chain.clone(), "backend" => self.backend.clone())
+            .record(secs);
+    }
+
+    pub fn record_receipt_size(&self, bytes: usize) {
+        histogram!("kailua_receipt_size_bytes", "chain" => self.chain.clone(), "backend" => self.backend.clone())
+            .record(bytes as f64);
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::CoreArgs;
+use anyhow::{bail, Context};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct TraceArgs {
+    /// Directory to record the rpc traffic of this run to, or replay it from
+    #[clap(long, env)]
+    pub rpc_trace_dir: Option<PathBuf>,
+    /// Whether to record the rpc traffic to the trace directory or replay it from there
+    #[clap(long, env, value_enum, default_value_t = TraceMode::Record)]
+    pub rpc_trace_mode: TraceMode,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceMode {
+    /// Forwards requests to the configured endpoints and records the exchanges
+    #[default]
+    Record,
+    /// Serves the recorded responses without contacting the configured endpoints
+    Replay,
+}
+
+/// A request made to an endpoint and the response it received
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceExchange {
+    /// The http method, path and normalized body of the request
+    pub request: String,
+    pub status: u16,
+    pub response: String,
+}
+
+/// The local servers standing in for the configured endpoints while tracing
+#[derive(Debug)]
+pub struct TraceSession {
+    servers: Vec<JoinHandle<()>>,
+}
+
+impl Drop for TraceSession {
+    fn drop(&mut self) {
+        for server in &self.servers {
+            server.abort();
+        }
+    }
+}
+
+impl CoreArgs {
+    /// Redirects all endpoints to local servers recording or replaying their traffic, if a
+    /// trace directory is configured
+    pub async fn start_rpc_trace(&mut self) -> anyhow::Result<Option<TraceSession>> {
+        let Some(dir) = self.trace_args.rpc_trace_dir.clone() else {
+            return Ok(None);
+        };
+        let mode = self.trace_args.rpc_trace_mode;
+        std::fs::create_dir_all(&dir).context("Failed to create rpc trace directory")?;
+        info!("Tracing rpc traffic in {mode:?} mode at {}.", dir.display());
+
+        let mut servers = vec![];
+        for (name, url) in self.endpoints_mut() {
+            let file = dir.join(format!("{name}.jsonl"));
+            let endpoint = match mode {
+                TraceMode::Record => TracedEndpoint::record(url.clone(), &file)?,
+                TraceMode::Replay => TracedEndpoint::replay(&file)?,
+            };
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .context("TcpListener::bind")?;
+            let local_url = format!("http://{}", listener.local_addr()?);
+            info!("Serving traced {name} endpoint at {local_url}.");
+            let app = Router::new()
+                .fallback(handle)
+                .with_state(Arc::new(endpoint));
+            servers.push(tokio::spawn(async move {
+                if let Err(err) = axum::serve(listener, app).await {
+                    error!("Traced {name} endpoint stopped: {err:?}");
+                }
+            }));
+            *url = local_url;
+        }
+        Ok(Some(TraceSession { servers }))
+    }
+}
+
+enum TracedEndpoint {
+    Record {
+        upstream: String,
+        client: reqwest::Client,
+        file: Mutex<File>,
+    },
+    Replay {
+        /// The recorded responses to each request, in the order they were received
+        responses: Mutex<HashMap<String, VecDeque<TraceExchange>>>,
+    },
+}
+
+impl TracedEndpoint {
+    fn record(upstream: String, file: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .with_context(|| format!("Failed to open {}", file.display()))?;
+        Ok(Self::Record {
+            upstream: upstream.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+            file: Mutex::new(file),
+        })
+    }
+
+    fn replay(file: &Path) -> anyhow::Result<Self> {
+        let mut responses: HashMap<String, VecDeque<TraceExchange>> = HashMap::new();
+        if file.exists() {
+            let reader = BufReader::new(
+                File::open(file).with_context(|| format!("Failed to open {}", file.display()))?,
+            );
+            for line in reader.lines() {
+                let exchange: TraceExchange = serde_json::from_str(&line?)?;
+                responses
+                    .entry(exchange.request.clone())
+                    .or_default()
+                    .push_back(exchange);
+            }
+        } else {
+            warn!("No rpc trace found at {}.", file.display());
+        }
+        Ok(Self::Replay {
+            responses: Mutex::new(responses),
+        })
+    }
+
+    async fn exchange(
+        &self,
+        method: Method,
+        uri: Uri,
+        request: String,
+        body: Bytes,
+    ) -> anyhow::Result<TraceExchange> {
+        match self {
+            TracedEndpoint::Record {
+                upstream,
+                client,
+                file,
+            } => {
+                let path = uri.path_and_query().map_or("/", |p| p.as_str());
+                let url = if path == "/" {
+                    upstream.clone()
+                } else {
+                    format!("{upstream}{path}")
+                };
+                let response = client
+                    .request(method, url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body)
+                    .send()
+                    .await
+                    .context("send")?;
+                let exchange = TraceExchange {
+                    request,
+                    status: response.status().as_u16(),
+                    response: response.text().await.context("text")?,
+                };
+                let mut file = file.lock().unwrap();
+                writeln!(file, "{}", serde_json::to_string(&exchange)?)?;
+                file.flush()?;
+                Ok(exchange)
+            }
+            TracedEndpoint::Replay { responses } => {
+                let mut responses = responses.lock().unwrap();
+                let Some(queue) = responses.get_mut(&request) else {
+                    bail!("No recorded response to {request}");
+                };
+                // Repeat the last response once the recorded ones run out, e.g. when polling
+                if queue.len() > 1 {
+                    Ok(queue.pop_front().unwrap())
+                } else {
+                    Ok(queue.front().unwrap().clone())
+                }
+            }
+        }
+    }
+}
+
+/// Returns the request with json-rpc ids removed, such that identical calls match on replay
+fn normalize(method: &Method, uri: &Uri, body: &[u8]) -> (String, Option<Value>) {
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let Ok(mut json) = serde_json::from_slice::<Value>(body) else {
+        return (
+            format!("{method} {path} {}", String::from_utf8_lossy(body)),
+            None,
+        );
+    };
+    let ids = match &mut json {
+        Value::Object(call) => call.remove("id"),
+        Value::Array(calls) => Some(Value::Array(
+            calls
+                .iter_mut()
+                .map(|call| call.as_object_mut().and_then(|c| c.remove("id")))
+                .map(Option::unwrap_or_default)
+                .collect(),
+        )),
+        _ => None,
+    };
+    (format!("{method} {path} {json}"), ids)
+}
+
+/// Sets the json-rpc ids of the response to those of the request being served
+fn restore_ids(response: &str, ids: Value) -> String {
+    let Ok(mut json) = serde_json::from_str::<Value>(response) else {
+        return response.to_string();
+    };
+    match (&mut json, ids) {
+        (Value::Object(response), id) if !id.is_array() => {
+            response.insert("id".to_string(), id);
+        }
+        (Value::Array(responses), Value::Array(ids)) => {
+            for (response, id) in responses.iter_mut().zip(ids) {
+                if let Some(response) = response.as_object_mut() {
+                    response.insert("id".to_string(), id);
+                }
+            }
+        }
+        _ => {}
+    }
+    json.to_string()
+}
+
+async fn handle(
+    State(endpoint): State<Arc<TracedEndpoint>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let (request, ids) = normalize(&method, &uri, &body);
+    match endpoint.exchange(method, uri, request, body).await {
+        Ok(exchange) => {
+            let response = match ids {
+                Some(ids) => restore_ids(&exchange.response, ids),
+                None => exchange.response,
+            };
+            let status = StatusCode::from_u16(exchange.status).unwrap_or(StatusCode::BAD_GATEWAY);
+            (
+                status,
+                [(axum::http::header::CONTENT_TYPE, "application/json")],
+                response,
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("Traced request failed: {err:?}");
+            (StatusCode::BAD_GATEWAY, format!("{err:?}")).into_response()
+        }
+    }
+}
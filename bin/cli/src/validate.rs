@@ -12,23 +12,50 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::alert::{AlertArgs, AlertSeverity, Alerts};
+use crate::anomaly::{AnomalyArgs, ChainAnomalies, ChainAnomaly};
+use crate::api::{ProofSubmission, SharedValidatorStatus, ValidatorEvent, ValidatorEvents};
+use crate::audit::{AuditLog, AuditOutcome};
+use crate::backfill::{backfill, BackfillArgs};
+use crate::backup::{Backup, BackupArgs};
+use crate::cadence::PollCadence;
 use crate::channel::DuplexChannel;
+use crate::costs::{ProvingCostLedger, PROVING_COSTS_FILE};
 use crate::db::proposal::Proposal;
-use crate::db::KailuaDB;
+use crate::db::state::MatchDeadline;
+use crate::db::{KailuaDB, L1Confirmation};
+use crate::export::{agent_wallet, ExportArgs, TxExporter};
+use crate::gas::{GasAccountant, TxCategory};
+use crate::heartbeat::{Heartbeat, HeartbeatArgs};
+use crate::layout::{chain_data_dir, PREIMAGES_DIR, RECEIPTS_DIR};
 use crate::providers::beacon::BlobProvider;
 use crate::providers::optimism::OpNodeProvider;
-use crate::{stall::Stall, CoreArgs, KAILUA_GAME_TYPE};
+use crate::proxy::ProxiedContract;
+use crate::receipts::{ReceiptStore, ReceiptStoreArgs};
+use crate::reload::{take_reloaded, ReloadArgs, Reloader};
+use crate::respected::{RespectedGameTypeArgs, RespectedGameTypeMonitor};
+use crate::retention::{collect_receipts, track_proven_receipt, RetentionArgs};
+use crate::rewards::{ProvenMatch, RewardLedger, REWARDS_FILE};
+use crate::telemetry::{
+    install_prometheus_exporter, proving_backend, record_permissive_journal_mismatches,
+    ProvingLabels,
+};
+use crate::wallet::{WalletArgs, WalletMonitor};
+use crate::workers::{JobQueue, WorkerPoolArgs};
+use crate::{stall::Stall, CoreArgs, CONTROL_ROOT, KAILUA_GAME_TYPE, SET_BUILDER_ID};
 use alloy::eips::eip4844::IndexedBlobHash;
 use alloy::eips::BlockNumberOrTag;
 use alloy::network::primitives::BlockTransactionsKind;
-use alloy::network::EthereumWallet;
-use alloy::primitives::{Bytes, FixedBytes, U256};
+use alloy::network::{Network, ReceiptResponse};
+use alloy::primitives::{Address, Bytes, FixedBytes, B256, U256};
 use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
-use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
 use anyhow::{anyhow, bail, Context};
 use boundless_market::storage::StorageProviderConfig;
-use kailua_client::proof::{fpvm_proof_file_name, Proof};
-use kailua_client::BoundlessArgs;
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_client::proof::{fpvm_proof_file_name, fpvm_proof_file_name_from_journal, Proof};
+use kailua_client::stats::ProvingStats;
+use kailua_client::{find_fpvm_elf, BoundlessArgs, ProvingCostArgs, EXIT_CODE_OUTPUT_DIVERGENCE};
 use kailua_common::blobs::hash_to_fe;
 use kailua_common::blobs::BlobFetchRequest;
 use kailua_common::client::config_hash;
@@ -36,17 +63,24 @@ use kailua_common::journal::ProofJournal;
 use kailua_common::precondition::{precondition_hash, PreconditionValidationData};
 use kailua_contracts::*;
 use kailua_host::fetch_rollup_config;
+use kailua_host::registry::FpvmRegistry;
 use op_alloy_protocol::BlockInfo;
-use risc0_zkvm::is_dev_mode;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::sha::Digestible;
+use risc0_zkvm::{is_dev_mode, Groth16ReceiptVerifierParameters};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::process::exit;
-use std::str::FromStr;
-use std::time::Duration;
+use std::process::{exit, ExitStatus};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
-use tokio::time::sleep;
-use tokio::{spawn, try_join};
+use tokio::sync::mpsc::Sender;
+use tokio::time::{sleep, timeout};
+use tokio::{select, spawn, try_join};
 use tracing::{debug, error, info, warn};
 
 #[derive(clap::Args, Debug, Clone)]
@@ -54,13 +88,128 @@ pub struct ValidateArgs {
     #[clap(flatten)]
     pub core: CoreArgs,
 
-    /// Path to the kailua host binary to use for proving
-    #[clap(long, env)]
-    pub kailua_host: PathBuf,
+    /// Path to the kailua host binary to use for proving (Defaults to the embedded host when built
+    /// with the embedded-host feature)
+    #[clap(long, env, required = !cfg!(feature = "embedded-host"))]
+    pub kailua_host: Option<PathBuf>,
 
     /// Secret key of L1 wallet to use for challenging and proving outputs
+    #[clap(long, env, required_unless_present = "unsigned_tx_sender")]
+    pub validator_key: Option<String>,
+
+    /// Whether to prove the validity of new canonical proposals for fast finality
+    #[clap(long, env, default_value_t = false)]
+    pub fast_finality: bool,
+    /// Only prove the validity of proposals made by this address (e.g. this validator's own)
+    #[clap(long, env, requires = "fast_finality")]
+    pub fast_finality_proposer: Option<Address>,
+
+    /// Directory of FPVM ELFs to prove with for games that expect a different image id
+    #[clap(long, env)]
+    pub fpvm_elf_dir: Option<PathBuf>,
+    /// Registry file of known FPVM builds, consulted before scanning the ELF directory
+    #[clap(long, env)]
+    pub fpvm_registry: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub proving_cost_args: ProvingCostArgs,
+
+    #[clap(flatten)]
+    pub worker_pool_args: WorkerPoolArgs,
+
+    #[clap(flatten)]
+    pub receipt_store_args: ReceiptStoreArgs,
+
+    /// Only ingest proposals created in finalized L1 blocks
+    #[clap(
+        long,
+        env,
+        default_value_t = false,
+        conflicts_with = "l1_confirmations"
+    )]
+    pub l1_finalized_only: bool,
+    /// Only ingest proposals created in L1 blocks with at least this many confirmations
+    #[clap(long, env)]
+    pub l1_confirmations: Option<u64>,
+
+    /// Whether to also verify intermediate outputs through the proposal contracts after verifying
+    /// them locally
+    #[clap(long, env, default_value_t = false)]
+    pub onchain_output_check: bool,
+    /// Whether to dry-run each seal against the on-chain verifier through an `eth_call` before
+    /// submitting it
+    #[clap(long, env, default_value_t = false)]
+    pub onchain_seal_check: bool,
+
+    /// Expected number of seconds needed to generate a proof, used to alert on approaching deadlines
+    #[clap(long, env, default_value_t = 3600)]
+    pub expected_proving_time: u64,
+
+    /// Seconds after which kailua-host abandons its native client run, e.g. when stuck on an
+    /// unresponsive rpc endpoint
+    #[clap(long, env)]
+    pub native_client_timeout: Option<u64>,
+
+    #[clap(flatten)]
+    pub retention_args: RetentionArgs,
+
+    #[clap(flatten)]
+    pub backup_args: BackupArgs,
+
+    #[clap(flatten)]
+    pub backfill_args: BackfillArgs,
+
+    /// Socket address on which to serve prometheus metrics
+    #[clap(long, env)]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Socket address on which to serve the json status api
+    #[clap(long, env)]
+    pub status_api_addr: Option<SocketAddr>,
+
+    /// Seconds after which processing a single proposal is aborted and retried later
+    #[clap(long, env, default_value_t = 300)]
+    pub proposal_timeout: u64,
+
+    /// Seconds between summaries of the gas spent by submitted transactions
+    #[clap(long, env, default_value_t = 3600)]
+    pub gas_report_interval: u64,
+
+    #[clap(flatten)]
+    pub alert_args: AlertArgs,
+
+    #[clap(flatten)]
+    pub reload_args: ReloadArgs,
+
+    #[clap(flatten)]
+    pub anomaly_args: AnomalyArgs,
+
+    #[clap(flatten)]
+    pub heartbeat_args: HeartbeatArgs,
+
+    #[clap(flatten)]
+    pub wallet_args: WalletArgs,
+
+    #[clap(flatten)]
+    pub respected_game_type_args: RespectedGameTypeArgs,
+
+    #[clap(flatten)]
+    pub export_args: ExportArgs,
+
+    /// How to handle proof journals that fail consistency checks against on-chain data
+    #[clap(long, env, value_enum, default_value_t = JournalCheckPolicy::Permissive)]
+    pub journal_check_policy: JournalCheckPolicy,
+
+    /// Secondary op-node used to clear a detected divergence of the local op-node
     #[clap(long, env)]
-    pub validator_key: String,
+    pub op_node_cross_check_url: Option<String>,
+    /// Clear a previously detected divergence of the local op-node
+    #[clap(long, env, default_value_t = false)]
+    pub clear_op_node_divergence: bool,
+
+    /// Allow running in RISC0_DEV_MODE against a verifier that does not accept mock proofs
+    #[clap(long, env, default_value_t = false)]
+    pub i_know_this_is_devnet: bool,
 
     #[clap(flatten)]
     pub boundless_args: Option<BoundlessArgs>,
@@ -69,17 +218,76 @@ pub struct ValidateArgs {
     pub boundless_storage_config: Option<StorageProviderConfig>,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JournalCheckPolicy {
+    /// Abort the submission of proofs that fail any check
+    Strict,
+    /// Submit proofs regardless of failed checks
+    #[default]
+    Permissive,
+}
+
 pub async fn validate(args: ValidateArgs, data_dir: PathBuf) -> anyhow::Result<()> {
     // We run two concurrent tasks, one for the chain, and one for the prover.
     // Both tasks communicate using the duplex channel
     let channel_pair = DuplexChannel::new_pair(4096);
 
+    if let Some(addr) = args.metrics_addr {
+        install_prometheus_exporter(addr).context("install_prometheus_exporter")?;
+    }
+
+    // Keep the data of each chain apart
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
+    let rollup_config_hash = config_hash(&config).context("config_hash")?;
+    let data_dir = chain_data_dir(
+        &data_dir,
+        config.l2_chain_id,
+        B256::from(rollup_config_hash),
+    )
+    .context("chain_data_dir")?;
+
+    // Audit a historical range of games instead of validating the chain
+    if args.backfill_args.from_index.is_some() {
+        return backfill(args, data_dir).await;
+    }
+
+    // Recover the state of a previous deployment before either task reads it
+    if let Some(backup) = Backup::from_args(&args.backup_args).context("Backup::from_args")? {
+        backup.restore(&data_dir).await.context("Backup::restore")?;
+    }
+
+    // Both tasks follow the settings reloaded at runtime
+    let reloader = Reloader::new(args).context("Reloader::new")?;
+    spawn({
+        let reloader = reloader.clone();
+        async move {
+            if let Err(err) = reloader.reload_on_sighup().await {
+                error!("Runtime config reloading failure: {err:?}");
+            }
+        }
+    });
+
+    // Both tasks publish their decisions to the same event stream
+    let validator_events = ValidatorEvents::default();
     let handle_proposals = spawn(handle_proposals(
         channel_pair.0,
-        args.clone(),
+        reloader.clone(),
         data_dir.clone(),
+        validator_events.clone(),
+    ));
+    let handle_proofs = spawn(handle_proofs(
+        channel_pair.1,
+        reloader,
+        data_dir,
+        validator_events,
     ));
-    let handle_proofs = spawn(handle_proofs(channel_pair.1, args, data_dir));
 
     let (proposals_task, proofs_task) = try_join!(handle_proposals, handle_proofs)?;
     proposals_task.context("handle_proposals")?;
@@ -94,7 +302,9 @@ pub enum Message {
     // The proposal and its parent
     Proposal {
         index: u64,
+        fpvm_image_id: B256,
         precondition_validation_data: Option<PreconditionValidationData>,
+        precondition_version: u8,
         l1_head: FixedBytes<32>,
         agreed_l2_head_hash: FixedBytes<32>,
         agreed_l2_output_root: FixedBytes<32>,
@@ -104,47 +314,71 @@ pub enum Message {
     Proof(u64, Proof),
 }
 
+/// Seconds to wait between checks for the installation of the fault proof game
+const GAME_INSTALLATION_POLL_SECS: u64 = 30;
+
 pub async fn handle_proposals(
     mut channel: DuplexChannel<Message>,
-    args: ValidateArgs,
+    reloader: Reloader,
     data_dir: PathBuf,
+    validator_events: ValidatorEvents,
 ) -> anyhow::Result<()> {
+    let mut reloaded_args = reloader.subscribe();
+    let mut args = reloaded_args.borrow_and_update().clone();
+    let mut alerts = Alerts::from_args(&args.alert_args);
     // initialize blockchain connections
     info!("Initializing rpc connections.");
-    let op_node_provider =
+    let mut op_node_provider =
         OpNodeProvider(ProviderBuilder::new().on_http(args.core.op_node_url.as_str().try_into()?));
-    let eth_rpc_provider =
+    let mut eth_rpc_provider =
         ProviderBuilder::new().on_http(args.core.eth_rpc_url.as_str().try_into()?);
-    let op_geth_provider =
+    let mut op_geth_provider =
         ProviderBuilder::new().on_http(args.core.op_geth_url.as_str().try_into()?);
-    let cl_node_provider = BlobProvider::new(args.core.beacon_rpc_url.as_str()).await?;
+    let mut cl_node_provider = BlobProvider::new(
+        args.core.beacon_rpc_url.as_str(),
+        args.core.blob_archive_url.as_deref(),
+    )
+    .await?;
 
     info!("Fetching rollup configuration from rpc endpoints.");
     // fetch rollup config
-    let config = fetch_rollup_config(&args.core.op_node_url, &args.core.op_geth_url, None)
-        .await
-        .context("fetch_rollup_config")?;
+    let config = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await
+    .context("fetch_rollup_config")?;
     let rollup_config_hash = config_hash(&config).expect("Configuration hash derivation error");
+    let proving_labels = ProvingLabels {
+        chain: config.l2_chain_id.to_string(),
+        backend: proving_backend(args.boundless_args.is_some()).to_string(),
+    };
     info!("RollupConfigHash({})", hex::encode(rollup_config_hash));
 
     // load system config
     let system_config = SystemConfig::new(config.l1_system_config_address, &eth_rpc_provider);
     let dgf_address = system_config.disputeGameFactory().stall().await.addr_;
+    let portal_address = system_config.optimismPortal().stall().await.addr_;
 
     // initialize validator wallet
     info!("Initializing validator wallet.");
-    let validator_signer = LocalSigner::from_str(&args.validator_key)?;
-    let validator_address = validator_signer.address();
-    let validator_wallet = EthereumWallet::from(validator_signer);
-    let validator_provider = ProviderBuilder::new()
+    let (validator_address, validator_wallet) =
+        agent_wallet(args.validator_key.as_deref(), &args.export_args)?;
+    let mut validator_provider = ProviderBuilder::new()
         .with_recommended_fillers()
-        .wallet(validator_wallet)
+        .wallet(validator_wallet.clone())
         .on_http(args.core.eth_rpc_url.as_str().try_into()?);
     info!("Validator address: {validator_address}");
 
     // Init factory contract
-    let dispute_game_factory = IDisputeGameFactory::new(dgf_address, &validator_provider);
+    let mut dispute_game_factory =
+        IDisputeGameFactory::new(dgf_address, validator_provider.clone());
     info!("DisputeGameFactory({:?})", dispute_game_factory.address());
+    ProxiedContract::fetch(&eth_rpc_provider, dgf_address)
+        .await?
+        .check("DisputeGameFactory", Some(validator_address))?;
     let game_count: u64 = dispute_game_factory
         .gameCount()
         .stall()
@@ -152,102 +386,343 @@ pub async fn handle_proposals(
         .gameCount_
         .to();
     info!("There have been {game_count} games created using DisputeGameFactory");
-    let kailua_game_implementation = KailuaGame::new(
-        dispute_game_factory
+    // Wait for the game type to be activated if the validator is deployed ahead of it
+    let mut game_not_installed_alerted = false;
+    let kailua_game_implementation = loop {
+        let game_implementation = dispute_game_factory
             .gameImpls(KAILUA_GAME_TYPE)
             .stall()
             .await
-            .impl_,
-        &validator_provider,
-    );
+            .impl_;
+        if !game_implementation.is_zero() {
+            break KailuaGame::new(game_implementation, &validator_provider);
+        }
+        error!("Fault proof game is not installed! Re-checking in {GAME_INSTALLATION_POLL_SECS} seconds.");
+        if !game_not_installed_alerted {
+            alerts
+                .raise(
+                    AlertSeverity::Critical,
+                    "game_not_installed",
+                    String::from("Fault proof game is not installed. Waiting for its activation."),
+                )
+                .await;
+            game_not_installed_alerted = true;
+        }
+        sleep(Duration::from_secs(GAME_INSTALLATION_POLL_SECS)).await;
+    };
     info!("KailuaGame({:?})", kailua_game_implementation.address());
-    if kailua_game_implementation.address().is_zero() {
-        error!("Fault proof game is not installed!");
-        exit(1);
+    ProxiedContract::fetch(&eth_rpc_provider, *kailua_game_implementation.address())
+        .await?
+        .check("KailuaGame", Some(validator_address))?;
+    // Check that the verifier accepts proofs from the linked zkVM version
+    info!(
+        "Using risc0 zkVM {} with FPVM image id {}.",
+        risc0_zkvm::get_version().unwrap(),
+        Digest::from(KAILUA_FPVM_ID)
+    );
+    if !is_dev_mode() {
+        let verifier_address = kailua_game_implementation
+            .verifier()
+            .stall()
+            .await
+            .verifier_;
+        check_verifier_compatibility(
+            verifier_address,
+            &validator_provider,
+            args.boundless_args.is_some(),
+        )
+        .await
+        .context("check_verifier_compatibility")?;
+    }
+    // Refuse to submit fake proofs to a verifier that cannot accept them
+    if is_dev_mode() {
+        let verifier_address = kailua_game_implementation
+            .verifier()
+            .stall()
+            .await
+            .verifier_;
+        if is_mock_verifier(verifier_address, &validator_provider).await {
+            warn!("RISC0_DEV_MODE is set and RiscZeroVerifierRouter({verifier_address}) accepts mock proofs.");
+        } else if args.i_know_this_is_devnet {
+            warn!("RISC0_DEV_MODE is set but RiscZeroVerifierRouter({verifier_address}) does not accept mock proofs.");
+        } else {
+            error!("RISC0_DEV_MODE is set but RiscZeroVerifierRouter({verifier_address}) does not accept mock proofs. Pass --i-know-this-is-devnet to override.");
+            exit(1);
+        }
     }
+    // Check that proofs can be generated for the image id expected by the game
+    let fpvm_registry = load_fpvm_registry(&args)?;
+    check_fpvm_image_id(
+        &args,
+        &fpvm_registry,
+        kailua_game_implementation.imageId().stall().await.imageId_,
+    )?;
+    let mut kailua_game_implementation_address = *kailua_game_implementation.address();
     // Initialize empty DB
     info!("Initializing..");
-    let mut kailua_db = KailuaDB::init(data_dir, &dispute_game_factory).await?;
+    // Load any unresolved divergence of the local op-node
+    let divergence_file = data_dir.join(OP_NODE_DIVERGENCE_FILE);
+    if args.clear_op_node_divergence && divergence_file.exists() {
+        warn!("Manually clearing local op-node divergence.");
+        std::fs::remove_file(&divergence_file).context("remove divergence file")?;
+    }
+    let mut op_node_divergence = OpNodeDivergence::load(&divergence_file)?;
+    if let Some(divergence) = &op_node_divergence {
+        error!("HALTED: Unresolved local op-node divergence {divergence:?}. Pass --clear-op-node-divergence to clear.");
+        alerts
+            .raise(
+                AlertSeverity::Critical,
+                "op_node_divergence",
+                format!("Proof submissions halted by unresolved local op-node divergence {divergence:?}."),
+            )
+            .await;
+    }
+    let cross_check_provider = match &args.op_node_cross_check_url {
+        Some(url) => Some(OpNodeProvider(
+            ProviderBuilder::new().on_http(url.as_str().try_into()?),
+        )),
+        None => None,
+    };
+    let mut withheld_proofs = Vec::new();
+    let mut total_journal_mismatches = 0u64;
+    let mut last_reorg_check_block = 0u64;
+    // start with an empty quarantine because all proposals are reloaded
+    let quarantine_file = data_dir.join(PROPOSAL_QUARANTINE_FILE);
+    let mut proposal_quarantine = ProposalQuarantine::default();
+    proposal_quarantine.save(&quarantine_file)?;
+    // serve the validator status if requested
+    let validator_status = SharedValidatorStatus::default();
+    if let Some(addr) = args.status_api_addr {
+        let validator_status = validator_status.clone();
+        let validator_events = validator_events.clone();
+        let reloader = reloader.clone();
+        spawn(async move {
+            if let Err(err) =
+                crate::api::serve(addr, validator_status, validator_events, reloader).await
+            {
+                error!("Status api failure: {err:?}");
+            }
+        });
+    }
+    let mut kailua_db = KailuaDB::init(data_dir.clone(), &dispute_game_factory).await?;
+    kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+    kailua_db.chain_anomalies = ChainAnomalies::new(args.anomaly_args.clone());
     info!("KailuaTreasury({:?})", kailua_db.treasury.address);
+    if args.fast_finality && !kailua_db.config.revision.validity_proofs {
+        warn!("Fast finality disabled as the deployed contracts do not accept validity proofs.");
+    }
+    let mut gas_accountant = GasAccountant::new(args.gas_report_interval);
+    let audit_log = AuditLog::new(&data_dir);
+    // Rewards accumulate across restarts
+    let rewards_file = data_dir.join(REWARDS_FILE);
+    let mut reward_ledger = RewardLedger::load(&rewards_file).context("RewardLedger::load")?;
+    let mut heartbeat = Heartbeat::new(&args.heartbeat_args);
+    let mut wallet_monitor =
+        WalletMonitor::new(&args.wallet_args, validator_address, &args.core.eth_rpc_url)?;
+    let mut respected_game_type_monitor =
+        RespectedGameTypeMonitor::new(&args.respected_game_type_args, portal_address);
+    let mut tx_exporter = TxExporter::new(&args.export_args, validator_address)?;
+    let mut backup = Backup::from_args(&args.backup_args).context("Backup::from_args")?;
+    kailua_db.l1_confirmation = if args.l1_finalized_only {
+        L1Confirmation::Finalized
+    } else if let Some(confirmations) = args.l1_confirmations {
+        L1Confirmation::Confirmations(confirmations)
+    } else {
+        L1Confirmation::Latest
+    };
+    info!(
+        "Ingesting proposals as of {:?} L1 blocks.",
+        kailua_db.l1_confirmation
+    );
     // Run the validator loop
     info!(
         "Starting from proposal at factory index {}",
         kailua_db.state.next_factory_index
     );
+    let mut poll_cadence = PollCadence::new(&args.core.cadence_args);
     loop {
         // Wait for new data on every iteration
-        sleep(Duration::from_secs(1)).await;
+        poll_cadence.wait(&eth_rpc_provider).await;
+        // reconnect with the reloaded settings
+        if let Some(reloaded) = take_reloaded(&mut reloaded_args) {
+            args = reloaded;
+            alerts = Alerts::from_args(&args.alert_args);
+            kailua_db.ingestion_concurrency = args.core.ingestion_concurrency;
+            // keep the previous connection to any endpoint that cannot be parsed
+            match args.core.op_node_url.as_str().try_into() {
+                Ok(url) => op_node_provider = OpNodeProvider(ProviderBuilder::new().on_http(url)),
+                Err(err) => error!("Failed to reconnect to the op-node endpoint: {err:?}"),
+            }
+            match args.core.eth_rpc_url.as_str().try_into() {
+                Ok(url) => {
+                    eth_rpc_provider = ProviderBuilder::new().on_http(Clone::clone(&url));
+                    validator_provider = ProviderBuilder::new()
+                        .with_recommended_fillers()
+                        .wallet(validator_wallet.clone())
+                        .on_http(url);
+                    dispute_game_factory =
+                        IDisputeGameFactory::new(dgf_address, validator_provider.clone());
+                    wallet_monitor.reconnect(&args.core.eth_rpc_url);
+                }
+                Err(err) => error!("Failed to reconnect to the eth-rpc endpoint: {err:?}"),
+            }
+            match args.core.op_geth_url.as_str().try_into() {
+                Ok(url) => op_geth_provider = ProviderBuilder::new().on_http(url),
+                Err(err) => error!("Failed to reconnect to the op-geth endpoint: {err:?}"),
+            }
+            match BlobProvider::new(
+                args.core.beacon_rpc_url.as_str(),
+                args.core.blob_archive_url.as_deref(),
+            )
+            .await
+            {
+                Ok(provider) => cl_node_provider = provider,
+                Err(err) => error!("Failed to reconnect to the beacon endpoint: {err:?}"),
+            }
+            info!("Applied reloaded settings.");
+        }
         // fetch latest games
         let loaded_proposals = kailua_db
             .load_proposals(&dispute_game_factory, &op_node_provider, &cl_node_provider)
             .await
             .context("load_proposals")?;
-
-        // check new proposals for fault and queue potential responses
-        for proposal_index in loaded_proposals {
-            let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
-                error!("Proposal {proposal_index} missing from database.");
-                continue;
-            };
-            // skip this proposal if it has no contender
-            let Some(contender) = proposal.contender else {
-                continue;
-            };
-            // request a proof of the match results
-            let Some(contender) = kailua_db.get_local_proposal(&contender) else {
-                error!("Contender {contender} missing from database.");
-                continue;
-            };
-            // Look up parent proposal
-            let Some(proposal_parent) = kailua_db.get_local_proposal(&proposal.parent) else {
-                error!(
-                    "Proposal {} parent {} missing from database.",
-                    proposal.index, proposal.parent
-                );
-                continue;
-            };
-            let proposal_parent_contract =
-                proposal_parent.tournament_contract_instance(&validator_provider);
-            // Look up indices of children in parent
-            let Some(u_index) = proposal_parent.child_index(contender.index) else {
-                error!(
-                    "Could not look up contender {} index in parent tournament {}",
-                    contender.index, proposal_parent.index
-                );
-                continue;
-            };
-            let Some(v_index) = proposal_parent.child_index(proposal.index) else {
-                error!(
-                    "Could not look up proposal {} index in parent tournament {}",
-                    proposal.index, proposal_parent.index
-                );
-                continue;
-            };
-            // Check that proof had not already been posted
-            let proof_status = proposal_parent_contract
-                .proofStatus(U256::from(u_index), U256::from(v_index))
+        // check the image id expected by newly installed game versions
+        if !loaded_proposals.is_empty() {
+            let game_implementation = dispute_game_factory
+                .gameImpls(KAILUA_GAME_TYPE)
                 .stall()
                 .await
-                ._0;
-            // Prove if unproven
-            if proof_status == 0 {
-                request_proof(
+                .impl_;
+            if game_implementation != kailua_game_implementation_address {
+                info!("New KailuaGame({game_implementation}) installed.");
+                let fpvm_image_id = KailuaGame::new(game_implementation, &validator_provider)
+                    .imageId()
+                    .stall()
+                    .await
+                    .imageId_;
+                check_fpvm_image_id(&args, &fpvm_registry, fpvm_image_id)?;
+                kailua_game_implementation_address = game_implementation;
+            }
+        }
+
+        // check new proposals for fault and queue potential responses
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let retried_proposals = proposal_quarantine.due_for_retry(now);
+        validator_status
+            .write()
+            .await
+            .update_proposals(&kailua_db, &loaded_proposals);
+        for proposal in loaded_proposals
+            .iter()
+            .filter_map(|i| kailua_db.get_local_proposal(i))
+        {
+            validator_events.emit(ValidatorEvent::ProposalIngested {
+                index: proposal.index,
+                contract: proposal.contract,
+                parent: proposal.parent,
+                output_block_number: proposal.output_block_number,
+                correct: proposal.is_correct(),
+                canonical: proposal.canonical,
+            });
+            if proposal.is_correct() == Some(false) {
+                validator_events.emit(ValidatorEvent::FaultDetected {
+                    index: proposal.index,
+                    contract: proposal.contract,
+                    proposer: proposal.proposer,
+                    output_block_number: proposal.output_block_number,
+                });
+            }
+        }
+        for proposal_index in prioritize_proposals(&kailua_db, loaded_proposals)
+            .into_iter()
+            .chain(retried_proposals)
+        {
+            let result = timeout(
+                Duration::from_secs(args.proposal_timeout),
+                process_proposal(
+                    &args,
+                    &mut kailua_db,
                     &mut channel,
-                    &contender,
-                    &proposal,
+                    proposal_index,
+                    &validator_provider,
                     &eth_rpc_provider,
                     &op_geth_provider,
                     &op_node_provider,
-                )
-                .await?;
-            } else {
-                info!(
-                    "Match between children {u_index} and {v_index} already proven {proof_status}"
-                );
+                ),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("timed out after {}s", args.proposal_timeout)));
+            let updated = match result {
+                Ok(()) => proposal_quarantine.record_success(proposal_index),
+                Err(err) => {
+                    error!("Failed to process proposal {proposal_index}: {err:?}");
+                    proposal_quarantine.record_failure(proposal_index, now, format!("{err:#}"));
+                    true
+                }
+            };
+            if updated {
+                proposal_quarantine.save(&quarantine_file)?;
             }
         }
 
+        // alert on chain anomalies that occur too frequently
+        kailua_db.chain_anomalies.raise_alerts(&alerts).await;
+        // alert on proofs that are running out of time
+        for ((contender_index, proposal_index), message) in
+            check_match_deadlines(&mut kailua_db, args.expected_proving_time)
+        {
+            alerts
+                .raise_incident(
+                    AlertSeverity::Critical,
+                    "proof_deadline",
+                    proof_deadline_incident(contender_index, proposal_index),
+                    message,
+                )
+                .await;
+        }
+        validator_status.write().await.update_health(
+            &kailua_db,
+            op_node_divergence.is_some(),
+            withheld_proofs.len(),
+            proposal_quarantine
+                .failures
+                .values()
+                .filter(|f| f.is_quarantined())
+                .count(),
+        );
+
+        // clean up receipts of resolved matches
+        collect_receipts(
+            &args.retention_args,
+            &mut kailua_db,
+            &data_dir,
+            &validator_provider,
+        )
+        .await
+        .context("collect_receipts")?;
+        if let Some(backup) = &mut backup {
+            backup.snapshot_if_due(&kailua_db, &data_dir);
+        }
+        gas_accountant.report_if_due();
+        heartbeat.beat_if_due(&kailua_db).await;
+        if let Err(e) = wallet_monitor
+            .check_if_due(&validator_provider, &alerts, &audit_log)
+            .await
+        {
+            warn!("Failed to check wallet balance: {e:?}");
+        }
+        if let Err(e) = respected_game_type_monitor
+            .check_if_due(&validator_provider, &alerts)
+            .await
+        {
+            warn!("Failed to check respected game type: {e:?}");
+        }
+
         // publish computed proofs and resolve proven challenges
+        let mut computed_proofs = Vec::new();
         while !channel.receiver.is_empty() {
             let Message::Proof(proposal_index, proof) = channel
                 .receiver
@@ -257,32 +732,82 @@ pub async fn handle_proposals(
             else {
                 bail!("Unexpected message type.");
             };
+            computed_proofs.push((proposal_index, proof));
+        }
+        // resubmit proofs dropped from l1 by a reorg once per l1 block
+        let latest_l1_block = validator_provider
+            .get_block_number()
+            .await
+            .context("get_block_number")?;
+        if latest_l1_block != last_reorg_check_block {
+            last_reorg_check_block = latest_l1_block;
+            kailua_db
+                .chain_anomalies
+                .check_l1_reorg(&validator_provider, latest_l1_block)
+                .await
+                .context("check_l1_reorg")?;
+            computed_proofs.extend(
+                find_reverted_proofs(&mut kailua_db, &data_dir, &validator_provider)
+                    .await
+                    .context("find_reverted_proofs")?,
+            );
+            // account for the bonds paid out for proven matches
+            if !reward_ledger.pending.is_empty() {
+                let rewards = reward_ledger
+                    .collect(&validator_provider, &mut kailua_db.treasury)
+                    .await
+                    .context("RewardLedger::collect")?;
+                for reward in &rewards {
+                    info!(
+                        "Received bond of {} wei for eliminating proposer {} of proposal {}.",
+                        reward.amount, reward.proposer, reward.proposal_index
+                    );
+                }
+                if let Err(e) = reward_ledger.save(&rewards_file) {
+                    warn!("Failed to save rewards: {e:?}");
+                }
+            }
+        }
+        // withhold proofs while the local op-node is untrusted
+        if let Some(divergence) = &op_node_divergence {
+            if divergence
+                .is_cleared(&op_node_provider, cross_check_provider.as_ref())
+                .await?
+            {
+                info!("Local op-node divergence cleared by cross-check.");
+                std::fs::remove_file(&divergence_file).context("remove divergence file")?;
+                op_node_divergence = None;
+            } else {
+                if !computed_proofs.is_empty() {
+                    error!(
+                        "HALTED: Withholding {} proof(s) due to local op-node divergence {divergence:?}.",
+                        computed_proofs.len()
+                    );
+                }
+                withheld_proofs.extend(computed_proofs);
+                continue;
+            }
+        }
+        withheld_proofs.extend(computed_proofs);
+        for (proposal_index, proof) in std::mem::take(&mut withheld_proofs) {
+            if op_node_divergence.is_some() {
+                withheld_proofs.push((proposal_index, proof));
+                continue;
+            }
             let proposal = kailua_db.get_local_proposal(&proposal_index).unwrap();
             let proposal_parent = kailua_db.get_local_proposal(&proposal.parent).unwrap();
             let proposal_parent_contract =
                 proposal_parent.tournament_contract_instance(&validator_provider);
             let proof_journal = ProofJournal::decode_packed(proof.journal().as_ref())?;
             info!("Proof journal: {:?}", proof_journal);
-            let contender_index = proposal.contender.unwrap();
-            let contender = kailua_db.get_local_proposal(&contender_index).unwrap();
-
-            let u_index = proposal_parent
-                .child_index(contender_index)
-                .expect("Could not look up contender's index in parent tournament");
-            let v_index = proposal_parent
-                .child_index(proposal.index)
-                .expect("Could not look up contender's index in parent tournament");
-
-            let challenge_position =
-                proof_journal.claimed_l2_block_number - proposal_parent.output_block_number - 1;
-
             let expected_image_id = proposal_parent_contract.imageId().stall().await.imageId_.0;
+            let receipt_file_name =
+                fpvm_proof_file_name_from_journal(Digest::from(expected_image_id), &proof_journal);
 
             // patch the proof if in dev mode
             #[cfg(feature = "devnet")]
             let proof = if is_dev_mode() || needs_selector_patch(&proof) {
                 use alloy::sol_types::SolValue;
-                use risc0_zkvm::sha::Digestible;
 
                 let mut proof = proof;
                 match &mut proof {
@@ -307,7 +832,7 @@ pub async fn handle_proposals(
                                 if seal.rootSeal.is_empty() {
                                     // build the claim for the fpvm
                                     let fpvm_claim_digest = risc0_zkvm::ReceiptClaim::ok(
-                                        risc0_zkvm::sha::Digest::from(kailua_build::KAILUA_FPVM_ID),
+                                        risc0_zkvm::sha::Digest::from(expected_image_id),
                                         journal.bytes.clone(),
                                     )
                                     .digest();
@@ -378,28 +903,84 @@ pub async fn handle_proposals(
                 }
             }
 
-            let contender_output = contender.output_at(challenge_position);
-            if contender_output != hash_to_fe(proof_journal.claimed_l2_output_root) {
-                warn!(
-                    "Contender output fe {contender_output} doesn't match proof fe {}",
-                    hash_to_fe(proof_journal.claimed_l2_output_root)
-                );
+            // submit validity proofs directly to the parent tournament
+            if proof_journal.precondition_output
+                == proposal.validity_precondition_hash(kailua_db.config.precondition_version())
+            {
+                submit_validity_proof(
+                    &proposal_parent,
+                    &proposal,
+                    &proof_journal,
+                    proof,
+                    &validator_provider,
+                    &proving_labels,
+                    &mut gas_accountant,
+                    &audit_log,
+                    &validator_events,
+                    tx_exporter.as_mut(),
+                    args.onchain_seal_check,
+                )
+                .await?;
+                continue;
             }
+
+            let contender_index = proposal.contender.unwrap();
+            let contender = kailua_db.get_local_proposal(&contender_index).unwrap();
+
+            let u_index = proposal_parent
+                .child_index(contender_index)
+                .expect("Could not look up contender's index in parent tournament");
+            let v_index = proposal_parent
+                .child_index(proposal.index)
+                .expect("Could not look up contender's index in parent tournament");
+
+            let challenge_position =
+                proof_journal.claimed_l2_block_number - proposal_parent.output_block_number - 1;
+
+            // count failed consistency checks of the proof journal
+            let mut journal_mismatches = 0u64;
+            // exactly one side of the match is expected to agree with the proven output
+            let contender_output = contender.output_at(challenge_position);
             let proposal_output = proposal.output_at(challenge_position);
-            if proposal_output != hash_to_fe(proof_journal.claimed_l2_output_root) {
-                warn!(
-                    "Proposal output fe {proposal_output} doesn't match proof fe {}",
-                    hash_to_fe(proof_journal.claimed_l2_output_root)
-                );
+            let proof_output = hash_to_fe(proof_journal.claimed_l2_output_root);
+            match (
+                contender_output == proof_output,
+                proposal_output == proof_output,
+            ) {
+                (false, false) => {
+                    warn!("Neither contender output fe {contender_output} nor proposal output fe {proposal_output} match proof fe {proof_output}");
+                    journal_mismatches += 1;
+                }
+                (true, true) => {
+                    warn!("Both contender and proposal outputs match proof fe {proof_output}");
+                    journal_mismatches += 1;
+                }
+                _ => {}
             }
             let op_node_output = op_node_provider
                 .output_at_block(proof_journal.claimed_l2_block_number)
                 .await?;
             if op_node_output != proof_journal.claimed_l2_output_root {
-                error!(
-                    "Local op node output {op_node_output} doesn't match proof {}",
-                    proof_journal.claimed_l2_output_root
-                );
+                let divergence = OpNodeDivergence {
+                    block_number: proof_journal.claimed_l2_block_number,
+                    op_node_output,
+                    proven_output: proof_journal.claimed_l2_output_root,
+                };
+                error!("CRITICAL: Local op node output {op_node_output} doesn't match proof {}. Halting submissions until the divergence is cleared.", proof_journal.claimed_l2_output_root);
+                kailua_db
+                    .chain_anomalies
+                    .record(ChainAnomaly::OpNodeMismatch);
+                alerts
+                    .raise(
+                        AlertSeverity::Critical,
+                        "op_node_divergence",
+                        format!("Local op node output {op_node_output} doesn't match proof {} at block {}. Proof submissions halted.", proof_journal.claimed_l2_output_root, proof_journal.claimed_l2_block_number),
+                    )
+                    .await;
+                divergence.save(&divergence_file)?;
+                op_node_divergence = Some(divergence);
+                withheld_proofs.push((proposal_index, proof));
+                continue;
             } else {
                 info!(
                     "Proven output matches local op node output {}:{op_node_output}.",
@@ -415,11 +996,43 @@ pub async fn handle_proposals(
                 ._0;
             if proof_status != 0 {
                 warn!("Skipping proof submission for already proven game at local index {proposal_index}.");
+                if let Some(match_deadline) = kailua_db
+                    .state
+                    .match_deadlines
+                    .remove(&(contender_index, proposal.index))
+                {
+                    if match_deadline.alert_level > 1 {
+                        alerts
+                            .resolve_incident(&proof_deadline_incident(
+                                contender_index,
+                                proposal.index,
+                            ))
+                            .await;
+                    }
+                }
+                track_proven_receipt(
+                    &mut kailua_db,
+                    receipt_file_name,
+                    contender_index,
+                    proposal.index,
+                );
                 continue;
             } else {
                 info!("Proof status: {proof_status}");
             }
 
+            // wrap the proof only once it is about to be submitted
+            let wrapping_start = Instant::now();
+            let proof = match proof.wrap_groth16().await {
+                Ok(proof) => {
+                    proving_labels.record_wrapping_duration(wrapping_start.elapsed().as_secs_f64());
+                    proof
+                }
+                Err(e) => {
+                    error!("Failed to wrap proof: {e:?}");
+                    continue;
+                }
+            };
             let encoded_seal = Bytes::from(proof.encoded_seal()?);
 
             // create kzg proofs
@@ -459,6 +1072,7 @@ pub async fn handle_proposals(
                         contender.output_root,
                         contender.output_at(challenge_position)
                     );
+                    journal_mismatches += 1;
                 } else {
                     info!("Contender proposed output confirmed.");
                 }
@@ -468,37 +1082,40 @@ pub async fn handle_proposals(
                         proposal.output_root,
                         proposal.output_at(challenge_position)
                     );
+                    journal_mismatches += 1;
                 } else {
                     info!("Proposal proposed output confirmed.");
                 }
             } else {
-                let contender_has_output = contender_contract
-                    .verifyIntermediateOutput(
-                        challenge_position,
-                        contender.output_at(challenge_position),
-                        commitments[0].last().unwrap().clone(),
-                        proofs[0].last().unwrap().clone(),
-                    )
-                    .stall()
-                    .await
-                    .success;
+                let contender_has_output = verify_intermediate_output(
+                    &contender,
+                    &contender_contract,
+                    challenge_position,
+                    contender.output_at(challenge_position),
+                    commitments[0].last().unwrap(),
+                    proofs[0].last().unwrap(),
+                    args.onchain_output_check,
+                )
+                .await;
                 if !contender_has_output {
                     warn!("Could not verify proposed output for contender");
+                    journal_mismatches += 1;
                 } else {
                     info!("Contender proposed output confirmed.");
                 }
-                let proposal_has_output = proposal_contract
-                    .verifyIntermediateOutput(
-                        challenge_position,
-                        proposal.output_at(challenge_position),
-                        commitments[1].last().unwrap().clone(),
-                        proofs[1].last().unwrap().clone(),
-                    )
-                    .stall()
-                    .await
-                    .success;
+                let proposal_has_output = verify_intermediate_output(
+                    &proposal,
+                    &proposal_contract,
+                    challenge_position,
+                    proposal.output_at(challenge_position),
+                    commitments[1].last().unwrap(),
+                    proofs[1].last().unwrap(),
+                    args.onchain_output_check,
+                )
+                .await;
                 if !proposal_has_output {
                     warn!("Could not verify proposed output for proposal");
+                    journal_mismatches += 1;
                 } else {
                     info!("Proposal proposed output confirmed.");
                 }
@@ -512,36 +1129,39 @@ pub async fn handle_proposals(
                         "Parent claim {} is last common output and does not match {}",
                         proposal_parent.output_root, proof_journal.agreed_l2_output_root
                     );
+                    journal_mismatches += 1;
                 }
                 parent_output_matches
             } else {
-                let contender_has_output = contender_contract
-                    .verifyIntermediateOutput(
-                        challenge_position - 1,
-                        proof_journal.agreed_l2_output_root,
-                        commitments[0].first().unwrap().clone(),
-                        proofs[0].first().unwrap().clone(),
-                    )
-                    .stall()
-                    .await
-                    .success;
+                let contender_has_output = verify_intermediate_output(
+                    &contender,
+                    &contender_contract,
+                    challenge_position - 1,
+                    proof_journal.agreed_l2_output_root,
+                    commitments[0].first().unwrap(),
+                    proofs[0].first().unwrap(),
+                    args.onchain_output_check,
+                )
+                .await;
                 if !contender_has_output {
                     warn!("Could not verify last common output for contender");
+                    journal_mismatches += 1;
                 } else {
                     info!("Contender common output confirmed.");
                 }
-                let proposal_has_output = proposal_contract
-                    .verifyIntermediateOutput(
-                        challenge_position - 1,
-                        proof_journal.agreed_l2_output_root,
-                        commitments[1].first().unwrap().clone(),
-                        proofs[1].first().unwrap().clone(),
-                    )
-                    .stall()
-                    .await
-                    .success;
-                if !proposal_has_output {
-                    warn!("Could not verify last common output for proposal");
+                let proposal_has_output = verify_intermediate_output(
+                    &proposal,
+                    &proposal_contract,
+                    challenge_position - 1,
+                    proof_journal.agreed_l2_output_root,
+                    commitments[1].first().unwrap(),
+                    proofs[1].first().unwrap(),
+                    args.onchain_output_check,
+                )
+                .await;
+                if !proposal_has_output {
+                    warn!("Could not verify last common output for proposal");
+                    journal_mismatches += 1;
                 } else {
                     info!("Proposal common output confirmed.");
                 }
@@ -557,11 +1177,13 @@ pub async fn handle_proposals(
             let possible_precondition_hash = precondition_hash(
                 &contender.io_blob_for(challenge_position).0,
                 &proposal.io_blob_for(challenge_position).0,
+                kailua_db.config.precondition_version(),
             );
             if proofs[0].len() == 2
                 && possible_precondition_hash != proof_journal.precondition_output
             {
                 warn!("Possible precondition hash mismatch. Found {}, computed {possible_precondition_hash}", proof_journal.precondition_output);
+                journal_mismatches += 1;
             } else {
                 info!("Proof Precondition hash confirmed.")
             }
@@ -576,6 +1198,7 @@ pub async fn handle_proposals(
                     "Config hash mismatch. Found {}, expected {config_hash}.",
                     proof_journal.config_hash
                 );
+                journal_mismatches += 1;
             } else {
                 info!("Proof Config hash confirmed.");
             }
@@ -585,6 +1208,7 @@ pub async fn handle_proposals(
                     "L1 head mismatch. Found {}, expected {}.",
                     proof_journal.l1_head, proposal.l1_head
                 );
+                journal_mismatches += 1;
             } else {
                 info!("Proof L1 head confirmed.");
             }
@@ -596,63 +1220,586 @@ pub async fn handle_proposals(
                     "Claimed l2 block number mismatch. Found {}, expected {expected_block_number}.",
                     proof_journal.claimed_l2_block_number
                 );
+                journal_mismatches += 1;
             } else {
                 info!("Claimed l2 block number confirmed.");
             }
 
-            match proposal_parent_contract
-                .prove(
-                    [u_index, v_index, challenge_position],
-                    encoded_seal.clone(),
-                    proof_journal.agreed_l2_output_root,
-                    [
-                        contender.output_at(challenge_position),
-                        proposal.output_at(challenge_position),
-                    ],
-                    proof_journal.claimed_l2_output_root,
-                    commitments,
-                    proofs,
+            if journal_mismatches > 0 {
+                total_journal_mismatches += journal_mismatches;
+                match args.journal_check_policy {
+                    JournalCheckPolicy::Strict => {
+                        error!("Aborting proof submission for local index {proposal_index} after {journal_mismatches} failed journal check(s).");
+                        continue;
+                    }
+                    JournalCheckPolicy::Permissive => {
+                        warn!("Submitting proof for local index {proposal_index} despite {journal_mismatches} failed journal check(s) ({total_journal_mismatches} in total).");
+                        record_permissive_journal_mismatches(journal_mismatches);
+                    }
+                }
+            }
+
+            if args.onchain_seal_check {
+                if let Err(e) = verify_seal_onchain(
+                    &proposal_parent_contract,
+                    &encoded_seal,
+                    &proof.journal().bytes,
                 )
-                .send()
                 .await
-                .context("prove (send)")
-            {
-                Ok(txn) => match txn.get_receipt().await.context("prove (get_receipt)") {
-                    Ok(receipt) => {
-                        info!("Proof submitted: {receipt:?}");
-                        let proof_status = proposal_parent_contract
-                            .proofStatus(U256::from(u_index), U256::from(v_index))
-                            .stall()
-                            .await
-                            ._0;
-                        info!(
-                            "Match between {contender_index} and {} proven: {proof_status}",
-                            proposal.index
-                        );
-                    }
-                    Err(e) => {
-                        error!("Failed to confirm proof txn: {e:?}");
+                {
+                    error!("Aborting proof submission for local index {proposal_index}: {e:?}");
+                    continue;
+                }
+                info!("On-chain verifier accepted proof seal.");
+            }
+
+            let intent = format!(
+                "prove match between {contender_index} and {} in tournament {}",
+                proposal.index, proposal_parent.index
+            );
+            let prove_call = proposal_parent_contract.prove(
+                [u_index, v_index, challenge_position],
+                encoded_seal.clone(),
+                proof_journal.agreed_l2_output_root,
+                [
+                    contender.output_at(challenge_position),
+                    proposal.output_at(challenge_position),
+                ],
+                proof_journal.claimed_l2_output_root,
+                commitments,
+                proofs,
+            );
+            if let Some(tx_exporter) = tx_exporter.as_mut() {
+                if let Err(e) = tx_exporter
+                    .export(
+                        &validator_provider,
+                        &intent,
+                        prove_call.into_transaction_request(),
+                    )
+                    .await
+                {
+                    error!("Failed to export proof txn: {e:?}");
+                }
+                continue;
+            }
+            match prove_call.send().await.context("prove (send)") {
+                Ok(txn) => {
+                    let tx_hash = *txn.tx_hash();
+                    validator_events.emit(ValidatorEvent::TransactionSubmitted {
+                        intent: intent.clone(),
+                        to: proposal_parent.contract,
+                        tx_hash,
+                    });
+                    match txn.get_receipt().await.context("prove (get_receipt)") {
+                        Ok(receipt) => {
+                            info!("Proof submitted: {receipt:?}");
+                            audit_log.record(
+                                intent,
+                                proposal_parent.contract,
+                                prove_call.calldata(),
+                                Some(tx_hash),
+                                AuditOutcome::confirmed(&receipt),
+                            );
+                            gas_accountant.record(TxCategory::Prove, &receipt);
+                            let proof_status = proposal_parent_contract
+                                .proofStatus(U256::from(u_index), U256::from(v_index))
+                                .stall()
+                                .await
+                                ._0;
+                            info!(
+                                "Match between {contender_index} and {} proven: {proof_status}",
+                                proposal.index
+                            );
+                            // this validator is paid the loser's bond if it proved the match
+                            if receipt.status() && proof_status != 0 {
+                                reward_ledger.pending.push(ProvenMatch::new(
+                                    &proposal_parent,
+                                    &contender,
+                                    &proposal,
+                                    proof_status,
+                                ));
+                                if let Err(e) = reward_ledger.save(&rewards_file) {
+                                    warn!("Failed to save rewards: {e:?}");
+                                }
+                            }
+                            validator_status
+                                .write()
+                                .await
+                                .record_submission(ProofSubmission {
+                                    contender: contender_index,
+                                    proposal: proposal.index,
+                                    tx_hash: receipt.transaction_hash,
+                                    proof_status,
+                                    submitted_at: SystemTime::now()
+                                        .duration_since(UNIX_EPOCH)
+                                        .unwrap()
+                                        .as_secs(),
+                                });
+                            heartbeat.last_action = Some(format!(
+                                "proved match between {contender_index} and {}",
+                                proposal.index
+                            ));
+                            if let Some(match_deadline) = kailua_db
+                                .state
+                                .match_deadlines
+                                .remove(&(contender_index, proposal.index))
+                            {
+                                if match_deadline.alert_level > 1 {
+                                    alerts
+                                        .resolve_incident(&proof_deadline_incident(
+                                            contender_index,
+                                            proposal.index,
+                                        ))
+                                        .await;
+                                }
+                            }
+                            // a reverted proof status is detected and resubmitted later
+                            track_proven_receipt(
+                                &mut kailua_db,
+                                receipt_file_name,
+                                contender_index,
+                                proposal.index,
+                            );
+                        }
+                        Err(e) => {
+                            error!("Failed to confirm proof txn: {e:?}");
+                            audit_log.record(
+                                intent,
+                                proposal_parent.contract,
+                                prove_call.calldata(),
+                                Some(tx_hash),
+                                AuditOutcome::unconfirmed(&e),
+                            );
+                            // resubmit the proof if the transaction was dropped
+                            track_proven_receipt(
+                                &mut kailua_db,
+                                receipt_file_name,
+                                contender_index,
+                                proposal.index,
+                            );
+                        }
                     }
-                },
+                }
                 Err(e) => {
                     error!("Failed to send proof txn: {e:?}");
+                    audit_log.record(
+                        intent,
+                        proposal_parent.contract,
+                        prove_call.calldata(),
+                        None,
+                        AuditOutcome::failed(&e),
+                    );
+                    alerts
+                        .raise(
+                            AlertSeverity::Critical,
+                            "proof_failed",
+                            format!(
+                                "Failed to send proof txn for match between {contender_index} and {}: {e:?}",
+                                proposal.index
+                            ),
+                        )
+                        .await;
                 }
             }
         }
     }
 }
 
-async fn request_proof(
+/// Checks a newly loaded proposal for fault and queues potential responses
+#[allow(clippy::too_many_arguments)]
+async fn process_proposal<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    args: &ValidateArgs,
+    kailua_db: &mut KailuaDB,
     channel: &mut DuplexChannel<Message>,
+    proposal_index: u64,
+    validator_provider: P,
+    eth_rpc_provider: &ReqwestProvider,
+    op_geth_provider: &ReqwestProvider,
+    op_node_provider: &OpNodeProvider,
+) -> anyhow::Result<()> {
+    let Some(proposal) = kailua_db.get_local_proposal(&proposal_index) else {
+        error!("Proposal {proposal_index} missing from database.");
+        return Ok(());
+    };
+    // request a validity proof to finalize canonical proposals without waiting
+    if args.fast_finality
+        && kailua_db.config.revision.validity_proofs
+        && proposal.has_parent()
+        && proposal.canonical.unwrap_or_default()
+        && args
+            .fast_finality_proposer
+            .map_or(true, |proposer| proposer == proposal.proposer)
+    {
+        let Some(proposal_parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+            error!(
+                "Proposal {} parent {} missing from database.",
+                proposal.index, proposal.parent
+            );
+            return Ok(());
+        };
+        let proposal_parent_contract =
+            proposal_parent.tournament_contract_instance(&validator_provider);
+        let valid_child = proposal_parent_contract.validChild().stall().await._0;
+        if valid_child.is_zero() {
+            let fpvm_image_id = proposal_parent_contract.imageId().stall().await.imageId_;
+            request_validity_proof(
+                channel,
+                fpvm_image_id,
+                kailua_db.config.precondition_version(),
+                &proposal_parent,
+                &proposal,
+                eth_rpc_provider,
+                op_geth_provider,
+            )
+            .await?;
+        } else {
+            info!(
+                "Tournament {} already has a valid child {valid_child}",
+                proposal_parent.index
+            );
+        }
+    }
+    // skip this proposal if it has no contender
+    let Some(contender) = proposal.contender else {
+        return Ok(());
+    };
+    // request a proof of the match results
+    let Some(contender) = kailua_db.get_local_proposal(&contender) else {
+        error!("Contender {contender} missing from database.");
+        return Ok(());
+    };
+    // Look up parent proposal
+    let Some(proposal_parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+        error!(
+            "Proposal {} parent {} missing from database.",
+            proposal.index, proposal.parent
+        );
+        return Ok(());
+    };
+    let proposal_parent_contract =
+        proposal_parent.tournament_contract_instance(&validator_provider);
+    // Look up indices of children in parent
+    let Some(u_index) = proposal_parent.child_index(contender.index) else {
+        error!(
+            "Could not look up contender {} index in parent tournament {}",
+            contender.index, proposal_parent.index
+        );
+        return Ok(());
+    };
+    let Some(v_index) = proposal_parent.child_index(proposal.index) else {
+        error!(
+            "Could not look up proposal {} index in parent tournament {}",
+            proposal.index, proposal_parent.index
+        );
+        return Ok(());
+    };
+    // Skip matches in tournaments that were already decided
+    if let Some(reason) =
+        settled_match_reason(&proposal_parent, &contender, &proposal, &validator_provider).await?
+    {
+        info!(
+            "Skipping match between children {u_index} and {v_index} of tournament {}: {reason}.",
+            proposal_parent.index
+        );
+        return Ok(());
+    }
+    // Skip matches whose result would be ignored due to a prior elimination
+    let mut eliminated = false;
+    for player in [&contender, &proposal] {
+        if kailua_db
+            .treasury
+            .is_proposer_eliminated(&validator_provider, player.proposer, player.index)
+            .await?
+        {
+            info!(
+                "Proposer {} of proposal {} is already eliminated.",
+                player.proposer, player.index
+            );
+            eliminated = true;
+        }
+    }
+    if eliminated {
+        info!(
+            "Skipping match between children {u_index} and {v_index} of tournament {}.",
+            proposal_parent.index
+        );
+        return Ok(());
+    }
+    // Check that proof had not already been posted
+    let proof_status = proposal_parent_contract
+        .proofStatus(U256::from(u_index), U256::from(v_index))
+        .stall()
+        .await
+        ._0;
+    // Prove if unproven
+    if proof_status == 0 {
+        // track the time left to submit the proof
+        kailua_db.state.match_deadlines.insert(
+            (contender.index, proposal.index),
+            MatchDeadline {
+                deadline: proposal.challenge_deadline(kailua_db.config.timeout),
+                alert_level: 0,
+            },
+        );
+        let fpvm_image_id = proposal_parent_contract.imageId().stall().await.imageId_;
+        request_proof(
+            channel,
+            fpvm_image_id,
+            kailua_db.config.precondition_version(),
+            &contender,
+            &proposal,
+            eth_rpc_provider,
+            op_geth_provider,
+            op_node_provider,
+        )
+        .await?;
+    } else {
+        info!("Match between children {u_index} and {v_index} already proven {proof_status}");
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn submit_validity_proof<T: Transport + Clone, P: Provider<T>>(
+    proposal_parent: &Proposal,
+    proposal: &Proposal,
+    proof_journal: &ProofJournal,
+    proof: Proof,
+    provider: P,
+    proving_labels: &ProvingLabels,
+    gas_accountant: &mut GasAccountant,
+    audit_log: &AuditLog,
+    validator_events: &ValidatorEvents,
+    tx_exporter: Option<&mut TxExporter>,
+    onchain_seal_check: bool,
+) -> anyhow::Result<()> {
+    let proposal_parent_contract = proposal_parent.tournament_contract_instance(&provider);
+    let Some(child_index) = proposal_parent.child_index(proposal.index) else {
+        error!(
+            "Could not look up proposal {} index in parent tournament {}",
+            proposal.index, proposal_parent.index
+        );
+        return Ok(());
+    };
+    if proof_journal.claimed_l2_output_root != proposal.output_root {
+        error!(
+            "Validity proof output {} does not match proposal {} output {}",
+            proof_journal.claimed_l2_output_root, proposal.index, proposal.output_root
+        );
+        return Ok(());
+    }
+    // only prove tournaments without a valid child
+    let valid_child = proposal_parent_contract.validChild().stall().await._0;
+    if !valid_child.is_zero() {
+        warn!(
+            "Skipping validity proof submission for tournament {} with valid child {valid_child}.",
+            proposal_parent.index
+        );
+        return Ok(());
+    }
+
+    // wrap the proof only once it is about to be submitted
+    let wrapping_start = Instant::now();
+    let proof = match proof.wrap_groth16().await {
+        Ok(proof) => {
+            proving_labels.record_wrapping_duration(wrapping_start.elapsed().as_secs_f64());
+            proof
+        }
+        Err(e) => {
+            error!("Failed to wrap validity proof: {e:?}");
+            return Ok(());
+        }
+    };
+    let encoded_seal = Bytes::from(proof.encoded_seal()?);
+    if onchain_seal_check {
+        if let Err(e) = verify_seal_onchain(
+            &proposal_parent_contract,
+            &encoded_seal,
+            &proof.journal().bytes,
+        )
+        .await
+        {
+            error!(
+                "Aborting validity proof submission for proposal {}: {e:?}",
+                proposal.index
+            );
+            return Ok(());
+        }
+        info!("On-chain verifier accepted validity proof seal.");
+    }
+
+    info!(
+        "Submitting validity proof to tournament at index {} for child {child_index}.",
+        proposal_parent.index
+    );
+    let intent = format!(
+        "prove validity of proposal {} in tournament {}",
+        proposal.index, proposal_parent.index
+    );
+    let prove_validity_call = proposal_parent_contract.proveValidity(child_index, encoded_seal);
+    if let Some(tx_exporter) = tx_exporter {
+        if let Err(e) = tx_exporter
+            .export(
+                &provider,
+                &intent,
+                prove_validity_call.into_transaction_request(),
+            )
+            .await
+        {
+            error!("Failed to export validity proof txn: {e:?}");
+        }
+        return Ok(());
+    }
+    match prove_validity_call
+        .send()
+        .await
+        .context("proveValidity (send)")
+    {
+        Ok(txn) => {
+            let tx_hash = *txn.tx_hash();
+            validator_events.emit(ValidatorEvent::TransactionSubmitted {
+                intent: intent.clone(),
+                to: proposal_parent.contract,
+                tx_hash,
+            });
+            match txn
+                .get_receipt()
+                .await
+                .context("proveValidity (get_receipt)")
+            {
+                Ok(receipt) => {
+                    info!("Validity proof submitted: {receipt:?}");
+                    audit_log.record(
+                        intent,
+                        proposal_parent.contract,
+                        prove_validity_call.calldata(),
+                        Some(tx_hash),
+                        AuditOutcome::confirmed(&receipt),
+                    );
+                    gas_accountant.record(TxCategory::Prove, &receipt);
+                    info!("Proposal {} proven valid.", proposal.index);
+                }
+                Err(e) => {
+                    error!("Failed to confirm validity proof txn: {e:?}");
+                    audit_log.record(
+                        intent,
+                        proposal_parent.contract,
+                        prove_validity_call.calldata(),
+                        Some(tx_hash),
+                        AuditOutcome::unconfirmed(&e),
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            error!("Failed to send validity proof txn: {e:?}");
+            audit_log.record(
+                intent,
+                proposal_parent.contract,
+                prove_validity_call.calldata(),
+                None,
+                AuditOutcome::failed(&e),
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn request_validity_proof(
+    channel: &mut DuplexChannel<Message>,
+    fpvm_image_id: B256,
+    precondition_version: u8,
+    proposal_parent: &Proposal,
+    proposal: &Proposal,
+    l1_node_provider: &ReqwestProvider,
+    l2_node_provider: &ReqwestProvider,
+) -> anyhow::Result<()> {
+    info!("Requesting validity proof for proposal {}.", proposal.index);
+    let agreed_l2_head_hash = l2_node_provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(proposal_parent.output_block_number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await
+        .context("agreed_l2_head_hash")?
+        .expect("Agreed l2 head not found")
+        .header
+        .hash;
+    debug!("l2_head {:?}", &agreed_l2_head_hash);
+
+    // Prepare precondition validation data for all the proposal blobs
+    let blob_block_parent = l1_node_provider
+        .get_block_by_hash(proposal.l1_head, BlockTransactionsKind::Hashes)
+        .await
+        .context("blob_block_parent get_block_by_hash")?
+        .expect("blob_block_parent not found");
+    let blob_block = l1_node_provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(blob_block_parent.header.number + 1),
+            BlockTransactionsKind::Hashes,
+        )
+        .await
+        .context("blob_block get_block_by_number")?
+        .expect("blob_block not found");
+    let block_ref = BlockInfo {
+        hash: blob_block.header.hash,
+        number: blob_block.header.number,
+        parent_hash: blob_block.header.parent_hash,
+        timestamp: blob_block.header.timestamp,
+    };
+    let validated_blobs = proposal
+        .io_blobs
+        .iter()
+        .map(|(blob_hash, blob)| BlobFetchRequest {
+            block_ref,
+            blob_hash: IndexedBlobHash {
+                index: blob.index,
+                hash: *blob_hash,
+            },
+        })
+        .collect();
+
+    // Message proving task
+    channel
+        .sender
+        .send(Message::Proposal {
+            index: proposal.index,
+            fpvm_image_id,
+            precondition_validation_data: Some(PreconditionValidationData::Validity {
+                proposal_l2_head_number: proposal_parent.output_block_number,
+                proposal_output_count: proposal.output_block_number
+                    - proposal_parent.output_block_number,
+                validated_blobs,
+            }),
+            precondition_version,
+            l1_head: proposal.l1_head,
+            agreed_l2_head_hash,
+            agreed_l2_output_root: proposal_parent.output_root,
+            claimed_l2_block_number: proposal.output_block_number,
+            claimed_l2_output_root: proposal.output_root,
+        })
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn request_proof(
+    channel: &mut DuplexChannel<Message>,
+    fpvm_image_id: B256,
+    precondition_version: u8,
     contender: &Proposal,
     proposal: &Proposal,
     l1_node_provider: &ReqwestProvider,
     l2_node_provider: &ReqwestProvider,
     op_node_provider: &OpNodeProvider,
 ) -> anyhow::Result<()> {
-    let challenge_point = contender
-        .divergence_point(proposal)
-        .expect("Contender does not diverge from proposal.") as u64;
+    let Some(challenge_point) = contender.divergence_point(proposal) else {
+        warn!(
+            "Skipping proof request for proposal {} identical to contender {}.",
+            proposal.index, contender.index
+        );
+        return Ok(());
+    };
+    let challenge_point = challenge_point as u64;
 
     // Read additional data for Kona invocation
     info!("Requesting proof for proposal {}.", proposal.index);
@@ -719,7 +1866,7 @@ async fn request_proof(
             v_blob.index,
         );
 
-        Some(PreconditionValidationData {
+        Some(PreconditionValidationData::Fault {
             validated_blobs: [
                 // u's blob (contender)
                 BlobFetchRequest {
@@ -757,7 +1904,9 @@ async fn request_proof(
         .sender
         .send(Message::Proposal {
             index: proposal.index,
+            fpvm_image_id,
             precondition_validation_data,
+            precondition_version,
             l1_head: proposal.l1_head,
             agreed_l2_head_hash,
             agreed_l2_output_root,
@@ -768,188 +1917,1081 @@ async fn request_proof(
     Ok(())
 }
 
+/// A queued invocation of kailua-host
+pub(crate) struct ProvingJob {
+    /// Identifies the job across the logs and stats of the validator and kailua-host
+    pub(crate) correlation_id: String,
+    pub(crate) proposal_index: u64,
+    pub(crate) fpvm_image_id: Digest,
+    pub(crate) proof_file_name: String,
+    /// Where kailua-host writes the proof file to
+    pub(crate) proof_file: PathBuf,
+    pub(crate) proving_args: Vec<String>,
+}
+
+/// Verifies that the proposal commits to the output at the position using the cached blobs, and
+/// optionally confirms the result through the proposal contract
+async fn verify_intermediate_output<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    proposal: &Proposal,
+    proposal_contract: &KailuaTournament::KailuaTournamentInstance<T, P, N>,
+    position: u64,
+    output: B256,
+    commitment: &Bytes,
+    proof: &Bytes,
+    onchain_check: bool,
+) -> bool {
+    let local_result =
+        match proposal.verify_intermediate_output(position, output, commitment, proof) {
+            Ok(result) => result,
+            Err(e) => {
+                error!(
+                    "Failed to verify output {position} of proposal {} locally: {e:?}",
+                    proposal.index
+                );
+                false
+            }
+        };
+    if !onchain_check {
+        return local_result;
+    }
+    let onchain_result = proposal_contract
+        .verifyIntermediateOutput(position, output, commitment.clone(), proof.clone())
+        .stall()
+        .await
+        .success;
+    if onchain_result != local_result {
+        warn!(
+            "Local verification of output {position} of proposal {} returned {local_result} but on-chain verification returned {onchain_result}.",
+            proposal.index
+        );
+    }
+    local_result && onchain_result
+}
+
 pub async fn handle_proofs(
     mut channel: DuplexChannel<Message>,
-    args: ValidateArgs,
+    reloader: Reloader,
     data_dir: PathBuf,
+    validator_events: ValidatorEvents,
 ) -> anyhow::Result<()> {
+    let mut reloaded_args = reloader.subscribe();
+    let mut args = reloaded_args.borrow_and_update().clone();
+    let mut alerts = Alerts::from_args(&args.alert_args);
     // Fetch rollup configuration
-    let l2_chain_id = fetch_rollup_config(&args.core.op_node_url, &args.core.op_geth_url, None)
-        .await?
-        .l2_chain_id
-        .to_string();
+    let l2_chain_id = fetch_rollup_config(
+        &args.core.op_node_url,
+        &args.core.op_geth_url,
+        None,
+        &args.core.hardfork_args.overrides(),
+    )
+    .await?
+    .l2_chain_id
+    .to_string();
+    let proving_labels = ProvingLabels {
+        chain: l2_chain_id.clone(),
+        backend: proving_backend(args.boundless_args.is_some()).to_string(),
+    };
+    // Load known program builds
+    let fpvm_registry = load_fpvm_registry(&args)?;
+    // Costs accumulate across restarts
+    let proving_costs_file = data_dir.join(PROVING_COSTS_FILE);
+    let mut proving_costs =
+        ProvingCostLedger::load(&proving_costs_file).context("ProvingCostLedger::load")?;
+    let mut chain_anomalies = ChainAnomalies::new(args.anomaly_args.clone());
+    // kailua-host writes proof files to its working directory
+    let receipts_dir = data_dir.join(RECEIPTS_DIR);
+    // Reuse proofs published by other validators of the same chain
+    let receipt_store =
+        ReceiptStore::from_args(&args.receipt_store_args).context("ReceiptStore::from_args")?;
+    // Lease jobs to remote workers instead of proving locally if requested
+    if let Some(addr) = args.worker_pool_args.worker_pool_addr {
+        let (job_queue, mut outcomes) =
+            JobQueue::new(&args.worker_pool_args, validator_events.clone());
+        spawn({
+            let job_queue = job_queue.clone();
+            async move {
+                if let Err(err) = job_queue.serve(addr).await {
+                    error!("Worker pool failure: {err:?}");
+                }
+            }
+        });
+        loop {
+            select! {
+                Ok(()) = reloaded_args.changed() => {
+                    args = reloaded_args.borrow_and_update().clone();
+                    alerts = Alerts::from_args(&args.alert_args);
+                }
+                message = channel.receiver.recv() => {
+                    let message = message.ok_or(anyhow!("proof receiver channel closed"))?;
+                    let Some(job) =
+                        prepare_proving_job(&args, &l2_chain_id, &data_dir, &fpvm_registry, message)?
+                    else {
+                        continue;
+                    };
+                    if let Some(receipt_store) = &receipt_store {
+                        if receipt_store.fetch(&job).await {
+                            collect_proof(
+                                &channel.sender,
+                                &job,
+                                &proving_labels,
+                                &mut proving_costs,
+                                &proving_costs_file,
+                            )
+                            .await?;
+                            continue;
+                        }
+                    }
+                    info!(correlation_id = %job.correlation_id, "Queueing proof for local index {}.", job.proposal_index);
+                    job_queue.push(job).await;
+                }
+                Some((job, outcome)) = outcomes.recv() => {
+                    validator_events.emit(ValidatorEvent::ProofCompleted {
+                        proposal_index: job.proposal_index,
+                        correlation_id: job.correlation_id.clone(),
+                        success: outcome.is_success(),
+                    });
+                    if outcome.exit_code == Some(EXIT_CODE_OUTPUT_DIVERGENCE) {
+                        report_output_divergence(&args, &alerts, &mut chain_anomalies, &job).await;
+                        continue;
+                    } else if !outcome.is_success() {
+                        error!(correlation_id = %job.correlation_id, "Proving task failure on worker {}.", outcome.worker);
+                        continue;
+                    }
+                    info!(correlation_id = %job.correlation_id, "Proving task successful on worker {}.", outcome.worker);
+                    if let Err(e) = outcome.save(&job.proof_file.to_string_lossy()) {
+                        error!(correlation_id = %job.correlation_id, "Failed to save proof of worker {}: {e:?}", outcome.worker);
+                        continue;
+                    }
+                    collect_proof(
+                        &channel.sender,
+                        &job,
+                        &proving_labels,
+                        &mut proving_costs,
+                        &proving_costs_file,
+                    )
+                    .await?;
+                    if let Some(receipt_store) = &receipt_store {
+                        receipt_store.publish(&job).await;
+                    }
+                }
+            }
+        }
+    }
     // Run proof generator loop
+    let mut preflighted_job = None;
     loop {
+        // New jobs are proven with the reloaded settings
+        if let Some(reloaded) = take_reloaded(&mut reloaded_args) {
+            args = reloaded;
+            alerts = Alerts::from_args(&args.alert_args);
+        }
         // Dequeue messages
-        let Message::Proposal {
-            index: proposal_index,
-            precondition_validation_data,
-            l1_head,
-            agreed_l2_head_hash,
-            agreed_l2_output_root,
-            claimed_l2_block_number,
-            claimed_l2_output_root,
-        } = channel
-            .receiver
-            .recv()
+        let job = match preflighted_job.take() {
+            Some(job) => job,
+            None => {
+                let message = channel
+                    .receiver
+                    .recv()
+                    .await
+                    .ok_or(anyhow!("proof receiver channel closed"))?;
+                let Some(job) =
+                    prepare_proving_job(&args, &l2_chain_id, &data_dir, &fpvm_registry, message)?
+                else {
+                    continue;
+                };
+                job
+            }
+        };
+        if let Some(receipt_store) = &receipt_store {
+            if receipt_store.fetch(&job).await {
+                collect_proof(
+                    &channel.sender,
+                    &job,
+                    &proving_labels,
+                    &mut proving_costs,
+                    &proving_costs_file,
+                )
+                .await?;
+                continue;
+            }
+        }
+        info!(correlation_id = %job.correlation_id, "Processing proof for local index {}.", job.proposal_index);
+        validator_events.emit(ValidatorEvent::ProofStarted {
+            proposal_index: job.proposal_index,
+            correlation_id: job.correlation_id.clone(),
+        });
+        // Preflight the next queued job while proving the current one
+        let preflight_task = async {
+            let Ok(message) = channel.receiver.try_recv() else {
+                return Ok(None);
+            };
+            let Some(next_job) =
+                prepare_proving_job(&args, &l2_chain_id, &data_dir, &fpvm_registry, message)?
+            else {
+                return Ok(None);
+            };
+            info!(
+                correlation_id = %next_job.correlation_id,
+                "Preflighting proof for local index {}.",
+                next_job.proposal_index
+            );
+            let preflight_start = Instant::now();
+            match run_kailua_host(
+                args.kailua_host.as_deref(),
+                &next_job.proving_args,
+                true,
+                Some(&receipts_dir),
+            )
             .await
-            .ok_or(anyhow!("proof receiver channel closed"))?
-        else {
-            bail!("Unexpected message type.");
+            {
+                Ok(status) if status.success() => {
+                    proving_labels
+                        .record_preflight_duration(preflight_start.elapsed().as_secs_f64());
+                    info!(
+                        correlation_id = %next_job.correlation_id,
+                        "Preflight for local index {} successful.",
+                        next_job.proposal_index
+                    )
+                }
+                Ok(status) => {
+                    warn!(
+                        correlation_id = %next_job.correlation_id,
+                        "Preflight for local index {} failed ({status}).",
+                        next_job.proposal_index
+                    )
+                }
+                Err(e) => {
+                    error!(correlation_id = %next_job.correlation_id, "Failed to invoke kailua-host: {e:?}")
+                }
+            }
+            Ok::<_, anyhow::Error>(Some(next_job))
         };
-        info!("Processing proof for local index {proposal_index}.");
-        // Prepare kailua-host parameters
-        let precondition_hash = precondition_validation_data
-            .as_ref()
-            .map(|d| d.precondition_hash())
-            .unwrap_or_default();
-        let proof_file_name = fpvm_proof_file_name(
-            precondition_hash,
-            l1_head,
-            claimed_l2_output_root,
-            claimed_l2_block_number,
-            agreed_l2_output_root,
+        let proving_task = run_kailua_host(
+            args.kailua_host.as_deref(),
+            &job.proving_args,
+            false,
+            Some(&receipts_dir),
         );
-        let l1_head = l1_head.to_string();
-        let agreed_l2_head_hash = agreed_l2_head_hash.to_string();
-        let agreed_l2_output_root = agreed_l2_output_root.to_string();
-        let claimed_l2_output_root = claimed_l2_output_root.to_string();
-        let claimed_l2_block_number = claimed_l2_block_number.to_string();
-        let verbosity = [
-            String::from("-"),
-            (0..args.core.v).map(|_| 'v').collect::<String>(),
-        ]
-        .concat();
-        let mut proving_args = vec![
-            String::from("--l1-head"), // l1 head from on-chain proposal
-            l1_head,
-            String::from("--agreed-l2-head-hash"), // l2 starting block hash from on-chain proposal
-            agreed_l2_head_hash,
-            String::from("--agreed-l2-output-root"), // l2 starting output root
-            agreed_l2_output_root,
-            String::from("--claimed-l2-output-root"), // proposed output root
-            claimed_l2_output_root,
-            String::from("--claimed-l2-block-number"), // proposed block number
-            claimed_l2_block_number,
-            String::from("--l2-chain-id"), // rollup chain id
-            l2_chain_id.clone(),
-            String::from("--l1-node-address"), // l1 el node
-            args.core.eth_rpc_url.clone(),
-            String::from("--l1-beacon-address"), // l1 cl node
-            args.core.beacon_rpc_url.clone(),
-            String::from("--l2-node-address"), // l2 el node
-            args.core.op_geth_url.clone(),
-            String::from("--op-node-address"), // l2 cl node
-            args.core.op_node_url.clone(),
-            String::from("--data-dir"), // path to cache
-            data_dir.to_str().unwrap().to_string(),
-            String::from("--native"), // run the client natively
-        ];
-        // precondition data
-        if let Some(precondition_data) = precondition_validation_data {
+        let (proving_result, preflight_result) = tokio::join!(proving_task, preflight_task);
+        preflighted_job = preflight_result?;
+        let proven = proving_result.as_ref().is_ok_and(|status| status.success());
+        validator_events.emit(ValidatorEvent::ProofCompleted {
+            proposal_index: job.proposal_index,
+            correlation_id: job.correlation_id.clone(),
+            success: proven,
+        });
+        match proving_result {
+            Ok(proving_task) => {
+                if proving_task.code() == Some(EXIT_CODE_OUTPUT_DIVERGENCE) {
+                    report_output_divergence(&args, &alerts, &mut chain_anomalies, &job).await;
+                    continue;
+                } else if !proving_task.success() {
+                    error!(correlation_id = %job.correlation_id, "Proving task failure.");
+                } else {
+                    info!(correlation_id = %job.correlation_id, "Proving task successful.");
+                }
+            }
+            Err(e) => {
+                error!(correlation_id = %job.correlation_id, "Failed to invoke kailua-host: {e:?}");
+            }
+        }
+        sleep(Duration::from_secs(1)).await;
+        collect_proof(
+            &channel.sender,
+            &job,
+            &proving_labels,
+            &mut proving_costs,
+            &proving_costs_file,
+        )
+        .await?;
+        if let (true, Some(receipt_store)) = (proven, &receipt_store) {
+            receipt_store.publish(&job).await;
+        }
+    }
+}
+
+/// Alerts on kailua-host aborting a job because the local op-node reported a diverging output
+pub(crate) async fn report_output_divergence(
+    args: &ValidateArgs,
+    alerts: &Alerts,
+    chain_anomalies: &mut ChainAnomalies,
+    job: &ProvingJob,
+) {
+    error!(correlation_id = %job.correlation_id, "LOCAL NODE DIVERGENCE: The output derived for local index {} differs from the one reported by the op-node at {}. Aborted proving.", job.proposal_index, args.core.op_node_url);
+    alerts
+        .raise(
+            AlertSeverity::Critical,
+            "op_node_divergence",
+            format!(
+                "The output derived for local index {} differs from the one reported by the op-node.",
+                job.proposal_index
+            ),
+        )
+        .await;
+    chain_anomalies.record(ChainAnomaly::OpNodeMismatch);
+    chain_anomalies.raise_alerts(alerts).await;
+}
+
+/// Reads the proof file of a finished job, records its stats and forwards the proof
+pub(crate) async fn collect_proof(
+    sender: &Sender<Message>,
+    job: &ProvingJob,
+    proving_labels: &ProvingLabels,
+    proving_costs: &mut ProvingCostLedger,
+    proving_costs_file: &Path,
+) -> anyhow::Result<()> {
+    // Read receipt file
+    let proof_file_name = &job.proof_file.to_string_lossy().to_string();
+    if !Path::new(proof_file_name).exists() {
+        error!(correlation_id = %job.correlation_id, "Proof file {proof_file_name} not found.");
+    } else {
+        info!(correlation_id = %job.correlation_id, "Found proof file.");
+    }
+    let mut proof_file = match File::open(proof_file_name).await {
+        Ok(f) => f,
+        Err(e) => {
+            error!(correlation_id = %job.correlation_id, "Failed to open proof file {proof_file_name}: {e:?}");
+            return Ok(());
+        }
+    };
+    info!(correlation_id = %job.correlation_id, "Opened proof file {proof_file_name}.");
+    let mut proof_data = Vec::new();
+    if let Err(e) = proof_file.read_to_end(&mut proof_data).await {
+        error!(correlation_id = %job.correlation_id, "Failed to read proof file {proof_file_name}: {e:?}");
+        return Ok(());
+    }
+    info!(correlation_id = %job.correlation_id, "Read entire proof file.");
+    proving_labels.record_receipt_size(proof_data.len());
+    match ProvingStats::load(proof_file_name) {
+        Ok(Some(stats)) => {
+            proving_labels.record_proving_stats(&stats);
+            let dispute = proving_costs.record(job.proposal_index, &stats);
+            info!(
+                correlation_id = %job.correlation_id,
+                "Proving for local index {} has cost {} jobs, {} cycles, {:.0}s, ${:.2} so far.",
+                job.proposal_index,
+                dispute.jobs,
+                dispute.total_cycles,
+                dispute.proving_secs,
+                dispute.estimated_cost
+            );
+            if let Err(e) = proving_costs.save(proving_costs_file) {
+                warn!(correlation_id = %job.correlation_id, "Failed to save proving costs: {e:?}");
+            }
+        }
+        Ok(None) => {
+            debug!(correlation_id = %job.correlation_id, "No proving stats found for {proof_file_name}.")
+        }
+        Err(e) => {
+            warn!(correlation_id = %job.correlation_id, "Failed to load proving stats for {proof_file_name}: {e:?}")
+        }
+    }
+    match Proof::from_file_bytes(&proof_data, job.fpvm_image_id) {
+        Ok(proof) => {
+            // Send proof via the channel
+            sender
+                .send(Message::Proof(job.proposal_index, proof))
+                .await?;
+            info!(correlation_id = %job.correlation_id, "Proof for local index {} complete.", job.proposal_index);
+        }
+        Err(e) => {
+            error!(correlation_id = %job.correlation_id, "Failed to load proof file {proof_file_name}: {e:?}");
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn load_fpvm_registry(args: &ValidateArgs) -> anyhow::Result<FpvmRegistry> {
+    match &args.fpvm_registry {
+        Some(path) => FpvmRegistry::load(path).context("FpvmRegistry::load"),
+        None => Ok(FpvmRegistry::bundled()),
+    }
+}
+
+/// Returns the path of the ELF to prove with for the given image id, or None for the bundled one
+fn locate_fpvm_elf(
+    args: &ValidateArgs,
+    fpvm_registry: &FpvmRegistry,
+    fpvm_image_id: Digest,
+) -> anyhow::Result<Option<PathBuf>> {
+    if fpvm_image_id == Digest::from(KAILUA_FPVM_ID) {
+        return Ok(None);
+    }
+    let registered = match fpvm_registry.resolve_elf(B256::from_slice(fpvm_image_id.as_bytes())) {
+        Ok(path) => path,
+        Err(err) => {
+            debug!("Registry lookup failed: {err:?}");
+            None
+        }
+    };
+    let found = match (registered, &args.fpvm_elf_dir) {
+        (Some(path), _) => Some(path),
+        (None, Some(dir)) => find_fpvm_elf(dir, fpvm_image_id)?,
+        (None, None) => None,
+    };
+    let Some(path) = found else {
+        bail!("No FPVM ELF available for image id {fpvm_image_id}.");
+    };
+    info!("Proving with FPVM ELF {path:?} for image id {fpvm_image_id}.");
+    Ok(Some(path))
+}
+
+/// Fails if no receipts that verify on-chain can be generated for the image id expected by a game
+fn check_fpvm_image_id(
+    args: &ValidateArgs,
+    fpvm_registry: &FpvmRegistry,
+    fpvm_image_id: B256,
+) -> anyhow::Result<()> {
+    match locate_fpvm_elf(args, fpvm_registry, Digest::from(fpvm_image_id.0)) {
+        Ok(_) => Ok(()),
+        // proofs are patched to the expected image id on devnets
+        Err(err) if cfg!(feature = "devnet") => {
+            warn!("DEVNET-ONLY: {err:?}");
+            Ok(())
+        }
+        Err(err) => Err(err.context(format!(
+            "Game expects FPVM image id {fpvm_image_id} but local build is {}.",
+            Digest::from(KAILUA_FPVM_ID)
+        ))),
+    }
+}
+
+/// Invokes kailua-host with the given arguments, optionally only to preflight the proof
+pub(crate) async fn run_kailua_host(
+    kailua_host: Option<&Path>,
+    proving_args: &[String],
+    preflight_only: bool,
+    working_dir: Option<&Path>,
+) -> anyhow::Result<ExitStatus> {
+    // Prove via kailua-host (re dev mode/bonsai: env vars inherited!)
+    let mut kailua_host_command = kailua_host_command(kailua_host)?;
+    // proof files are written to the working directory
+    if let Some(working_dir) = working_dir {
+        kailua_host_command.current_dir(working_dir);
+    }
+    // get fake receipts when building under devnet
+    if is_dev_mode() {
+        kailua_host_command.env("RISC0_DEV_MODE", "1");
+    }
+    // pass arguments to point at target block
+    kailua_host_command.args(proving_args);
+    if preflight_only {
+        kailua_host_command.arg("--preflight-only");
+    }
+    debug!("kailua_host_command {:?}", &kailua_host_command);
+    Ok(kailua_host_command
+        .kill_on_drop(true)
+        .spawn()
+        .context("Invoking kailua-host")?
+        .wait()
+        .await?)
+}
+
+/// Returns a command invoking the kailua-host binary at the path, or the host embedded in this
+/// binary if none is given
+fn kailua_host_command(kailua_host: Option<&Path>) -> anyhow::Result<Command> {
+    match kailua_host {
+        Some(kailua_host) => Ok(Command::new(kailua_host)),
+        #[cfg(feature = "embedded-host")]
+        None => {
+            let mut command = Command::new(std::env::current_exe().context("current_exe")?);
+            command.arg("host");
+            Ok(command)
+        }
+        #[cfg(not(feature = "embedded-host"))]
+        None => bail!("No kailua-host binary specified."),
+    }
+}
+
+/// Derives a unique identifier for a proving job from its proof file name and the current time
+fn proving_job_correlation_id(proof_file_name: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let digest = Sha256::digest(format!("{proof_file_name}:{nanos}"));
+    hex::encode(&digest[..8])
+}
+
+/// Prepares the kailua-host arguments for proving the proposal, or returns None if it cannot be
+/// proven.
+pub(crate) fn prepare_proving_job(
+    args: &ValidateArgs,
+    l2_chain_id: &str,
+    data_dir: &Path,
+    fpvm_registry: &FpvmRegistry,
+    message: Message,
+) -> anyhow::Result<Option<ProvingJob>> {
+    let Message::Proposal {
+        index: proposal_index,
+        fpvm_image_id,
+        precondition_validation_data,
+        precondition_version,
+        l1_head,
+        agreed_l2_head_hash,
+        agreed_l2_output_root,
+        claimed_l2_block_number,
+        claimed_l2_output_root,
+    } = message
+    else {
+        bail!("Unexpected message type.");
+    };
+    // Refuse preconditions the program expected by the game cannot validate
+    if let Err(err) = fpvm_registry.check_precondition_version(fpvm_image_id, precondition_version)
+    {
+        error!("{err:?} Skipping proof for local index {proposal_index}.");
+        return Ok(None);
+    }
+    // Locate the program expected by the game if it differs from the bundled one
+    let fpvm_image_id = Digest::from(fpvm_image_id.0);
+    let fpvm_elf = match locate_fpvm_elf(args, fpvm_registry, fpvm_image_id) {
+        Ok(fpvm_elf) => fpvm_elf,
+        Err(err) => {
+            error!("{err:?} Skipping proof for local index {proposal_index}.");
+            return Ok(None);
+        }
+    };
+    // Prepare kailua-host parameters
+    let precondition_hash = precondition_validation_data
+        .as_ref()
+        .map(|d| d.precondition_hash(precondition_version))
+        .unwrap_or_default();
+    let proof_file_name = fpvm_proof_file_name(
+        fpvm_image_id,
+        precondition_hash,
+        l1_head,
+        claimed_l2_output_root,
+        claimed_l2_block_number,
+        agreed_l2_output_root,
+    );
+    // separate preimage stores allow preflighting one job while proving another
+    let job_data_dir = data_dir.join(PREIMAGES_DIR).join(&proof_file_name);
+    // validity proofs of blobless proposals need no block to fetch blobs from
+    let proposal_block_hash = precondition_validation_data
+        .as_ref()
+        .and_then(|d| d.validated_blobs().first())
+        .map(|b| b.block_ref.hash)
+        .unwrap_or(l1_head)
+        .to_string();
+    let l1_head = l1_head.to_string();
+    let agreed_l2_head_hash = agreed_l2_head_hash.to_string();
+    let agreed_l2_output_root = agreed_l2_output_root.to_string();
+    let claimed_l2_output_root = claimed_l2_output_root.to_string();
+    let claimed_l2_block_number = claimed_l2_block_number.to_string();
+    let verbosity = [
+        String::from("-"),
+        (0..args.core.v).map(|_| 'v').collect::<String>(),
+    ]
+    .concat();
+    let mut proving_args = vec![
+        String::from("--l1-head"), // l1 head from on-chain proposal
+        l1_head,
+        String::from("--agreed-l2-head-hash"), // l2 starting block hash from on-chain proposal
+        agreed_l2_head_hash,
+        String::from("--agreed-l2-output-root"), // l2 starting output root
+        agreed_l2_output_root,
+        String::from("--claimed-l2-output-root"), // proposed output root
+        claimed_l2_output_root,
+        String::from("--claimed-l2-block-number"), // proposed block number
+        claimed_l2_block_number,
+        String::from("--l2-chain-id"), // rollup chain id
+        l2_chain_id.to_string(),
+        String::from("--l1-node-address"), // l1 el node
+        args.core.eth_rpc_url.clone(),
+        String::from("--l1-beacon-address"), // l1 cl node
+        args.core.beacon_rpc_url.clone(),
+        String::from("--l2-node-address"), // l2 el node
+        args.core.op_geth_url.clone(),
+        String::from("--op-node-address"), // l2 cl node
+        args.core.op_node_url.clone(),
+        String::from("--data-dir"), // path to cache
+        job_data_dir.to_str().unwrap().to_string(),
+        String::from("--native"), // run the client natively
+    ];
+    // hardfork overrides committed to by the configuration hash
+    proving_args.extend(args.core.hardfork_args.to_args());
+    // precondition encoding expected by the contracts
+    if precondition_validation_data.is_some() {
+        proving_args.extend(vec![
+            String::from("--precondition-version"),
+            precondition_version.to_string(),
+        ]);
+    }
+    // precondition data
+    match precondition_validation_data {
+        Some(PreconditionValidationData::Fault { validated_blobs }) => {
             proving_args.extend(vec![
                 String::from("--u-block-hash"),
-                precondition_data.validated_blobs[0]
-                    .block_ref
-                    .hash
-                    .to_string(),
+                validated_blobs[0].block_ref.hash.to_string(),
                 String::from("--u-blob-kzg-hash"),
-                precondition_data.validated_blobs[0]
-                    .blob_hash
-                    .hash
-                    .to_string(),
+                validated_blobs[0].blob_hash.hash.to_string(),
                 String::from("--v-block-hash"),
-                precondition_data.validated_blobs[1]
-                    .block_ref
-                    .hash
-                    .to_string(),
+                validated_blobs[1].block_ref.hash.to_string(),
                 String::from("--v-blob-kzg-hash"),
-                precondition_data.validated_blobs[1]
-                    .blob_hash
-                    .hash
-                    .to_string(),
+                validated_blobs[1].blob_hash.hash.to_string(),
             ]);
         }
-        // boundless args
-        if let Some(boundless_args) = &args.boundless_args {
-            proving_args.extend(boundless_args.to_arg_vec(&args.boundless_storage_config));
+        Some(PreconditionValidationData::Validity {
+            proposal_output_count,
+            validated_blobs,
+            ..
+        }) => {
+            proving_args.extend(vec![
+                String::from("--block-count"),
+                proposal_output_count.to_string(),
+                String::from("--proposal-block-hash"),
+                proposal_block_hash,
+            ]);
+            if !validated_blobs.is_empty() {
+                proving_args.extend(vec![
+                    String::from("--proposal-blob-kzg-hashes"),
+                    validated_blobs
+                        .iter()
+                        .map(|b| b.blob_hash.hash.to_string())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ]);
+            }
         }
-        // verbosity level
-        if args.core.v > 0 {
-            proving_args.push(verbosity);
+        None => {}
+    }
+    // alternative fpvm program
+    if let Some(fpvm_elf) = fpvm_elf {
+        proving_args.extend(vec![
+            String::from("--fpvm-elf"),
+            fpvm_elf.to_str().unwrap().to_string(),
+        ]);
+    }
+    // native client deadline
+    if let Some(timeout) = args.native_client_timeout {
+        proving_args.extend(vec![
+            String::from("--native-client-timeout"),
+            timeout.to_string(),
+        ]);
+    }
+    // proving cost estimation
+    proving_args.extend(args.proving_cost_args.to_arg_vec());
+    // boundless args
+    if let Some(boundless_args) = &args.boundless_args {
+        proving_args.extend(boundless_args.to_arg_vec(&args.boundless_storage_config));
+    }
+    // verbosity level
+    if args.core.v > 0 {
+        proving_args.push(verbosity);
+    }
+    // job identifier
+    let correlation_id = proving_job_correlation_id(&proof_file_name);
+    proving_args.extend(vec![
+        String::from("--correlation-id"),
+        correlation_id.clone(),
+    ]);
+    Ok(Some(ProvingJob {
+        correlation_id,
+        proposal_index,
+        fpvm_image_id,
+        proof_file: data_dir.join(RECEIPTS_DIR).join(&proof_file_name),
+        proof_file_name,
+        proving_args,
+    }))
+}
+
+#[cfg(feature = "devnet")]
+fn needs_selector_patch(proof: &Proof) -> bool {
+    match proof {
+        Proof::ZKVMReceipt(_) => false,
+        Proof::BoundlessSeal(seal, _) => {
+            &seal[..4] != kailua_client::set_verifier_selector(crate::SET_BUILDER_ID).as_slice()
         }
-        // Prove via kailua-host (re dev mode/bonsai: env vars inherited!)
-        let mut kailua_host_command = Command::new(&args.kailua_host);
-        // get fake receipts when building under devnet
-        if is_dev_mode() {
-            kailua_host_command.env("RISC0_DEV_MODE", "1");
+    }
+}
+
+/// Name of the file recording an unresolved divergence of the local op-node
+const OP_NODE_DIVERGENCE_FILE: &str = "op-node-divergence.json";
+
+/// A disagreement between the local op-node and a proof generated by this validator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpNodeDivergence {
+    pub block_number: u64,
+    pub op_node_output: B256,
+    pub proven_output: B256,
+}
+
+impl OpNodeDivergence {
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
         }
-        // pass arguments to point at target block
-        kailua_host_command.args(proving_args);
-        debug!("kailua_host_command {:?}", &kailua_host_command);
-        {
-            match kailua_host_command
-                .kill_on_drop(true)
-                .spawn()
-                .context("Invoking kailua-host")?
-                .wait()
-                .await
-            {
-                Ok(proving_task) => {
-                    if !proving_task.success() {
-                        error!("Proving task failure.");
-                    } else {
-                        info!("Proving task successful.");
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to invoke kailua-host: {e:?}");
-                }
+        let data = std::fs::read(path).context("read divergence file")?;
+        Ok(Some(
+            serde_json::from_slice(&data).context("parse divergence file")?,
+        ))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?).context("write divergence file")
+    }
+
+    /// Returns true if both the local and cross-check op-nodes now agree with the proven output
+    pub async fn is_cleared(
+        &self,
+        op_node_provider: &OpNodeProvider,
+        cross_check_provider: Option<&OpNodeProvider>,
+    ) -> anyhow::Result<bool> {
+        let Some(cross_check_provider) = cross_check_provider else {
+            return Ok(false);
+        };
+        for provider in [op_node_provider, cross_check_provider] {
+            if provider.output_at_block(self.block_number).await? != self.proven_output {
+                return Ok(false);
             }
         }
-        sleep(Duration::from_secs(1)).await;
-        // Read receipt file
-        if !Path::new(&proof_file_name).exists() {
-            error!("Proof file {proof_file_name} not found.");
-        } else {
-            info!("Found proof file.");
+        Ok(true)
+    }
+}
+
+/// Name of the file listing proposals that repeatedly failed processing
+pub const PROPOSAL_QUARANTINE_FILE: &str = "proposal-quarantine.json";
+/// Number of consecutive failures after which a proposal is quarantined
+const PROPOSAL_QUARANTINE_THRESHOLD: u32 = 3;
+/// Seconds between retries of quarantined proposals
+const PROPOSAL_QUARANTINE_RETRY_SECS: u64 = 600;
+
+/// Proposals whose processing failed, retried at lower frequency once quarantined
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProposalQuarantine {
+    pub failures: BTreeMap<u64, ProposalFailure>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProposalFailure {
+    pub count: u32,
+    pub last_attempt: u64,
+    pub error: String,
+}
+
+impl ProposalFailure {
+    pub fn is_quarantined(&self) -> bool {
+        self.count >= PROPOSAL_QUARANTINE_THRESHOLD
+    }
+}
+
+impl ProposalQuarantine {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
         }
-        let mut proof_file = match File::open(proof_file_name.clone()).await {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Failed to open proof file {proof_file_name}: {e:?}");
-                continue;
-            }
+        let data = std::fs::read(path).context("read quarantine file")?;
+        serde_json::from_slice(&data).context("parse quarantine file")
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?).context("write quarantine file")
+    }
+
+    /// Returns the failed proposals that should be processed again
+    pub fn due_for_retry(&self, now: u64) -> Vec<u64> {
+        self.failures
+            .iter()
+            .filter(|(_, f)| {
+                !f.is_quarantined() || f.last_attempt + PROPOSAL_QUARANTINE_RETRY_SECS <= now
+            })
+            .map(|(index, _)| *index)
+            .collect()
+    }
+
+    /// Returns true if the proposal had previously failed
+    pub fn record_success(&mut self, proposal_index: u64) -> bool {
+        let failed = self.failures.remove(&proposal_index).is_some();
+        if failed {
+            info!("Proposal {proposal_index} processed after previous failures.");
+        }
+        failed
+    }
+
+    pub fn record_failure(&mut self, proposal_index: u64, now: u64, error: String) {
+        let failure = self
+            .failures
+            .entry(proposal_index)
+            .or_insert(ProposalFailure {
+                count: 0,
+                last_attempt: now,
+                error: String::new(),
+            });
+        failure.count += 1;
+        failure.last_attempt = now;
+        failure.error = error;
+        if failure.count == PROPOSAL_QUARANTINE_THRESHOLD {
+            error!(
+                "QUARANTINED: Proposal {proposal_index} failed {} times and will be retried every {PROPOSAL_QUARANTINE_RETRY_SECS}s.",
+                failure.count
+            );
+        }
+    }
+}
+
+/// Reloads the cached receipts of matches whose on-chain proof status was reverted
+async fn find_reverted_proofs<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    kailua_db: &mut KailuaDB,
+    data_dir: &Path,
+    provider: P,
+) -> anyhow::Result<Vec<(u64, Proof)>> {
+    let pending = kailua_db
+        .state
+        .proven_receipts
+        .iter()
+        .filter(|(_, r)| r.resolved_at.is_none())
+        .map(|(name, r)| (name.clone(), *r))
+        .collect::<Vec<_>>();
+    let mut reverted_proofs = Vec::new();
+    for (receipt_file_name, proven_receipt) in pending {
+        let (Some(contender), Some(proposal)) = (
+            kailua_db.get_local_proposal(&proven_receipt.contender),
+            kailua_db.get_local_proposal(&proven_receipt.proposal),
+        ) else {
+            continue;
+        };
+        let Some(parent) = kailua_db.get_local_proposal(&proposal.parent) else {
+            continue;
         };
-        info!("Opened proof file {proof_file_name}.");
-        let mut proof_data = Vec::new();
-        if let Err(e) = proof_file.read_to_end(&mut proof_data).await {
-            error!("Failed to read proof file {proof_file_name}: {e:?}");
+        let (Some(u_index), Some(v_index)) = (
+            parent.child_index(contender.index),
+            parent.child_index(proposal.index),
+        ) else {
+            continue;
+        };
+        let parent_contract = parent.tournament_contract_instance(&provider);
+        let proof_status = parent_contract
+            .proofStatus(U256::from(u_index), U256::from(v_index))
+            .stall()
+            .await
+            ._0;
+        if proof_status != 0 {
             continue;
         }
-        info!("Read entire proof file.");
-        match bincode::deserialize::<Proof>(&proof_data) {
+        warn!(
+            "Proof of match between {} and {} reverted on L1.",
+            contender.index, proposal.index
+        );
+        // the match is unproven again
+        kailua_db.state.proven_receipts.remove(&receipt_file_name);
+        kailua_db.state.match_deadlines.insert(
+            (contender.index, proposal.index),
+            MatchDeadline {
+                deadline: proposal.challenge_deadline(kailua_db.config.timeout),
+                alert_level: 0,
+            },
+        );
+        let image_id = parent_contract.imageId().stall().await.imageId_;
+        match std::fs::read(data_dir.join(RECEIPTS_DIR).join(&receipt_file_name))
+            .context("read receipt")
+            .and_then(|data| Proof::from_file_bytes(&data, Digest::from(image_id.0)))
+        {
             Ok(proof) => {
-                // Send proof via the channel
-                channel
-                    .sender
-                    .send(Message::Proof(proposal_index, proof))
-                    .await?;
-                info!("Proof for local index {proposal_index} complete.");
+                info!("Resubmitting cached receipt {receipt_file_name}.");
+                reverted_proofs.push((proposal.index, proof));
             }
             Err(e) => {
-                error!("Failed to deserialize proof: {e:?}");
+                error!("Failed to load cached receipt {receipt_file_name}: {e:?}");
             }
         }
     }
+    Ok(reverted_proofs)
 }
 
-#[cfg(feature = "devnet")]
-fn needs_selector_patch(proof: &Proof) -> bool {
-    match proof {
-        Proof::ZKVMReceipt(_) => false,
-        Proof::BoundlessSeal(seal, _) => {
-            &seal[..4] != kailua_client::set_verifier_selector(crate::SET_BUILDER_ID).as_slice()
+/// Orders proposals by challenge deadline, deferring those whose challenge window has elapsed
+fn prioritize_proposals(kailua_db: &KailuaDB, proposals: Vec<u64>) -> Vec<u64> {
+    if proposals.len() < 2 {
+        return proposals;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (mut live, deferred): (Vec<_>, Vec<_>) = proposals
+        .into_iter()
+        .map(|index| {
+            let deadline = kailua_db
+                .get_local_proposal(&index)
+                .map_or(0, |p| p.challenge_deadline(kailua_db.config.timeout));
+            (deadline, index)
+        })
+        .partition(|(deadline, _)| *deadline > now);
+    if !deferred.is_empty() {
+        info!(
+            "Deferring {} proposal(s) with elapsed challenge windows behind {} live one(s).",
+            deferred.len(),
+            live.len()
+        );
+    }
+    live.sort();
+    live.into_iter()
+        .chain(deferred)
+        .map(|(_, index)| index)
+        .collect()
+}
+
+/// Fires escalating alerts for unproven matches whose deadline approaches the expected proving time,
+/// returning the matches that newly became critical
+fn check_match_deadlines(
+    kailua_db: &mut KailuaDB,
+    expected_proving_time: u64,
+) -> Vec<((u64, u64), String)> {
+    let mut critical = Vec::new();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    for ((contender_index, proposal_index), match_deadline) in
+        kailua_db.state.match_deadlines.iter_mut()
+    {
+        let remaining = match_deadline.deadline.saturating_sub(now);
+        let alert_level = if remaining == 0 {
+            3
+        } else if remaining < expected_proving_time {
+            2
+        } else if remaining < 2 * expected_proving_time {
+            1
+        } else {
+            0
+        };
+        if alert_level <= match_deadline.alert_level {
+            continue;
+        }
+        match_deadline.alert_level = alert_level;
+        match alert_level {
+            1 => warn!("Match between {contender_index} and {proposal_index} has {remaining}s left to be proven."),
+            2 => {
+                error!("Match between {contender_index} and {proposal_index} has {remaining}s left to be proven, less than the expected proving time of {expected_proving_time}s!");
+                critical.push(((*contender_index, *proposal_index), format!("Match between {contender_index} and {proposal_index} has {remaining}s left to be proven, less than the expected proving time of {expected_proving_time}s.")));
+            }
+            _ => {
+                error!("DEADLINE MISSED: Match between {contender_index} and {proposal_index} is still unproven!");
+                critical.push(((*contender_index, *proposal_index), format!("Match between {contender_index} and {proposal_index} missed its proof deadline.")));
+            }
+        }
+    }
+    critical
+}
+
+/// Key under which the deadline incident of a match is raised
+fn proof_deadline_incident(contender_index: u64, proposal_index: u64) -> String {
+    format!("kailua-proof-deadline-{contender_index}-{proposal_index}")
+}
+
+/// Returns the reason why the result of a match between two children no longer matters, if any
+pub async fn settled_match_reason<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    parent: &Proposal,
+    contender: &Proposal,
+    proposal: &Proposal,
+    provider: P,
+) -> anyhow::Result<Option<String>> {
+    if parent.fetch_finality(&provider).await? == Some(false) {
+        return Ok(Some(String::from("tournament was resolved as faulty")));
+    }
+    let valid_child = parent
+        .tournament_contract_instance(&provider)
+        .validChild()
+        .stall()
+        .await
+        ._0;
+    if !valid_child.is_zero() {
+        return Ok(Some(format!("tournament has valid child {valid_child}")));
+    }
+    for player in [contender, proposal] {
+        if player.fetch_finality(&provider).await?.is_some() {
+            return Ok(Some(format!(
+                "proposal {} is already resolved",
+                player.index
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// Fails if the verifier of the tournament rejects the seal for the journal when called through
+/// `eth_call`, which surfaces selector and version mismatches before paying for a reverted proof
+async fn verify_seal_onchain<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    tournament: &KailuaTournament::KailuaTournamentInstance<T, P, N>,
+    encoded_seal: &Bytes,
+    journal: &[u8],
+) -> anyhow::Result<()> {
+    let verifier_address = tournament.verifier().stall().await.verifier_;
+    let image_id = tournament.imageId().stall().await.imageId_;
+    let journal_digest = B256::from_slice(Sha256::digest(journal).as_slice());
+    IRiscZeroVerifier::new(verifier_address, tournament.provider())
+        .verify(encoded_seal.clone(), image_id, journal_digest)
+        .call()
+        .await
+        .with_context(|| {
+            format!(
+                "IRiscZeroVerifier({verifier_address})::verify rejected seal with selector 0x{}",
+                hex::encode(&encoded_seal[..4.min(encoded_seal.len())])
+            )
+        })?;
+    Ok(())
+}
+
+/// Returns true if the verifier is a router with a mock verifier installed under the zero selector
+async fn is_mock_verifier<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    verifier_address: Address,
+    provider: P,
+) -> bool {
+    let router = RiscZeroVerifierRouter::new(verifier_address, provider);
+    match router.verifiers([0u8; 4].into()).call().await {
+        // removed verifiers are replaced with a tombstone at address 1
+        Ok(res) => !res._0.is_zero() && res._0 != Address::with_last_byte(1),
+        Err(err) => {
+            debug!("Failed to query verifier router: {err:?}");
+            false
         }
     }
 }
+
+/// Fails if the verifier does not route the proofs generated using the linked zkVM version
+async fn check_verifier_compatibility<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+    verifier_address: Address,
+    provider: P,
+    boundless: bool,
+) -> anyhow::Result<()> {
+    let risc0_version = risc0_zkvm::get_version().unwrap();
+    let groth16_params = Groth16ReceiptVerifierParameters::default();
+    if groth16_params.control_root.as_bytes() != CONTROL_ROOT.as_slice() {
+        bail!(
+            "Groth16 control root {} of risc0 {risc0_version} differs from expected {CONTROL_ROOT}.",
+            groth16_params.control_root
+        );
+    }
+    let mut selectors = vec![(
+        "RiscZeroGroth16Verifier",
+        <[u8; 4]>::try_from(&groth16_params.digest().as_bytes()[..4])?,
+    )];
+    if boundless {
+        selectors.push((
+            "RiscZeroSetVerifier",
+            kailua_client::set_verifier_selector(SET_BUILDER_ID),
+        ));
+    }
+    let router = RiscZeroVerifierRouter::new(verifier_address, provider);
+    for (name, selector) in selectors {
+        let verifier = router
+            .verifiers(selector.into())
+            .call()
+            .await
+            .context(format!(
+                "RiscZeroVerifierRouter({verifier_address})::verifiers"
+            ))?
+            ._0;
+        // removed verifiers are replaced with a tombstone at address 1
+        if verifier.is_zero() || verifier == Address::with_last_byte(1) {
+            bail!(
+                "RiscZeroVerifierRouter({verifier_address}) has no {name} for selector 0x{} required by risc0 {risc0_version}.",
+                hex::encode(selector)
+            );
+        }
+        info!(
+            "Using {name}({verifier}) for selector 0x{}.",
+            hex::encode(selector)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::alert::{AlertSeverity, Alerts};
+use crate::audit::{AuditLog, AuditOutcome};
+use alloy::network::{EthereumWallet, Network, TransactionBuilder};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::LocalSigner;
+use alloy::transports::Transport;
+use anyhow::Context;
+use metrics::gauge;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct WalletArgs {
+    /// Balance (wei) below which the signing wallet is considered low on funds
+    #[clap(long, env)]
+    pub wallet_balance_floor: Option<u128>,
+    /// Secret key of an L1 wallet to top up the signing wallet from when it is low on funds
+    #[clap(long, env, requires = "wallet_balance_floor")]
+    pub funder_key: Option<String>,
+    /// Balance (wei) to top up the signing wallet to (Defaults to twice the floor)
+    #[clap(long, env, requires = "funder_key")]
+    pub wallet_refill_target: Option<u128>,
+    /// Seconds between checks of the signing wallet balance
+    #[clap(long, env, default_value_t = 60)]
+    pub wallet_check_interval: u64,
+}
+
+/// Periodically checks the balance of the signing wallet and tops it up if configured
+pub struct WalletMonitor {
+    address: Address,
+    floor: Option<U256>,
+    refill_target: U256,
+    funder: Option<(Address, EthereumWallet)>,
+    eth_rpc_url: String,
+    interval: Duration,
+    last_check: Option<Instant>,
+    low_balance_alerted: bool,
+}
+
+impl WalletMonitor {
+    pub fn new(args: &WalletArgs, address: Address, eth_rpc_url: &str) -> anyhow::Result<Self> {
+        let funder = match &args.funder_key {
+            Some(funder_key) => {
+                let funder_signer = LocalSigner::from_str(funder_key)?;
+                let funder_address = funder_signer.address();
+                info!("Funder address: {funder_address}");
+                Some((funder_address, EthereumWallet::from(funder_signer)))
+            }
+            None => None,
+        };
+        let floor = args.wallet_balance_floor.map(U256::from);
+        let refill_target = args
+            .wallet_refill_target
+            .map(U256::from)
+            .unwrap_or(floor.unwrap_or_default() * U256::from(2));
+        Ok(Self {
+            address,
+            floor,
+            refill_target,
+            funder,
+            eth_rpc_url: eth_rpc_url.to_string(),
+            interval: Duration::from_secs(args.wallet_check_interval),
+            last_check: None,
+            low_balance_alerted: false,
+        })
+    }
+
+    /// Sends future refills through the given endpoint
+    pub fn reconnect(&mut self, eth_rpc_url: &str) {
+        self.eth_rpc_url = eth_rpc_url.to_string();
+    }
+
+    /// Checks the balance if the interval has elapsed since the last check
+    pub async fn check_if_due<T: Transport + Clone, P: Provider<T, N>, N: Network>(
+        &mut self,
+        provider: P,
+        alerts: &Alerts,
+        audit_log: &AuditLog,
+    ) -> anyhow::Result<()> {
+        if self.last_check.is_some_and(|t| t.elapsed() < self.interval) {
+            return Ok(());
+        }
+        self.last_check = Some(Instant::now());
+        let balance = provider
+            .get_balance(self.address)
+            .await
+            .context("get_balance")?;
+        gauge!("kailua_wallet_balance_gwei")
+            .set((balance / U256::from(1_000_000_000u64)).saturating_to::<u64>() as f64);
+        let Some(floor) = self.floor else {
+            return Ok(());
+        };
+        if balance >= floor {
+            self.low_balance_alerted = false;
+            return Ok(());
+        }
+        warn!(
+            "LOW BALANCE: Wallet {} holds {balance} wei, below the floor of {floor} wei.",
+            self.address
+        );
+        let refilled = match self.refill(balance, audit_log).await {
+            Ok(refilled) => refilled,
+            Err(e) => {
+                error!("Failed to top up wallet: {e:?}");
+                false
+            }
+        };
+        if !refilled && !self.low_balance_alerted {
+            alerts
+                .raise(
+                    AlertSeverity::Critical,
+                    "low_wallet_balance",
+                    format!(
+                        "Wallet {} holds {balance} wei, below the floor of {floor} wei.",
+                        self.address
+                    ),
+                )
+                .await;
+            self.low_balance_alerted = true;
+        }
+        Ok(())
+    }
+
+    /// Transfers funds from the funder to bring the balance up to the refill target
+    async fn refill(&self, balance: U256, audit_log: &AuditLog) -> anyhow::Result<bool> {
+        let Some((funder_address, funder_wallet)) = &self.funder else {
+            return Ok(false);
+        };
+        let amount = self.refill_target.saturating_sub(balance);
+        if amount.is_zero() {
+            return Ok(false);
+        }
+        let funder_provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(funder_wallet)
+            .on_http(self.eth_rpc_url.as_str().try_into()?);
+        let funder_balance = funder_provider
+            .get_balance(*funder_address)
+            .await
+            .context("get_balance")?;
+        if funder_balance < amount {
+            warn!("Funder {funder_address} holds {funder_balance} wei, which is not enough to top up {amount} wei.");
+            return Ok(false);
+        }
+        info!(
+            "Topping up wallet {} with {amount} wei from {funder_address}.",
+            self.address
+        );
+        let intent = format!("top up wallet {} with {amount} wei", self.address);
+        let transaction = TransactionRequest::default()
+            .with_to(self.address)
+            .with_value(amount);
+        let txn = match funder_provider
+            .send_transaction(transaction)
+            .await
+            .context("top up (send)")
+        {
+            Ok(txn) => txn,
+            Err(e) => {
+                audit_log.record(intent, self.address, &[], None, AuditOutcome::failed(&e));
+                return Err(e);
+            }
+        };
+        let tx_hash = *txn.tx_hash();
+        match txn.get_receipt().await.context("top up (get_receipt)") {
+            Ok(receipt) => {
+                info!("Wallet topped up: {receipt:?}");
+                audit_log.record(
+                    intent,
+                    self.address,
+                    &[],
+                    Some(tx_hash),
+                    AuditOutcome::confirmed(&receipt),
+                );
+                Ok(true)
+            }
+            Err(e) => {
+                audit_log.record(
+                    intent,
+                    self.address,
+                    &[],
+                    Some(tx_hash),
+                    AuditOutcome::unconfirmed(&e),
+                );
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,475 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::api::{ValidatorEvent, ValidatorEvents};
+use crate::layout::PREIMAGES_DIR;
+use crate::validate::{run_kailua_host, ProvingJob};
+use alloy::primitives::Bytes;
+use anyhow::{bail, Context};
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use kailua_client::stats::ProvingStats;
+use metrics::gauge;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct WorkerPoolArgs {
+    /// Socket address on which to lease proving jobs to `prove-worker` processes instead of
+    /// invoking kailua-host locally
+    #[clap(long, env)]
+    pub worker_pool_addr: Option<SocketAddr>,
+    /// Seconds without a heartbeat after which a leased job is offered to another worker
+    #[clap(long, env, default_value_t = 120)]
+    pub worker_lease_timeout: u64,
+    /// Maximum number of times a job is leased before it is abandoned
+    #[clap(long, env, default_value_t = 3)]
+    pub worker_max_attempts: u32,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct WorkerArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    pub v: u8,
+
+    /// Url of the worker pool served by the validator
+    #[clap(long, env)]
+    pub coordinator_url: String,
+    /// Path to the kailua host binary to use for proving (Defaults to the embedded host when built
+    /// with the embedded-host feature)
+    #[clap(long, env, required = !cfg!(feature = "embedded-host"))]
+    pub kailua_host: Option<PathBuf>,
+    /// Name identifying this worker in the logs of the validator (Defaults to a random one)
+    #[clap(long, env)]
+    pub worker_name: Option<String>,
+    /// Directory to use for caching data and writing proofs to
+    #[clap(long, env)]
+    pub data_dir: Option<PathBuf>,
+    /// Seconds between lease requests while no jobs are queued
+    #[clap(long, env, default_value_t = 10)]
+    pub worker_poll_interval: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaseRequest {
+    pub worker: String,
+}
+
+/// A proving job leased to a worker
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeasedJob {
+    pub job_id: u64,
+    pub correlation_id: String,
+    pub proof_file_name: String,
+    pub proving_args: Vec<String>,
+    /// Seconds within which the worker must send a heartbeat to keep the lease
+    pub lease_timeout: u64,
+}
+
+/// The result of a leased job reported by a worker
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JobOutcome {
+    pub worker: String,
+    /// The exit code of kailua-host, if it ran to completion
+    pub exit_code: Option<i32>,
+    /// The contents of the proof file
+    pub proof: Option<Bytes>,
+    pub stats: Option<ProvingStats>,
+}
+
+impl JobOutcome {
+    pub fn is_success(&self) -> bool {
+        self.exit_code == Some(0) && self.proof.is_some()
+    }
+
+    /// Writes the proof file and its stats as kailua-host would have locally
+    pub fn save(&self, proof_file_name: &str) -> anyhow::Result<()> {
+        let Some(proof) = &self.proof else {
+            bail!("No proof reported by worker {}.", self.worker);
+        };
+        std::fs::write(proof_file_name, proof).context("write proof file")?;
+        if let Some(stats) = &self.stats {
+            stats.save(proof_file_name)?;
+        }
+        Ok(())
+    }
+}
+
+struct QueuedJob {
+    job_id: u64,
+    job: ProvingJob,
+    attempts: u32,
+}
+
+struct Lease {
+    queued: QueuedJob,
+    worker: String,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_job_id: u64,
+    pending: VecDeque<QueuedJob>,
+    leased: HashMap<u64, Lease>,
+}
+
+/// The queue of proving jobs leased to remote workers
+#[derive(Clone)]
+pub(crate) struct JobQueue {
+    state: Arc<Mutex<QueueState>>,
+    outcomes: UnboundedSender<(ProvingJob, JobOutcome)>,
+    lease_timeout: Duration,
+    max_attempts: u32,
+    validator_events: ValidatorEvents,
+}
+
+impl JobQueue {
+    /// Returns the queue along with the receiver of the outcomes of its jobs
+    pub fn new(
+        args: &WorkerPoolArgs,
+        validator_events: ValidatorEvents,
+    ) -> (Self, UnboundedReceiver<(ProvingJob, JobOutcome)>) {
+        let (outcomes, receiver) = unbounded_channel();
+        let queue = Self {
+            state: Default::default(),
+            outcomes,
+            lease_timeout: Duration::from_secs(args.worker_lease_timeout),
+            max_attempts: args.worker_max_attempts.max(1),
+            validator_events,
+        };
+        (queue, receiver)
+    }
+
+    pub async fn push(&self, job: ProvingJob) {
+        let mut state = self.state.lock().await;
+        let job_id = state.next_job_id;
+        state.next_job_id += 1;
+        state.pending.push_back(QueuedJob {
+            job_id,
+            job,
+            attempts: 0,
+        });
+        report_queue_size(&state);
+    }
+
+    /// Leases the next pending job to the worker
+    pub async fn lease(&self, worker: String) -> Option<LeasedJob> {
+        let mut state = self.state.lock().await;
+        self.expire(&mut state);
+        let mut queued = state.pending.pop_front()?;
+        queued.attempts += 1;
+        info!(
+            correlation_id = %queued.job.correlation_id,
+            "Leased proof for local index {} to worker {worker} (attempt {}).",
+            queued.job.proposal_index,
+            queued.attempts
+        );
+        self.validator_events.emit(ValidatorEvent::ProofStarted {
+            proposal_index: queued.job.proposal_index,
+            correlation_id: queued.job.correlation_id.clone(),
+        });
+        let leased = LeasedJob {
+            job_id: queued.job_id,
+            correlation_id: queued.job.correlation_id.clone(),
+            proof_file_name: queued.job.proof_file_name.clone(),
+            proving_args: queued.job.proving_args.clone(),
+            lease_timeout: self.lease_timeout.as_secs(),
+        };
+        state.leased.insert(
+            queued.job_id,
+            Lease {
+                queued,
+                worker,
+                expires_at: Instant::now() + self.lease_timeout,
+            },
+        );
+        report_queue_size(&state);
+        Some(leased)
+    }
+
+    /// Extends the lease of the job, returning false if the worker no longer holds it
+    pub async fn heartbeat(&self, job_id: u64, worker: &str) -> bool {
+        let mut state = self.state.lock().await;
+        match state.leased.get_mut(&job_id) {
+            Some(lease) if lease.worker == worker => {
+                lease.expires_at = Instant::now() + self.lease_timeout;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Releases the job with the reported outcome, returning false if the worker no longer holds
+    /// its lease
+    pub async fn complete(&self, job_id: u64, outcome: JobOutcome) -> bool {
+        let mut state = self.state.lock().await;
+        match state.leased.get(&job_id) {
+            Some(lease) if lease.worker == outcome.worker => {}
+            _ => return false,
+        }
+        let lease = state.leased.remove(&job_id).unwrap();
+        report_queue_size(&state);
+        let _ = self.outcomes.send((lease.queued.job, outcome));
+        true
+    }
+
+    /// Requeues or abandons jobs whose leases expired, e.g. because their worker crashed
+    fn expire(&self, state: &mut QueueState) {
+        let now = Instant::now();
+        let expired = state
+            .leased
+            .iter()
+            .filter(|(_, lease)| lease.expires_at <= now)
+            .map(|(job_id, _)| *job_id)
+            .collect::<Vec<_>>();
+        for job_id in expired {
+            let lease = state.leased.remove(&job_id).unwrap();
+            let queued = lease.queued;
+            if queued.attempts < self.max_attempts {
+                warn!(
+                    correlation_id = %queued.job.correlation_id,
+                    "Lease of worker {} expired. Requeueing proof for local index {}.",
+                    lease.worker,
+                    queued.job.proposal_index
+                );
+                state.pending.push_front(queued);
+            } else {
+                error!(
+                    correlation_id = %queued.job.correlation_id,
+                    "Lease of worker {} expired. Abandoning proof for local index {} after {} attempts.",
+                    lease.worker,
+                    queued.job.proposal_index,
+                    queued.attempts
+                );
+                let _ = self.outcomes.send((
+                    queued.job,
+                    JobOutcome {
+                        worker: lease.worker,
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+        report_queue_size(state);
+    }
+
+    /// Serves the lease api to workers while expiring stale leases
+    pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
+        let expiry_queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(expiry_queue.lease_timeout / 4).await;
+                let mut state = expiry_queue.state.lock().await;
+                expiry_queue.expire(&mut state);
+            }
+        });
+        let app = Router::new()
+            .route("/jobs/lease", post(lease))
+            .route("/jobs/:job_id/heartbeat", post(heartbeat))
+            .route("/jobs/:job_id/complete", post(complete))
+            .with_state(self);
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .context("TcpListener::bind")?;
+        info!("Serving worker pool on {addr}.");
+        axum::serve(listener, app).await.context("axum::serve")
+    }
+}
+
+fn report_queue_size(state: &QueueState) {
+    gauge!("kailua_worker_pool_jobs", "state" => "pending").set(state.pending.len() as f64);
+    gauge!("kailua_worker_pool_jobs", "state" => "leased").set(state.leased.len() as f64);
+}
+
+async fn lease(
+    State(queue): State<JobQueue>,
+    Json(request): Json<LeaseRequest>,
+) -> Json<Option<LeasedJob>> {
+    Json(queue.lease(request.worker).await)
+}
+
+async fn heartbeat(
+    State(queue): State<JobQueue>,
+    UrlPath(job_id): UrlPath<u64>,
+    Json(request): Json<LeaseRequest>,
+) -> StatusCode {
+    if queue.heartbeat(job_id, &request.worker).await {
+        StatusCode::OK
+    } else {
+        StatusCode::GONE
+    }
+}
+
+async fn complete(
+    State(queue): State<JobQueue>,
+    UrlPath(job_id): UrlPath<u64>,
+    Json(outcome): Json<JobOutcome>,
+) -> StatusCode {
+    if queue.complete(job_id, outcome).await {
+        StatusCode::OK
+    } else {
+        StatusCode::GONE
+    }
+}
+
+/// Leases proving jobs from a validator and proves them with the local kailua-host
+pub async fn prove_worker(args: WorkerArgs, data_dir: PathBuf) -> anyhow::Result<()> {
+    let worker = args.worker_name.clone().unwrap_or_else(|| {
+        format!(
+            "worker-{:08x}",
+            RandomState::new().build_hasher().finish() as u32
+        )
+    });
+    let coordinator_url = args.coordinator_url.trim_end_matches('/').to_string();
+    let client = reqwest::Client::new();
+    info!("Proving as {worker} for {coordinator_url}.");
+    loop {
+        let leased = client
+            .post(format!("{coordinator_url}/jobs/lease"))
+            .json(&LeaseRequest {
+                worker: worker.clone(),
+            })
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let leased: Option<LeasedJob> = match leased {
+            Ok(response) => response.json().await.context("lease (json)")?,
+            Err(err) => {
+                warn!("Failed to lease job: {err:?}");
+                None
+            }
+        };
+        let Some(job) = leased else {
+            tokio::time::sleep(Duration::from_secs(args.worker_poll_interval)).await;
+            continue;
+        };
+        info!(correlation_id = %job.correlation_id, "Leased job {}.", job.job_id);
+
+        let proving_task = prove_leased_job(&args, &data_dir, &worker, &job);
+        let heartbeat_task = keep_lease(&client, &coordinator_url, &worker, &job);
+        let outcome = tokio::select! {
+            outcome = proving_task => outcome,
+            _ = heartbeat_task => {
+                // dropping the proving task kills kailua-host
+                warn!(correlation_id = %job.correlation_id, "Lost lease of job {}. Aborted proving.", job.job_id);
+                continue;
+            }
+        };
+        let outcome = outcome.unwrap_or_else(|err| {
+            error!(correlation_id = %job.correlation_id, "Failed to prove job {}: {err:?}", job.job_id);
+            JobOutcome {
+                worker: worker.clone(),
+                ..Default::default()
+            }
+        });
+        match client
+            .post(format!("{coordinator_url}/jobs/{}/complete", job.job_id))
+            .json(&outcome)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => {
+                info!(correlation_id = %job.correlation_id, "Reported outcome of job {}.", job.job_id);
+                // the validator keeps its own copy of the proof
+                let proof_file_name = data_dir.join(&job.proof_file_name);
+                let stats_file_name = ProvingStats::file_name(&proof_file_name.to_string_lossy());
+                let _ = std::fs::remove_file(proof_file_name);
+                let _ = std::fs::remove_file(stats_file_name);
+            }
+            Err(err) => {
+                error!(correlation_id = %job.correlation_id, "Failed to report outcome of job {}: {err:?}", job.job_id)
+            }
+        }
+    }
+}
+
+/// Sends heartbeats for the leased job, returning once the lease is lost
+async fn keep_lease(
+    client: &reqwest::Client,
+    coordinator_url: &str,
+    worker: &str,
+    job: &LeasedJob,
+) {
+    let interval = Duration::from_secs((job.lease_timeout / 3).max(1));
+    loop {
+        tokio::time::sleep(interval).await;
+        let response = client
+            .post(format!("{coordinator_url}/jobs/{}/heartbeat", job.job_id))
+            .json(&LeaseRequest {
+                worker: worker.to_string(),
+            })
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status() == reqwest::StatusCode::GONE => return,
+            Ok(_) => {}
+            // the lease may survive a transient failure to reach the validator
+            Err(err) => warn!("Failed to send heartbeat: {err:?}"),
+        }
+    }
+}
+
+/// Runs kailua-host on the leased job with local paths and collects its proof
+async fn prove_leased_job(
+    args: &WorkerArgs,
+    data_dir: &Path,
+    worker: &str,
+    job: &LeasedJob,
+) -> anyhow::Result<JobOutcome> {
+    let mut proving_args = job.proving_args.clone();
+    // cache preimages on this machine
+    if let Some(position) = proving_args.iter().position(|arg| arg == "--data-dir") {
+        let job_data_dir = data_dir.join(PREIMAGES_DIR).join(&job.proof_file_name);
+        proving_args[position + 1] = job_data_dir.to_string_lossy().to_string();
+    }
+    let status = run_kailua_host(
+        args.kailua_host.as_deref(),
+        &proving_args,
+        false,
+        Some(data_dir),
+    )
+    .await?;
+    let proof_file = data_dir.join(&job.proof_file_name);
+    let proof_file_name = proof_file.to_string_lossy().to_string();
+    let proof = match std::fs::read(&proof_file) {
+        Ok(proof) => Some(Bytes::from(proof)),
+        Err(err) => {
+            warn!(correlation_id = %job.correlation_id, "Failed to read proof file {proof_file_name}: {err:?}");
+            None
+        }
+    };
+    let stats = ProvingStats::load(&proof_file_name).unwrap_or_else(|err| {
+        warn!(correlation_id = %job.correlation_id, "Failed to load proving stats: {err:?}");
+        None
+    });
+    Ok(JobOutcome {
+        worker: worker.to_string(),
+        exit_code: status.code(),
+        proof,
+        stats,
+    })
+}
@@ -0,0 +1,128 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::proof::Proof;
+use anyhow::{bail, Context};
+use bonsai_sdk::non_blocking::{Client, SessionId};
+use kailua_common::witness::Witness;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{is_dev_mode, Receipt};
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Returns true if proofs should be requested from Bonsai
+pub fn is_bonsai_enabled() -> bool {
+    !is_dev_mode()
+        && std::env::var("BONSAI_API_KEY").is_ok()
+        && std::env::var("BONSAI_API_URL").is_ok()
+}
+
+/// Proves the witness using Bonsai, persisting the session id under the given file name so that
+/// an interrupted job reattaches to its running session instead of starting a new one.
+pub async fn run_bonsai_client(
+    witness: Witness,
+    elf: &[u8],
+    image_id: Digest,
+    session_file_name: &str,
+) -> anyhow::Result<(Proof, Option<u64>)> {
+    info!("Running bonsai client.");
+    let client = Client::from_env(risc0_zkvm::VERSION).context("Client::from_env")?;
+    // Reattach to a previously started session
+    let mut session = match tokio::fs::read_to_string(session_file_name).await {
+        Ok(uuid) => {
+            let session = SessionId::new(uuid.trim().to_string());
+            match session.status(&client).await {
+                Ok(status) if status.status == "RUNNING" || status.status == "SUCCEEDED" => {
+                    info!("Reattaching to bonsai session {}.", session.uuid);
+                    Some(session)
+                }
+                Ok(status) => {
+                    warn!(
+                        "Discarding bonsai session {} with status {}.",
+                        session.uuid, status.status
+                    );
+                    None
+                }
+                Err(e) => {
+                    warn!("Failed to query bonsai session {}: {e:?}", session.uuid);
+                    None
+                }
+            }
+        }
+        Err(_) => None,
+    };
+    // Start a new session
+    if session.is_none() {
+        let image_id_hex = image_id.to_string();
+        client
+            .upload_img(&image_id_hex, elf.to_vec())
+            .await
+            .context("upload_img")?;
+        let data = witness.encode()?;
+        // Frame the witness data as read by the guest
+        let input = [
+            (data.len() as u32).to_le_bytes().as_slice(),
+            data.as_slice(),
+        ]
+        .concat();
+        let input_id = client.upload_input(input).await.context("upload_input")?;
+        let new_session = client
+            .create_session(image_id_hex, input_id, vec![], false)
+            .await
+            .context("create_session")?;
+        info!("Started bonsai session {}.", new_session.uuid);
+        tokio::fs::write(session_file_name, &new_session.uuid)
+            .await
+            .context("write session file")?;
+        session = Some(new_session);
+    }
+    let session = session.unwrap();
+    // Wait for the session to complete
+    let (receipt_url, total_cycles) = loop {
+        let status = session.status(&client).await.context("status")?;
+        match status.status.as_str() {
+            "RUNNING" => sleep(Duration::from_secs(15)).await,
+            "SUCCEEDED" => {
+                break (
+                    status.receipt_url.context("receipt_url")?,
+                    status.stats.map(|stats| stats.total_cycles),
+                )
+            }
+            _ => {
+                // Do not reattach to a failed session
+                let _ = tokio::fs::remove_file(session_file_name).await;
+                bail!(
+                    "Bonsai session {} failed with status {}: {}",
+                    session.uuid,
+                    status.status,
+                    status.error_msg.unwrap_or_default()
+                );
+            }
+        }
+    };
+    let receipt_buf = client.download(&receipt_url).await.context("download")?;
+    let receipt: Receipt = bincode::deserialize(&receipt_buf).context("deserialize receipt")?;
+    receipt.verify(image_id).context("receipt verification")?;
+    info!("Receipt verified.");
+    // The session is no longer needed once its receipt is retrieved
+    if Path::new(session_file_name).exists() {
+        tokio::fs::remove_file(session_file_name)
+            .await
+            .context("remove session file")?;
+    }
+
+    Ok((Proof::ZKVMReceipt(Box::new(receipt)), total_cycles))
+}
@@ -12,12 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod bonsai;
+pub mod mock;
 pub mod oracle;
 pub mod proof;
+pub mod snapshot;
+pub mod stats;
 pub mod witness;
 
+use crate::mock::MockProverArgs;
 use crate::proof::Proof;
-use crate::witness::{BlobWitnessProvider, OracleWitnessProvider};
+use crate::snapshot::Snapshot;
+use crate::stats::ProvingStats;
+use crate::witness::{record_witness, BlobWitnessProvider, OracleWitnessProvider};
 use alloy::signers::k256::ecdsa::signature::digest::Digest;
 use alloy::sol_types::SolValue;
 use alloy::transports::http::reqwest::Url;
@@ -40,20 +47,55 @@ use kona_preimage::{HintWriterClient, PreimageOracleClient};
 use kona_proof::l1::OracleBlobProvider;
 use kona_proof::{BootInfo, CachingOracle};
 use risc0_zkvm::sha::Digestible;
-use risc0_zkvm::{default_executor, default_prover, is_dev_mode, ExecutorEnv, Journal, ProverOpts};
+use risc0_zkvm::{
+    compute_image_id, default_executor, default_prover, is_dev_mode, ExecutorEnv, Journal,
+    ProverOpts,
+};
 use std::fmt::Debug;
 use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use tokio::task::spawn_blocking;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 /// The size of the LRU cache in the oracle.
 pub const ORACLE_LRU_SIZE: usize = 1024;
 
+/// The exit code of kailua-host when the derived output differs from the claimed one.
+pub const EXIT_CODE_OUTPUT_DIVERGENCE: i32 = 3;
+
+/// The output derived by the native client differs from the one it was asked to prove.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputDivergence {
+    pub claimed: B256,
+    /// None if the data as of the l1 head is insufficient to derive the output
+    pub computed: Option<B256>,
+}
+
+impl std::fmt::Display for OutputDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.computed {
+            Some(computed) => write!(
+                f,
+                "Derived output {computed} differs from claimed output {}.",
+                self.claimed
+            ),
+            None => write!(
+                f,
+                "Insufficient data to derive claimed output {}.",
+                self.claimed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OutputDivergence {}
+
 /// The client binary CLI application arguments.
 #[derive(Parser, Clone, Debug)]
 pub struct KailuaClientCli {
@@ -63,6 +105,28 @@ pub struct KailuaClientCli {
     #[clap(long, value_parser = parse_b256, env)]
     pub precondition_validation_data_hash: Option<B256>,
 
+    /// Whether to emit a pprof file profiling the cycles spent by the guest
+    #[clap(long, default_value_t = false, env)]
+    pub profile: bool,
+
+    /// Path to an alternative FPVM ELF to prove with instead of the bundled one
+    #[clap(long, env)]
+    pub fpvm_elf: Option<PathBuf>,
+    /// Directory to record the canonical witness of the proof in, e.g. for replay fuzzing
+    #[clap(long, env)]
+    pub witness_dir: Option<PathBuf>,
+    /// Directory to record a regression snapshot of the proof in
+    #[clap(long, env)]
+    pub snapshot_dir: Option<PathBuf>,
+    /// Identifier of the proving job, recorded in its stats file
+    #[clap(long, env)]
+    pub correlation_id: Option<String>,
+
+    #[clap(flatten)]
+    pub proving_cost_args: ProvingCostArgs,
+    #[clap(flatten)]
+    pub mock_prover_args: MockProverArgs,
+
     #[clap(flatten)]
     pub boundless_args: Option<BoundlessArgs>,
     /// Storage provider to use for elf and input
@@ -70,6 +134,35 @@ pub struct KailuaClientCli {
     pub boundless_storage_config: Option<StorageProviderConfig>,
 }
 
+#[derive(Parser, Debug, Clone, Default)]
+pub struct ProvingCostArgs {
+    /// Price in USD per million cycles used to estimate the cost of a proof before proving
+    #[clap(long, env)]
+    pub proving_cost_per_mcycle: Option<f64>,
+    /// Skip proofs whose estimated cost in USD exceeds this ceiling
+    #[clap(long, env, requires = "proving_cost_per_mcycle")]
+    pub proving_cost_ceiling: Option<f64>,
+}
+
+impl ProvingCostArgs {
+    pub fn to_arg_vec(&self) -> Vec<String> {
+        let mut proving_args = Vec::new();
+        if let Some(price) = self.proving_cost_per_mcycle {
+            proving_args.extend(vec![
+                String::from("--proving-cost-per-mcycle"),
+                price.to_string(),
+            ]);
+        }
+        if let Some(ceiling) = self.proving_cost_ceiling {
+            proving_args.extend(vec![
+                String::from("--proving-cost-ceiling"),
+                ceiling.to_string(),
+            ]);
+        }
+        proving_args
+    }
+}
+
 #[derive(Parser, Debug, Clone)]
 #[group(requires_all = ["boundless_rpc_url", "boundless_wallet_key", "boundless_set_verifier_address", "boundless_market_address"])]
 pub struct BoundlessArgs {
@@ -187,49 +280,184 @@ pub fn parse_b256(s: &str) -> Result<B256, String> {
     B256::from_str(s).map_err(|_| format!("Invalid B256 value: {}", s))
 }
 
+/// Loads the FPVM ELF and its image id, defaulting to the bundled build
+pub fn load_fpvm(elf_path: Option<&PathBuf>) -> anyhow::Result<(Vec<u8>, risc0_zkvm::sha::Digest)> {
+    match elf_path {
+        Some(path) => {
+            let elf = std::fs::read(path).context("read fpvm elf")?;
+            let image_id = compute_image_id(&elf).context("compute_image_id")?;
+            Ok((elf, image_id))
+        }
+        None => Ok((KAILUA_FPVM_ELF.to_vec(), KAILUA_FPVM_ID.into())),
+    }
+}
+
+/// Fails if the bundled FPVM ELF does not match the bundled image id under the linked zkVM version
+pub fn check_fpvm_compatibility() -> anyhow::Result<()> {
+    let risc0_version = risc0_zkvm::get_version().unwrap();
+    let expected_image_id = risc0_zkvm::sha::Digest::from(KAILUA_FPVM_ID);
+    let image_id = compute_image_id(KAILUA_FPVM_ELF).context("compute_image_id")?;
+    ensure!(
+        image_id == expected_image_id,
+        "Bundled FPVM ELF has image id {image_id} under risc0 {risc0_version} instead of {expected_image_id}."
+    );
+    info!("Using risc0 zkVM {risc0_version} with FPVM image id {image_id}.");
+    Ok(())
+}
+
+/// Searches the directory for an FPVM ELF with the given image id
+pub fn find_fpvm_elf(
+    dir: &Path,
+    image_id: risc0_zkvm::sha::Digest,
+) -> anyhow::Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(dir).context("read_dir")?.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(elf) = std::fs::read(&path) else {
+            continue;
+        };
+        if let Ok(elf_image_id) = compute_image_id(&elf) {
+            if elf_image_id == image_id {
+                return Ok(Some(path));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn run_client<P, H>(
     boundless_args: Option<BoundlessArgs>,
     boundless_storage_config: Option<StorageProviderConfig>,
     oracle_client: P,
     hint_client: H,
     precondition_validation_data_hash: B256,
+    cancel: CancellationToken,
+    profile: bool,
+    fpvm_elf: Option<PathBuf>,
+    witness_dir: Option<PathBuf>,
+    snapshot_dir: Option<PathBuf>,
+    proving_cost_args: ProvingCostArgs,
+    mock_prover_args: MockProverArgs,
+    correlation_id: Option<String>,
 ) -> anyhow::Result<()>
 where
     P: PreimageOracleClient + Send + Sync + Debug + Clone + 'static,
     H: HintWriterClient + Send + Sync + Debug + Clone + 'static,
 {
+    let (elf, image_id) = load_fpvm(fpvm_elf.as_ref())?;
+    if fpvm_elf.is_some() {
+        info!("Proving using FPVM image id {image_id}.");
+    }
     // preload all data natively
     info!("Running native client.");
     let (journal, witness) = run_native_client(
         oracle_client.clone(),
         hint_client.clone(),
         precondition_validation_data_hash,
+        cancel,
     )
     .await
-    .expect("Failed to run native client.");
+    .context("Failed to run native client.")?;
+    // record the witness for offline replay
+    if let Some(witness_dir) = &witness_dir {
+        let witness_file = record_witness(witness_dir, &witness)
+            .await
+            .context("Failed to record witness.")?;
+        info!("Recorded witness to {}.", witness_file.display());
+    }
+    // record the expected journal for regression testing
+    if let Some(snapshot_dir) = &snapshot_dir {
+        let snapshot_file = Snapshot::record(snapshot_dir, &witness, &journal)
+            .await
+            .context("Failed to record snapshot.")?;
+        info!("Recorded snapshot to {}.", snapshot_file.display());
+    }
+    // estimate the cost of proving before committing to it
+    let mut estimated_cost = None;
+    if let Some(price_per_mcycle) = proving_cost_args.proving_cost_per_mcycle {
+        let mcycles = estimate_mcycles(&witness, &elf).await?;
+        let cost = mcycles as f64 * price_per_mcycle;
+        info!("Proving estimated at {mcycles} Mcycles costing ${cost:.2}.");
+        if let Some(ceiling) = proving_cost_args.proving_cost_ceiling {
+            ensure!(
+                cost <= ceiling,
+                "Estimated proving cost ${cost:.2} exceeds ceiling ${ceiling:.2}."
+            );
+        }
+        estimated_cost = Some(cost);
+    }
+    // name auxiliary files after the proof they were collected for
+    let proof_file_name = proof::fpvm_proof_file_name_from_journal(image_id, &journal);
+    let profile_file_name = profile.then(|| format!("{proof_file_name}.pprof"));
     // compute the receipt in the zkvm
-    let proof = match boundless_args {
-        Some(args) => run_boundless_client(args, boundless_storage_config, journal, witness)
+    let proving_start = Instant::now();
+    let (proof, backend, total_cycles) = match boundless_args {
+        _ if mock_prover_args.is_enabled() => {
+            let proof = mock::run_mock_client(&mock_prover_args, &journal, image_id)
+                .await
+                .context("Failed to run mock client.")?;
+            (proof, "mock", None)
+        }
+        Some(args) => {
+            if profile {
+                warn!("Guest profiling is unavailable when proving using boundless.");
+            }
+            let proof = run_boundless_client(
+                args,
+                boundless_storage_config,
+                journal,
+                witness,
+                &elf,
+                image_id,
+            )
             .await
-            .context("Failed to run boundless client.")?,
-        None => run_zkvm_client(witness)
+            .context("Failed to run boundless client.")?;
+            (proof, "boundless", None)
+        }
+        None if bonsai::is_bonsai_enabled() => {
+            if profile {
+                warn!("Guest profiling is unavailable when proving using bonsai.");
+            }
+            let (proof, total_cycles) = bonsai::run_bonsai_client(
+                witness,
+                &elf,
+                image_id,
+                &format!("{proof_file_name}.bonsai"),
+            )
             .await
-            .context("Failed to run zkvm client.")?,
+            .context("Failed to run bonsai client.")?;
+            (proof, "bonsai", total_cycles)
+        }
+        None => {
+            let (proof, total_cycles) = run_zkvm_client(witness, profile_file_name, elf, image_id)
+                .await
+                .context("Failed to run zkvm client.")?;
+            (proof, "local", Some(total_cycles))
+        }
+    };
+    let proving_stats = ProvingStats {
+        backend: backend.to_string(),
+        total_cycles,
+        proving_secs: proving_start.elapsed().as_secs_f64(),
+        estimated_cost,
+        correlation_id,
     };
     // Prepare proof file
     let proof_journal = ProofJournal::decode_packed(proof.journal().as_ref())
         .expect("Failed to decode proof output");
-    let mut output_file = File::create(proof::fpvm_proof_file_name(
-        proof_journal.precondition_output,
-        proof_journal.l1_head,
-        proof_journal.claimed_l2_output_root,
-        proof_journal.claimed_l2_block_number,
-        proof_journal.agreed_l2_output_root,
+    let mut output_file = File::create(proof::fpvm_proof_file_name_from_journal(
+        image_id,
+        &proof_journal,
     ))
     .await
     .expect("Failed to create proof output file");
     // Write proof data to file
-    let proof_bytes = bincode::serialize(&proof).expect("Could not serialize proof.");
+    let proof_bytes = proof
+        .to_file_bytes(image_id)
+        .expect("Could not serialize proof.");
     output_file
         .write_all(proof_bytes.as_slice())
         .await
@@ -238,14 +466,23 @@ where
         .flush()
         .await
         .expect("Failed to flush proof output file data.");
+    // Record the measurements of this job for the validator
+    if let Err(e) = proving_stats.save(&proof_file_name) {
+        warn!("Failed to save proving stats: {e:?}");
+    }
 
     Ok(())
 }
 
+/// Runs the client natively to collect the witness of the proof
+///
+/// Cancelling the token aborts any pending preimage request, failing the run instead of waiting
+/// indefinitely on an unresponsive preimage server.
 pub async fn run_native_client<P, H>(
     oracle_client: P,
     hint_client: H,
     precondition_validation_data_hash: B256,
+    cancel: CancellationToken,
 ) -> anyhow::Result<(ProofJournal, Witness)>
 where
     P: PreimageOracleClient + Send + Sync + Debug + Clone,
@@ -257,6 +494,7 @@ where
     let oracle = Arc::new(OracleWitnessProvider {
         oracle: CachingOracle::new(ORACLE_LRU_SIZE, oracle_client, hint_client),
         witness: oracle_witness.clone(),
+        cancel,
     });
     let boot = Arc::new(
         BootInfo::load(oracle.as_ref())
@@ -274,35 +512,104 @@ where
         boot.clone(),
         beacon,
     )?;
-    // Check output
+    // Check output before committing to a proof that would contradict it
+    let divergence = OutputDivergence {
+        claimed: boot.claimed_l2_output_root,
+        computed: real_output_hash,
+    };
     if let Some(computed_output) = real_output_hash {
         // With sufficient data, the input l2_claim must be true
-        assert_eq!(boot.claimed_l2_output_root, computed_output);
+        ensure!(boot.claimed_l2_output_root == computed_output, divergence);
     } else {
         // We use the zero claim hash to denote that the data as of l1 head is insufficient
-        assert_eq!(boot.claimed_l2_output_root, B256::ZERO);
+        ensure!(boot.claimed_l2_output_root == B256::ZERO, divergence);
     }
     let witness = Witness {
         oracle_witness: core::mem::take(oracle_witness.lock().unwrap().deref_mut()),
         blobs_witness: core::mem::take(blobs_witness.lock().unwrap().deref_mut()),
         precondition_validation_data_hash,
     };
-    let journal_output = ProofJournal::new(precondition_hash, boot.as_ref());
-    Ok((journal_output, witness))
+    let journal = ProofJournal::new(precondition_hash, boot.as_ref());
+    // Replay the witness to reject it before proving if it is incomplete
+    info!("Validating witness.");
+    let (witness, replayed_journal) = witness
+        .canonicalize(boot.as_ref())
+        .context("Witness::canonicalize")?;
+    ensure!(
+        replayed_journal.encode_packed() == journal.encode_packed(),
+        "Witness replay commits to a different journal"
+    );
+    Ok((journal, witness))
 }
 
-pub async fn run_zkvm_client(witness: Witness) -> anyhow::Result<Proof> {
-    info!("Running zkvm client.");
-    let prove_info = spawn_blocking(move || {
-        let data = rkyv::to_bytes::<rkyv::rancor::Error>(&witness)?.to_vec();
-        // Execution environment
+/// Executes the fpvm on the witness to count the millions of cycles that proving it would take
+pub async fn estimate_mcycles(witness: &Witness, elf: &[u8]) -> anyhow::Result<u64> {
+    info!("Estimating proving cycles.");
+    let execution = execute_zkvm_client(witness, elf).await?;
+    Ok(execution.total_cycles.div_ceil(1_000_000))
+}
+
+/// The outcome of executing the fpvm on a witness without proving it
+#[derive(Clone, Debug)]
+pub struct ZkvmExecution {
+    /// The packed journal committed to by the fpvm
+    pub journal: Vec<u8>,
+    /// The cycles spent executing the fpvm, excluding padding
+    pub user_cycles: u64,
+    /// The cycles that proving the execution would take, including padding
+    pub total_cycles: u64,
+}
+
+/// Executes the fpvm on the witness and returns the packed journal it commits to
+pub async fn execute_zkvm_client(witness: &Witness, elf: &[u8]) -> anyhow::Result<ZkvmExecution> {
+    let input_frame = witness.encode()?;
+    let elf = elf.to_vec();
+    let session_info = spawn_blocking(move || {
         let env = ExecutorEnv::builder()
             // Pass in witness data
-            .write_frame(&data)
+            .write_frame(&input_frame)
             .build()?;
+        default_executor().execute(env, &elf)
+    })
+    .await??;
+    Ok(ZkvmExecution {
+        user_cycles: session_info
+            .segments
+            .iter()
+            .map(|segment| segment.cycles as u64)
+            .sum(),
+        total_cycles: session_info
+            .segments
+            .iter()
+            .map(|segment| 1 << segment.po2)
+            .sum(),
+        journal: session_info.journal.bytes,
+    })
+}
+
+pub async fn run_zkvm_client(
+    witness: Witness,
+    profile_file_name: Option<String>,
+    elf: Vec<u8>,
+    image_id: risc0_zkvm::sha::Digest,
+) -> anyhow::Result<(Proof, u64)> {
+    info!("Running zkvm client.");
+    let prove_info = spawn_blocking(move || {
+        let data = witness.encode()?;
+        // Execution environment
+        let mut builder = ExecutorEnv::builder();
+        // Pass in witness data
+        builder.write_frame(&data);
+        // Attribute cycles to guest functions
+        if let Some(profile_file_name) = profile_file_name {
+            info!("Writing guest profile to {profile_file_name}.");
+            builder.enable_profiler(profile_file_name);
+        }
+        let env = builder.build()?;
         let prover = default_prover();
         let prove_info = prover
-            .prove_with_opts(env, KAILUA_FPVM_ELF, &ProverOpts::groth16())
+            // groth16 wrapping is deferred until the proof is submitted on-chain
+            .prove_with_opts(env, &elf, &ProverOpts::succinct())
             .context("prove_with_opts")?;
         Ok::<_, anyhow::Error>(prove_info)
     })
@@ -314,11 +621,14 @@ pub async fn run_zkvm_client(witness: Witness) -> anyhow::Result<Proof> {
     );
     prove_info
         .receipt
-        .verify(KAILUA_FPVM_ID)
+        .verify(image_id)
         .context("receipt verification")?;
     info!("Receipt verified.");
 
-    Ok(Proof::ZKVMReceipt(Box::new(prove_info.receipt)))
+    Ok((
+        Proof::ZKVMReceipt(Box::new(prove_info.receipt)),
+        prove_info.stats.total_cycles,
+    ))
 }
 
 pub async fn run_boundless_client(
@@ -326,6 +636,8 @@ pub async fn run_boundless_client(
     storage: Option<StorageProviderConfig>,
     journal: ProofJournal,
     witness: Witness,
+    elf: &[u8],
+    image_id: risc0_zkvm::sha::Digest,
 ) -> anyhow::Result<Proof> {
     info!("Running boundless client.");
     let proof_journal = Journal::new(journal.encode_packed());
@@ -365,10 +677,7 @@ pub async fn run_boundless_client(
     }
 
     // Set the proof request requirements
-    let requirements = Requirements::new(
-        KAILUA_FPVM_ID,
-        Predicate::digest_match(proof_journal.digest()),
-    );
+    let requirements = Requirements::new(image_id, Predicate::digest_match(proof_journal.digest()));
 
     // Check if an unexpired request had already been made recently
     let boundless_wallet_address = boundless_client.signer.address();
@@ -414,12 +723,12 @@ pub async fn run_boundless_client(
 
     // Preflight execution to get cycle count
     info!("Preflighting execution.");
-    let input_frame = rkyv::to_bytes::<rkyv::rancor::Error>(&witness)?.to_vec();
+    let input_frame = witness.encode()?;
     let env = ExecutorEnv::builder()
         // Pass in witness data
         .write_frame(&input_frame)
         .build()?;
-    let session_info = default_executor().execute(env, KAILUA_FPVM_ELF)?;
+    let session_info = default_executor().execute(env, elf)?;
     let mcycles_count = session_info
         .segments
         .iter()
@@ -433,7 +742,7 @@ pub async fn run_boundless_client(
         boundless_client.storage_provider.is_some(),
         "A storage provider is required to host the FPVM program and input."
     );
-    let image_url = boundless_client.upload_image(KAILUA_FPVM_ELF).await?;
+    let image_url = boundless_client.upload_image(elf).await?;
     info!("Uploaded image to {}", image_url);
     // Upload input
     let input = InputBuilder::new().write_frame(&input_frame).build();
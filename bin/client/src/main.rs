@@ -15,6 +15,7 @@
 use clap::Parser;
 use kailua_client::oracle::{HINT_WRITER, ORACLE_READER};
 use kailua_client::KailuaClientCli;
+use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -29,6 +30,14 @@ async fn main() -> anyhow::Result<()> {
         ORACLE_READER,
         HINT_WRITER,
         precondition_validation_data_hash,
+        CancellationToken::new(),
+        args.profile,
+        args.fpvm_elf,
+        args.witness_dir,
+        args.snapshot_dir,
+        args.proving_cost_args,
+        args.mock_prover_args,
+        args.correlation_id,
     )
     .await
 }
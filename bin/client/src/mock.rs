@@ -0,0 +1,81 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::proof::Proof;
+use anyhow::{bail, ensure};
+use kailua_common::journal::ProofJournal;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{is_dev_mode, FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Parameters of a prover that skips proving and returns fake receipts, e.g. to load test the
+/// validator without proving costs
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct MockProverArgs {
+    /// Seconds after which to return a fake receipt without executing the fpvm
+    ///
+    /// Requires RISC0_DEV_MODE. Proving is mocked only if this is set.
+    #[clap(long, env)]
+    pub mock_proving_delay: Option<u64>,
+    /// Maximum number of seconds randomly added to the mock proving delay
+    #[clap(long, env, default_value_t = 0)]
+    pub mock_proving_jitter: u64,
+    /// Probability in [0, 1] that mock proving fails after the delay instead of returning a receipt
+    #[clap(long, env, default_value_t = 0.0)]
+    pub mock_proving_failure_rate: f64,
+}
+
+impl MockProverArgs {
+    pub fn is_enabled(&self) -> bool {
+        self.mock_proving_delay.is_some()
+    }
+}
+
+/// Returns a uniformly random number in [0, 1)
+fn random_unit() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Waits for the configured delay and returns a fake receipt committing to the journal, or fails
+/// at the configured rate
+pub async fn run_mock_client(
+    args: &MockProverArgs,
+    journal: &ProofJournal,
+    image_id: Digest,
+) -> anyhow::Result<Proof> {
+    ensure!(is_dev_mode(), "Mock proving requires RISC0_DEV_MODE.");
+    ensure!(
+        (0.0..=1.0).contains(&args.mock_proving_failure_rate),
+        "Mock proving failure rate {} is not a probability.",
+        args.mock_proving_failure_rate
+    );
+    let jitter = (random_unit() * (args.mock_proving_jitter + 1) as f64) as u64;
+    let delay = args.mock_proving_delay.unwrap_or_default() + jitter;
+    warn!("Mock proving for {delay} seconds.");
+    tokio::time::sleep(Duration::from_secs(delay)).await;
+
+    if random_unit() < args.mock_proving_failure_rate {
+        bail!("Mock proving failed after {delay} seconds.");
+    }
+
+    let journal = journal.encode_packed();
+    let claim = ReceiptClaim::ok(image_id, journal.clone());
+    let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal);
+    info!("Mock receipt computed.");
+    Ok(Proof::ZKVMReceipt(Box::new(receipt)))
+}
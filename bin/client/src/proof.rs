@@ -13,9 +13,54 @@
 // limitations under the License.
 
 use alloy_primitives::{keccak256, B256};
-use kailua_build::KAILUA_FPVM_ID;
-use risc0_zkvm::{Journal, Receipt};
+use anyhow::{bail, Context};
+use kailua_common::journal::ProofJournal;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{default_prover, InnerReceipt, Journal, ProverOpts, Receipt};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::task::spawn_blocking;
+use tracing::{debug, info, warn};
+
+/// Magic bytes at the start of every proof file
+pub const PROOF_FILE_MAGIC: [u8; 8] = *b"KAILUAPF";
+
+/// The version of the proof file envelope format
+pub const PROOF_FILE_VERSION: u8 = 1;
+
+/// Metadata stored ahead of the serialized proof in a proof file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofFileHeader {
+    /// The version of the proof file envelope format
+    pub version: u8,
+    /// The RISC Zero zkVM version used to generate the proof
+    pub risc0_version: String,
+    /// The image id of the proven program
+    pub image_id: B256,
+    /// The packed journal committed by the program
+    pub journal: Vec<u8>,
+}
+
+impl ProofFileHeader {
+    /// Fails if the proof was not generated for the given image id
+    pub fn check_compatibility(&self, image_id: Digest) -> anyhow::Result<()> {
+        if self.image_id.as_slice() != image_id.as_bytes() {
+            bail!(
+                "Proof is for image id {} instead of {image_id}.",
+                self.image_id
+            );
+        }
+        let local_version = risc0_zkvm::get_version().unwrap().to_string();
+        if self.risc0_version != local_version {
+            warn!(
+                "Proof was generated using risc0 {} (local {local_version}).",
+                self.risc0_version
+            );
+        }
+        ProofJournal::decode_packed(&self.journal).context("decode_packed")?;
+        Ok(())
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Proof {
@@ -55,9 +100,127 @@ impl Proof {
             _ => None,
         }
     }
+
+    /// Serializes the proof into a proof file envelope describing its contents
+    pub fn to_file_bytes(&self, image_id: Digest) -> anyhow::Result<Vec<u8>> {
+        let header = ProofFileHeader {
+            version: PROOF_FILE_VERSION,
+            risc0_version: risc0_zkvm::get_version().unwrap().to_string(),
+            image_id: B256::from_slice(image_id.as_bytes()),
+            journal: self.journal().bytes.clone(),
+        };
+        let mut data = PROOF_FILE_MAGIC.to_vec();
+        bincode::serialize_into(&mut data, &header).context("serialize header")?;
+        bincode::serialize_into(&mut data, self).context("serialize proof")?;
+        Ok(data)
+    }
+
+    /// Reads the header of a proof file, returning it alongside the serialized proof
+    pub fn read_file_header(data: &[u8]) -> anyhow::Result<(ProofFileHeader, &[u8])> {
+        let Some(mut data) = data.strip_prefix(PROOF_FILE_MAGIC.as_slice()) else {
+            bail!("Unrecognized proof file format.");
+        };
+        let header: ProofFileHeader =
+            bincode::deserialize_from(&mut data).context("deserialize header")?;
+        if header.version != PROOF_FILE_VERSION {
+            bail!("Unsupported proof file version {}.", header.version);
+        }
+        Ok((header, data))
+    }
+
+    /// Deserializes a proof file after checking its compatibility with the given image id
+    pub fn from_file_bytes(data: &[u8], image_id: Digest) -> anyhow::Result<Self> {
+        let (header, data) = Self::read_file_header(data)?;
+        header.check_compatibility(image_id)?;
+        let proof: Self = bincode::deserialize(data).context("deserialize proof")?;
+        if proof.journal().bytes != header.journal {
+            bail!("Proof journal does not match proof file header.");
+        }
+        Ok(proof)
+    }
+
+    /// Wraps a succinct receipt into a groth16 receipt that can be verified on-chain
+    pub async fn wrap_groth16(self) -> anyhow::Result<Self> {
+        match self {
+            Proof::ZKVMReceipt(receipt) if matches!(receipt.inner, InnerReceipt::Succinct(_)) => {
+                info!("Wrapping succinct receipt into groth16.");
+                let receipt = spawn_blocking(move || {
+                    default_prover().compress(&ProverOpts::groth16(), &receipt)
+                })
+                .await?
+                .context("compress")?;
+                Ok(Proof::ZKVMReceipt(Box::new(receipt)))
+            }
+            proof => Ok(proof),
+        }
+    }
+}
+
+fn fpvm_proof_file_suffix() -> &'static str {
+    if risc0_zkvm::is_dev_mode() {
+        "fake"
+    } else {
+        "zkp"
+    }
+}
+
+/// Searches the directory for a valid proof that commits to the expected journal, regardless of
+/// the name of the file it was stored under.
+pub fn find_cached_proof(
+    dir: &Path,
+    image_id: Digest,
+    expected_journal: &ProofJournal,
+) -> Option<(PathBuf, Proof)> {
+    let suffix = format!(".{}", fpvm_proof_file_suffix());
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read proof directory {dir:?}: {e:?}");
+            return None;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !file_name.starts_with("risc0-") || !file_name.ends_with(&suffix) {
+            continue;
+        }
+        let Ok(data) = std::fs::read(&path) else {
+            continue;
+        };
+        let Ok((header, _)) = Proof::read_file_header(&data) else {
+            debug!("Skipping unreadable proof file {file_name}.");
+            continue;
+        };
+        let Ok(journal) = ProofJournal::decode_packed(&header.journal) else {
+            continue;
+        };
+        if journal != *expected_journal {
+            continue;
+        }
+        let proof = match Proof::from_file_bytes(&data, image_id) {
+            Ok(proof) => proof,
+            Err(e) => {
+                warn!("Skipping incompatible proof file {file_name}: {e:?}");
+                continue;
+            }
+        };
+        // Only reuse receipts that still verify against the current program
+        if let Some(receipt) = proof.as_receipt() {
+            if let Err(e) = receipt.verify(image_id) {
+                warn!("Skipping cached proof {file_name} that failed verification: {e:?}");
+                continue;
+            }
+        }
+        return Some((path, proof));
+    }
+    None
 }
 
 pub fn fpvm_proof_file_name(
+    image_id: Digest,
     precondition_output: B256,
     l1_head: B256,
     claimed_l2_output_root: B256,
@@ -65,14 +228,10 @@ pub fn fpvm_proof_file_name(
     agreed_l2_output_root: B256,
 ) -> String {
     let version = risc0_zkvm::get_version().unwrap();
-    let suffix = if risc0_zkvm::is_dev_mode() {
-        "fake"
-    } else {
-        "zkp"
-    };
+    let suffix = fpvm_proof_file_suffix();
     let claimed_l2_block_number = claimed_l2_block_number.to_be_bytes();
     let data = [
-        bytemuck::cast::<_, [u8; 32]>(KAILUA_FPVM_ID).as_slice(),
+        image_id.as_bytes(),
         precondition_output.as_slice(),
         l1_head.as_slice(),
         claimed_l2_output_root.as_slice(),
@@ -83,3 +242,15 @@ pub fn fpvm_proof_file_name(
     let file_name = keccak256(data);
     format!("risc0-{version}-{file_name}.{suffix}")
 }
+
+/// Returns the name of the file storing the proof of the journal by the given program
+pub fn fpvm_proof_file_name_from_journal(image_id: Digest, journal: &ProofJournal) -> String {
+    fpvm_proof_file_name(
+        image_id,
+        journal.precondition_output,
+        journal.l1_head,
+        journal.claimed_l2_output_root,
+        journal.claimed_l2_block_number,
+        journal.agreed_l2_output_root,
+    )
+}
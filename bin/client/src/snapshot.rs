@@ -0,0 +1,135 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{execute_zkvm_client, ZkvmExecution};
+use alloy_primitives::{hex, Bytes};
+use anyhow::{ensure, Context};
+use kailua_common::journal::ProofJournal;
+use kailua_common::witness::Witness;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The expected outcome of proving a recorded witness
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub chain_id: u64,
+    pub claimed_l2_block_number: u64,
+    pub journal: ProofJournal,
+    /// The packed journal committed to by the fpvm
+    pub encoded_journal: Bytes,
+    /// The maximum number of user cycles that executing the fpvm on the witness may take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_cycle_budget: Option<u64>,
+}
+
+impl Snapshot {
+    /// Returns the file stem under which the snapshot of the given claim is stored
+    pub fn name(chain_id: u64, claimed_l2_block_number: u64) -> String {
+        format!("{chain_id}-{claimed_l2_block_number}")
+    }
+
+    /// Stores the witness and its expected journal in the given directory
+    pub async fn record(
+        dir: &Path,
+        witness: &Witness,
+        journal: &ProofJournal,
+    ) -> anyhow::Result<PathBuf> {
+        let boot = witness.boot_info()?;
+        let snapshot = Self {
+            chain_id: boot.chain_id,
+            claimed_l2_block_number: boot.claimed_l2_block_number,
+            journal: *journal,
+            encoded_journal: journal.encode_packed().into(),
+            user_cycle_budget: None,
+        };
+        let name = Self::name(snapshot.chain_id, snapshot.claimed_l2_block_number);
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(dir.join(format!("{name}.witness")), witness.encode()?).await?;
+        let file = dir.join(format!("{name}.json"));
+        tokio::fs::write(&file, serde_json::to_vec_pretty(&snapshot)?).await?;
+        Ok(file)
+    }
+
+    /// Loads all snapshots stored in the given directory alongside their witnesses
+    pub async fn load(dir: &Path) -> anyhow::Result<Vec<(Self, Witness)>> {
+        let mut files = vec![];
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut snapshots = Vec::with_capacity(files.len());
+        for file in files {
+            let snapshot: Self = serde_json::from_slice(&tokio::fs::read(&file).await?)
+                .with_context(|| format!("Failed to parse {}", file.display()))?;
+            let data = tokio::fs::read(file.with_extension("witness"))
+                .await
+                .with_context(|| format!("Missing witness for {}", file.display()))?;
+            let witness = Witness::decode(&data)?;
+            snapshots.push((snapshot, witness));
+        }
+        Ok(snapshots)
+    }
+
+    /// Checks that the witness still yields the expected journal natively and in the fpvm, within
+    /// the cycle budget
+    ///
+    /// Returns the outcome of executing the fpvm.
+    pub async fn check(&self, witness: &Witness, elf: &[u8]) -> anyhow::Result<ZkvmExecution> {
+        let name = Self::name(self.chain_id, self.claimed_l2_block_number);
+        ensure!(
+            self.journal.encode_packed() == self.encoded_journal.as_ref(),
+            "Snapshot {name} journal does not match its recorded encoding."
+        );
+
+        let boot = witness.boot_info()?;
+        ensure!(
+            boot.chain_id == self.chain_id
+                && boot.claimed_l2_block_number == self.claimed_l2_block_number,
+            "Snapshot {name} witness is for block {} of chain {}.",
+            boot.claimed_l2_block_number,
+            boot.chain_id
+        );
+
+        let native_encoding = witness.replay().context("Witness::replay")?.encode_packed();
+        ensure!(
+            native_encoding == self.encoded_journal.as_ref(),
+            "Snapshot {name} native journal diverged: expected {} found {}.",
+            self.encoded_journal,
+            hex::encode_prefixed(&native_encoding)
+        );
+
+        let execution = execute_zkvm_client(witness, elf).await?;
+        ensure!(
+            execution.journal == self.encoded_journal.as_ref(),
+            "Snapshot {name} fpvm journal diverged: expected {} found {}.",
+            self.encoded_journal,
+            hex::encode_prefixed(&execution.journal)
+        );
+
+        if let Some(user_cycle_budget) = self.user_cycle_budget {
+            ensure!(
+                execution.user_cycles <= user_cycle_budget,
+                "Snapshot {name} took {} user cycles, exceeding its budget of {user_cycle_budget}.",
+                execution.user_cycles
+            );
+        }
+
+        Ok(execution)
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Measurements of the job that computed a proof, stored next to its proof file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProvingStats {
+    /// The backend that computed the proof
+    pub backend: String,
+    /// Total cycles proven, if reported by the backend
+    pub total_cycles: Option<u64>,
+    /// Seconds spent computing the proof
+    pub proving_secs: f64,
+    /// Estimated spend in USD at the configured price per million cycles
+    #[serde(default)]
+    pub estimated_cost: Option<f64>,
+    /// Identifier of the proving job assigned by the validator
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+impl ProvingStats {
+    pub fn file_name(proof_file_name: &str) -> String {
+        format!("{proof_file_name}.stats.json")
+    }
+
+    pub fn load(proof_file_name: &str) -> anyhow::Result<Option<Self>> {
+        let file_name = Self::file_name(proof_file_name);
+        if !Path::new(&file_name).exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read(&file_name).context("read stats file")?;
+        Ok(Some(
+            serde_json::from_slice(&data).context("parse stats file")?,
+        ))
+    }
+
+    pub fn save(&self, proof_file_name: &str) -> anyhow::Result<()> {
+        std::fs::write(Self::file_name(proof_file_name), serde_json::to_vec(self)?)
+            .context("write stats file")
+    }
+}
@@ -17,15 +17,26 @@ use alloy::eips::eip4844::IndexedBlobHash;
 use async_trait::async_trait;
 use kailua_common::blobs::BlobWitnessData;
 use kailua_common::oracle::OracleWitnessData;
+use kailua_common::witness::Witness;
 use kona_derive::prelude::BlobProvider;
-use kona_preimage::errors::PreimageOracleResult;
+use kona_preimage::errors::{PreimageOracleError, PreimageOracleResult};
 use kona_preimage::{
     CommsClient, HintWriterClient, PreimageKey, PreimageKeyType, PreimageOracleClient,
 };
 use kona_proof::FlushableCache;
 use op_alloy_protocol::BlockInfo;
 use std::fmt::Debug;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// Writes the serialized witness to a file in the directory named after its hash
+pub async fn record_witness(dir: &Path, witness: &Witness) -> anyhow::Result<PathBuf> {
+    tokio::fs::create_dir_all(dir).await?;
+    let file = dir.join(format!("{}.witness", witness.hash()?));
+    tokio::fs::write(&file, witness.encode()?).await?;
+    Ok(file)
+}
 
 #[derive(Clone, Debug)]
 pub struct BlobWitnessProvider<T: BlobProvider> {
@@ -68,6 +79,8 @@ impl<T: BlobProvider + Send> BlobProvider for BlobWitnessProvider<T> {
 pub struct OracleWitnessProvider<P: CommsClient + FlushableCache + Send + Sync + Debug + Clone> {
     pub oracle: P,
     pub witness: Arc<Mutex<OracleWitnessData>>,
+    /// Aborts pending and future requests to the oracle once cancelled
+    pub cancel: CancellationToken,
 }
 
 impl<P> OracleWitnessProvider<P>
@@ -79,8 +92,11 @@ where
             return;
         }
         let mut witness = self.witness.lock().unwrap();
-        witness.keys.push(key);
-        witness.data.push(value.to_vec());
+        witness.push(key, value);
+    }
+
+    fn cancelled(&self) -> PreimageOracleError {
+        PreimageOracleError::Other(String::from("Native client cancelled"))
     }
 }
 
@@ -90,13 +106,19 @@ where
     P: CommsClient + FlushableCache + Send + Sync + Debug + Clone,
 {
     async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
-        let value = self.oracle.get(key).await?;
+        let value = tokio::select! {
+            value = self.oracle.get(key) => value?,
+            _ = self.cancel.cancelled() => return Err(self.cancelled()),
+        };
         self.save(key, &value);
         Ok(value)
     }
 
     async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
-        self.oracle.get_exact(key, buf).await?;
+        tokio::select! {
+            result = self.oracle.get_exact(key, buf) => result?,
+            _ = self.cancel.cancelled() => return Err(self.cancelled()),
+        };
         let value = buf.to_vec();
         self.save(key, &value);
         Ok(())
@@ -109,7 +131,10 @@ where
     P: CommsClient + FlushableCache + Send + Sync + Debug + Clone,
 {
     async fn write(&self, hint: &str) -> PreimageOracleResult<()> {
-        self.oracle.write(hint).await
+        tokio::select! {
+            result = self.oracle.write(hint) => result,
+            _ = self.cancel.cancelled() => Err(self.cancelled()),
+        }
     }
 }
 
@@ -0,0 +1,34 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use kailua_build::KAILUA_FPVM_ELF;
+use kailua_client::snapshot::Snapshot;
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn recorded_snapshots_replay() -> anyhow::Result<()> {
+    let dir = std::env::var("KAILUA_SNAPSHOTS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots"));
+    for (snapshot, witness) in Snapshot::load(&dir).await? {
+        let execution = snapshot.check(&witness, KAILUA_FPVM_ELF).await?;
+        println!(
+            "{}: {} user cycles (budget {:?})",
+            Snapshot::name(snapshot.chain_id, snapshot.claimed_l2_block_number),
+            execution.user_cycles,
+            snapshot.user_cycle_budget
+        );
+    }
+    Ok(())
+}
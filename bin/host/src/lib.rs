@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod preflight;
+pub mod prove;
+pub mod registry;
+
 use alloy::consensus::Transaction;
 use alloy::network::primitives::BlockTransactionsKind;
 use alloy::primitives::{keccak256, B256};
@@ -21,9 +25,15 @@ use alloy_eips::eip4844::IndexedBlobHash;
 use anyhow::bail;
 use boundless_market::storage::StorageProviderConfig;
 use clap::Parser;
-use kailua_client::{parse_b256, BoundlessArgs};
+use kailua_client::mock::MockProverArgs;
+use kailua_client::{
+    parse_b256, BoundlessArgs, OutputDivergence, ProvingCostArgs, EXIT_CODE_OUTPUT_DIVERGENCE,
+};
 use kailua_common::blobs::BlobFetchRequest;
-use kailua_common::precondition::PreconditionValidationData;
+use kailua_common::client::HardforkOverrides;
+use kailua_common::precondition::{
+    PreconditionValidationData, PRECONDITION_VERSION, SUPPORTED_PRECONDITION_VERSIONS,
+};
 use kona_host::fetcher::Fetcher;
 use kona_host::kv::SharedKeyValueStore;
 use kona_host::start_native_preimage_server;
@@ -35,10 +45,12 @@ use serde_json::{json, Value};
 use std::env::set_var;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tempfile::TempDir;
 use tokio::sync::RwLock;
 use tokio::{fs, task};
-use tracing::{debug, info, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn, Instrument};
 use zeth_core::driver::CoreDriver;
 use zeth_core::mpt::{MptNode, MptNodeData};
 use zeth_core::stateless::data::StatelessClientData;
@@ -46,18 +58,65 @@ use zeth_core_optimism::OpRethCoreDriver;
 use zeth_preflight::client::PreflightClient;
 use zeth_preflight_optimism::OpRethPreflightClient;
 
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct HardforkArgs {
+    /// Granite activation timestamp to use instead of the one reported for the rollup
+    #[clap(long, env)]
+    pub granite_time_override: Option<u64>,
+    /// Holocene activation timestamp to use instead of the one reported for the rollup
+    #[clap(long, env)]
+    pub holocene_time_override: Option<u64>,
+}
+
+impl HardforkArgs {
+    pub fn overrides(&self) -> HardforkOverrides {
+        HardforkOverrides {
+            granite_time: self.granite_time_override,
+            holocene_time: self.holocene_time_override,
+        }
+    }
+
+    /// Returns the command line arguments that pass the same overrides to another binary
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(granite_time) = self.granite_time_override {
+            args.extend([
+                String::from("--granite-time-override"),
+                granite_time.to_string(),
+            ]);
+        }
+        if let Some(holocene_time) = self.holocene_time_override {
+            args.extend([
+                String::from("--holocene-time-override"),
+                holocene_time.to_string(),
+            ]);
+        }
+        args
+    }
+}
+
 /// The host binary CLI application arguments.
 #[derive(Parser, Clone, Debug)]
 pub struct KailuaHostCli {
     #[clap(flatten)]
     pub kona: kona_host::HostCli,
 
+    #[clap(flatten)]
+    pub hardfork_args: HardforkArgs,
+
     /// Address of OP-NODE endpoint to use
     #[clap(long, env)]
     pub op_node_address: Option<String>,
     /// Whether to skip running the zeth preflight engine
     #[clap(long, default_value_t = false, env)]
     pub skip_zeth_preflight: bool,
+    /// Whether to fetch the agreed l2 head through the standard eth namespace even if the L2 node
+    /// exposes debug_dbGet
+    #[clap(long, default_value_t = false, env)]
+    pub standard_l2_preflight: bool,
+    /// Whether to only fetch the data required for the proof without proving it
+    #[clap(long, default_value_t = false, env)]
+    pub preflight_only: bool,
 
     #[clap(long, default_value_t = 1, env)]
     /// Number of blocks to build in a single proof
@@ -70,6 +129,41 @@ pub struct KailuaHostCli {
     pub v_block_hash: Option<B256>,
     #[clap(long, value_parser = parse_b256, env)]
     pub v_blob_kzg_hash: Option<B256>,
+    /// Hash of the L1 block that published the proposal to prove valid
+    #[clap(long, value_parser = parse_b256, env)]
+    pub proposal_block_hash: Option<B256>,
+    /// Versioned hashes of the blobs of the proposal to prove valid
+    #[clap(long, value_parser = parse_b256, value_delimiter = ',', env)]
+    pub proposal_blob_kzg_hashes: Vec<B256>,
+    /// Version of the precondition encoding expected by the contracts (0 for releases prior to
+    /// 0.2.0)
+    #[clap(long, default_value_t = PRECONDITION_VERSION, env)]
+    pub precondition_version: u8,
+
+    /// Seconds after which the native client is abandoned if it has not yet completed
+    #[clap(long, env)]
+    pub native_client_timeout: Option<u64>,
+
+    /// Whether to emit a pprof file profiling the cycles spent by the guest
+    #[clap(long, default_value_t = false, env)]
+    pub profile: bool,
+    /// Path to an alternative FPVM ELF to prove with instead of the bundled one
+    #[clap(long, env)]
+    pub fpvm_elf: Option<PathBuf>,
+    /// Directory to record the canonical witness of the proof in, e.g. for replay fuzzing
+    #[clap(long, env)]
+    pub witness_dir: Option<PathBuf>,
+    /// Directory to record a regression snapshot of the proof in
+    #[clap(long, env)]
+    pub snapshot_dir: Option<PathBuf>,
+    /// Identifier of the proving job this invocation serves, attached to its logs and stats
+    #[clap(long, env)]
+    pub correlation_id: Option<String>,
+
+    #[clap(flatten)]
+    pub proving_cost_args: ProvingCostArgs,
+    #[clap(flatten)]
+    pub mock_prover_args: MockProverArgs,
 
     #[clap(flatten)]
     pub boundless_args: Option<BoundlessArgs>,
@@ -109,40 +203,88 @@ pub async fn start_server_and_native_client(
     };
 
     // Create the server and start it.
-    let server_task = task::spawn(start_native_preimage_server(
-        kv_store,
-        fetcher,
-        hint_chan.host,
-        preimage_chan.host,
-    ));
+    let server_task = task::spawn(
+        start_native_preimage_server(kv_store, fetcher, hint_chan.host, preimage_chan.host)
+            .in_current_span(),
+    );
+
+    // Abandon the native client once its deadline passes
+    let cancel = CancellationToken::new();
+    if let Some(timeout) = args.native_client_timeout {
+        let cancel = cancel.clone();
+        task::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(timeout)).await;
+            warn!("Native client timed out after {timeout} seconds.");
+            cancel.cancel();
+        });
+    }
 
     // Start the client program in a separate child process.
-    let program_task = task::spawn(kailua_client::run_client(
-        args.boundless_args,
-        args.boundless_storage_config,
-        OracleReader::new(preimage_chan.client),
-        HintWriter::new(hint_chan.client),
-        precondition_validation_data_hash,
-    ));
+    let program_task = if args.preflight_only {
+        // Populate the preimage store without proving
+        task::spawn(
+            async move {
+                kailua_client::run_native_client(
+                    OracleReader::new(preimage_chan.client),
+                    HintWriter::new(hint_chan.client),
+                    precondition_validation_data_hash,
+                    cancel,
+                )
+                .await
+                .map(|_| ())
+            }
+            .in_current_span(),
+        )
+    } else {
+        task::spawn(
+            kailua_client::run_client(
+                args.boundless_args,
+                args.boundless_storage_config,
+                OracleReader::new(preimage_chan.client),
+                HintWriter::new(hint_chan.client),
+                precondition_validation_data_hash,
+                cancel,
+                args.profile,
+                args.fpvm_elf,
+                args.witness_dir,
+                args.snapshot_dir,
+                args.proving_cost_args,
+                args.mock_prover_args,
+                args.correlation_id,
+            )
+            .in_current_span(),
+        )
+    };
 
     // Execute both tasks and wait for them to complete.
     info!("Starting preimage server and client program.");
     let (_, client_result) = tokio::try_join!(server_task, program_task,)?;
     info!(target: "kona_host", "Preimage server and client program have joined.");
 
-    Ok(client_result.is_err() as i32)
+    match client_result {
+        Ok(()) => Ok(0),
+        Err(e) if e.downcast_ref::<OutputDivergence>().is_some() => {
+            error!("Aborted proving due to output divergence: {e:?}");
+            Ok(EXIT_CODE_OUTPUT_DIVERGENCE)
+        }
+        Err(e) => {
+            error!("Client program failure: {e:?}");
+            Ok(1)
+        }
+    }
 }
 
 pub async fn generate_rollup_config(
     cfg: &mut KailuaHostCli,
     tmp_dir: &TempDir,
 ) -> anyhow::Result<RollupConfig> {
+    let overrides = cfg.hardfork_args.overrides();
     // generate a RollupConfig for the target network
-    match cfg.kona.read_rollup_config().ok() {
-        Some(rollup_config) => Ok(rollup_config),
+    let mut rollup_config = match cfg.kona.read_rollup_config().ok() {
+        Some(rollup_config) if overrides.is_empty() => return Ok(rollup_config),
+        Some(rollup_config) => rollup_config,
         None => {
             let registry = Registry::from_chain_list();
-            let tmp_cfg_file = tmp_dir.path().join("rollup-config.json");
             if let Some(rollup_config) = cfg
                 .kona
                 .l2_chain_id
@@ -152,8 +294,7 @@ pub async fn generate_rollup_config(
                     "Loading config for rollup with chain id {} from registry",
                     cfg.kona.l2_chain_id.unwrap()
                 );
-                let ser_config = serde_json::to_string(rollup_config)?;
-                fs::write(&tmp_cfg_file, &ser_config).await?;
+                rollup_config.clone()
             } else {
                 info!("Fetching rollup config from nodes.");
                 fetch_rollup_config(
@@ -166,20 +307,29 @@ pub async fn generate_rollup_config(
                         .clone()
                         .expect("Missing l2-node-address")
                         .as_str(),
-                    Some(&tmp_cfg_file),
+                    None,
+                    &overrides,
                 )
-                .await?;
+                .await?
             }
-            cfg.kona.rollup_config_path = Some(tmp_cfg_file);
-            cfg.kona.read_rollup_config()
         }
+    };
+    // the client reads the config from the file
+    if !overrides.is_empty() {
+        info!("Applying hardfork overrides {overrides:?}");
+        overrides.apply(&mut rollup_config);
     }
+    let tmp_cfg_file = tmp_dir.path().join("rollup-config.json");
+    fs::write(&tmp_cfg_file, serde_json::to_string(&rollup_config)?).await?;
+    cfg.kona.rollup_config_path = Some(tmp_cfg_file);
+    cfg.kona.read_rollup_config()
 }
 
 pub async fn fetch_rollup_config(
     op_node_address: &str,
     l2_node_address: &str,
     json_file_path: Option<&PathBuf>,
+    overrides: &HardforkOverrides,
 ) -> anyhow::Result<RollupConfig> {
     let op_node_provider = ProviderBuilder::new().on_http(op_node_address.try_into()?);
     let l2_node_provider = ProviderBuilder::new().on_http(l2_node_address.try_into()?);
@@ -231,13 +381,14 @@ pub async fn fetch_rollup_config(
             rollup_config[fork] = json!(value);
         }
     }
+    let mut rollup_config: RollupConfig = serde_json::from_value(rollup_config)?;
+    overrides.apply(&mut rollup_config);
     // export
-    let ser_config = serde_json::to_string(&rollup_config)?;
     if let Some(json_file_path) = json_file_path {
-        fs::write(json_file_path, &ser_config).await?;
+        fs::write(json_file_path, serde_json::to_string(&rollup_config)?).await?;
     }
 
-    Ok(serde_json::from_str(&ser_config)?)
+    Ok(rollup_config)
 }
 
 pub fn mpt_to_vec(node: &MptNode) -> Vec<(B256, Vec<u8>)> {
@@ -374,6 +525,12 @@ pub async fn get_blob_fetch_request(
 pub async fn fetch_precondition_data(
     cfg: &KailuaHostCli,
 ) -> anyhow::Result<Option<PreconditionValidationData>> {
+    if !SUPPORTED_PRECONDITION_VERSIONS.contains(&cfg.precondition_version) {
+        bail!(
+            "Unsupported precondition version {}.",
+            cfg.precondition_version
+        );
+    }
     // Determine precondition hash
     let hash_arguments = [
         cfg.u_block_hash,
@@ -382,10 +539,26 @@ pub async fn fetch_precondition_data(
         cfg.v_blob_kzg_hash,
     ];
 
-    // fetch necessary data to validate blob equivalence precondition
-    if hash_arguments.iter().all(|arg| arg.is_some()) {
+    // fetch necessary data to validate the proposal blobs for a validity proof
+    let precondition_validation_data = if let Some(proposal_block_hash) = cfg.proposal_block_hash {
+        if hash_arguments.iter().any(|arg| arg.is_some()) {
+            bail!("Cannot validate both a fault and a validity precondition.")
+        }
+        let (l1_provider, _, _) = cfg.kona.create_providers().await?;
+        let mut validated_blobs = Vec::with_capacity(cfg.proposal_blob_kzg_hashes.len());
+        for blob_hash in &cfg.proposal_blob_kzg_hashes {
+            validated_blobs
+                .push(get_blob_fetch_request(&l1_provider, proposal_block_hash, *blob_hash).await?);
+        }
+        PreconditionValidationData::Validity {
+            proposal_l2_head_number: cfg.kona.claimed_l2_block_number - cfg.block_count,
+            proposal_output_count: cfg.block_count,
+            validated_blobs,
+        }
+    } else if hash_arguments.iter().all(|arg| arg.is_some()) {
+        // fetch necessary data to validate blob equivalence precondition
         let (l1_provider, _, _) = cfg.kona.create_providers().await?;
-        let precondition_validation_data = PreconditionValidationData {
+        PreconditionValidationData::Fault {
             validated_blobs: [
                 get_blob_fetch_request(
                     &l1_provider,
@@ -400,20 +573,21 @@ pub async fn fetch_precondition_data(
                 )
                 .await?,
             ],
-        };
-        let kv_store = cfg.kona.construct_kv_store();
-        let mut store = kv_store.write().await;
-        let hash = precondition_validation_data.hash();
-        store.set(
-            PreimageKey::new(*hash, PreimageKeyType::Sha256).into(),
-            precondition_validation_data.to_vec(),
-        )?;
-        set_var("PRECONDITION_VALIDATION_DATA_HASH", hash.to_string());
-        Ok(Some(precondition_validation_data))
+        }
     } else if hash_arguments.iter().any(|arg| arg.is_some()) {
         bail!("Insufficient number of arguments provided for precondition hash.")
     } else {
         warn!("Proving without a precondition hash.");
-        Ok(None)
-    }
+        return Ok(None);
+    };
+
+    let kv_store = cfg.kona.construct_kv_store();
+    let mut store = kv_store.write().await;
+    let hash = precondition_validation_data.hash(cfg.precondition_version);
+    store.set(
+        PreimageKey::new(*hash, PreimageKeyType::Sha256).into(),
+        precondition_validation_data.to_vec(cfg.precondition_version),
+    )?;
+    set_var("PRECONDITION_VALIDATION_DATA_HASH", hash.to_string());
+    Ok(Some(precondition_validation_data))
 }
@@ -0,0 +1,127 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Preflight of the L2 preimages through the standard eth namespace, for L2 nodes that do not
+//! expose `debug_dbGet`
+
+use crate::{dump_mpt_to_kv_store, KailuaHostCli};
+use alloy::eips::eip2718::Encodable2718;
+use alloy::primitives::{address, keccak256, Address, Bytes, B256};
+use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use alloy::rpc::types::Header;
+use anyhow::{ensure, Context};
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use op_alloy_consensus::OpTxEnvelope;
+use serde_json::Value;
+use tracing::{info, warn};
+use zeth_core::mpt::MptNode;
+
+/// The predeploy whose storage root is committed to in L2 output roots
+pub const L2_TO_L1_MESSAGE_PASSER: Address = address!("4200000000000000000000000000000000000016");
+
+/// Returns whether the L2 node serves trie nodes by hash through `debug_dbGet`, which path-based
+/// databases and many hosted endpoints do not
+pub async fn supports_debug_db_get(l2_provider: &ReqwestProvider, state_root: B256) -> bool {
+    l2_provider
+        .client()
+        .request::<_, Bytes>("debug_dbGet", (state_root,))
+        .await
+        .is_ok()
+}
+
+/// Populates the kv-store with the header, transactions and output root preimage of the agreed
+/// l2 head using only `eth_getBlockByHash` and `eth_getProof`, unless the L2 node can serve them
+/// itself. The state accessed by the proven blocks is fetched by the zeth preflight.
+pub async fn standard_l2_preflight(cfg: &KailuaHostCli) -> anyhow::Result<()> {
+    // Limitation: Only works when disk caching is enabled
+    if cfg.kona.is_offline() || cfg.kona.data_dir.is_none() {
+        return Ok(());
+    }
+    let l2_node_address = cfg
+        .kona
+        .l2_node_address
+        .as_ref()
+        .context("Missing l2-node-address")?;
+    let l2_provider = ProviderBuilder::new().on_http(l2_node_address.as_str().try_into()?);
+    let agreed_l2_head_hash = cfg.kona.agreed_l2_head_hash;
+    // deposit transactions cannot be parsed as ethereum rpc transactions
+    let mut block: Value = l2_provider
+        .client()
+        .request("eth_getBlockByHash", (agreed_l2_head_hash, true))
+        .await
+        .context("eth_getBlockByHash")?;
+    ensure!(
+        !block.is_null(),
+        "Agreed l2 head {agreed_l2_head_hash} not found."
+    );
+    let header: Header = serde_json::from_value(block.clone()).context("parse header")?;
+    if !cfg.standard_l2_preflight && supports_debug_db_get(&l2_provider, header.state_root).await {
+        return Ok(());
+    }
+    info!("Fetching agreed l2 head preimages through the standard eth namespace.");
+    if cfg.skip_zeth_preflight {
+        warn!("Proving may fail as the zeth preflight is skipped while the l2 node does not expose debug_dbGet.");
+    }
+    let mut preimages = vec![];
+    // Header
+    let header_rlp = alloy::rlp::encode(&header.inner);
+    ensure!(
+        keccak256(&header_rlp) == agreed_l2_head_hash,
+        "Agreed l2 head header hash mismatch."
+    );
+    preimages.push(header_rlp);
+    // Transactions, which carry the l1 origin of the agreed l2 head
+    let transactions: Vec<OpTxEnvelope> =
+        serde_json::from_value(block["transactions"].take()).context("parse transactions")?;
+    let mut transactions_trie = MptNode::default();
+    for (index, transaction) in transactions.iter().enumerate() {
+        transactions_trie.insert(&alloy::rlp::encode(index), transaction.encoded_2718())?;
+    }
+    ensure!(
+        transactions_trie.hash() == header.transactions_root,
+        "Agreed l2 head transactions root mismatch."
+    );
+    let mut kv_store = cfg.kona.construct_kv_store();
+    dump_mpt_to_kv_store(&mut kv_store, &transactions_trie).await;
+    // Output root, opening the storage root of the message passer
+    let proof = l2_provider
+        .get_proof(L2_TO_L1_MESSAGE_PASSER, vec![])
+        .block_id(agreed_l2_head_hash.into())
+        .await
+        .context("eth_getProof")?;
+    preimages.extend(proof.account_proof.into_iter().map(Vec::from));
+    let mut output_preimage = Vec::with_capacity(128);
+    for word in [
+        B256::ZERO,
+        header.state_root,
+        proof.storage_hash,
+        agreed_l2_head_hash,
+    ] {
+        output_preimage.extend_from_slice(word.as_slice());
+    }
+    ensure!(
+        keccak256(&output_preimage) == cfg.kona.agreed_l2_output_root,
+        "Agreed l2 output root mismatch."
+    );
+    preimages.push(output_preimage);
+    // Write data to the cached Kona kv-store
+    let mut store = kv_store.write().await;
+    for preimage in preimages {
+        store.set(
+            PreimageKey::new(*keccak256(&preimage), PreimageKeyType::Keccak256).into(),
+            preimage,
+        )?;
+    }
+    Ok(())
+}
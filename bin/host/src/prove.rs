@@ -0,0 +1,122 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The proving workflow of kailua-host, shared with binaries that embed it
+
+use crate::preflight::standard_l2_preflight;
+use crate::{
+    fetch_precondition_data, generate_rollup_config, start_server_and_native_client,
+    zeth_execution_preflight, KailuaHostCli,
+};
+use alloy_primitives::B256;
+use anyhow::Context;
+use kailua_client::proof::{find_cached_proof, fpvm_proof_file_name};
+use kailua_common::client::config_hash;
+use kailua_common::journal::ProofJournal;
+use std::env::set_var;
+use std::path::Path;
+use tempfile::tempdir;
+use tracing::{info, info_span, Instrument, Span};
+
+/// Computes the proof requested by the arguments unless it is already cached, after the tracing
+/// subscriber has been initialized
+pub async fn run(args: KailuaHostCli) -> anyhow::Result<()> {
+    set_var("KAILUA_VERBOSITY", args.kona.v.to_string());
+    // tag all log lines with the job identifier assigned by the validator
+    let span = match &args.correlation_id {
+        Some(correlation_id) => info_span!("job", correlation_id = %correlation_id),
+        None => Span::none(),
+    };
+    prove(args).instrument(span).await
+}
+
+async fn prove(mut args: KailuaHostCli) -> anyhow::Result<()> {
+    // compute receipt if uncached
+    let (precondition_hash, precondition_validation_data_hash) =
+        match fetch_precondition_data(&args).await? {
+            Some(data) => {
+                let precondition_validation_data_hash = data.hash(args.precondition_version);
+                set_var(
+                    "PRECONDITION_VALIDATION_DATA_HASH",
+                    precondition_validation_data_hash.to_string(),
+                );
+                (
+                    data.precondition_hash(args.precondition_version),
+                    precondition_validation_data_hash,
+                )
+            }
+            None => (B256::ZERO, B256::ZERO),
+        };
+    // refuse to prove with a program that does not match its image id
+    if args.fpvm_elf.is_none() {
+        kailua_client::check_fpvm_compatibility().context("check_fpvm_compatibility")?;
+    }
+    let (_, fpvm_image_id) = kailua_client::load_fpvm(args.fpvm_elf.as_ref())?;
+    let file_name = fpvm_proof_file_name(
+        fpvm_image_id,
+        precondition_hash,
+        args.kona.l1_head,
+        args.kona.claimed_l2_output_root,
+        args.kona.claimed_l2_block_number,
+        args.kona.agreed_l2_output_root,
+    );
+    let tmp_dir = tempdir()?;
+    let rollup_config = generate_rollup_config(&mut args, &tmp_dir)
+        .await
+        .context("generate_rollup_config")?;
+    // only reuse proofs committing to the exact journal expected from this invocation
+    let expected_journal = ProofJournal {
+        precondition_output: precondition_hash,
+        l1_head: args.kona.l1_head,
+        agreed_l2_output_root: args.kona.agreed_l2_output_root,
+        claimed_l2_output_root: args.kona.claimed_l2_output_root,
+        claimed_l2_block_number: args.kona.claimed_l2_block_number,
+        config_hash: B256::from(config_hash(&rollup_config).context("config_hash")?),
+        dependencies: None,
+    };
+    if let Some((cached_path, proof)) =
+        find_cached_proof(Path::new("."), fpvm_image_id, &expected_journal)
+    {
+        if cached_path == Path::new(".").join(&file_name) {
+            info!("Proving skipped. Proof file {file_name} already exists.");
+        } else {
+            info!("Proving skipped. Reusing matching proof from {cached_path:?}.");
+            let proof_bytes = proof
+                .to_file_bytes(fpvm_image_id)
+                .context("Proof::to_file_bytes")?;
+            std::fs::write(&file_name, proof_bytes).context("write proof file")?;
+        }
+    } else {
+        info!("Computing uncached proof.");
+        // fetch the agreed l2 head for nodes without the debug namespace
+        standard_l2_preflight(&args)
+            .await
+            .context("standard_l2_preflight")?;
+        // run zeth preflight to fetch the necessary preimages
+        if !args.skip_zeth_preflight {
+            zeth_execution_preflight(&args, rollup_config).await?;
+        }
+
+        // generate a proof using the kailua client and kona server
+        let exit_code = start_server_and_native_client(args, precondition_validation_data_hash)
+            .await
+            .expect("Proving failure");
+        if exit_code != 0 {
+            std::process::exit(exit_code);
+        }
+    }
+
+    info!("Exiting host program.");
+    Ok(())
+}
@@ -0,0 +1,145 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::B256;
+use anyhow::{bail, Context};
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_common::journal::PROOF_JOURNAL_VERSION;
+use kailua_common::precondition::{PRECONDITION_VERSION, SUPPORTED_PRECONDITION_VERSIONS};
+use kailua_common::witness::WITNESS_VERSION;
+use risc0_zkvm::compute_image_id;
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// A known build of the fault proof program
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FpvmRegistryEntry {
+    /// The image id of the program
+    pub image_id: B256,
+    /// Path to the program ELF, relative to the registry file. None for the bundled build.
+    pub elf: Option<PathBuf>,
+    /// The RISC Zero zkVM version the program was built with
+    pub risc0_version: String,
+    /// The version of the proof journal format committed by the program
+    pub journal_version: u8,
+    /// The version of the witness format read by the program
+    pub witness_version: u8,
+    /// The latest precondition encoding version validated by the program
+    pub precondition_version: u8,
+}
+
+impl FpvmRegistryEntry {
+    /// Fails if this build cannot prepare inputs for the program or read its proofs
+    pub fn check_compatibility(&self) -> anyhow::Result<()> {
+        if self.journal_version != PROOF_JOURNAL_VERSION {
+            bail!("Unsupported journal version {}.", self.journal_version);
+        }
+        if self.witness_version != WITNESS_VERSION {
+            bail!("Unsupported witness version {}.", self.witness_version);
+        }
+        if !SUPPORTED_PRECONDITION_VERSIONS.contains(&self.precondition_version) {
+            bail!(
+                "Unsupported precondition version {}.",
+                self.precondition_version
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A mapping of image ids to known builds of the fault proof program
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FpvmRegistry {
+    pub entries: Vec<FpvmRegistryEntry>,
+}
+
+impl FpvmRegistry {
+    /// Returns a registry containing only the bundled build
+    pub fn bundled() -> Self {
+        Self {
+            entries: vec![Self::bundled_entry()],
+        }
+    }
+
+    pub fn bundled_entry() -> FpvmRegistryEntry {
+        FpvmRegistryEntry {
+            image_id: B256::from_slice(Digest::from(KAILUA_FPVM_ID).as_bytes()),
+            elf: None,
+            risc0_version: risc0_zkvm::get_version().unwrap().to_string(),
+            journal_version: PROOF_JOURNAL_VERSION,
+            witness_version: WITNESS_VERSION,
+            precondition_version: PRECONDITION_VERSION,
+        }
+    }
+
+    /// Loads the registry json file, resolving ELF paths relative to its location, and adds
+    /// the bundled build to it. Fails if any entry is incompatible with this build.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read(path).context("read registry")?;
+        let mut registry: Self = serde_json::from_slice(&data).context("parse registry")?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        for entry in registry.entries.iter_mut() {
+            entry
+                .check_compatibility()
+                .with_context(|| format!("Image id {}", entry.image_id))?;
+            entry.elf = entry.elf.as_ref().map(|elf| base_dir.join(elf));
+        }
+        registry.entries.push(Self::bundled_entry());
+        Ok(registry)
+    }
+
+    pub fn get(&self, image_id: B256) -> Option<&FpvmRegistryEntry> {
+        self.entries.iter().find(|e| e.image_id == image_id)
+    }
+
+    /// Returns the path of the ELF to prove with for the given image id, or None for the bundled
+    /// build
+    pub fn resolve_elf(&self, image_id: B256) -> anyhow::Result<Option<PathBuf>> {
+        let Some(entry) = self.get(image_id) else {
+            bail!("Image id {image_id} not found in registry.");
+        };
+        let Some(elf_path) = &entry.elf else {
+            return Ok(None);
+        };
+        // Confirm the artifact matches its registered image id
+        let elf = std::fs::read(elf_path).context("read fpvm elf")?;
+        let elf_image_id = compute_image_id(&elf).context("compute_image_id")?;
+        if elf_image_id.as_bytes() != image_id.as_slice() {
+            bail!("ELF {elf_path:?} has image id {elf_image_id} instead of {image_id}.");
+        }
+        let local_version = risc0_zkvm::get_version().unwrap().to_string();
+        if entry.risc0_version != local_version {
+            warn!(
+                "Image id {image_id} was built with risc0 {} (local {local_version}).",
+                entry.risc0_version
+            );
+        }
+        Ok(Some(elf_path.clone()))
+    }
+
+    /// Fails if the program registered for the given image id cannot validate preconditions of
+    /// the given encoding version
+    pub fn check_precondition_version(&self, image_id: B256, version: u8) -> anyhow::Result<()> {
+        // programs validate every encoding up to their own
+        match self.get(image_id) {
+            Some(entry) if entry.precondition_version < version => bail!(
+                "Image id {image_id} only validates preconditions up to version {}.",
+                entry.precondition_version
+            ),
+            _ => Ok(()),
+        }
+    }
+}
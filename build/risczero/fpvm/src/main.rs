@@ -13,34 +13,36 @@
 // limitations under the License.
 
 use alloy_primitives::B256;
-use kailua_common::blobs::PreloadedBlobProvider;
+use kailua_common::blobs::{BlobWitnessData, PreloadedBlobProvider};
 use kailua_common::journal::ProofJournal;
 use kailua_common::oracle::PreloadedOracle;
-use kailua_common::witness::{ArchivedWitness, Witness};
+use kailua_common::witness::Witness;
 use kona_proof::BootInfo;
 use risc0_zkvm::guest::env;
 use std::sync::Arc;
-use rkyv::rancor::Error;
 use kailua_common::client::log;
 
 fn main() {
-    let witness_data = env::read_frame();
-    log("ACCESS");
-    let witness_access = rkyv::access::<ArchivedWitness, Error>(&witness_data).expect("Failed to access witness data");
-    log("DESERIALIZE");
-    let witness = rkyv::deserialize::<Witness, Error>(witness_access).expect("Failed to deserialize witness");
+    // The frame lives for the whole run so that preimages can be read from it in place
+    let witness_data: &'static [u8] = env::read_frame().leak();
+    log("DECODE");
+    let witness = Witness::access(witness_data).expect("Failed to access witness data");
     log("RUN");
-    // let witness: Witness = pot::from_slice(&witness_data).expect("Failed to parse framed witness");
-    let oracle = Arc::new(PreloadedOracle::from(witness.oracle_witness));
+    let oracle = Arc::new(
+        PreloadedOracle::try_new(&witness.oracle_witness).expect("Invalid oracle witness"),
+    );
     let boot = Arc::new(kona_proof::block_on(async {
         BootInfo::load(oracle.as_ref())
             .await
             .expect("Failed to load BootInfo")
     }));
-    let beacon = PreloadedBlobProvider::from(witness.blobs_witness);
+    let blobs_witness =
+        rkyv::deserialize::<BlobWitnessData, rkyv::rancor::Error>(&witness.blobs_witness)
+            .expect("Failed to decode blobs witness");
+    let beacon = PreloadedBlobProvider::from(blobs_witness);
     // Attempt to recompute the output hash at the target block number using kona
     let (precondition_hash, real_output_hash) = kailua_common::client::run_client(
-        witness.precondition_validation_data_hash,
+        B256::from(witness.precondition_validation_data_hash.0),
         oracle.clone(),
         boot.clone(),
         beacon,
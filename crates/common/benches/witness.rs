@@ -0,0 +1,263 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the cost of witness serialization formats and compression settings.
+//!
+//! Encoding runs on the host, while decoding (and decompression) runs in the guest, where every
+//! byte of the frame is also paid for in cycles. The `rkyv` format is measured through
+//! [Witness::encode] and [Witness::decode], so changes to the format used by the fault proof
+//! program show up here against the alternatives.
+//!
+//! Recorded witnesses (e.g. from `kailua-host --witness-dir`) in the directory given by
+//! `KAILUA_BENCH_WITNESSES` are measured alongside the synthetic ones.
+
+use alloy_eips::eip4844::Blob;
+use alloy_primitives::{keccak256, B256};
+use c_kzg::Bytes48;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use kailua_common::witness::Witness;
+use kona_preimage::{PreimageKey, PreimageKeyType};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// The shape of a synthetic witness
+struct Profile {
+    name: &'static str,
+    preimages: usize,
+    /// The mean preimage length, which is roughly that of a branch node in a state trie
+    mean_preimage_len: usize,
+    blobs: usize,
+}
+
+const PROFILES: [Profile; 3] = [
+    Profile {
+        name: "small",
+        preimages: 1_000,
+        mean_preimage_len: 256,
+        blobs: 0,
+    },
+    Profile {
+        name: "median",
+        preimages: 10_000,
+        mean_preimage_len: 384,
+        blobs: 1,
+    },
+    Profile {
+        name: "worst",
+        preimages: 40_000,
+        mean_preimage_len: 512,
+        blobs: 6,
+    },
+];
+
+/// Fills the buffer with pseudorandom bytes derived from the seed
+fn fill(seed: &[u8], buffer: &mut [u8]) {
+    for (i, chunk) in buffer.chunks_mut(32).enumerate() {
+        let hash = keccak256([seed, &(i as u64).to_be_bytes()].concat());
+        chunk.copy_from_slice(&hash[..chunk.len()]);
+    }
+}
+
+fn synthetic_witness(profile: &Profile) -> Witness {
+    let mut witness = Witness::default();
+    for i in 0..profile.preimages {
+        let seed = (i as u64).to_be_bytes();
+        // Vary lengths between half and one and a half times the mean
+        let len = profile.mean_preimage_len / 2
+            + (keccak256(seed)[0] as usize * profile.mean_preimage_len) / 256;
+        let mut preimage = vec![0u8; len];
+        fill(&seed, &mut preimage);
+        // Mimic the rlp string headers preceding each child hash of a trie node
+        for header in preimage.iter_mut().step_by(33) {
+            *header = 0xa0;
+        }
+        let key = PreimageKey::new(*keccak256(&preimage), PreimageKeyType::Keccak256);
+        witness.oracle_witness.push(key, &preimage);
+    }
+    for i in 0..profile.blobs {
+        let mut blob = Blob::default();
+        fill(
+            &[b"blob".as_slice(), &(i as u64).to_be_bytes()].concat(),
+            blob.as_mut_slice(),
+        );
+        // Keep every field element below the BLS modulus
+        for fe in blob.as_mut_slice().chunks_mut(32) {
+            fe[0] = 0;
+        }
+        let mut commitment = [0u8; 48];
+        fill(
+            &[b"commitment".as_slice(), &(i as u64).to_be_bytes()].concat(),
+            &mut commitment,
+        );
+        let mut proof = [0u8; 48];
+        fill(
+            &[b"proof".as_slice(), &(i as u64).to_be_bytes()].concat(),
+            &mut proof,
+        );
+        witness.blobs_witness.blobs.push(blob);
+        witness
+            .blobs_witness
+            .commitments
+            .push(Bytes48::new(commitment));
+        witness.blobs_witness.proofs.push(Bytes48::new(proof));
+    }
+    witness.precondition_validation_data_hash = B256::repeat_byte(0x01);
+    witness
+}
+
+fn recorded_witnesses() -> Vec<(String, Witness)> {
+    let Ok(dir) = std::env::var("KAILUA_BENCH_WITNESSES").map(PathBuf::from) else {
+        return vec![];
+    };
+    let mut witnesses = vec![];
+    for entry in std::fs::read_dir(&dir).expect("Failed to read witness directory") {
+        let path = entry.expect("Failed to read witness directory").path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "witness")
+        {
+            continue;
+        }
+        let data = std::fs::read(&path).expect("Failed to read witness");
+        let witness = Witness::decode(&data).expect("Failed to deserialize witness");
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        witnesses.push((name, witness));
+    }
+    witnesses.sort_by(|a, b| a.0.cmp(&b.0));
+    witnesses
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    /// The format read by the fault proof program
+    Rkyv,
+    Bincode,
+    Pot,
+}
+
+impl Format {
+    const ALL: [Format; 3] = [Format::Rkyv, Format::Bincode, Format::Pot];
+
+    fn encode(self, witness: &Witness) -> Vec<u8> {
+        match self {
+            Format::Rkyv => witness.encode().unwrap(),
+            Format::Bincode => bincode::serialize(witness).unwrap(),
+            Format::Pot => pot::to_vec(witness).unwrap(),
+        }
+    }
+
+    fn decode(self, data: &[u8]) -> Witness {
+        match self {
+            Format::Rkyv => Witness::decode(data).unwrap(),
+            Format::Bincode => bincode::deserialize(data).unwrap(),
+            Format::Pot => pot::from_slice(data).unwrap(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Compression {
+    None,
+    Zstd(i32),
+    Deflate(u32),
+}
+
+impl Compression {
+    const ALL: [Compression; 5] = [
+        Compression::None,
+        Compression::Zstd(1),
+        Compression::Zstd(3),
+        Compression::Zstd(9),
+        Compression::Deflate(6),
+    ];
+
+    fn compress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => data.to_vec(),
+            Compression::Zstd(level) => zstd::encode_all(data, level).unwrap(),
+            Compression::Deflate(level) => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::new(level));
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::None => data.to_vec(),
+            Compression::Zstd(_) => zstd::decode_all(data).unwrap(),
+            Compression::Deflate(_) => {
+                let mut decoded = Vec::new();
+                flate2::read::DeflateDecoder::new(data)
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+                decoded
+            }
+        }
+    }
+}
+
+fn bench_witness(c: &mut Criterion, name: &str, witness: &Witness) {
+    let mut group = c.benchmark_group(format!("witness/{name}"));
+    group.sample_size(10);
+
+    for format in Format::ALL {
+        let encoded = format.encode(witness);
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_function(BenchmarkId::new("encode", format!("{format:?}")), |b| {
+            b.iter(|| format.encode(witness))
+        });
+        group.bench_function(BenchmarkId::new("decode", format!("{format:?}")), |b| {
+            b.iter(|| format.decode(&encoded))
+        });
+
+        for compression in Compression::ALL {
+            let compressed = compression.compress(&encoded);
+            // The frame size determines the cost of reading the witness into the guest
+            println!(
+                "witness/{name} {format:?}/{compression:?}: {} bytes ({:.1}% of {})",
+                compressed.len(),
+                100.0 * compressed.len() as f64 / encoded.len() as f64,
+                encoded.len()
+            );
+            if matches!(compression, Compression::None) {
+                continue;
+            }
+            let id = format!("{format:?}/{compression:?}");
+            group.throughput(Throughput::Bytes(compressed.len() as u64));
+            group.bench_function(BenchmarkId::new("compress", &id), |b| {
+                b.iter(|| compression.compress(&encoded))
+            });
+            group.bench_function(BenchmarkId::new("decompress+decode", &id), |b| {
+                b.iter(|| format.decode(&compression.decompress(&compressed)))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn witness_benchmarks(c: &mut Criterion) {
+    for profile in &PROFILES {
+        bench_witness(c, profile.name, &synthetic_witness(profile));
+    }
+    for (name, witness) in recorded_witnesses() {
+        bench_witness(c, &name, &witness);
+    }
+}
+
+criterion_group!(benches, witness_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use alloy_eips::eip4844::{Blob, BYTES_PER_BLOB, FIELD_ELEMENTS_PER_BLOB};
+use kailua_common::blobs::{
+    decode_intermediate_outputs, encode_intermediate_outputs, FieldElementEncoding,
+};
+use libfuzzer_sys::fuzz_target;
+
+// The first eight bytes select the proposal block count and the rest fills the blobs
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 8 {
+        return;
+    }
+    let (count, contents) = data.split_at(8);
+    let proposal_block_count =
+        u64::from_be_bytes(count.try_into().unwrap()) % (3 * FIELD_ELEMENTS_PER_BLOB + 2);
+    let blobs = contents
+        .chunks(BYTES_PER_BLOB)
+        .map(Blob::right_padding_from)
+        .collect::<Vec<_>>();
+    let Ok(outputs) = decode_intermediate_outputs(&blobs, proposal_block_count) else {
+        return;
+    };
+    assert_eq!(outputs.len() as u64, proposal_block_count.saturating_sub(1));
+    for output in &outputs {
+        let encoding = FieldElementEncoding::CURRENT;
+        if let Some(retained) = encoding.decode(*output) {
+            assert!(encoding.matches(*output, retained));
+        }
+    }
+    // Re-encoding the outputs reproduces the blobs up to their padding
+    let reencoded = encode_intermediate_outputs(&outputs);
+    let reencoded_outputs = decode_intermediate_outputs(&reencoded, proposal_block_count)
+        .expect("Failed to decode reencoded outputs");
+    assert_eq!(reencoded_outputs, outputs);
+});
@@ -0,0 +1,32 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use kailua_common::journal::ProofJournal;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte selects the journal version and the rest is the packed journal
+fuzz_target!(|data: &[u8]| {
+    let Some((version, encoded)) = data.split_first() else {
+        return;
+    };
+    if let Ok(journal) = ProofJournal::decode_packed(encoded) {
+        assert_eq!(journal.encode_packed(), encoded[..ProofJournal::PACKED_LEN]);
+    }
+    if let Ok(journal) = ProofJournal::decode_packed_versioned(encoded, *version) {
+        let packed_len = ProofJournal::packed_len(*version).unwrap();
+        assert_eq!(journal.encode_packed(), encoded[..packed_len]);
+    }
+});
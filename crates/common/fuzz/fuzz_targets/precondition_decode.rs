@@ -0,0 +1,35 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use kailua_common::precondition::PreconditionValidationData;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((version, precondition_validation_data)) = PreconditionValidationData::from_slice(data)
+    else {
+        return;
+    };
+    let _ = precondition_validation_data.precondition_hash(version);
+    let _ = precondition_validation_data.validated_blobs();
+    let reencoded = precondition_validation_data.to_vec(version);
+    let (decoded_version, decoded) = PreconditionValidationData::from_slice(&reencoded)
+        .expect("Failed to decode reencoded precondition validation data");
+    assert_eq!(decoded_version, version);
+    assert_eq!(
+        decoded.hash(version),
+        precondition_validation_data.hash(version)
+    );
+});
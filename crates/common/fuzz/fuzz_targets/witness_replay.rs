@@ -0,0 +1,154 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use kailua_common::oracle::OracleWitnessData;
+use kailua_common::witness::Witness;
+use libfuzzer_sys::fuzz_target;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+/// The recorded witnesses along with the packed journal each commits to
+static WITNESSES: LazyLock<Vec<(Witness, Vec<u8>)>> = LazyLock::new(|| {
+    let dir = std::env::var("KAILUA_FUZZ_WITNESSES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/witnesses")));
+    let mut witnesses = vec![];
+    for entry in std::fs::read_dir(&dir).expect("Failed to read witness directory") {
+        let path = entry.expect("Failed to read witness directory").path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "witness")
+        {
+            continue;
+        }
+        let data = std::fs::read(&path).expect("Failed to read witness");
+        let witness = Witness::decode(&data).expect("Failed to deserialize witness");
+        let journal = witness
+            .replay()
+            .unwrap_or_else(|e| panic!("Recorded witness {} fails: {e:?}", path.display()));
+        witnesses.push((witness, journal.encode_packed()));
+    }
+    assert!(
+        !witnesses.is_empty(),
+        "No witnesses in {}. Record some using kailua-host --witness-dir.",
+        dir.display()
+    );
+    witnesses
+});
+
+#[derive(Arbitrary, Debug)]
+enum Mutation {
+    FlipPreimageBit { preimage: usize, bit: usize },
+    TruncatePreimage { preimage: usize, len: usize },
+    SwapPreimages { a: usize, b: usize },
+    DropPreimage { preimage: usize },
+    FlipBlobBit { blob: usize, bit: usize },
+    SwapBlobs { a: usize, b: usize },
+    DropBlob { blob: usize },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    witness: usize,
+    mutations: Vec<Mutation>,
+}
+
+fn mutate(witness: &mut Witness, mutation: &Mutation) {
+    let mut preimages = witness
+        .oracle_witness
+        .iter()
+        .map(|(key, value)| (key, value.to_vec()))
+        .collect::<Vec<_>>();
+    let blobs = &mut witness.blobs_witness;
+    match *mutation {
+        Mutation::FlipPreimageBit { preimage, bit } => {
+            let Some((_, value)) = preimages.get_mut(preimage) else {
+                return;
+            };
+            if value.is_empty() {
+                return;
+            }
+            let bit = bit % (8 * value.len());
+            value[bit / 8] ^= 1 << (bit % 8);
+        }
+        Mutation::TruncatePreimage { preimage, len } => {
+            let Some((_, value)) = preimages.get_mut(preimage) else {
+                return;
+            };
+            value.truncate(len % (value.len() + 1));
+        }
+        Mutation::SwapPreimages { a, b } => {
+            if a >= preimages.len() || b >= preimages.len() {
+                return;
+            }
+            let value = preimages[a].1.clone();
+            preimages[a].1 = core::mem::replace(&mut preimages[b].1, value);
+        }
+        Mutation::DropPreimage { preimage } => {
+            if preimage < preimages.len() {
+                preimages.remove(preimage);
+            }
+        }
+        Mutation::FlipBlobBit { blob, bit } => {
+            let Some(blob) = blobs.blobs.get_mut(blob) else {
+                return;
+            };
+            let bit = bit % (8 * blob.len());
+            blob[bit / 8] ^= 1 << (bit % 8);
+        }
+        // Swaps entire entries so that the kzg proofs remain valid
+        Mutation::SwapBlobs { a, b } => {
+            if a >= blobs.blobs.len() || b >= blobs.blobs.len() {
+                return;
+            }
+            blobs.blobs.swap(a, b);
+            blobs.commitments.swap(a, b);
+            blobs.proofs.swap(a, b);
+        }
+        Mutation::DropBlob { blob } => {
+            if blob < blobs.blobs.len() {
+                blobs.blobs.remove(blob);
+                blobs.commitments.remove(blob);
+                blobs.proofs.remove(blob);
+            }
+        }
+    }
+    let mut oracle_witness = OracleWitnessData::default();
+    for (key, value) in preimages {
+        oracle_witness.push(key, &value);
+    }
+    witness.oracle_witness = oracle_witness;
+}
+
+// Mutated witnesses must either fail to replay or commit to a different journal
+fuzz_target!(|input: Input| {
+    let (witness, journal) = &WITNESSES[input.witness % WITNESSES.len()];
+    let mut mutated = witness.clone();
+    for mutation in &input.mutations {
+        mutate(&mut mutated, mutation);
+    }
+    if mutated.hash().unwrap() == witness.hash().unwrap() {
+        return;
+    }
+    if let Ok(replayed) = mutated.replay() {
+        assert_ne!(
+            &replayed.encode_packed(),
+            journal,
+            "Mutated witness commits to the recorded journal: {input:?}"
+        );
+    }
+});
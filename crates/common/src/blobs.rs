@@ -12,15 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloy_eips::eip4844::{kzg_to_versioned_hash, Blob, IndexedBlobHash, BYTES_PER_BLOB};
+use alloy_eips::eip4844::{
+    kzg_to_versioned_hash, Blob, IndexedBlobHash, BYTES_PER_BLOB, FIELD_ELEMENTS_PER_BLOB,
+};
 use alloy_primitives::B256;
-use alloy_rpc_types_beacon::sidecar::BlobData;
 use async_trait::async_trait;
 use c_kzg::{ethereum_kzg_settings, Bytes48};
 use kona_derive::errors::BlobProviderError;
 use kona_derive::traits::BlobProvider;
 use op_alloy_protocol::BlockInfo;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BlobFetchRequest {
@@ -40,6 +42,17 @@ pub struct BlobWitnessData {
     pub proofs: Vec<Bytes48>,
 }
 
+impl BlobWitnessData {
+    /// Returns the witness of only the blobs at the given indices, in the given order
+    pub fn select(&self, indices: &[usize]) -> Self {
+        Self {
+            blobs: indices.iter().map(|i| self.blobs[*i]).collect(),
+            commitments: indices.iter().map(|i| self.commitments[*i]).collect(),
+            proofs: indices.iter().map(|i| self.proofs[*i]).collect(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(remote = Blob)]
 #[rkyv(archived = ArchivedBlob)]
@@ -74,22 +87,28 @@ impl From<Bytes48Def> for Bytes48 {
 #[derive(Clone, Debug, Default)]
 pub struct PreloadedBlobProvider {
     entries: Vec<(B256, Blob)>,
+    total: usize,
+    consumed: Arc<Mutex<Vec<usize>>>,
 }
 
-impl From<BlobWitnessData> for PreloadedBlobProvider {
-    fn from(value: BlobWitnessData) -> Self {
+impl PreloadedBlobProvider {
+    /// Loads the witness after checking the kzg proof of each blob against its commitment
+    pub fn try_new(value: BlobWitnessData) -> anyhow::Result<Self> {
         let blobs = value
             .blobs
             .into_iter()
             .map(|b| c_kzg::Blob::new(b.0))
             .collect::<Vec<_>>();
-        c_kzg::KzgProof::verify_blob_kzg_proof_batch(
+        let valid = c_kzg::KzgProof::verify_blob_kzg_proof_batch(
             blobs.as_slice(),
             value.commitments.as_slice(),
             value.proofs.as_slice(),
             ethereum_kzg_settings(),
         )
-        .expect("Failed to batch validate kzg proofs");
+        .map_err(|e| anyhow::anyhow!("Failed to batch validate kzg proofs: {e:?}"))?;
+        if !valid {
+            anyhow::bail!("Invalid kzg proofs");
+        }
         let hashes = value
             .commitments
             .iter()
@@ -98,7 +117,22 @@ impl From<BlobWitnessData> for PreloadedBlobProvider {
         let entries = core::iter::zip(hashes, blobs.into_iter().map(|b| Blob::from(*b)))
             .rev()
             .collect::<Vec<_>>();
-        Self { entries }
+        Ok(Self {
+            total: entries.len(),
+            entries,
+            consumed: Default::default(),
+        })
+    }
+
+    /// Returns a handle to the indices of the witness blobs served so far, in order
+    pub fn consumed(&self) -> Arc<Mutex<Vec<usize>>> {
+        self.consumed.clone()
+    }
+}
+
+impl From<BlobWitnessData> for PreloadedBlobProvider {
+    fn from(value: BlobWitnessData) -> Self {
+        Self::try_new(value).expect("Invalid blob witness")
     }
 }
 
@@ -113,8 +147,17 @@ impl BlobProvider for PreloadedBlobProvider {
     ) -> Result<Vec<Box<Blob>>, Self::Error> {
         let mut blobs = Vec::with_capacity(blob_hashes.len());
         for hash in blob_hashes {
-            let (blob_hash, blob) = self.entries.pop().unwrap();
+            let Some((blob_hash, blob)) = self.entries.pop() else {
+                return Err(BlobProviderError::Backend(format!(
+                    "Witness is missing blob {}",
+                    hash.hash
+                )));
+            };
             if hash.hash == blob_hash {
+                self.consumed
+                    .lock()
+                    .unwrap()
+                    .push(self.total - 1 - self.entries.len());
                 blobs.push(Box::new(blob));
             }
         }
@@ -122,17 +165,126 @@ impl BlobProvider for PreloadedBlobProvider {
     }
 }
 
-pub fn intermediate_outputs(blob_data: &BlobData, blocks: usize) -> anyhow::Result<Vec<B256>> {
+/// Reads the first `blocks` field elements of the blob
+pub fn intermediate_outputs(blob: &Blob, blocks: usize) -> anyhow::Result<Vec<B256>> {
+    if blocks > FIELD_ELEMENTS_PER_BLOB as usize {
+        anyhow::bail!("Blob cannot hold {blocks} outputs");
+    }
     let mut outputs = vec![];
     for i in 0..blocks {
         let index = 32 * i;
-        let bytes: [u8; 32] = blob_data.blob.0[index..index + 32].try_into()?;
+        let bytes: [u8; 32] = blob.0[index..index + 32].try_into()?;
         outputs.push(B256::from(bytes));
     }
     Ok(outputs)
 }
 
-pub fn hash_to_fe(mut hash: B256) -> B256 {
-    hash.0[0] &= u8::MAX >> 2;
-    hash
+/// Returns the number of blobs that publish the intermediate outputs of a proposal
+pub fn intermediate_output_blob_count(proposal_block_count: u64) -> u64 {
+    proposal_block_count
+        .saturating_sub(1)
+        .div_ceil(FIELD_ELEMENTS_PER_BLOB)
+}
+
+/// Reconstructs the ordered intermediate output field elements of a proposal from its blobs
+///
+/// A proposal publishes the `proposal_block_count - 1` outputs preceding its root claim in order,
+/// filling every blob but the last. The trailing field elements of the last blob are padding and
+/// are ignored, as are any blobs beyond those needed to hold the outputs.
+pub fn decode_intermediate_outputs<'a>(
+    blobs: impl IntoIterator<Item = &'a Blob>,
+    proposal_block_count: u64,
+) -> anyhow::Result<Vec<B256>> {
+    let output_count = proposal_block_count.saturating_sub(1) as usize;
+    let mut outputs = Vec::with_capacity(output_count);
+    for blob in blobs {
+        let remaining = output_count - outputs.len();
+        if remaining == 0 {
+            break;
+        }
+        let in_blob = remaining.min(FIELD_ELEMENTS_PER_BLOB as usize);
+        outputs.extend(intermediate_outputs(blob, in_blob)?);
+    }
+    if outputs.len() < output_count {
+        anyhow::bail!(
+            "Blobs hold {} of {output_count} intermediate outputs",
+            outputs.len()
+        );
+    }
+    Ok(outputs)
+}
+
+/// Packs the intermediate output field elements of a proposal into blobs, zero padding the last
+pub fn encode_intermediate_outputs(io_field_elements: &[B256]) -> Vec<Blob> {
+    io_field_elements
+        .chunks(FIELD_ELEMENTS_PER_BLOB as usize)
+        .map(|chunk| Blob::right_padding_from(chunk.concat().as_slice()))
+        .collect()
+}
+
+/// The encodings of output roots into blob field elements
+///
+/// Field elements must be smaller than the BLS12-381 scalar modulus, so an encoding may discard
+/// bits of the output root. Distinct output roots that differ only in discarded bits share a field
+/// element, and thus an encoding offers `256 - discarded_bits()` bits of preimage resistance and
+/// half as many bits of collision resistance. The contracts implement the same encoding in
+/// `KailuaLib.hashToFe`, tagged by `KailuaLib.FE_ENCODING_VERSION`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FieldElementEncoding {
+    /// Clears the two most significant bits of the output root
+    #[default]
+    Truncated254 = 0,
+}
+
+impl FieldElementEncoding {
+    /// The encoding used by proposals of this build
+    pub const CURRENT: Self = Self::Truncated254;
+
+    pub fn version(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_version(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(Self::Truncated254),
+            _ => None,
+        }
+    }
+
+    /// The number of most significant bits of the output root discarded by the encoding
+    pub fn discarded_bits(self) -> u32 {
+        match self {
+            Self::Truncated254 => 2,
+        }
+    }
+
+    /// Encodes the output root into a field element
+    pub fn encode(self, output_root: B256) -> B256 {
+        match self {
+            Self::Truncated254 => {
+                let mut fe = output_root;
+                fe.0[0] &= u8::MAX >> self.discarded_bits();
+                fe
+            }
+        }
+    }
+
+    /// Returns the bits of the output root retained in the field element, or `None` if the field
+    /// element could not have been produced by this encoding
+    pub fn decode(self, fe: B256) -> Option<B256> {
+        match self {
+            Self::Truncated254 => (fe.0[0] >> (8 - self.discarded_bits()) == 0).then_some(fe),
+        }
+    }
+
+    /// Whether the field element is the encoding of the output root
+    pub fn matches(self, fe: B256, output_root: B256) -> bool {
+        self.encode(output_root) == fe
+    }
+}
+
+/// Encodes the output root into a field element using [FieldElementEncoding::CURRENT]
+pub fn hash_to_fe(hash: B256) -> B256 {
+    FieldElementEncoding::CURRENT.encode(hash)
 }
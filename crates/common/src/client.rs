@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::blobs;
+use crate::output::OutputRootPreimage;
 use crate::precondition::PreconditionValidationData;
 use alloy_consensus::Header;
 use alloy_eips::eip4844::FIELD_ELEMENTS_PER_BLOB;
@@ -33,6 +34,45 @@ use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use std::fmt::Debug;
 use std::sync::Arc;
 
+/// Guest-side hook for validating the cross-chain dependencies of derived outputs
+///
+/// Each output claimed by the client is passed to the hook after derivation, and the returned
+/// commitment, if any, is included in the output's [ProofJournal].
+pub trait InteropHook {
+    fn output_dependencies(
+        &mut self,
+        boot: &BootInfo,
+        l2_block_number: u64,
+        output_root: B256,
+    ) -> anyhow::Result<Option<B256>>;
+}
+
+/// The hook of chains without interop, which commits to no dependencies
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoInterop;
+
+impl InteropHook for NoInterop {
+    fn output_dependencies(
+        &mut self,
+        _boot: &BootInfo,
+        _l2_block_number: u64,
+        _output_root: B256,
+    ) -> anyhow::Result<Option<B256>> {
+        Ok(None)
+    }
+}
+
+/// The results of a client run
+#[derive(Clone, Debug, Default)]
+pub struct ClientOutput {
+    /// The hash of the validated precondition
+    pub precondition_hash: B256,
+    /// The derived output root of the claimed block, if sufficient L1 data was available
+    pub output_root: Option<B256>,
+    /// The cross-chain dependencies of the claimed output, if any
+    pub dependencies: Option<B256>,
+}
+
 pub fn run_client<
     O: CommsClient + FlushableCache + Send + Sync + Debug,
     B: BlobProvider + Send + Sync + Debug + Clone,
@@ -40,8 +80,32 @@ pub fn run_client<
     precondition_validation_data_hash: B256,
     oracle: Arc<O>,
     boot: Arc<BootInfo>,
-    mut beacon: B,
+    beacon: B,
 ) -> anyhow::Result<(B256, Option<B256>)>
+where
+    <B as BlobProvider>::Error: Debug,
+{
+    let output = run_client_with_interop(
+        precondition_validation_data_hash,
+        oracle,
+        boot,
+        beacon,
+        &mut NoInterop,
+    )?;
+    Ok((output.precondition_hash, output.output_root))
+}
+
+pub fn run_client_with_interop<
+    O: CommsClient + FlushableCache + Send + Sync + Debug,
+    B: BlobProvider + Send + Sync + Debug + Clone,
+    H: InteropHook,
+>(
+    precondition_validation_data_hash: B256,
+    oracle: Arc<O>,
+    boot: Arc<BootInfo>,
+    mut beacon: B,
+    interop: &mut H,
+) -> anyhow::Result<ClientOutput>
 where
     <B as BlobProvider>::Error: Debug,
 {
@@ -51,7 +115,7 @@ where
         ////////////////////////////////////////////////////////////////
 
         log("PRECONDITION");
-        let precondition_hash = validate_precondition(
+        let (precondition_hash, validity_outputs) = validate_precondition(
             precondition_validation_data_hash,
             oracle.clone(),
             boot.clone(),
@@ -74,10 +138,24 @@ where
             bail!("Invalid Claim");
         }
 
+        // A validity proof must cover exactly the proposal's range of blocks
+        if let Some((proposal_l2_head_number, outputs)) = &validity_outputs {
+            if safe_head.number != *proposal_l2_head_number
+                || proposal_l2_head_number + outputs.len() as u64 + 1
+                    != boot.claimed_l2_block_number
+            {
+                bail!("Validity precondition does not match the claimed block range");
+            }
+        }
+
         // In the case where the agreed upon L2 output root is the same as the claimed L2 output root,
         // trace extension is detected and we can skip the derivation and execution steps.
-        if boot.agreed_l2_output_root == boot.claimed_l2_output_root {
-            return Ok((precondition_hash, Some(boot.claimed_l2_output_root)));
+        if validity_outputs.is_none() && boot.agreed_l2_output_root == boot.claimed_l2_output_root {
+            return Ok(ClientOutput {
+                precondition_hash,
+                output_root: Some(boot.claimed_l2_output_root),
+                ..Default::default()
+            });
         }
 
         ////////////////////////////////////////////////////////////////
@@ -102,6 +180,25 @@ where
         // Run the derivation pipeline until we are able to produce the output root of the claimed
         // L2 block.
         log("ADVANCE");
+        // Validate each intermediate output published in the proposal blobs
+        if let Some((proposal_l2_head_number, outputs)) = validity_outputs {
+            for (i, expected_fe) in outputs.into_iter().enumerate() {
+                let target = proposal_l2_head_number + i as u64 + 1;
+                let (number, output_root) = driver
+                    .advance_to_target(&boot.rollup_config, Some(target))
+                    .await?;
+                if number < target {
+                    log(&format!("OUTPUT: {number}|{target}"));
+                    return Ok(ClientOutput {
+                        precondition_hash,
+                        ..Default::default()
+                    });
+                }
+                if blobs::hash_to_fe(output_root) != expected_fe {
+                    bail!("Output {output_root} at block {target} not found in proposal blobs");
+                }
+            }
+        }
         let (number, output_root) = driver
             .advance_to_target(&boot.rollup_config, Some(boot.claimed_l2_block_number))
             .await?;
@@ -114,10 +211,18 @@ where
         ));
 
         if number < boot.claimed_l2_block_number {
-            Ok((precondition_hash, None))
-        } else {
-            Ok((precondition_hash, Some(output_root)))
+            return Ok(ClientOutput {
+                precondition_hash,
+                ..Default::default()
+            });
         }
+        let dependencies = interop.output_dependencies(&boot, number, output_root)?;
+
+        Ok(ClientOutput {
+            precondition_hash,
+            output_root: Some(output_root),
+            dependencies,
+        })
     })
 }
 
@@ -132,7 +237,7 @@ async fn fetch_safe_head<O: CommsClient>(
         .write(&HintType::StartingL2Output.encode_with(&[boot_info.agreed_l2_output_root.as_ref()]))
         .await
         .map_err(OracleProviderError::Preimage)?;
-    let mut output_preimage = [0u8; 128];
+    let mut output_preimage = [0u8; OutputRootPreimage::LENGTH];
     caching_oracle
         .get_exact(
             PreimageKey::new(*boot_info.agreed_l2_output_root, PreimageKeyType::Keccak256),
@@ -141,17 +246,21 @@ async fn fetch_safe_head<O: CommsClient>(
         .await
         .map_err(OracleProviderError::Preimage)?;
 
-    let safe_hash = output_preimage[96..128]
-        .try_into()
-        .map_err(OracleProviderError::SliceConversion)?;
+    let safe_hash = OutputRootPreimage::decode(&output_preimage)
+        .expect("Output preimage of exact length")
+        .block_hash;
     l2_chain_provider
         .header_by_hash(safe_hash)
         .map(|header| Sealed::new_unchecked(header, safe_hash))
 }
 
 pub fn log(msg: &str) {
+    // Report the cycles spent up to each phase of the guest
     #[cfg(target_os = "zkvm")]
-    risc0_zkvm::guest::env::log(msg);
+    risc0_zkvm::guest::env::log(&format!(
+        "{msg} ({} cycles)",
+        risc0_zkvm::guest::env::cycle_count()
+    ));
     #[cfg(not(target_os = "zkvm"))]
     tracing::info!("{msg}");
 }
@@ -167,6 +276,45 @@ fn safe_default<V: Debug + Eq>(opt: Option<V>, default: V) -> anyhow::Result<V>
     }
 }
 
+/// Returns the hardfork activation times of the rollup in the order they are committed to by
+/// the configuration hash
+pub fn hardfork_activations(rollup_config: &RollupConfig) -> [(&'static str, Option<u64>); 7] {
+    [
+        ("regolith_time", rollup_config.regolith_time),
+        ("canyon_time", rollup_config.canyon_time),
+        ("delta_time", rollup_config.delta_time),
+        ("ecotone_time", rollup_config.ecotone_time),
+        ("fjord_time", rollup_config.fjord_time),
+        ("granite_time", rollup_config.granite_time),
+        ("holocene_time", rollup_config.holocene_time),
+    ]
+}
+
+/// Hardfork activation times that replace the ones reported for the rollup
+///
+/// These allow the configuration hash of a rollup to cover activations that were scheduled after
+/// its nodes or the superchain registry were last updated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HardforkOverrides {
+    pub granite_time: Option<u64>,
+    pub holocene_time: Option<u64>,
+}
+
+impl HardforkOverrides {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn apply(&self, rollup_config: &mut RollupConfig) {
+        if let Some(granite_time) = self.granite_time {
+            rollup_config.granite_time = Some(granite_time);
+        }
+        if let Some(holocene_time) = self.holocene_time {
+            rollup_config.holocene_time = Some(holocene_time);
+        }
+    }
+}
+
 pub fn config_hash(rollup_config: &RollupConfig) -> anyhow::Result<[u8; 32]> {
     // todo: check whether we need to include this, or if it is loaded from the config address
     let system_config_hash: [u8; 32] = rollup_config
@@ -194,6 +342,16 @@ pub fn config_hash(rollup_config: &RollupConfig) -> anyhow::Result<[u8; 32]> {
             Ok::<[u8; 32], anyhow::Error>(digest.as_bytes().try_into()?)
         })
         .unwrap_or(Ok([0u8; 32]))?;
+    // unscheduled hardforks are committed to as u64::MAX
+    let hardfork_times = hardfork_activations(rollup_config)
+        .into_iter()
+        .map(|(name, time)| {
+            safe_default(time, u64::MAX)
+                .context(name)
+                .map(u64::to_be_bytes)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .concat();
     let rollup_config_bytes = [
         rollup_config.genesis.l1.hash.0.as_slice(),
         rollup_config.genesis.l2.hash.0.as_slice(),
@@ -228,34 +386,7 @@ pub fn config_hash(rollup_config: &RollupConfig) -> anyhow::Result<[u8; 32]> {
             .elasticity_multiplier
             .to_be_bytes()
             .as_slice(),
-        safe_default(rollup_config.regolith_time, u64::MAX)
-            .context("regolith_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.canyon_time, u64::MAX)
-            .context("canyon_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.delta_time, u64::MAX)
-            .context("delta_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.ecotone_time, u64::MAX)
-            .context("ecotone_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.fjord_time, u64::MAX)
-            .context("fjord_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.granite_time, u64::MAX)
-            .context("granite_time")?
-            .to_be_bytes()
-            .as_slice(),
-        safe_default(rollup_config.holocene_time, u64::MAX)
-            .context("holocene_time")?
-            .to_be_bytes()
-            .as_slice(),
+        hardfork_times.as_slice(),
         safe_default(rollup_config.blobs_enabled_l1_timestamp, u64::MAX)
             .context("blobs_enabled_timestmap")?
             .to_be_bytes()
@@ -286,28 +417,30 @@ pub async fn validate_precondition<
     oracle: Arc<O>,
     boot: Arc<BootInfo>,
     beacon: &mut B,
-) -> anyhow::Result<B256>
+) -> anyhow::Result<(B256, Option<(u64, Vec<B256>)>)>
 where
     <B as BlobProvider>::Error: Debug,
 {
     // There is no condition to validate at blob boundaries
     if precondition_data_hash.is_zero() {
-        return Ok(B256::ZERO);
+        return Ok((B256::ZERO, None));
     }
     // Read the blob references to fetch
-    let precondition_validation_data: PreconditionValidationData = pot::from_slice(
-        &oracle
-            .get(PreimageKey::new(
-                *precondition_data_hash,
-                PreimageKeyType::Sha256,
-            ))
-            .await
-            .map_err(OracleProviderError::Preimage)?,
-    )?;
-    let precondition_hash = precondition_validation_data.precondition_hash();
+    log("BLOBS");
+    let (precondition_version, precondition_validation_data) =
+        PreconditionValidationData::from_slice(
+            &oracle
+                .get(PreimageKey::new(
+                    *precondition_data_hash,
+                    PreimageKeyType::Sha256,
+                ))
+                .await
+                .map_err(OracleProviderError::Preimage)?,
+        )?;
+    let precondition_hash = precondition_validation_data.precondition_hash(precondition_version);
     // Read the blobs to validate
     let mut blobs = Vec::new();
-    for request in precondition_validation_data.validated_blobs {
+    for request in precondition_validation_data.validated_blobs() {
         #[cfg(not(target_os = "zkvm"))]
         let expected_hash = request.blob_hash.hash;
 
@@ -329,6 +462,25 @@ where
 
         blobs.push(blob);
     }
+    // Extract the intermediate outputs a validity proof must derive
+    if let PreconditionValidationData::Validity {
+        proposal_l2_head_number,
+        proposal_output_count,
+        ..
+    } = precondition_validation_data
+    {
+        if blobs.len() as u64 * (FIELD_ELEMENTS_PER_BLOB as u64) + 1 < proposal_output_count {
+            bail!("Insufficient blobs to validate {proposal_output_count} outputs");
+        }
+        let outputs = (0..proposal_output_count.saturating_sub(1) as usize)
+            .map(|i| {
+                let blob = &blobs[i / FIELD_ELEMENTS_PER_BLOB as usize];
+                let index = 32 * (i % FIELD_ELEMENTS_PER_BLOB as usize);
+                B256::from_slice(&blob[index..index + 32])
+            })
+            .collect();
+        return Ok((precondition_hash, Some((proposal_l2_head_number, outputs))));
+    }
     // Check equivalence until divergence point
     for i in 0..FIELD_ELEMENTS_PER_BLOB {
         let index = 32 * i as usize;
@@ -351,5 +503,5 @@ where
         }
     }
     // Return the precondition hash
-    Ok(precondition_hash)
+    Ok((precondition_hash, None))
 }
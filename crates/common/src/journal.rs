@@ -17,7 +17,14 @@ use anyhow::Context;
 use kona_proof::BootInfo;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+/// The version of the journal format committed by the fault proof program
+pub const PROOF_JOURNAL_VERSION: u8 = 1;
+
+/// The version of the journal format that additionally commits to the cross-chain dependencies of
+/// the claimed outputs, reserved for interop-enabled programs
+pub const INTEROP_JOURNAL_VERSION: u8 = 2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ProofJournal {
     /// The last finalized L2 output
     pub precondition_output: B256,
@@ -31,6 +38,9 @@ pub struct ProofJournal {
     pub claimed_l2_block_number: u64,
     /// The configuration hash.
     pub config_hash: B256,
+    /// The commitment to the cross-chain messages the claimed outputs depend on, if any.
+    #[serde(default)]
+    pub dependencies: Option<B256>,
 }
 
 impl ProofJournal {
@@ -42,11 +52,45 @@ impl ProofJournal {
             claimed_l2_output_root: boot_info.claimed_l2_output_root,
             claimed_l2_block_number: boot_info.claimed_l2_block_number,
             config_hash: B256::from(crate::client::config_hash(&boot_info.rollup_config).unwrap()),
+            dependencies: None,
+        }
+    }
+
+    pub fn with_dependencies(self, dependencies: Option<B256>) -> Self {
+        Self {
+            dependencies,
+            ..self
+        }
+    }
+
+    /// The version of the packed encoding of this journal
+    ///
+    /// Journals without cross-chain dependencies are encoded as [PROOF_JOURNAL_VERSION] so that
+    /// their commitments remain unchanged for deployments without interop.
+    pub fn version(&self) -> u8 {
+        if self.dependencies.is_some() {
+            INTEROP_JOURNAL_VERSION
+        } else {
+            PROOF_JOURNAL_VERSION
         }
     }
 }
 
 impl ProofJournal {
+    /// The length of a packed journal
+    pub const PACKED_LEN: usize = 168;
+    /// The length of a packed journal committing to cross-chain dependencies
+    pub const INTEROP_PACKED_LEN: usize = Self::PACKED_LEN + 32;
+
+    /// Returns the packed length of journals of the given version, if it is known
+    pub fn packed_len(version: u8) -> Option<usize> {
+        match version {
+            PROOF_JOURNAL_VERSION => Some(Self::PACKED_LEN),
+            INTEROP_JOURNAL_VERSION => Some(Self::INTEROP_PACKED_LEN),
+            _ => None,
+        }
+    }
+
     pub fn encode_packed(&self) -> Vec<u8> {
         [
             self.precondition_output.as_slice(),
@@ -55,11 +99,18 @@ impl ProofJournal {
             self.claimed_l2_output_root.as_slice(),
             self.claimed_l2_block_number.to_be_bytes().as_slice(),
             self.config_hash.as_slice(),
+            self.dependencies
+                .as_ref()
+                .map(B256::as_slice)
+                .unwrap_or_default(),
         ]
         .concat()
     }
 
     pub fn decode_packed(encoded: &[u8]) -> Result<Self, anyhow::Error> {
+        if encoded.len() < Self::PACKED_LEN {
+            anyhow::bail!("Journal of length {} is too short", encoded.len());
+        }
         Ok(ProofJournal {
             precondition_output: encoded[..32].try_into().context("precondition_output")?,
             l1_head: encoded[32..64].try_into().context("l1_head")?,
@@ -75,6 +126,22 @@ impl ProofJournal {
                     .context("claimed_l2_block_number")?,
             ),
             config_hash: encoded[136..168].try_into().context("config_hash")?,
+            dependencies: None,
         })
     }
+
+    /// Decodes a journal packed in the given version of the encoding
+    pub fn decode_packed_versioned(encoded: &[u8], version: u8) -> Result<Self, anyhow::Error> {
+        match version {
+            PROOF_JOURNAL_VERSION => Self::decode_packed(encoded),
+            INTEROP_JOURNAL_VERSION => {
+                let dependencies = encoded
+                    .get(Self::PACKED_LEN..Self::INTEROP_PACKED_LEN)
+                    .context("dependencies")?;
+                Ok(Self::decode_packed(encoded)?
+                    .with_dependencies(Some(B256::from_slice(dependencies))))
+            }
+            _ => anyhow::bail!("Unsupported journal version {version}"),
+        }
+    }
 }
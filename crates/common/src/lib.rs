@@ -16,5 +16,6 @@ pub mod blobs;
 pub mod client;
 pub mod journal;
 pub mod oracle;
+pub mod output;
 pub mod precondition;
 pub mod witness;
@@ -14,84 +14,266 @@
 
 use alloy_primitives::keccak256;
 use async_trait::async_trait;
-use kona_preimage::errors::PreimageOracleResult;
+use kona_preimage::errors::{PreimageOracleError, PreimageOracleResult};
 use kona_preimage::{HintWriterClient, PreimageKey, PreimageKeyType, PreimageOracleClient};
 use kona_proof::FlushableCache;
 use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 
+/// The preimages read by the client, in order, packed into a single byte region
+///
+/// Storing preimages contiguously instead of as nested vectors avoids an allocation per preimage
+/// when the guest loads the witness.
 #[derive(
     Clone, Debug, Default, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
 )]
 pub struct OracleWitnessData {
-    pub data: Vec<Vec<u8>>,
+    /// The concatenated preimages
+    pub data: Vec<u8>,
+    /// The end offset of each preimage in `data`
+    pub ends: Vec<u64>,
     pub keys: Vec<PreimageKey>,
 }
 
-pub type PreimageStore = Arc<Mutex<Vec<(PreimageKey, Vec<u8>)>>>;
+impl OracleWitnessData {
+    pub fn push(&mut self, key: PreimageKey, value: &[u8]) {
+        self.data.extend_from_slice(value);
+        self.ends.push(self.data.len() as u64);
+        self.keys.push(key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the key and preimage at the given index
+    pub fn get(&self, index: usize) -> Option<(PreimageKey, &[u8])> {
+        let key = *self.keys.get(index)?;
+        let start = index.checked_sub(1).map_or(0, |i| self.ends[i] as usize);
+        let end = *self.ends.get(index)? as usize;
+        Some((key, self.data.get(start..end)?))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PreimageKey, &[u8])> {
+        (0..self.len()).map_while(|i| self.get(i))
+    }
+
+    /// Returns the witness of only the preimages at the given indices, in the given order
+    pub fn select(&self, indices: &[usize]) -> Self {
+        let mut selected = Self::default();
+        for (key, value) in indices.iter().filter_map(|i| self.get(*i)) {
+            selected.push(key, value);
+        }
+        selected
+    }
+}
+
+/// Read access to the preimages of a witness, whether deserialized or archived
+///
+/// The fault proof program reads preimages directly from the archived witness in its input frame,
+/// so that the concatenated preimages are never copied out of it.
+pub trait OracleWitnessStore: Send + Sync {
+    /// Returns the number of preimages
+    fn len(&self) -> usize;
+
+    /// Returns the key and preimage at the given index
+    fn get(&self, index: usize) -> Option<(PreimageKey, &[u8])>;
+
+    /// Fails if the keys, end offsets and concatenated preimages are inconsistent
+    fn check_layout(&self) -> anyhow::Result<()>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Fails unless there is an end offset per key, in order, with the last one at the end of the data
+fn check_layout(keys: usize, ends: &[u64], data: usize) -> anyhow::Result<()> {
+    if keys != ends.len()
+        || ends.last().map_or(0, |end| *end as usize) != data
+        || ends.windows(2).any(|w| w[0] > w[1])
+    {
+        anyhow::bail!(
+            "Witness has {keys} keys for {} preimages spanning {data} bytes",
+            ends.len()
+        );
+    }
+    Ok(())
+}
+
+impl OracleWitnessStore for OracleWitnessData {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, index: usize) -> Option<(PreimageKey, &[u8])> {
+        self.get(index)
+    }
+
+    fn check_layout(&self) -> anyhow::Result<()> {
+        check_layout(self.keys.len(), &self.ends, self.data.len())
+    }
+}
+
+impl OracleWitnessStore for ArchivedOracleWitnessData {
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn get(&self, index: usize) -> Option<(PreimageKey, &[u8])> {
+        let key =
+            rkyv::deserialize::<PreimageKey, rkyv::rancor::Error>(self.keys.get(index)?).ok()?;
+        let start = index
+            .checked_sub(1)
+            .map_or(0, |i| self.ends[i].to_native() as usize);
+        let end = self.ends.get(index)?.to_native() as usize;
+        Some((key, self.data.get(start..end)?))
+    }
+
+    fn check_layout(&self) -> anyhow::Result<()> {
+        let ends = self
+            .ends
+            .iter()
+            .map(|end| end.to_native())
+            .collect::<Vec<_>>();
+        check_layout(self.keys.len(), &ends, self.data.len())
+    }
+}
+
+impl<T: OracleWitnessStore + ?Sized> OracleWitnessStore for &T {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn get(&self, index: usize) -> Option<(PreimageKey, &[u8])> {
+        (**self).get(index)
+    }
+
+    fn check_layout(&self) -> anyhow::Result<()> {
+        (**self).check_layout()
+    }
+}
 
+/// The progress of a [PreloadedOracle] through its witness
 #[derive(Clone, Debug, Default)]
-pub struct PreloadedOracle {
-    preimages: PreimageStore,
+struct ReadCursor {
+    /// The index of the next preimage to be read
+    next: usize,
+    /// The indices of the preimages passed over without being read
+    skipped: Vec<usize>,
 }
 
-impl From<OracleWitnessData> for PreloadedOracle {
-    fn from(witness: OracleWitnessData) -> Self {
-        let preimages = core::iter::zip(witness.keys, witness.data)
-            .rev()
-            .map(|(key, value)| {
-                let key_type = key.key_type();
-                let image = match key_type {
-                    PreimageKeyType::Keccak256 => Some(keccak256(&value).0),
-                    PreimageKeyType::Sha256 => {
-                        let x = SHA2::hash_bytes(&value);
-                        Some(x.as_bytes().try_into().unwrap())
-                    }
-                    PreimageKeyType::Precompile => {
-                        unimplemented!("Precompile acceleration not yet supported");
-                    }
-                    PreimageKeyType::Local
-                    | PreimageKeyType::GlobalGeneric
-                    | PreimageKeyType::Blob => None,
-                };
-                if let Some(image) = image {
-                    assert_eq!(key, PreimageKey::new(image, key_type));
+#[derive(Clone, Default)]
+pub struct PreloadedOracle<W: OracleWitnessStore = OracleWitnessData> {
+    witness: Arc<W>,
+    cursor: Arc<Mutex<ReadCursor>>,
+}
+
+impl<W: OracleWitnessStore> Debug for PreloadedOracle<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreloadedOracle")
+            .field("preimages", &self.witness.len())
+            .field("cursor", &self.cursor)
+            .finish()
+    }
+}
+
+impl<W: OracleWitnessStore> PreloadedOracle<W> {
+    /// Loads the witness after checking that each hashed preimage matches its key
+    pub fn try_new(witness: W) -> anyhow::Result<Self> {
+        witness.check_layout()?;
+        for (key, value) in (0..witness.len()).map_while(|i| witness.get(i)) {
+            let key_type = key.key_type();
+            let image = match key_type {
+                PreimageKeyType::Keccak256 => Some(keccak256(value).0),
+                PreimageKeyType::Sha256 => {
+                    let x = SHA2::hash_bytes(value);
+                    Some(x.as_bytes().try_into().unwrap())
+                }
+                PreimageKeyType::Precompile => {
+                    anyhow::bail!("Precompile acceleration not yet supported");
+                }
+                PreimageKeyType::Local | PreimageKeyType::GlobalGeneric | PreimageKeyType::Blob => {
+                    None
                 }
-                (key, value)
-            })
-            .collect();
-        Self {
-            preimages: Arc::new(Mutex::new(preimages)),
+            };
+            if let Some(image) = image {
+                if key != PreimageKey::new(image, key_type) {
+                    anyhow::bail!("Preimage does not match key {key:?}");
+                }
+            }
         }
+        Ok(Self {
+            witness: Arc::new(witness),
+            cursor: Default::default(),
+        })
     }
-}
 
-impl FlushableCache for PreloadedOracle {
-    fn flush(&self) {}
-}
+    /// Returns the indices of the preimages read so far, in order
+    pub fn consumed(&self) -> Vec<usize> {
+        let cursor = self.cursor.lock().unwrap();
+        (0..cursor.next)
+            .filter(|i| cursor.skipped.binary_search(i).is_err())
+            .collect()
+    }
 
-#[async_trait]
-impl PreimageOracleClient for PreloadedOracle {
-    async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
-        let mut preimages = self.preimages.lock().unwrap();
+    /// Advances past the next preimage with the given key and returns it
+    fn next(&self, key: PreimageKey) -> PreimageOracleResult<&[u8]> {
+        let mut cursor = self.cursor.lock().unwrap();
         loop {
-            let (k, v) = preimages.pop().unwrap();
+            let index = cursor.next;
+            let Some((k, v)) = self.witness.get(index) else {
+                return Err(PreimageOracleError::Other(format!(
+                    "Witness is missing preimage {key:?}"
+                )));
+            };
+            cursor.next += 1;
             if k == key {
                 break Ok(v);
             }
+            cursor.skipped.push(index);
         }
     }
+}
+
+impl From<OracleWitnessData> for PreloadedOracle {
+    fn from(witness: OracleWitnessData) -> Self {
+        Self::try_new(witness).expect("Invalid oracle witness")
+    }
+}
+
+impl<W: OracleWitnessStore> FlushableCache for PreloadedOracle<W> {
+    fn flush(&self) {}
+}
+
+#[async_trait]
+impl<W: OracleWitnessStore> PreimageOracleClient for PreloadedOracle<W> {
+    async fn get(&self, key: PreimageKey) -> PreimageOracleResult<Vec<u8>> {
+        Ok(self.next(key)?.to_vec())
+    }
 
     async fn get_exact(&self, key: PreimageKey, buf: &mut [u8]) -> PreimageOracleResult<()> {
-        let v = self.get(key).await?;
-        buf.copy_from_slice(v.as_slice());
+        let v = self.next(key)?;
+        if v.len() != buf.len() {
+            return Err(PreimageOracleError::Other(format!(
+                "Preimage {key:?} has length {} instead of {}",
+                v.len(),
+                buf.len()
+            )));
+        }
+        buf.copy_from_slice(v);
         Ok(())
     }
 }
 
 #[async_trait]
-impl HintWriterClient for PreloadedOracle {
+impl<W: OracleWitnessStore> HintWriterClient for PreloadedOracle<W> {
     async fn write(&self, _hint: &str) -> PreimageOracleResult<()> {
         Ok(())
     }
@@ -0,0 +1,112 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+/// The only output root version defined by the OP Stack
+pub const OUTPUT_ROOT_VERSION: B256 = B256::ZERO;
+
+/// The components committed to by an L2 output root
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OutputRootPreimage {
+    pub version: B256,
+    /// The state root of the L2 block
+    pub state_root: B256,
+    /// The storage root of the L2ToL1MessagePasser contract
+    pub withdrawal_storage_root: B256,
+    /// The hash of the L2 block
+    pub block_hash: B256,
+}
+
+impl OutputRootPreimage {
+    /// The length of an encoded preimage
+    pub const LENGTH: usize = 128;
+
+    pub fn new(state_root: B256, withdrawal_storage_root: B256, block_hash: B256) -> Self {
+        Self {
+            version: OUTPUT_ROOT_VERSION,
+            state_root,
+            withdrawal_storage_root,
+            block_hash,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; Self::LENGTH] {
+        let mut encoded = [0u8; Self::LENGTH];
+        encoded[..32].copy_from_slice(self.version.as_slice());
+        encoded[32..64].copy_from_slice(self.state_root.as_slice());
+        encoded[64..96].copy_from_slice(self.withdrawal_storage_root.as_slice());
+        encoded[96..].copy_from_slice(self.block_hash.as_slice());
+        encoded
+    }
+
+    /// Parses an encoded preimage, or returns `None` if it is not of the expected length
+    pub fn decode(encoded: &[u8]) -> Option<Self> {
+        if encoded.len() != Self::LENGTH {
+            return None;
+        }
+        Some(Self {
+            version: B256::from_slice(&encoded[..32]),
+            state_root: B256::from_slice(&encoded[32..64]),
+            withdrawal_storage_root: B256::from_slice(&encoded[64..96]),
+            block_hash: B256::from_slice(&encoded[96..]),
+        })
+    }
+
+    /// Computes the output root committing to these components
+    pub fn output_root(&self) -> B256 {
+        keccak256(self.encode())
+    }
+}
+
+/// Computes the version 0 output root of an L2 block from its components
+pub fn output_root(state_root: B256, withdrawal_storage_root: B256, block_hash: B256) -> B256 {
+    OutputRootPreimage::new(state_root, withdrawal_storage_root, block_hash).output_root()
+}
+
+/// The L2 block reference included in op-node output responses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputBlockRef {
+    pub hash: B256,
+    pub number: u64,
+}
+
+/// The response of op-node to `optimism_outputAtBlock`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputResponse {
+    pub version: B256,
+    pub output_root: B256,
+    pub block_ref: OutputBlockRef,
+    pub withdrawal_storage_root: B256,
+    pub state_root: B256,
+}
+
+impl OutputResponse {
+    /// Returns the components of the reported output root
+    pub fn preimage(&self) -> OutputRootPreimage {
+        OutputRootPreimage {
+            version: self.version,
+            state_root: self.state_root,
+            withdrawal_storage_root: self.withdrawal_storage_root,
+            block_hash: self.block_ref.hash,
+        }
+    }
+
+    /// Whether the reported output root commits to the reported components
+    pub fn is_consistent(&self) -> bool {
+        self.preimage().output_root() == self.output_root
+    }
+}
@@ -17,30 +17,140 @@ use alloy_primitives::B256;
 use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use serde::{Deserialize, Serialize};
 
+/// The version of the precondition hash encodings
+pub const PRECONDITION_VERSION: u8 = 1;
+
+/// The version of the unprefixed encodings expected by contracts older than 0.2.0
+pub const LEGACY_PRECONDITION_VERSION: u8 = 0;
+
+/// The precondition encoding versions this build can produce and validate
+pub const SUPPORTED_PRECONDITION_VERSIONS: [u8; 2] =
+    [LEGACY_PRECONDITION_VERSION, PRECONDITION_VERSION];
+
+/// The domain tags separating the preimages of each kind of precondition hash
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreconditionDomain {
+    /// The serialized [PreconditionValidationData] passed to the client
+    ValidationData = 0,
+    /// Blob equivalence up to a divergence point
+    Fault = 1,
+    /// Inclusion of all intermediate outputs of a proposal in its published blobs
+    Validity = 2,
+}
+
+impl PreconditionDomain {
+    /// The version and domain tag prepended to hashed preimages of this domain, which legacy
+    /// encodings omit
+    pub fn prefix(self, version: u8) -> Vec<u8> {
+        if version == LEGACY_PRECONDITION_VERSION {
+            vec![]
+        } else {
+            vec![version, self as u8]
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct PreconditionValidationData {
-    pub validated_blobs: [BlobFetchRequest; 2],
+pub enum PreconditionValidationData {
+    /// Blob equivalence between a contender and a proposal up to their divergence point
+    Fault {
+        validated_blobs: [BlobFetchRequest; 2],
+    },
+    /// Inclusion of all intermediate outputs of a proposal in its published blobs
+    Validity {
+        proposal_l2_head_number: u64,
+        proposal_output_count: u64,
+        validated_blobs: Vec<BlobFetchRequest>,
+    },
 }
 
 impl PreconditionValidationData {
-    pub fn to_vec(&self) -> Vec<u8> {
-        pot::to_vec(self).unwrap()
+    /// Serializes the data behind the version and domain tag of the given encoding version
+    pub fn to_vec(&self, version: u8) -> Vec<u8> {
+        [
+            PreconditionDomain::ValidationData.prefix(version),
+            pot::to_vec(self).unwrap(),
+        ]
+        .concat()
     }
 
-    pub fn hash(&self) -> B256 {
-        let digest = *SHA2::hash_bytes(&self.to_vec());
+    /// Deserializes data produced by [PreconditionValidationData::to_vec] along with the version
+    /// of its encoding
+    pub fn from_slice(data: &[u8]) -> anyhow::Result<(u8, Self)> {
+        let prefix = PreconditionDomain::ValidationData.prefix(PRECONDITION_VERSION);
+        match data.strip_prefix(prefix.as_slice()) {
+            Some(serialized) => Ok((PRECONDITION_VERSION, pot::from_slice(serialized)?)),
+            // legacy data starts with the pot header instead
+            None => Ok((LEGACY_PRECONDITION_VERSION, pot::from_slice(data)?)),
+        }
+    }
+
+    pub fn hash(&self, version: u8) -> B256 {
+        let digest = *SHA2::hash_bytes(&self.to_vec(version));
         B256::from_slice(digest.as_bytes())
     }
 
-    pub fn precondition_hash(&self) -> B256 {
-        precondition_hash(
-            &self.validated_blobs[0].blob_hash.hash,
-            &self.validated_blobs[1].blob_hash.hash,
-        )
+    pub fn validated_blobs(&self) -> &[BlobFetchRequest] {
+        match self {
+            PreconditionValidationData::Fault { validated_blobs } => validated_blobs.as_slice(),
+            PreconditionValidationData::Validity {
+                validated_blobs, ..
+            } => validated_blobs.as_slice(),
+        }
+    }
+
+    pub fn precondition_hash(&self, version: u8) -> B256 {
+        match self {
+            PreconditionValidationData::Fault { validated_blobs } => precondition_hash(
+                &validated_blobs[0].blob_hash.hash,
+                &validated_blobs[1].blob_hash.hash,
+                version,
+            ),
+            PreconditionValidationData::Validity {
+                proposal_l2_head_number,
+                proposal_output_count,
+                validated_blobs,
+            } => validity_precondition_hash(
+                *proposal_l2_head_number,
+                *proposal_output_count,
+                &validated_blobs
+                    .iter()
+                    .map(|b| b.blob_hash.hash)
+                    .collect::<Vec<_>>(),
+                version,
+            ),
+        }
     }
 }
 
-pub fn precondition_hash(contender: &B256, proposal: &B256) -> B256 {
-    let digest = *SHA2::hash_bytes(&[contender.as_slice(), proposal.as_slice()].concat());
+pub fn precondition_hash(contender: &B256, proposal: &B256, version: u8) -> B256 {
+    let digest = *SHA2::hash_bytes(
+        &[
+            PreconditionDomain::Fault.prefix(version).as_slice(),
+            contender.as_slice(),
+            proposal.as_slice(),
+        ]
+        .concat(),
+    );
+    B256::from_slice(digest.as_bytes())
+}
+
+pub fn validity_precondition_hash(
+    proposal_l2_head_number: u64,
+    proposal_output_count: u64,
+    blob_hashes: &[B256],
+    version: u8,
+) -> B256 {
+    let mut data = [
+        PreconditionDomain::Validity.prefix(version).as_slice(),
+        proposal_l2_head_number.to_be_bytes().as_slice(),
+        proposal_output_count.to_be_bytes().as_slice(),
+    ]
+    .concat();
+    for blob_hash in blob_hashes {
+        data.extend_from_slice(blob_hash.as_slice());
+    }
+    let digest = *SHA2::hash_bytes(&data);
     B256::from_slice(digest.as_bytes())
 }
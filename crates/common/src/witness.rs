@@ -12,10 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::blobs::BlobWitnessData;
-use crate::oracle::OracleWitnessData;
+use crate::blobs::{BlobWitnessData, PreloadedBlobProvider};
+use crate::client::{config_hash, run_client};
+use crate::journal::ProofJournal;
+use crate::oracle::{OracleWitnessData, PreloadedOracle};
 use alloy_primitives::B256;
+use anyhow::{ensure, Context};
+use kona_proof::BootInfo;
+use risc0_zkvm::sha::{Impl as SHA2, Sha256};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The version of the witness format read by the fault proof program
+pub const WITNESS_VERSION: u8 = 1;
 
 #[derive(
     Clone, Debug, Default, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
@@ -27,6 +36,123 @@ pub struct Witness {
     pub precondition_validation_data_hash: B256,
 }
 
+impl Witness {
+    /// Replays the witness natively to check that it suffices to prove the claim in the boot info
+    ///
+    /// Returns the journal that the fault proof program would commit to on this witness.
+    pub fn validate(&self, boot_info: &BootInfo) -> anyhow::Result<ProofJournal> {
+        Ok(self.canonicalize(boot_info)?.1)
+    }
+
+    /// Replays the witness natively like [Witness::validate] and returns its canonical form along
+    /// with the journal committed to
+    ///
+    /// The canonical witness holds exactly the preimages and blobs read by the client, in the order
+    /// they are read, so that identical disputes produce byte-identical witnesses.
+    pub fn canonicalize(&self, boot_info: &BootInfo) -> anyhow::Result<(Self, ProofJournal)> {
+        let replay = self.execute()?;
+        let boot = replay.boot;
+        ensure!(
+            boot.l1_head == boot_info.l1_head
+                && boot.agreed_l2_output_root == boot_info.agreed_l2_output_root
+                && boot.claimed_l2_output_root == boot_info.claimed_l2_output_root
+                && boot.claimed_l2_block_number == boot_info.claimed_l2_block_number
+                && boot.chain_id == boot_info.chain_id,
+            "Witness boot info does not match the claim"
+        );
+        ensure!(
+            config_hash(&boot.rollup_config)? == config_hash(&boot_info.rollup_config)?,
+            "Witness rollup config does not match the claim"
+        );
+        let canonical = Self {
+            oracle_witness: self.oracle_witness.select(&replay.consumed_preimages),
+            blobs_witness: self.blobs_witness.select(&replay.consumed_blobs),
+            precondition_validation_data_hash: self.precondition_validation_data_hash,
+        };
+        Ok((canonical, replay.journal))
+    }
+
+    /// Replays the witness natively with the same checks as the fault proof program, returning
+    /// the journal it would commit to, or an error where the program would fail to prove
+    pub fn replay(&self) -> anyhow::Result<ProofJournal> {
+        Ok(self.execute()?.journal)
+    }
+
+    /// Loads the boot info of the claim proven by the witness
+    pub fn boot_info(&self) -> anyhow::Result<BootInfo> {
+        let oracle = PreloadedOracle::try_new(self.oracle_witness.clone())
+            .context("PreloadedOracle::try_new")?;
+        kona_proof::block_on(BootInfo::load(&oracle)).context("BootInfo::load")
+    }
+
+    fn execute(&self) -> anyhow::Result<Replay> {
+        let oracle = Arc::new(
+            PreloadedOracle::try_new(self.oracle_witness.clone())
+                .context("PreloadedOracle::try_new")?,
+        );
+        let boot = Arc::new(
+            kona_proof::block_on(BootInfo::load(oracle.as_ref())).context("BootInfo::load")?,
+        );
+        let beacon = PreloadedBlobProvider::try_new(self.blobs_witness.clone())
+            .context("PreloadedBlobProvider::try_new")?;
+        let consumed_blobs = beacon.consumed();
+        let (precondition_hash, output_root) = run_client(
+            self.precondition_validation_data_hash,
+            oracle.clone(),
+            boot.clone(),
+            beacon,
+        )
+        .context("Witness is insufficient to run the client")?;
+        // The program asserts that the claim is either correct or zero for insufficient data
+        ensure!(
+            boot.claimed_l2_output_root == output_root.unwrap_or_default(),
+            "Witness derives output {output_root:?} instead of the claimed {}",
+            boot.claimed_l2_output_root
+        );
+        let journal = ProofJournal::new(precondition_hash, boot.as_ref());
+        let consumed_blobs = consumed_blobs.lock().unwrap().clone();
+        Ok(Replay {
+            boot,
+            journal,
+            consumed_preimages: oracle.consumed(),
+            consumed_blobs,
+        })
+    }
+
+    /// Serializes the witness into the frame read by the fault proof program
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(rkyv::to_bytes::<rkyv::rancor::Error>(self)?.to_vec())
+    }
+
+    /// Validates and accesses a witness in a frame produced by [Witness::encode] in place, the way
+    /// the fault proof program does
+    pub fn access(data: &[u8]) -> anyhow::Result<&ArchivedWitness> {
+        Ok(rkyv::access::<ArchivedWitness, rkyv::rancor::Error>(data)?)
+    }
+
+    /// Deserializes a witness from a frame produced by [Witness::encode]
+    pub fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(rkyv::deserialize::<Witness, rkyv::rancor::Error>(
+            Self::access(data)?,
+        )?)
+    }
+
+    /// Returns the sha256 digest of the serialized witness, which identifies canonical witnesses
+    pub fn hash(&self) -> anyhow::Result<B256> {
+        let bytes = self.encode()?;
+        let digest = *SHA2::hash_bytes(&bytes);
+        Ok(B256::from_slice(digest.as_bytes()))
+    }
+}
+
+/// The outcome of natively replaying a witness
+struct Replay {
+    boot: Arc<BootInfo>,
+    journal: ProofJournal,
+    consumed_preimages: Vec<usize>,
+    consumed_blobs: Vec<usize>,
+}
+
 #[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 #[rkyv(remote = B256)]
 #[rkyv(archived = ArchivedB256)]
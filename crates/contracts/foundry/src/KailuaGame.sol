@@ -23,8 +23,8 @@ import "./KailuaTreasury.sol";
 
 contract KailuaGame is KailuaTournament {
     /// @notice Semantic version.
-    /// @custom:semver 0.1.0
-    string public constant version = "0.1.0";
+    /// @custom:semver 0.2.0
+    string public constant version = "0.2.0";
 
     // ------------------------------
     // Immutable configuration
@@ -204,6 +204,9 @@ contract KailuaGame is KailuaTournament {
 
         // Update the status and emit the resolved event, note that we're performing a storage update here.
         emit Resolved(status = status_ = GameStatus.DEFENDER_WINS);
+
+        // Release the proposer's bond from this proposal
+        KAILUA_TREASURY.markResolved();
     }
 
     // ------------------------------
@@ -257,6 +260,11 @@ contract KailuaGame is KailuaTournament {
             revert GameNotInProgress();
         }
 
+        // A proposal proven valid need not wait for challengers
+        if (address(parentGame().validChild()) == address(this)) {
+            return Duration.wrap(0);
+        }
+
         // Compute the duration elapsed of the potential challenger's clock.
         uint64 elapsed = uint64(asOfTimestamp - createdAt.raw());
         uint64 maximum = MAX_CLOCK_DURATION.raw();
@@ -104,16 +104,30 @@ error InvalidDuplicationCounter();
 /// @param initialized This game's l2 block number
 error BlockNumberMismatch(uint256 anchored, uint256 initialized);
 
+// 0x63b4904e
+/// @notice Occurs when a proposal's bond lock is released twice
+error AlreadyReleased();
+
 /// @notice Emitted when an output is proven.
 /// @param u The preexisting proposal
 /// @param v The subsequent proposal
 /// @param status The proven status of the match
 event Proven(uint64 indexed u, uint64 indexed v, ProofStatus indexed status);
 
+/// @notice Emitted when a child proposal is proven valid.
+/// @param child The index of the valid child proposal
+event ValidityProven(uint64 indexed child);
+
 /// @notice Emitted when the participation bond is updated
 /// @param amount The new required bond amount
 event BondUpdated(uint256 amount);
 
+/// @notice Emitted when a proposer withdraws their bond
+/// @param proposer The proposer that paid the bond
+/// @param recipient The address the bond was transferred to
+/// @param amount The withdrawn bond amount
+event BondWithdrawn(address indexed proposer, address recipient, uint256 amount);
+
 interface IKailuaTreasury {
     /// @notice Returns the game index at which proposer was proven faulty
     function eliminationRound(address proposer) external returns (uint256);
@@ -126,6 +140,9 @@ interface IKailuaTreasury {
 
     /// @notice Returns true iff a proposal is currently being submitted
     function isProposing() external returns (bool);
+
+    /// @notice Releases the bond of the calling proposal's proposer from its resolution lock
+    function markResolved() external;
 }
 
 library KailuaLib {
@@ -147,9 +164,22 @@ library KailuaLib {
     uint256 internal constant ROOT_OF_UNITY =
         39033254847818212395286706435128746857159659164139250548781411570340225835782;
 
+    /// @notice The version of the precondition hash encodings
+    uint8 internal constant PRECONDITION_VERSION = 1;
+
+    /// @notice The domain tag of blob equivalence preconditions
+    uint8 internal constant PRECONDITION_FAULT_DOMAIN = 1;
+
+    /// @notice The domain tag of intermediate output inclusion preconditions
+    uint8 internal constant PRECONDITION_VALIDITY_DOMAIN = 2;
+
     /// @notice The po2 for the number of field elements in a single blob
     uint256 internal constant FIELD_ELEMENTS_PER_BLOB_PO2 = 12;
 
+    /// @notice The version of the output root to field element encoding implemented by hashToFe
+    /// @dev Must match the version of FieldElementEncoding::CURRENT in kailua-common
+    uint8 internal constant FE_ENCODING_VERSION = 0;
+
     function blobIndex(uint256 element) internal pure returns (uint256 index) {
         index = element / (1 << FIELD_ELEMENTS_PER_BLOB_PO2);
     }
@@ -163,6 +193,7 @@ library KailuaLib {
         hash = ((sha256(blobCommitment) << 8) >> 8) | KZG_COMMITMENT_VERSION;
     }
 
+    /// @notice Encodes an output root into a blob field element by clearing its two most significant bits
     function hashToFe(bytes32 hash) internal pure returns (bytes32 fe) {
         fe = ((hash << 2) >> 2);
     }
@@ -192,6 +192,8 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
             if (KailuaLib.blobPosition(uvo[2]) != 0 && uvo[2] < PROPOSAL_BLOCK_COUNT - 1) {
                 preconditionHash = sha256(
                     abi.encodePacked(
+                        KailuaLib.PRECONDITION_VERSION,
+                        KailuaLib.PRECONDITION_FAULT_DOMAIN,
                         childContracts[0].proposalBlobHashes(divergentBlobIndex).raw(),
                         childContracts[1].proposalBlobHashes(divergentBlobIndex).raw()
                     )
@@ -295,6 +297,68 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
         provenAt[uvo[0]][uvo[1]] = Timestamp.wrap(uint64(block.timestamp));
     }
 
+    // ------------------------------
+    // Validity proving
+    // ------------------------------
+
+    /// @notice The child proven valid, if any
+    KailuaTournament public validChild;
+
+    /// @notice Proves the validity of a child's output root and all its intermediate outputs
+    function proveValidity(uint64 childIndex, bytes calldata encodedSeal) external {
+        KailuaTournament childContract = children[childIndex];
+        // INVARIANT: Validity cannot be proven unless the child is playing.
+        if (childContract.status() != GameStatus.IN_PROGRESS) {
+            revert GameNotInProgress();
+        }
+
+        // INVARIANT: Validity can only be proven once
+        if (address(validChild) != address(0x0)) {
+            revert AlreadyProven();
+        }
+
+        // Commit to all the intermediate outputs published in the child's blobs
+        bytes memory blobHashes;
+        for (uint256 i = 0; i < PROPOSAL_BLOBS; i++) {
+            blobHashes = abi.encodePacked(blobHashes, childContract.proposalBlobHashes(i).raw());
+        }
+        bytes32 preconditionHash = sha256(
+            abi.encodePacked(
+                KailuaLib.PRECONDITION_VERSION,
+                KailuaLib.PRECONDITION_VALIDITY_DOMAIN,
+                uint64(l2BlockNumber()),
+                uint64(PROPOSAL_BLOCK_COUNT),
+                blobHashes
+            )
+        );
+
+        // Construct the expected journal
+        bytes32 journalDigest = sha256(
+            abi.encodePacked(
+                // The intermediate outputs commitment
+                preconditionHash,
+                // The L1 head hash containing the safe L2 chain data that may reproduce the L2 head hash.
+                childContract.l1Head().raw(),
+                // The latest finalized L2 output root.
+                rootClaim().raw(),
+                // The L2 output root claim.
+                childContract.rootClaim().raw(),
+                // The L2 claim block number.
+                uint64(childContract.l2BlockNumber()),
+                // The configuration hash for this game
+                ROLLUP_CONFIG_HASH
+            )
+        );
+
+        // reverts on failure
+        RISC_ZERO_VERIFIER.verify(encodedSeal, FPVM_IMAGE_ID, journalDigest);
+
+        // Mark the child as the survivor of this tournament
+        validChild = childContract;
+
+        emit ValidityProven(childIndex);
+    }
+
     /// @notice Registers a new proposal that extends this one
     function appendChild() external {
         // INVARIANT: The calling contract is a newly deployed contract by the dispute game factory
@@ -325,6 +389,11 @@ abstract contract KailuaTournament is Clone, IDisputeGame {
             revert NotProposed();
         }
 
+        // A child proven valid survives without playing any matches
+        if (address(validChild) != address(0x0)) {
+            return validChild;
+        }
+
         // Select the first possible survivor
         uint256 u;
         for (u = 0; u < children.length; u++) {
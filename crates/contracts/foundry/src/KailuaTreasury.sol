@@ -22,8 +22,8 @@ import "./KailuaTournament.sol";
 
 contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
     /// @notice Semantic version.
-    /// @custom:semver 0.1.0
-    string public constant version = "0.1.0";
+    /// @custom:semver 0.2.0
+    string public constant version = "0.2.0";
 
     // ------------------------------
     // Immutable configuration
@@ -159,6 +159,15 @@ contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
 
     mapping(address => uint256) public paidBonds;
 
+    /// @notice The number of proposals made by each proposer that are yet to be resolved
+    mapping(address => uint256) public unresolvedProposals;
+
+    /// @notice Whether a proposal no longer counts towards its proposer's unresolved proposals
+    mapping(address => bool) public isReleased;
+
+    /// @notice The first child of each proposal to have been resolved
+    mapping(address => address) public resolvedChildOf;
+
     modifier onlyFactoryOwner() {
         OwnableUpgradeable factoryContract = OwnableUpgradeable(address(DISPUTE_GAME_FACTORY));
         require(msg.sender == factoryContract.owner(), "Ownable: caller is not the owner");
@@ -171,6 +180,78 @@ contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
         if (!success) revert BondTransferFailed();
     }
 
+    /// @inheritdoc IKailuaTreasury
+    function markResolved() external {
+        // INVARIANT: Only proposals made through this treasury may be marked
+        address proposer = proposerOf[msg.sender];
+        if (proposer == address(0x0) || msg.sender == address(this)) {
+            revert NotProposed();
+        }
+        // INVARIANT: Only resolved proposals release their proposer's bond
+        if (KailuaTournament(msg.sender).status() != GameStatus.DEFENDER_WINS) {
+            revert GameNotResolved();
+        }
+        // Record the parent's successor
+        address parent = address(KailuaTournament(msg.sender).parentGame());
+        if (resolvedChildOf[parent] == address(0x0)) {
+            resolvedChildOf[parent] = msg.sender;
+        }
+        release(msg.sender, proposer);
+    }
+
+    /// @notice Releases the bond lock of a proposal that can no longer be resolved
+    function releaseProposal(address _proposal) external {
+        // INVARIANT: Only proposals made through this treasury may be released
+        address proposer = proposerOf[_proposal];
+        if (proposer == address(0x0) || _proposal == address(this)) {
+            revert NotProposed();
+        }
+        // INVARIANT: Cannot double-release proposals
+        if (isReleased[_proposal]) {
+            revert AlreadyReleased();
+        }
+        KailuaTournament parent = KailuaTournament(_proposal).parentGame();
+        // A proposal is outlived once a sibling is resolved as its parent's successor
+        address resolvedSibling = resolvedChildOf[address(parent)];
+        bool outlived = resolvedSibling != address(0x0) && resolvedSibling != _proposal;
+        // A proposal is orphaned once its parent is released without being resolved
+        bool orphaned = isReleased[address(parent)] && parent.status() != GameStatus.DEFENDER_WINS;
+        // INVARIANT: Only proposals that can no longer be resolved are released early
+        if (!outlived && !orphaned) {
+            revert GameNotResolved();
+        }
+        release(_proposal, proposer);
+    }
+
+    /// @notice Removes a proposal from its proposer's unresolved proposals
+    function release(address proposal, address proposer) internal {
+        if (isReleased[proposal]) {
+            return;
+        }
+        isReleased[proposal] = true;
+        unresolvedProposals[proposer]--;
+    }
+
+    /// @notice Transfers the caller's bond to the recipient once all of their proposals are resolved
+    function withdrawBond(address recipient) external {
+        // INVARIANT: Eliminated proposers forfeit their bond
+        if (eliminationRound[msg.sender] > 0) {
+            revert AlreadyEliminated();
+        }
+        // INVARIANT: The bond remains locked while any of the proposer's proposals is unresolved
+        if (unresolvedProposals[msg.sender] > 0) {
+            revert GameNotResolved();
+        }
+        // INVARIANT: Only paid in bonds can be withdrawn
+        uint256 amount = paidBonds[msg.sender];
+        if (amount == 0) {
+            revert NoCreditToClaim();
+        }
+        paidBonds[msg.sender] = 0;
+        pay(amount, recipient);
+        emit BondWithdrawn(msg.sender, recipient, amount);
+    }
+
     /// @notice Updates the required bond for new proposals
     function setParticipationBond(uint256 amount) external onlyFactoryOwner {
         participationBond = amount;
@@ -203,5 +284,6 @@ contract KailuaTreasury is KailuaTournament, IKailuaTreasury {
         isProposing = false;
         // Record proposer
         proposerOf[address(gameContract)] = msg.sender;
+        unresolvedProposals[msg.sender]++;
     }
 }
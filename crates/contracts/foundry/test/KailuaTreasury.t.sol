@@ -0,0 +1,137 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.15;
+
+import {Test} from "forge-std/Test.sol";
+import "../src/vendor/FlatOPImportV1.4.0.sol";
+import "../src/vendor/FlatR0ImportV1.2.0.sol";
+import "../src/KailuaGame.sol";
+import "../src/KailuaLib.sol";
+import "../src/KailuaTournament.sol";
+import "../src/KailuaTreasury.sol";
+
+contract KailuaTreasuryTest is Test {
+    GameType internal constant GAME_TYPE = GameType.wrap(1337);
+    uint256 internal constant BOND = 1 wei;
+    uint64 internal constant TIMEOUT = 100;
+
+    DisputeGameFactory internal factory;
+    KailuaTreasury internal treasury;
+
+    address internal alice = makeAddr("alice");
+    address internal bob = makeAddr("bob");
+
+    function setUp() public {
+        vm.warp(1000);
+        vm.deal(alice, 1 ether);
+        vm.deal(bob, 1 ether);
+
+        // The factory is deployed without an owner, so claim it for this test
+        factory = new DisputeGameFactory();
+        vm.prank(address(0x0));
+        factory.transferOwnership(address(this));
+
+        RiscZeroMockVerifier verifier = new RiscZeroMockVerifier(bytes4(0));
+        treasury = new KailuaTreasury(verifier, bytes32(0), bytes32(0), 1, GAME_TYPE, factory);
+        KailuaGame game = new KailuaGame(
+            treasury, verifier, bytes32(0), bytes32(0), 1, GAME_TYPE, factory, 0, 1, 0, Duration.wrap(TIMEOUT)
+        );
+        treasury.setParticipationBond(BOND);
+
+        // Anchor the proposals on a resolved treasury instance
+        factory.setImplementation(GAME_TYPE, IDisputeGame(address(treasury)));
+        IDisputeGame anchor = factory.create(GAME_TYPE, Claim.wrap(bytes32(0)), abi.encodePacked(uint64(0)));
+        anchor.resolve();
+        factory.setImplementation(GAME_TYPE, IDisputeGame(address(game)));
+    }
+
+    function propose(address proposer, uint64 l2BlockNumber, uint64 parentIndex, uint64 duplicationCounter)
+        internal
+        returns (KailuaTournament proposal)
+    {
+        // All proposals publish the same intermediate outputs
+        bytes32[] memory blobHashes = new bytes32[](1);
+        blobHashes[0] = keccak256("blob");
+        vm.blobhashes(blobHashes);
+
+        vm.prank(proposer);
+        proposal = treasury.propose{value: treasury.paidBonds(proposer) == 0 ? BOND : 0}(
+            Claim.wrap(bytes32(uint256(l2BlockNumber))),
+            abi.encodePacked(l2BlockNumber, parentIndex, duplicationCounter)
+        );
+    }
+
+    function test_twinBondsAreWithdrawable() public {
+        // Both proposers submit the same correct proposal
+        KailuaTournament first = propose(alice, 1, 0, 0);
+        KailuaTournament twin = propose(bob, 1, 0, 1);
+        assertEq(treasury.unresolvedProposals(alice), 1);
+        assertEq(treasury.unresolvedProposals(bob), 1);
+
+        // The twin cannot be released before its sibling is resolved
+        vm.expectRevert(GameNotResolved.selector);
+        treasury.releaseProposal(address(twin));
+
+        // Only the first proposal can be resolved
+        vm.warp(block.timestamp + TIMEOUT);
+        first.resolve();
+        vm.expectRevert();
+        twin.resolve();
+        assertEq(treasury.unresolvedProposals(alice), 0);
+
+        // The twin's bond is locked until it is released
+        vm.prank(bob);
+        vm.expectRevert(GameNotResolved.selector);
+        treasury.withdrawBond(bob);
+        treasury.releaseProposal(address(twin));
+        assertEq(treasury.unresolvedProposals(bob), 0);
+        vm.expectRevert(AlreadyReleased.selector);
+        treasury.releaseProposal(address(twin));
+
+        // Both proposers can withdraw their bonds
+        uint256 aliceBalance = alice.balance;
+        vm.prank(alice);
+        treasury.withdrawBond(alice);
+        assertEq(alice.balance, aliceBalance + BOND);
+        uint256 bobBalance = bob.balance;
+        vm.prank(bob);
+        treasury.withdrawBond(bob);
+        assertEq(bob.balance, bobBalance + BOND);
+    }
+
+    function test_orphanedBondsAreWithdrawable() public {
+        KailuaTournament first = propose(alice, 1, 0, 0);
+        KailuaTournament twin = propose(bob, 1, 0, 1);
+        // Extend the twin, which can never be resolved
+        KailuaTournament orphan = propose(bob, 2, uint64(twin.gameIndex()), 0);
+        assertEq(treasury.unresolvedProposals(bob), 2);
+
+        // The orphan cannot be released while its parent may still be resolved
+        vm.warp(block.timestamp + TIMEOUT);
+        vm.expectRevert(GameNotResolved.selector);
+        treasury.releaseProposal(address(orphan));
+
+        first.resolve();
+        treasury.releaseProposal(address(twin));
+        treasury.releaseProposal(address(orphan));
+        assertEq(treasury.unresolvedProposals(bob), 0);
+
+        uint256 bobBalance = bob.balance;
+        vm.prank(bob);
+        treasury.withdrawBond(bob);
+        assertEq(bob.balance, bobBalance + BOND);
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{IDisputeGameFactory, KailuaGame, KailuaTournament, KailuaTreasury};
+use alloy::primitives::{Address, Log, B256, U256};
+use alloy::sol_types::SolEvent;
+
+/// An event emitted by the dispute game factory, the Kailua games or the Kailua treasury
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KailuaEvent {
+    /// A game instance was created by the dispute game factory
+    ProposalCreated {
+        game: Address,
+        game_type: u32,
+        root_claim: B256,
+    },
+    /// A match between two children of a tournament was proven
+    Proven { u: u64, v: u64, status: u8 },
+    /// A child of a tournament was proven valid
+    ValidityProven { child: u64 },
+    /// A game or treasury instance was resolved
+    Resolved { status: u8 },
+    /// The participation bond required by the treasury was updated
+    BondUpdated { amount: U256 },
+    /// A proposer withdrew their bond from the treasury
+    BondWithdrawn {
+        proposer: Address,
+        recipient: Address,
+        amount: U256,
+    },
+}
+
+impl KailuaEvent {
+    /// The topics of all events decoded by [KailuaEvent::decode]
+    pub const SIGNATURES: [B256; 6] = [
+        IDisputeGameFactory::DisputeGameCreated::SIGNATURE_HASH,
+        KailuaTournament::Proven::SIGNATURE_HASH,
+        KailuaTournament::ValidityProven::SIGNATURE_HASH,
+        KailuaGame::Resolved::SIGNATURE_HASH,
+        KailuaTreasury::BondUpdated::SIGNATURE_HASH,
+        KailuaTreasury::BondWithdrawn::SIGNATURE_HASH,
+    ];
+
+    /// Decodes the log, or returns `None` if it is not one of the [KailuaEvent::SIGNATURES]
+    ///
+    /// The emitting contract is not checked, which is left to the caller.
+    pub fn decode(log: &Log) -> alloy::sol_types::Result<Option<Self>> {
+        let Some(topic) = log.topics().first() else {
+            return Ok(None);
+        };
+        let event = match *topic {
+            IDisputeGameFactory::DisputeGameCreated::SIGNATURE_HASH => {
+                let event = IDisputeGameFactory::DisputeGameCreated::decode_log(log, true)?;
+                Self::ProposalCreated {
+                    game: event.disputeProxy,
+                    game_type: event.gameType,
+                    root_claim: event.rootClaim,
+                }
+            }
+            KailuaTournament::Proven::SIGNATURE_HASH => {
+                let event = KailuaTournament::Proven::decode_log(log, true)?;
+                Self::Proven {
+                    u: event.u,
+                    v: event.v,
+                    status: event.status,
+                }
+            }
+            KailuaTournament::ValidityProven::SIGNATURE_HASH => {
+                let event = KailuaTournament::ValidityProven::decode_log(log, true)?;
+                Self::ValidityProven { child: event.child }
+            }
+            KailuaGame::Resolved::SIGNATURE_HASH => {
+                let event = KailuaGame::Resolved::decode_log(log, true)?;
+                Self::Resolved {
+                    status: event.status,
+                }
+            }
+            KailuaTreasury::BondUpdated::SIGNATURE_HASH => {
+                let event = KailuaTreasury::BondUpdated::decode_log(log, true)?;
+                Self::BondUpdated {
+                    amount: event.amount,
+                }
+            }
+            KailuaTreasury::BondWithdrawn::SIGNATURE_HASH => {
+                let event = KailuaTreasury::BondWithdrawn::decode_log(log, true)?;
+                Self::BondWithdrawn {
+                    proposer: event.proposer,
+                    recipient: event.recipient,
+                    amount: event.amount,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+}
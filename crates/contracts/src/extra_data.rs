@@ -0,0 +1,101 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::Bytes;
+
+/// The `extraData` of a KailuaGame proposal, as packed by the dispute game factory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GameExtraData {
+    /// The L2 block number of the proposed output
+    pub l2_block_number: u64,
+    /// The factory index of the parent proposal
+    pub parent_game_index: u64,
+    /// The counter distinguishing duplicates of the same proposal
+    pub duplication_counter: u64,
+}
+
+impl GameExtraData {
+    pub const LENGTH: usize = 0x18;
+
+    pub fn new(l2_block_number: u64, parent_game_index: u64) -> Self {
+        Self {
+            l2_block_number,
+            parent_game_index,
+            duplication_counter: 0,
+        }
+    }
+
+    /// Returns the extra data of the next duplicate of this proposal
+    pub fn next_duplicate(self) -> Self {
+        Self {
+            duplication_counter: self.duplication_counter + 1,
+            ..self
+        }
+    }
+
+    pub fn encode(&self) -> Bytes {
+        [
+            self.l2_block_number.to_be_bytes(),
+            self.parent_game_index.to_be_bytes(),
+            self.duplication_counter.to_be_bytes(),
+        ]
+        .concat()
+        .into()
+    }
+
+    /// Parses the extra data, or returns `None` if it is not of the length the game accepts
+    pub fn decode(extra_data: &[u8]) -> Option<Self> {
+        if extra_data.len() != Self::LENGTH {
+            return None;
+        }
+        Some(Self {
+            l2_block_number: read_u64(extra_data, 0x00),
+            parent_game_index: read_u64(extra_data, 0x08),
+            duplication_counter: read_u64(extra_data, 0x10),
+        })
+    }
+}
+
+/// The `extraData` of a KailuaTreasury instance, as packed by the dispute game factory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TreasuryExtraData {
+    /// The L2 block number of the starting output
+    pub l2_block_number: u64,
+}
+
+impl TreasuryExtraData {
+    pub const LENGTH: usize = 0x08;
+
+    pub fn new(l2_block_number: u64) -> Self {
+        Self { l2_block_number }
+    }
+
+    pub fn encode(&self) -> Bytes {
+        Bytes::copy_from_slice(&self.l2_block_number.to_be_bytes())
+    }
+
+    /// Parses the extra data, or returns `None` if it is not of the length the treasury accepts
+    pub fn decode(extra_data: &[u8]) -> Option<Self> {
+        if extra_data.len() != Self::LENGTH {
+            return None;
+        }
+        Some(Self {
+            l2_block_number: read_u64(extra_data, 0x00),
+        })
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(data[offset..offset + 8].try_into().unwrap())
+}
@@ -14,6 +14,10 @@
 
 #![allow(clippy::too_many_arguments)]
 
+pub mod events;
+pub mod extra_data;
+pub mod revision;
+
 use alloy::sol;
 
 sol!(
@@ -76,6 +80,12 @@ sol!(
     "foundry/out/FlatOPImportV1.4.0.sol/IDisputeGameFactory.json"
 );
 
+sol!(
+    #[sol(rpc)]
+    DisputeGameFactory,
+    "foundry/out/FlatOPImportV1.4.0.sol/DisputeGameFactory.json"
+);
+
 sol!(
     #[sol(rpc)]
     Safe,
@@ -0,0 +1,72 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{KailuaTournament, KailuaTreasury};
+use alloy::sol_types::SolCall;
+use semver::Version;
+
+/// The first contract release that supports every feature known to this build
+pub const FULL_FEATURE_VERSION: Version = Version::new(0, 2, 0);
+
+/// The features supported by a deployed revision of the KailuaGame and KailuaTreasury contracts
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContractRevision {
+    /// The version reported by the KailuaGame implementation
+    pub version: Option<Version>,
+    /// Whether tournaments accept validity proofs through `proveValidity`
+    pub validity_proofs: bool,
+    /// Whether the treasury allows proposers to withdraw their bonds
+    pub bond_withdrawals: bool,
+    /// Whether precondition hashes are prefixed with their encoding version and domain
+    pub versioned_preconditions: bool,
+}
+
+impl ContractRevision {
+    /// Returns the revision of a deployment that reports at least [FULL_FEATURE_VERSION]
+    pub fn from_version(version: Version) -> Option<Self> {
+        (version >= FULL_FEATURE_VERSION).then_some(Self {
+            version: Some(version),
+            validity_proofs: true,
+            bond_withdrawals: true,
+            versioned_preconditions: true,
+        })
+    }
+
+    /// Infers the revision from the function selectors dispatched by the deployed bytecode
+    ///
+    /// Releases reporting version 0.1.0 differ in their supported features, so their bytecode is
+    /// inspected instead. None of them prefix their precondition hashes.
+    pub fn from_bytecode(
+        version: Option<Version>,
+        game_code: &[u8],
+        treasury_code: &[u8],
+    ) -> Option<Self> {
+        if !dispatches(game_code, KailuaTournament::proveCall::SELECTOR) {
+            return None;
+        }
+        Some(Self {
+            version,
+            validity_proofs: dispatches(game_code, KailuaTournament::proveValidityCall::SELECTOR),
+            bond_withdrawals: dispatches(treasury_code, KailuaTreasury::withdrawBondCall::SELECTOR),
+            versioned_preconditions: false,
+        })
+    }
+}
+
+/// Whether the function dispatcher in the bytecode pushes the given selector
+fn dispatches(code: &[u8], selector: [u8; 4]) -> bool {
+    // PUSH4 <selector>
+    code.windows(5)
+        .any(|window| window[0] == 0x63 && window[1..] == selector)
+}
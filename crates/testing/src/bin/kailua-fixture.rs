@@ -0,0 +1,70 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use clap::Parser;
+use kailua_cli::logging::init_tracing;
+use kailua_testing::fixture::{generate, FixtureSpec};
+use kailua_testing::malicious::{simulate, Tampering};
+use kailua_testing::Devnet;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Generates a reproducible set of proposals against a fresh devnet
+#[derive(Parser, Debug, Clone)]
+struct FixtureArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    v: u8,
+
+    /// JSON file describing the proposals to make
+    #[clap(long, env)]
+    spec: PathBuf,
+    /// JSON file to write the proposals and their expected outcomes to
+    #[clap(long, env)]
+    output: PathBuf,
+    /// JSON file to write the outcomes of submitting tampered proofs against the faulty
+    /// proposals to, if the malicious validator simulation should run
+    #[clap(long, env)]
+    malicious_report: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = FixtureArgs::parse();
+    init_tracing(args.v, None)?;
+
+    let spec: FixtureSpec =
+        serde_json::from_slice(&std::fs::read(&args.spec).context("Failed to read fixture spec")?)?;
+    let devnet = Devnet::spawn(spec.devnet_config()).await?;
+    let fixture = generate(&devnet, &spec).await?;
+    std::fs::write(&args.output, serde_json::to_vec_pretty(&fixture)?)
+        .context("Failed to write fixture")?;
+    info!(
+        "Wrote {} proposals to {}.",
+        fixture.proposals.len(),
+        args.output.display()
+    );
+
+    if let Some(malicious_report) = &args.malicious_report {
+        let reports = simulate(&devnet, &fixture, &Tampering::ALL).await?;
+        std::fs::write(malicious_report, serde_json::to_vec_pretty(&reports)?)
+            .context("Failed to write malicious validator report")?;
+        info!(
+            "All {} tampered proofs were rejected. Wrote report to {}.",
+            reports.len(),
+            malicious_report.display()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use clap::Parser;
+use kailua_testing::journal::SealedJournalInput;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Packs a journal described in JSON and produces a mock seal for it
+#[derive(Parser, Debug, Clone)]
+struct JournalArgs {
+    /// JSON file describing the journal (Defaults to stdin)
+    #[clap(long, env)]
+    input: Option<PathBuf>,
+    /// JSON file to write the packed journal, its digest and the seal to (Defaults to stdout)
+    #[clap(long, env)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = JournalArgs::parse();
+
+    let input = match &args.input {
+        Some(path) => std::fs::read(path).context("Failed to read journal input")?,
+        None => {
+            let mut input = vec![];
+            std::io::stdin()
+                .read_to_end(&mut input)
+                .context("Failed to read journal input")?;
+            input
+        }
+    };
+    let input: SealedJournalInput = serde_json::from_slice(&input)?;
+    let output = serde_json::to_string_pretty(&input.encode()?)?;
+
+    match &args.output {
+        Some(path) => std::fs::write(path, output).context("Failed to write encoded journal")?,
+        None => println!("{output}"),
+    }
+    Ok(())
+}
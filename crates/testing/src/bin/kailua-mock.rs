@@ -0,0 +1,120 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::providers::ProviderBuilder;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use kailua_cli::logging::init_tracing;
+use kailua_cli::providers::beacon::BlobProvider;
+use kailua_cli::providers::optimism::OpNodeProvider;
+use kailua_testing::mock::{MockRecording, MockServers};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Records and serves canned op-node and beacon node responses for offline development
+#[derive(Parser, Debug, Clone)]
+struct MockArgs {
+    #[arg(long, short, help = "Verbosity level (0-4)", action = clap::ArgAction::Count)]
+    v: u8,
+
+    #[command(subcommand)]
+    command: MockCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum MockCommand {
+    /// Records the responses of live nodes to a JSON file
+    Record(RecordArgs),
+    /// Serves the responses recorded in a JSON file
+    Serve(ServeArgs),
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct RecordArgs {
+    /// Address of the OP-NODE endpoint to use
+    #[clap(long, env)]
+    op_node_url: String,
+    /// Address of the OP-GETH endpoint to use (eth and debug namespace required).
+    #[clap(long, env)]
+    op_geth_url: String,
+    /// Address of the L1 Beacon API endpoint to use.
+    #[clap(long, env)]
+    beacon_rpc_url: String,
+
+    /// The first l2 block to record
+    #[clap(long, env)]
+    first_block: u64,
+    /// The last l2 block to record
+    #[clap(long, env)]
+    last_block: u64,
+    /// The beacon slots to record the blob sidecars of
+    #[clap(long, env, value_delimiter = ',')]
+    slots: Vec<u64>,
+
+    /// JSON file to write the recording to
+    #[clap(long, env)]
+    output: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ServeArgs {
+    /// JSON file to read the recording from
+    #[clap(long, env)]
+    recording: PathBuf,
+    /// Address to serve the op-node (and op-geth) json-rpc api on
+    #[clap(long, env, default_value = "127.0.0.1:7545")]
+    op_node_addr: SocketAddr,
+    /// Address to serve the beacon node api on
+    #[clap(long, env, default_value = "127.0.0.1:5052")]
+    beacon_addr: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = MockArgs::parse();
+    init_tracing(args.v, None)?;
+
+    match args.command {
+        MockCommand::Record(args) => {
+            let op_node_provider = OpNodeProvider(
+                ProviderBuilder::new().on_http(args.op_node_url.as_str().try_into()?),
+            );
+            let op_geth_provider =
+                ProviderBuilder::new().on_http(args.op_geth_url.as_str().try_into()?);
+            let beacon_provider = BlobProvider::new(&args.beacon_rpc_url, None).await?;
+            let recording = MockRecording::record(
+                &op_node_provider,
+                &op_geth_provider,
+                &beacon_provider,
+                args.first_block..=args.last_block,
+                &args.slots,
+            )
+            .await?;
+            std::fs::write(&args.output, serde_json::to_vec_pretty(&recording)?)
+                .context("Failed to write recording")?;
+            info!("Wrote recording to {}.", args.output.display());
+        }
+        MockCommand::Serve(args) => {
+            let recording: MockRecording = serde_json::from_slice(
+                &std::fs::read(&args.recording).context("Failed to read recording")?,
+            )?;
+            MockServers::bind(recording, args.op_node_addr, args.beacon_addr)
+                .await?
+                .join()
+                .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Devnet, DevnetConfig};
+use alloy::primitives::{keccak256, Address, B256};
+use anyhow::{bail, ensure};
+use kailua_cli::KAILUA_GAME_TYPE;
+use kailua_common::blobs::hash_to_fe;
+use kailua_contracts::extra_data::GameExtraData;
+use kailua_contracts::{IDisputeGameFactory, KailuaTournament};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The anvil account making all honest proposals
+pub const HONEST_ACCOUNT: usize = 1;
+/// The first anvil account making faulty proposals
+pub const FIRST_FAULTY_ACCOUNT: usize = 2;
+/// The number of accounts anvil funds by default
+pub const ANVIL_ACCOUNTS: usize = 10;
+
+/// A proposal to make in a fixture round
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalKind {
+    /// Publishes the honest outputs
+    Honest,
+    /// Publishes a faulty output for the block at the given offset from the parent proposal, in
+    /// the range `1..=proposal_block_count`, and the honest outputs otherwise
+    Faulty { offset: u64 },
+}
+
+/// The proposals a fixture is made of
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureSpec {
+    /// The seed from which all outputs are derived
+    pub seed: B256,
+    /// The proposals made in each round, in order
+    ///
+    /// Every round must contain exactly one honest proposal, which the next round extends.
+    pub rounds: Vec<Vec<ProposalKind>>,
+}
+
+impl FixtureSpec {
+    /// Returns the devnet configuration anchoring the proposal tree at the honest starting output
+    pub fn devnet_config(&self) -> DevnetConfig {
+        let config = DevnetConfig::default();
+        DevnetConfig {
+            starting_output_root: honest_output(self.seed, config.starting_block_number),
+            ..config
+        }
+    }
+
+    fn validate(&self, proposal_block_count: u64) -> anyhow::Result<()> {
+        for (round, proposals) in self.rounds.iter().enumerate() {
+            let honest_count = proposals
+                .iter()
+                .filter(|kind| matches!(kind, ProposalKind::Honest))
+                .count();
+            ensure!(
+                honest_count == 1,
+                "Round {round} has {honest_count} honest proposals instead of one."
+            );
+            for kind in proposals {
+                if let ProposalKind::Faulty { offset } = kind {
+                    ensure!(
+                        (1..=proposal_block_count).contains(offset),
+                        "Round {round} has a fault at offset {offset} outside of 1..={proposal_block_count}."
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The expected tournament outcome of a proposal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpectedOutcome {
+    /// The proposal survives its tournament and resolves
+    Survives,
+    /// The proposal is eliminated by a fault proof for the given block
+    Eliminated { divergence_block_number: u64 },
+}
+
+/// A proposal made while generating a fixture
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureProposal {
+    pub round: usize,
+    pub position: usize,
+    pub kind: ProposalKind,
+    pub proposer: Address,
+    pub factory_index: u64,
+    pub address: Address,
+    pub parent_index: u64,
+    pub l2_block_number: u64,
+    pub output_root: B256,
+    pub duplication_counter: u64,
+    pub expected: ExpectedOutcome,
+}
+
+/// A reproducible set of proposals made against a devnet, and their expected outcomes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub spec: FixtureSpec,
+    pub proposal_block_count: u64,
+    pub treasury: Address,
+    pub proposals: Vec<FixtureProposal>,
+}
+
+/// Returns the honest output root of the given l2 block
+pub fn honest_output(seed: B256, l2_block_number: u64) -> B256 {
+    keccak256([seed.as_slice(), &l2_block_number.to_be_bytes()].concat())
+}
+
+/// Returns the faulty output root published by the given proposal of a round
+pub fn faulty_output(seed: B256, round: usize, position: usize) -> B256 {
+    keccak256(
+        [
+            seed.as_slice(),
+            b"fault",
+            &(round as u64).to_be_bytes(),
+            &(position as u64).to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+/// Returns the outputs published by the given proposal of a round, ending with its root claim
+pub fn proposal_outputs(
+    seed: B256,
+    round: usize,
+    position: usize,
+    kind: ProposalKind,
+    parent_block_number: u64,
+    proposal_block_count: u64,
+) -> Vec<B256> {
+    let mut outputs: Vec<B256> = (1..=proposal_block_count)
+        .map(|offset| honest_output(seed, parent_block_number + offset))
+        .collect();
+    if let ProposalKind::Faulty { offset } = kind {
+        outputs[offset as usize - 1] = faulty_output(seed, round, position);
+    }
+    outputs
+}
+
+impl Fixture {
+    /// Returns the outputs published by the given proposal, ending with its root claim
+    pub fn outputs(&self, proposal: &FixtureProposal) -> Vec<B256> {
+        proposal_outputs(
+            self.spec.seed,
+            proposal.round,
+            proposal.position,
+            proposal.kind,
+            proposal.l2_block_number - self.proposal_block_count,
+            self.proposal_block_count,
+        )
+    }
+}
+
+/// Makes the proposals of the given spec on the devnet and records their expected outcomes
+pub async fn generate(devnet: &Devnet, spec: &FixtureSpec) -> anyhow::Result<Fixture> {
+    let proposal_block_count = devnet.config.proposal_block_count;
+    spec.validate(proposal_block_count)?;
+    let anchor_output = honest_output(spec.seed, devnet.config.starting_block_number);
+    if devnet.config.starting_output_root != anchor_output {
+        bail!(
+            "Devnet is anchored at {} instead of the honest output {anchor_output}.",
+            devnet.config.starting_output_root
+        );
+    }
+
+    let provider = devnet.provider(0);
+    let factory = IDisputeGameFactory::new(devnet.factory, &provider);
+    let mut parent_index: u64 = KailuaTournament::new(devnet.treasury_instance, &provider)
+        .gameIndex()
+        .call()
+        .await?
+        ._0
+        .to();
+    let mut parent_block_number = devnet.config.starting_block_number;
+    let mut faulty_proposals = 0;
+    let mut proposals = vec![];
+
+    for (round, kinds) in spec.rounds.iter().enumerate() {
+        let l2_block_number = parent_block_number + proposal_block_count;
+        let mut next_parent_index = None;
+        for (position, kind) in kinds.iter().enumerate() {
+            // Derive the published outputs
+            let mut outputs = proposal_outputs(
+                spec.seed,
+                round,
+                position,
+                *kind,
+                parent_block_number,
+                proposal_block_count,
+            );
+            let (account, expected) = match kind {
+                ProposalKind::Honest => (HONEST_ACCOUNT, ExpectedOutcome::Survives),
+                ProposalKind::Faulty { offset } => {
+                    let honest = honest_output(spec.seed, parent_block_number + offset);
+                    ensure!(
+                        hash_to_fe(outputs[*offset as usize - 1]) != hash_to_fe(honest),
+                        "Faulty output of proposal {position} in round {round} is not distinguishable."
+                    );
+                    let account = FIRST_FAULTY_ACCOUNT
+                        + faulty_proposals % (ANVIL_ACCOUNTS - FIRST_FAULTY_ACCOUNT);
+                    faulty_proposals += 1;
+                    let expected = ExpectedOutcome::Eliminated {
+                        divergence_block_number: parent_block_number + offset,
+                    };
+                    (account, expected)
+                }
+            };
+            let output_root = outputs.pop().unwrap();
+            let io_field_elements: Vec<_> = outputs.into_iter().map(hash_to_fe).collect();
+
+            // Find the first unused duplication counter
+            let mut extra_data = GameExtraData::new(l2_block_number, parent_index);
+            while !factory
+                .games(KAILUA_GAME_TYPE, output_root, extra_data.encode())
+                .call()
+                .await?
+                .proxy_
+                .is_zero()
+            {
+                extra_data = extra_data.next_duplicate();
+            }
+
+            let factory_index: u64 = factory.gameCount().call().await?.gameCount_.to();
+            let address = devnet
+                .propose(account, output_root, extra_data, &io_field_elements)
+                .await?;
+            info!("Round {round} proposal {position} ({kind:?}) created at index {factory_index}.");
+            if matches!(kind, ProposalKind::Honest) {
+                next_parent_index = Some(factory_index);
+            }
+            proposals.push(FixtureProposal {
+                round,
+                position,
+                kind: *kind,
+                proposer: devnet.signer(account).address(),
+                factory_index,
+                address,
+                parent_index,
+                l2_block_number,
+                output_root,
+                duplication_counter: extra_data.duplication_counter,
+                expected,
+            });
+        }
+        parent_index = next_parent_index.unwrap();
+        parent_block_number = l2_block_number;
+    }
+
+    Ok(Fixture {
+        spec: spec.clone(),
+        proposal_block_count,
+        treasury: devnet.treasury,
+        proposals,
+    })
+}
@@ -0,0 +1,159 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Packed journals and seals produced from human-readable inputs, for checking that contract-side
+//! journal construction matches [ProofJournal] byte for byte.
+
+use crate::seal::mock_seal;
+use alloy::primitives::{Bytes, B256};
+use anyhow::{ensure, Context};
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_common::journal::ProofJournal;
+use kailua_common::precondition::{
+    precondition_hash, validity_precondition_hash, PRECONDITION_VERSION,
+};
+use risc0_zkvm::sha::{Impl as SHA2, Sha256};
+use serde::{Deserialize, Serialize};
+
+/// The commitment a journal makes in place of the precondition output
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreconditionInput {
+    /// No precondition, committed to as the zero hash
+    #[default]
+    None,
+    /// A precomputed precondition hash
+    Hash(B256),
+    /// Blob equivalence between a contender and a proposal up to their divergence point
+    Fault {
+        contender_blob_hash: B256,
+        proposal_blob_hash: B256,
+    },
+    /// Inclusion of all intermediate outputs of a proposal in its published blobs
+    Validity {
+        proposal_l2_head_number: u64,
+        proposal_output_count: u64,
+        blob_hashes: Vec<B256>,
+    },
+}
+
+impl PreconditionInput {
+    /// Returns the precondition hash committed to by the journal
+    pub fn hash(&self) -> B256 {
+        match self {
+            Self::None => B256::ZERO,
+            Self::Hash(hash) => *hash,
+            Self::Fault {
+                contender_blob_hash,
+                proposal_blob_hash,
+            } => precondition_hash(
+                contender_blob_hash,
+                proposal_blob_hash,
+                PRECONDITION_VERSION,
+            ),
+            Self::Validity {
+                proposal_l2_head_number,
+                proposal_output_count,
+                blob_hashes,
+            } => validity_precondition_hash(
+                *proposal_l2_head_number,
+                *proposal_output_count,
+                blob_hashes,
+                PRECONDITION_VERSION,
+            ),
+        }
+    }
+}
+
+/// The fields of a journal, with its precondition given by the data it commits to
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalInput {
+    #[serde(default)]
+    pub precondition: PreconditionInput,
+    pub l1_head: B256,
+    pub agreed_l2_output_root: B256,
+    pub claimed_l2_output_root: B256,
+    pub claimed_l2_block_number: u64,
+    pub config_hash: B256,
+    #[serde(default)]
+    pub dependencies: Option<B256>,
+}
+
+impl JournalInput {
+    pub fn journal(&self) -> ProofJournal {
+        ProofJournal {
+            precondition_output: self.precondition.hash(),
+            l1_head: self.l1_head,
+            agreed_l2_output_root: self.agreed_l2_output_root,
+            claimed_l2_output_root: self.claimed_l2_output_root,
+            claimed_l2_block_number: self.claimed_l2_block_number,
+            config_hash: self.config_hash,
+            dependencies: self.dependencies,
+        }
+    }
+}
+
+/// A journal along with the image id to produce its seal for
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedJournalInput {
+    /// The image id the seal is produced for (Defaults to that of the fault proof program)
+    #[serde(default)]
+    pub image_id: Option<B256>,
+    #[serde(flatten)]
+    pub journal: JournalInput,
+}
+
+/// A packed journal along with a seal the `RiscZeroMockVerifier` accepts for it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SealedJournal {
+    pub image_id: B256,
+    pub journal: ProofJournal,
+    pub version: u8,
+    pub packed: Bytes,
+    /// The sha256 digest of the packed journal, as passed to the verifier
+    pub digest: B256,
+    pub seal: Bytes,
+}
+
+fn sha256(data: &[u8]) -> B256 {
+    B256::from_slice(SHA2::hash_bytes(data).as_bytes())
+}
+
+impl SealedJournalInput {
+    /// Packs the journal, checking that it decodes back to the same encoding
+    pub fn encode(&self) -> anyhow::Result<SealedJournal> {
+        let image_id = self
+            .image_id
+            .unwrap_or_else(|| B256::from(bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID)));
+
+        let journal = self.journal.journal();
+        let version = journal.version();
+        let packed = journal.encode_packed();
+        let decoded = ProofJournal::decode_packed_versioned(&packed, version)
+            .context("decode_packed_versioned")?;
+        ensure!(
+            decoded.encode_packed() == packed,
+            "Packed journal does not decode to the same encoding."
+        );
+
+        Ok(SealedJournal {
+            image_id,
+            journal,
+            version,
+            digest: sha256(&packed),
+            seal: mock_seal(image_id, packed.clone())?,
+            packed: packed.into(),
+        })
+    }
+}
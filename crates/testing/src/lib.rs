@@ -0,0 +1,369 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod fixture;
+pub mod journal;
+pub mod malicious;
+pub mod mock;
+pub mod seal;
+
+use alloy::network::{Ethereum, EthereumWallet};
+use alloy::node_bindings::{Anvil, AnvilInstance};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::ext::AnvilApi;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use alloy::transports::http::{Client, Http};
+use anyhow::{bail, Context};
+use kailua_build::KAILUA_FPVM_ID;
+use kailua_cli::providers::beacon::blob_sidecar;
+use kailua_cli::KAILUA_GAME_TYPE;
+use kailua_common::blobs::encode_intermediate_outputs;
+use kailua_contracts::extra_data::{GameExtraData, TreasuryExtraData};
+use kailua_contracts::*;
+use tracing::info;
+
+/// The storage slot of `OwnableUpgradeable._owner` in the `DisputeGameFactory`
+const FACTORY_OWNER_SLOT: u64 = 51;
+
+/// The parameters of the Kailua deployment on a test chain
+#[derive(Clone, Debug)]
+pub struct DevnetConfig {
+    /// The image id of the fault proof program expected by the game contracts
+    pub image_id: B256,
+    /// The rollup configuration hash expected by the game contracts
+    pub config_hash: B256,
+    /// The number of blocks that a proposal must cover
+    pub proposal_block_count: u64,
+    /// The l2 genesis timestamp
+    pub genesis_timestamp: u64,
+    /// The l2 block time
+    pub l2_block_time: u64,
+    /// The time gap before a proposal can be made
+    pub proposal_time_gap: u64,
+    /// The timeout after which a counter-proposal can not be made
+    pub challenge_timeout: u64,
+    /// The collateral (wei) that must be locked up by a sequencer to propose
+    pub participation_bond: U256,
+    /// The l2 block number of the output the treasury instance is anchored at
+    pub starting_block_number: u64,
+    /// The output root the treasury instance is anchored at
+    pub starting_output_root: B256,
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        Self {
+            image_id: B256::from(bytemuck::cast::<[u32; 8], [u8; 32]>(KAILUA_FPVM_ID)),
+            config_hash: B256::ZERO,
+            proposal_block_count: 60,
+            genesis_timestamp: 0,
+            l2_block_time: 2,
+            proposal_time_gap: 0,
+            challenge_timeout: 300,
+            participation_bond: U256::from(1),
+            starting_block_number: 0,
+            starting_output_root: B256::ZERO,
+        }
+    }
+}
+
+/// An anvil instance running the Kailua contracts against the `RiscZeroMockVerifier`
+///
+/// The first anvil account owns the dispute game factory and deployed the contracts, while the
+/// remaining accounts are available to act as proposers and validators. Dropping the devnet kills
+/// the anvil process.
+pub struct Devnet {
+    pub config: DevnetConfig,
+    pub anvil: AnvilInstance,
+    pub factory: Address,
+    pub verifier: Address,
+    /// The treasury through which proposals are made
+    pub treasury: Address,
+    /// The resolved treasury instance at the root of the proposal tree
+    pub treasury_instance: Address,
+    pub game_implementation: Address,
+}
+
+impl Devnet {
+    /// Spawns anvil and deploys the Kailua contracts using the given configuration
+    pub async fn spawn(config: DevnetConfig) -> anyhow::Result<Self> {
+        let anvil = Anvil::new()
+            .args(["--hardfork", "cancun"])
+            .try_spawn()
+            .context("Failed to spawn anvil")?;
+        Self::deploy(anvil, config).await
+    }
+
+    /// Deploys the Kailua contracts to an already running anvil instance
+    pub async fn deploy(anvil: AnvilInstance, config: DevnetConfig) -> anyhow::Result<Self> {
+        let owner = anvil.addresses()[0];
+        let owner_provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(PrivateKeySigner::from(
+                anvil.keys()[0].clone(),
+            )))
+            .on_http(anvil.endpoint_url());
+
+        // Deploy a factory and take ownership of it, as its constructor disables initialization
+        info!("Deploying DisputeGameFactory contract.");
+        let factory = DisputeGameFactory::deploy(&owner_provider)
+            .await
+            .context("DisputeGameFactory contract deployment error")?;
+        owner_provider
+            .anvil_set_storage_at(
+                *factory.address(),
+                U256::from(FACTORY_OWNER_SLOT),
+                owner.into_word(),
+            )
+            .await
+            .context("anvil_set_storage_at")?;
+        let factory_owner = OwnableUpgradeable::new(*factory.address(), &owner_provider)
+            .owner()
+            .call()
+            .await?
+            ._0;
+        if factory_owner != owner {
+            bail!("Failed to assign DisputeGameFactory ownership to {owner} ({factory_owner}).");
+        }
+
+        // Deploy mock verifier
+        info!("Deploying RiscZeroMockVerifier contract.");
+        let verifier = RiscZeroMockVerifier::deploy(&owner_provider, [0u8; 4].into())
+            .await
+            .context("RiscZeroMockVerifier contract deployment error")?;
+
+        // Deploy KailuaTreasury contract
+        info!("Deploying KailuaTreasury contract.");
+        let treasury_implementation = KailuaTreasury::deploy(
+            &owner_provider,
+            *verifier.address(),
+            config.image_id,
+            config.config_hash,
+            U256::from(config.proposal_block_count),
+            KAILUA_GAME_TYPE,
+            *factory.address(),
+        )
+        .await
+        .context("KailuaTreasury implementation contract deployment error")?;
+
+        // Deploy KailuaGame contract
+        info!("Deploying KailuaGame contract.");
+        let game_implementation = KailuaGame::deploy(
+            &owner_provider,
+            *treasury_implementation.address(),
+            *verifier.address(),
+            config.image_id,
+            config.config_hash,
+            U256::from(config.proposal_block_count),
+            KAILUA_GAME_TYPE,
+            *factory.address(),
+            U256::from(config.genesis_timestamp),
+            U256::from(config.l2_block_time),
+            U256::from(config.proposal_time_gap),
+            config.challenge_timeout,
+        )
+        .await
+        .context("KailuaGame contract deployment error")?;
+
+        // Configure bonds
+        factory
+            .setInitBond(KAILUA_GAME_TYPE, U256::ZERO)
+            .send()
+            .await
+            .context("setInitBond (send)")?
+            .get_receipt()
+            .await
+            .context("setInitBond (get_receipt)")?;
+        treasury_implementation
+            .setParticipationBond(config.participation_bond)
+            .send()
+            .await
+            .context("setParticipationBond (send)")?
+            .get_receipt()
+            .await
+            .context("setParticipationBond (get_receipt)")?;
+
+        // Create and resolve the treasury instance anchoring the proposal tree
+        info!("Creating KailuaTreasury instance.");
+        factory
+            .setImplementation(KAILUA_GAME_TYPE, *treasury_implementation.address())
+            .send()
+            .await
+            .context("setImplementation KailuaTreasury (send)")?
+            .get_receipt()
+            .await
+            .context("setImplementation KailuaTreasury (get_receipt)")?;
+        let extra_data = TreasuryExtraData::new(config.starting_block_number).encode();
+        factory
+            .create(
+                KAILUA_GAME_TYPE,
+                config.starting_output_root,
+                extra_data.clone(),
+            )
+            .send()
+            .await
+            .context("create KailuaTreasury (send)")?
+            .get_receipt()
+            .await
+            .context("create KailuaTreasury (get_receipt)")?;
+        let treasury_instance = factory
+            .games(KAILUA_GAME_TYPE, config.starting_output_root, extra_data)
+            .call()
+            .await?
+            .proxy_;
+        KailuaTreasury::new(treasury_instance, &owner_provider)
+            .resolve()
+            .send()
+            .await
+            .context("resolve KailuaTreasury (send)")?
+            .get_receipt()
+            .await
+            .context("resolve KailuaTreasury (get_receipt)")?;
+
+        // Accept proposals through KailuaGame
+        factory
+            .setImplementation(KAILUA_GAME_TYPE, *game_implementation.address())
+            .send()
+            .await
+            .context("setImplementation KailuaGame (send)")?
+            .get_receipt()
+            .await
+            .context("setImplementation KailuaGame (get_receipt)")?;
+
+        Ok(Self {
+            config,
+            factory: *factory.address(),
+            verifier: *verifier.address(),
+            treasury: *treasury_implementation.address(),
+            treasury_instance,
+            game_implementation: *game_implementation.address(),
+            anvil,
+        })
+    }
+
+    /// The rpc endpoint of the anvil instance
+    pub fn endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+
+    /// The private key of the anvil account with the given index, hex encoded
+    pub fn secret_key(&self, account: usize) -> String {
+        alloy::hex::encode_prefixed(self.signer(account).to_bytes())
+    }
+
+    /// The signer of the anvil account with the given index
+    pub fn signer(&self, account: usize) -> PrivateKeySigner {
+        PrivateKeySigner::from(self.anvil.keys()[account].clone())
+    }
+
+    /// A provider signing transactions with the anvil account with the given index
+    pub fn provider(&self, account: usize) -> impl Provider<Http<Client>, Ethereum> + Clone {
+        ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(self.signer(account)))
+            .on_http(self.anvil.endpoint_url())
+    }
+
+    /// Returns the address of the proposal at the given index in the dispute game factory
+    pub async fn game_at_index(&self, index: u64) -> anyhow::Result<Address> {
+        let factory = IDisputeGameFactory::new(self.factory, self.provider(0));
+        Ok(factory.gameAtIndex(U256::from(index)).call().await?.proxy_)
+    }
+
+    /// Publishes a proposal through the treasury from the given account, returning its address
+    ///
+    /// The intermediate output field elements are published in the proposal's blobs, and the
+    /// participation bond is topped up as needed.
+    pub async fn propose(
+        &self,
+        account: usize,
+        output_root: B256,
+        extra_data: GameExtraData,
+        io_field_elements: &[B256],
+    ) -> anyhow::Result<Address> {
+        let provider = self.provider(account);
+        let proposer = self.anvil.addresses()[account];
+        let treasury = KailuaTreasury::new(self.treasury, &provider);
+        let paid_bond = treasury.paidBonds(proposer).call().await?._0;
+        let owed_bond = self.config.participation_bond.saturating_sub(paid_bond);
+
+        let mut transaction = treasury
+            .propose(output_root, extra_data.encode())
+            .value(owed_bond);
+        if !io_field_elements.is_empty() {
+            let sidecar = blob_sidecar(encode_intermediate_outputs(io_field_elements))?;
+            transaction = transaction.sidecar(sidecar);
+        }
+        transaction
+            .send()
+            .await
+            .context("propose (send)")?
+            .get_receipt()
+            .await
+            .context("propose (get_receipt)")?;
+
+        let factory = IDisputeGameFactory::new(self.factory, &provider);
+        let game = factory
+            .games(KAILUA_GAME_TYPE, output_root, extra_data.encode())
+            .call()
+            .await?
+            .proxy_;
+        if game.is_zero() {
+            bail!("Proposal of {output_root} was not created.");
+        }
+        Ok(game)
+    }
+
+    /// Resolves the given proposal from the given account
+    pub async fn resolve(&self, account: usize, game: Address) -> anyhow::Result<()> {
+        KailuaTournament::new(game, self.provider(account))
+            .resolve()
+            .send()
+            .await
+            .context("resolve (send)")?
+            .get_receipt()
+            .await
+            .context("resolve (get_receipt)")?;
+        Ok(())
+    }
+
+    /// Returns the status of the given proposal
+    pub async fn status(&self, game: Address) -> anyhow::Result<u8> {
+        Ok(KailuaTournament::new(game, self.provider(0))
+            .status()
+            .call()
+            .await?
+            ._0)
+    }
+
+    /// Advances the chain clock by the given number of seconds and mines a block
+    pub async fn advance_time(&self, seconds: u64) -> anyhow::Result<()> {
+        let provider = self.provider(0);
+        provider
+            .anvil_increase_time(U256::from(seconds))
+            .await
+            .context("anvil_increase_time")?;
+        provider
+            .anvil_mine(Some(U256::from(1)), None)
+            .await
+            .context("anvil_mine")?;
+        Ok(())
+    }
+
+    /// Produces a seal accepted by the mock verifier for the given journal
+    pub fn mock_seal(&self, journal: impl Into<Vec<u8>>) -> anyhow::Result<Bytes> {
+        seal::mock_seal(self.config.image_id, journal)
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::fixture::{Fixture, FixtureProposal, ProposalKind};
+use crate::seal::mock_seal;
+use crate::Devnet;
+use alloy::eips::eip4844::{kzg_to_versioned_hash, FIELD_ELEMENTS_PER_BLOB};
+use alloy::primitives::{Bytes, B256};
+use anyhow::{bail, Context};
+use kailua_cli::providers::beacon::{blob_fe_proof, blob_sidecar, verify_blob_fe_proof};
+use kailua_common::blobs::{encode_intermediate_outputs, hash_to_fe};
+use kailua_common::journal::ProofJournal;
+use kailua_common::precondition::{precondition_hash, PRECONDITION_VERSION};
+use kailua_contracts::KailuaTournament;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// The anvil account submitting tampered proofs
+pub const MALICIOUS_ACCOUNT: usize = 9;
+
+/// The ways in which a malicious validator tampers with a fault proof submission
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tampering {
+    /// Submits a seal proving a journal with a different computed output
+    MismatchedSeal,
+    /// Submits a seal proving the journal under a different image id
+    WrongImageId,
+    /// Corrupts the kzg proof of the divergent output
+    CorruptKzgProof,
+    /// Substitutes the blob commitment of one proposal with that of the other
+    ForeignCommitment,
+    /// Claims a divergent output other than the one published
+    WrongProposedOutput,
+    /// Claims that both proposals published the same divergent output
+    NoConflict,
+    /// Claims a different agreed output, with a seal matching the claim
+    WrongAcceptedOutput,
+}
+
+impl Tampering {
+    pub const ALL: [Tampering; 7] = [
+        Tampering::MismatchedSeal,
+        Tampering::WrongImageId,
+        Tampering::CorruptKzgProof,
+        Tampering::ForeignCommitment,
+        Tampering::WrongProposedOutput,
+        Tampering::NoConflict,
+        Tampering::WrongAcceptedOutput,
+    ];
+
+    /// Whether the tampering only applies when the divergent output is published in a blob
+    pub fn requires_blob_output(&self) -> bool {
+        matches!(
+            self,
+            Tampering::CorruptKzgProof | Tampering::ForeignCommitment
+        )
+    }
+
+    /// Whether the kzg checks performed by honest validators detect the tampering
+    pub fn is_detected_locally(&self) -> bool {
+        matches!(
+            self,
+            Tampering::CorruptKzgProof | Tampering::ForeignCommitment | Tampering::NoConflict
+        )
+    }
+}
+
+/// The outcome of submitting a tampered fault proof
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TamperingReport {
+    pub tampering: Tampering,
+    pub contender_index: u64,
+    pub proposal_index: u64,
+    pub rejected_onchain: bool,
+    pub rejected_locally: bool,
+}
+
+/// The arguments of a `KailuaTournament.prove` call
+#[derive(Clone, Debug)]
+pub struct ProveArgs {
+    pub uvo: [u64; 3],
+    pub encoded_seal: Bytes,
+    pub accepted_output: B256,
+    pub proposed_output: [B256; 2],
+    pub computed_output: B256,
+    pub blob_commitments: [Vec<Bytes>; 2],
+    pub kzg_proofs: [Vec<Bytes>; 2],
+    /// The journal proven by the seal
+    pub journal: ProofJournal,
+    /// The blob hashes published by the two proposals
+    pub blob_hashes: [Vec<B256>; 2],
+    /// The number of intermediate outputs published in the blobs of each proposal
+    pub io_count: u64,
+}
+
+impl ProveArgs {
+    /// Builds a valid fault proof submission for the match between the two proposals
+    pub async fn new(
+        devnet: &Devnet,
+        fixture: &Fixture,
+        contender: &FixtureProposal,
+        proposal: &FixtureProposal,
+    ) -> anyhow::Result<Self> {
+        let provider = devnet.provider(0);
+        let parent = devnet.game_at_index(contender.parent_index).await?;
+        let parent_contract = KailuaTournament::new(parent, &provider);
+        let parent_output = parent_contract.rootClaim().call().await?.rootClaim_;
+        let parent_block_number = contender.l2_block_number - fixture.proposal_block_count;
+
+        // Locate the first divergent output
+        let outputs = [fixture.outputs(contender), fixture.outputs(proposal)];
+        let Some(divergence) = (0..outputs[0].len())
+            .find(|i| hash_to_fe(outputs[0][*i]) != hash_to_fe(outputs[1][*i]))
+        else {
+            bail!("Proposals do not diverge.");
+        };
+        let computed_output = fixture.outputs(&FixtureProposal {
+            kind: ProposalKind::Honest,
+            ..contender.clone()
+        })[divergence];
+        let is_root_divergence = divergence == outputs[0].len() - 1;
+
+        // Reconstruct the published blobs
+        let mut blob_hashes = [vec![], vec![]];
+        let mut blob_commitments = [vec![], vec![]];
+        let mut kzg_proofs = [vec![], vec![]];
+        for (i, outputs) in outputs.iter().enumerate() {
+            let io_field_elements: Vec<_> = outputs[..outputs.len() - 1]
+                .iter()
+                .copied()
+                .map(hash_to_fe)
+                .collect();
+            let blobs = encode_intermediate_outputs(&io_field_elements);
+            let sidecar = blob_sidecar(blobs.clone())?;
+            blob_hashes[i] = sidecar.versioned_hashes().collect();
+            let mut push_proof = |position: usize| -> anyhow::Result<()> {
+                let blob_index = position / FIELD_ELEMENTS_PER_BLOB as usize;
+                let (proof, _) = blob_fe_proof(
+                    &blobs[blob_index],
+                    position % FIELD_ELEMENTS_PER_BLOB as usize,
+                )?;
+                blob_commitments[i].push(Bytes::from(sidecar.commitments[blob_index].to_vec()));
+                kzg_proofs[i].push(Bytes::from(proof.to_vec()));
+                Ok(())
+            };
+            if divergence > 0 {
+                push_proof(divergence - 1)?;
+            }
+            if !is_root_divergence {
+                push_proof(divergence)?;
+            }
+        }
+
+        // Prove the journal of the match
+        let accepted_output = if divergence == 0 {
+            parent_output
+        } else {
+            outputs[0][divergence - 1]
+        };
+        let position_in_blob = divergence as u64 % FIELD_ELEMENTS_PER_BLOB;
+        let precondition_output = if divergence > 0 && position_in_blob != 0 && !is_root_divergence
+        {
+            let blob_index = divergence / FIELD_ELEMENTS_PER_BLOB as usize;
+            precondition_hash(
+                &blob_hashes[0][blob_index],
+                &blob_hashes[1][blob_index],
+                PRECONDITION_VERSION,
+            )
+        } else {
+            B256::ZERO
+        };
+        let l1_head = KailuaTournament::new(proposal.address, &provider)
+            .l1Head()
+            .call()
+            .await?
+            .l1Head_;
+        let journal = ProofJournal {
+            precondition_output,
+            l1_head,
+            agreed_l2_output_root: accepted_output,
+            claimed_l2_output_root: computed_output,
+            claimed_l2_block_number: parent_block_number + divergence as u64 + 1,
+            config_hash: devnet.config.config_hash,
+            dependencies: None,
+        };
+        let encoded_seal = devnet.mock_seal(journal.encode_packed())?;
+
+        // Locate the proposals among the children of their parent
+        let siblings: Vec<_> = fixture
+            .proposals
+            .iter()
+            .filter(|p| p.parent_index == contender.parent_index)
+            .map(|p| p.factory_index)
+            .collect();
+        let child_index = |index: u64| siblings.iter().position(|i| *i == index).unwrap() as u64;
+
+        Ok(Self {
+            uvo: [
+                child_index(contender.factory_index),
+                child_index(proposal.factory_index),
+                divergence as u64,
+            ],
+            encoded_seal,
+            accepted_output,
+            proposed_output: [outputs[0][divergence], outputs[1][divergence]],
+            computed_output,
+            blob_commitments,
+            kzg_proofs,
+            journal,
+            blob_hashes,
+            io_count: outputs[0].len() as u64 - 1,
+        })
+    }
+
+    /// Whether the divergent output is published in the proposal blobs instead of as root claim
+    pub fn has_blob_output(&self) -> bool {
+        self.uvo[2] < self.io_count
+    }
+
+    /// Applies the tampering to a copy of this submission
+    pub fn tamper(&self, devnet: &Devnet, tampering: Tampering) -> anyhow::Result<Self> {
+        let mut args = self.clone();
+        match tampering {
+            Tampering::MismatchedSeal => {
+                let journal = ProofJournal {
+                    claimed_l2_output_root: B256::repeat_byte(0xcc),
+                    ..args.journal
+                };
+                args.encoded_seal = devnet.mock_seal(journal.encode_packed())?;
+            }
+            Tampering::WrongImageId => {
+                let image_id = B256::from(devnet.config.image_id.0.map(|b| !b));
+                args.encoded_seal = mock_seal(image_id, args.journal.encode_packed())?;
+            }
+            Tampering::CorruptKzgProof => {
+                let mut proof = args.kzg_proofs[1].pop().unwrap().to_vec();
+                proof[47] ^= 0x01;
+                args.kzg_proofs[1].push(proof.into());
+            }
+            Tampering::ForeignCommitment => {
+                let commitment = args.blob_commitments[0].last().unwrap().clone();
+                *args.blob_commitments[1].last_mut().unwrap() = commitment;
+                let proof = args.kzg_proofs[0].last().unwrap().clone();
+                *args.kzg_proofs[1].last_mut().unwrap() = proof;
+            }
+            Tampering::WrongProposedOutput => {
+                args.proposed_output[1] = B256::repeat_byte(0xbb);
+            }
+            Tampering::NoConflict => {
+                args.proposed_output[1] = args.proposed_output[0];
+            }
+            Tampering::WrongAcceptedOutput => {
+                args.accepted_output = B256::repeat_byte(0xaa);
+                args.journal.agreed_l2_output_root = args.accepted_output;
+                args.encoded_seal = devnet.mock_seal(args.journal.encode_packed())?;
+            }
+        }
+        Ok(args)
+    }
+
+    /// Whether the kzg checks performed by honest validators accept the published outputs
+    pub fn passes_local_checks(&self) -> bool {
+        if self.proposed_output[0] == self.proposed_output[1] {
+            return false;
+        }
+        let position = self.uvo[2];
+        for (i, proposed_output) in self.proposed_output.iter().enumerate() {
+            let mut claims = vec![];
+            if position > 0 {
+                claims.push((position - 1, self.accepted_output));
+            }
+            if self.has_blob_output() {
+                claims.push((position, *proposed_output));
+            }
+            for ((position, output), (commitment, proof)) in claims.into_iter().zip(
+                self.blob_commitments[i]
+                    .iter()
+                    .zip(self.kzg_proofs[i].iter()),
+            ) {
+                let blob_index = (position / FIELD_ELEMENTS_PER_BLOB) as usize;
+                if kzg_to_versioned_hash(commitment) != self.blob_hashes[i][blob_index] {
+                    return false;
+                }
+                let index = (position % FIELD_ELEMENTS_PER_BLOB) as usize;
+                // malformed commitments or proofs fail verification
+                if !verify_blob_fe_proof(commitment, index, output, proof).unwrap_or(false) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Submits the proof to the parent tournament, returning whether it was accepted
+    pub async fn submit(&self, devnet: &Devnet, account: usize, parent_index: u64) -> bool {
+        let parent = match devnet.game_at_index(parent_index).await {
+            Ok(parent) => parent,
+            Err(e) => {
+                warn!("Failed to locate tournament at index {parent_index}: {e:?}");
+                return false;
+            }
+        };
+        let provider = devnet.provider(account);
+        let tournament = KailuaTournament::new(parent, &provider);
+        let call = tournament.prove(
+            self.uvo,
+            self.encoded_seal.clone(),
+            self.accepted_output,
+            self.proposed_output,
+            self.computed_output,
+            self.blob_commitments.clone(),
+            self.kzg_proofs.clone(),
+        );
+        match call.send().await.context("prove (send)") {
+            Ok(pending) => match pending.get_receipt().await.context("prove (get_receipt)") {
+                Ok(receipt) => receipt.status(),
+                Err(e) => {
+                    info!("Proof rejected: {e:?}");
+                    false
+                }
+            },
+            Err(e) => {
+                info!("Proof rejected: {e:?}");
+                false
+            }
+        }
+    }
+}
+
+/// Submits tampered fault proofs against every faulty proposal of the fixture
+///
+/// Every tampered submission must be rejected by the tournament contract, and those detectable by
+/// the kzg checks of honest validators must also be rejected locally. Finally, the untampered
+/// proof is submitted to confirm that the rejections were caused by the tampering alone.
+pub async fn simulate(
+    devnet: &Devnet,
+    fixture: &Fixture,
+    tamperings: &[Tampering],
+) -> anyhow::Result<Vec<TamperingReport>> {
+    let mut reports = vec![];
+    for proposal in &fixture.proposals {
+        if !matches!(proposal.kind, ProposalKind::Faulty { .. }) {
+            continue;
+        }
+        let honest = fixture
+            .proposals
+            .iter()
+            .find(|p| p.round == proposal.round && p.kind == ProposalKind::Honest)
+            .context("Round has no honest proposal")?;
+        let (contender, opponent) = if honest.factory_index < proposal.factory_index {
+            (honest, proposal)
+        } else {
+            (proposal, honest)
+        };
+        let args = ProveArgs::new(devnet, fixture, contender, opponent).await?;
+
+        for tampering in tamperings {
+            if tampering.requires_blob_output() && !args.has_blob_output() {
+                continue;
+            }
+            let tampered = args.tamper(devnet, *tampering)?;
+            let rejected_locally = !tampered.passes_local_checks();
+            let rejected_onchain = !tampered
+                .submit(devnet, MALICIOUS_ACCOUNT, contender.parent_index)
+                .await;
+            let report = TamperingReport {
+                tampering: *tampering,
+                contender_index: contender.factory_index,
+                proposal_index: opponent.factory_index,
+                rejected_onchain,
+                rejected_locally,
+            };
+            if !rejected_onchain {
+                bail!("Tampered proof was accepted: {report:?}");
+            }
+            if tampering.is_detected_locally() && !rejected_locally {
+                bail!("Tampered proof passed local checks: {report:?}");
+            }
+            reports.push(report);
+        }
+
+        if !args.passes_local_checks() {
+            bail!(
+                "Untampered proof between {} and {} fails local checks.",
+                contender.factory_index,
+                opponent.factory_index
+            );
+        }
+        if !args
+            .submit(devnet, MALICIOUS_ACCOUNT, contender.parent_index)
+            .await
+        {
+            bail!(
+                "Untampered proof between {} and {} was rejected.",
+                contender.factory_index,
+                opponent.factory_index
+            );
+        }
+    }
+    Ok(reports)
+}
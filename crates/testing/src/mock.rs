@@ -0,0 +1,276 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::providers::{Provider, ReqwestProvider};
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use kailua_cli::providers::beacon::BlobProvider;
+use kailua_cli::providers::optimism::OpNodeProvider;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+/// Canned op-node, op-geth and beacon node responses
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MockRecording {
+    /// The `optimism_syncStatus` response
+    pub sync_status: Option<Value>,
+    /// The `optimism_rollupConfig` response
+    pub rollup_config: Option<Value>,
+    /// The `optimism_outputAtBlock` responses by l2 block number
+    pub outputs: BTreeMap<u64, Value>,
+    /// The `eth_getBlockByNumber` responses by l2 block number
+    pub blocks: BTreeMap<u64, Value>,
+    /// The beacon chain genesis time
+    pub genesis_time: u64,
+    /// The beacon chain slot duration
+    pub seconds_per_slot: u64,
+    /// The `blob_sidecars` responses by beacon slot
+    pub blob_sidecars: BTreeMap<u64, Value>,
+}
+
+impl MockRecording {
+    /// Records the responses of live nodes for the given l2 blocks and beacon slots
+    pub async fn record(
+        op_node_provider: &OpNodeProvider,
+        op_geth_provider: &ReqwestProvider,
+        beacon_provider: &BlobProvider,
+        l2_blocks: RangeInclusive<u64>,
+        slots: &[u64],
+    ) -> anyhow::Result<Self> {
+        let mut recording = Self {
+            sync_status: Some(op_node_provider.sync_status().await?),
+            rollup_config: Some(op_node_provider.rollup_config().await?),
+            genesis_time: beacon_provider.genesis_time,
+            seconds_per_slot: beacon_provider.seconds_per_slot,
+            ..Default::default()
+        };
+        for block_number in l2_blocks {
+            let output = op_node_provider
+                .output_response_at_block(block_number)
+                .await?;
+            recording
+                .outputs
+                .insert(block_number, serde_json::to_value(output)?);
+            let block: Value = op_geth_provider
+                .client()
+                .request(
+                    "eth_getBlockByNumber",
+                    (format!("0x{block_number:x}"), false),
+                )
+                .await
+                .context(format!("eth_getBlockByNumber {block_number}"))?;
+            recording.blocks.insert(block_number, block);
+        }
+        for slot in slots {
+            let sidecars = beacon_provider
+                .get::<Value>(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
+                .await
+                .context(format!("blob_sidecars {slot}"))?;
+            recording.blob_sidecars.insert(*slot, sidecars);
+        }
+        info!(
+            "Recorded {} l2 blocks and {} beacon slots.",
+            recording.outputs.len(),
+            recording.blob_sidecars.len()
+        );
+        Ok(recording)
+    }
+
+    fn rpc_result(&self, method: &str, params: &Value) -> Result<Value, String> {
+        match method {
+            "optimism_syncStatus" => self
+                .sync_status
+                .clone()
+                .ok_or_else(|| "sync status not recorded".to_string()),
+            "optimism_rollupConfig" => self
+                .rollup_config
+                .clone()
+                .ok_or_else(|| "rollup config not recorded".to_string()),
+            "optimism_outputAtBlock" => {
+                let block_number = self.block_number_param(params)?;
+                self.outputs
+                    .get(&block_number)
+                    .cloned()
+                    .ok_or_else(|| format!("output at block {block_number} not recorded"))
+            }
+            "eth_getBlockByNumber" => {
+                let block_number = self.block_number_param(params)?;
+                // nodes respond with null for unknown blocks
+                Ok(self
+                    .blocks
+                    .get(&block_number)
+                    .cloned()
+                    .unwrap_or(Value::Null))
+            }
+            "eth_blockNumber" => {
+                let latest = self.blocks.keys().next_back().copied().unwrap_or_default();
+                Ok(json!(format!("0x{latest:x}")))
+            }
+            _ => Err(format!("method {method} not supported")),
+        }
+    }
+
+    fn block_number_param(&self, params: &Value) -> Result<u64, String> {
+        match params.get(0).and_then(Value::as_str) {
+            Some("latest" | "safe" | "finalized") => self
+                .blocks
+                .keys()
+                .chain(self.outputs.keys())
+                .max()
+                .copied()
+                .ok_or_else(|| "no blocks recorded".to_string()),
+            Some(hex) => u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+                .map_err(|err| format!("invalid block number {hex}: {err}")),
+            None => Err("missing block number".to_string()),
+        }
+    }
+}
+
+/// Mock op-node and beacon servers running in the background until dropped
+#[derive(Debug)]
+pub struct MockServers {
+    /// The url serving the op-node and op-geth json-rpc methods
+    pub op_node_url: String,
+    /// The url serving the beacon node api
+    pub beacon_url: String,
+    op_node_server: JoinHandle<anyhow::Result<()>>,
+    beacon_server: JoinHandle<anyhow::Result<()>>,
+}
+
+impl MockServers {
+    /// Serves the recording on random local ports
+    pub async fn spawn(recording: MockRecording) -> anyhow::Result<Self> {
+        Self::bind(
+            recording,
+            ([127, 0, 0, 1], 0).into(),
+            ([127, 0, 0, 1], 0).into(),
+        )
+        .await
+    }
+
+    /// Serves the recording on the given addresses
+    pub async fn bind(
+        recording: MockRecording,
+        op_node_addr: SocketAddr,
+        beacon_addr: SocketAddr,
+    ) -> anyhow::Result<Self> {
+        let recording = Arc::new(recording);
+        let op_node_listener = TcpListener::bind(op_node_addr)
+            .await
+            .context("TcpListener::bind")?;
+        let beacon_listener = TcpListener::bind(beacon_addr)
+            .await
+            .context("TcpListener::bind")?;
+        let op_node_url = format!("http://{}", op_node_listener.local_addr()?);
+        let beacon_url = format!("http://{}", beacon_listener.local_addr()?);
+
+        let op_node_app = Router::new()
+            .route("/", post(json_rpc))
+            .with_state(recording.clone());
+        let beacon_app = Router::new()
+            .route("/eth/v1/beacon/genesis", get(genesis))
+            .route("/eth/v1/config/spec", get(spec))
+            .route("/eth/v1/beacon/blob_sidecars/:slot", get(blob_sidecars))
+            .with_state(recording);
+        let op_node_server = tokio::spawn(async move {
+            axum::serve(op_node_listener, op_node_app)
+                .await
+                .context("axum::serve")
+        });
+        let beacon_server = tokio::spawn(async move {
+            axum::serve(beacon_listener, beacon_app)
+                .await
+                .context("axum::serve")
+        });
+        info!("Serving mock op-node at {op_node_url} and mock beacon node at {beacon_url}.");
+
+        Ok(Self {
+            op_node_url,
+            beacon_url,
+            op_node_server,
+            beacon_server,
+        })
+    }
+
+    /// Waits until either server stops
+    pub async fn join(mut self) -> anyhow::Result<()> {
+        tokio::select! {
+            result = &mut self.op_node_server => result?,
+            result = &mut self.beacon_server => result?,
+        }
+    }
+}
+
+impl Drop for MockServers {
+    fn drop(&mut self) {
+        self.op_node_server.abort();
+        self.beacon_server.abort();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+async fn json_rpc(
+    State(recording): State<Arc<MockRecording>>,
+    Json(request): Json<JsonRpcRequest>,
+) -> Json<Value> {
+    debug!("{} {}", request.method, request.params);
+    Json(
+        match recording.rpc_result(&request.method, &request.params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": request.id, "result": result}),
+            Err(message) => json!({
+                "jsonrpc": "2.0",
+                "id": request.id,
+                "error": {"code": -32000, "message": message}
+            }),
+        },
+    )
+}
+
+async fn genesis(State(recording): State<Arc<MockRecording>>) -> Json<Value> {
+    Json(json!({"data": {"genesis_time": recording.genesis_time.to_string()}}))
+}
+
+async fn spec(State(recording): State<Arc<MockRecording>>) -> Json<Value> {
+    Json(json!({"data": {"SECONDS_PER_SLOT": recording.seconds_per_slot.to_string()}}))
+}
+
+async fn blob_sidecars(
+    State(recording): State<Arc<MockRecording>>,
+    Path(slot): Path<u64>,
+) -> Result<Json<Value>, StatusCode> {
+    recording
+        .blob_sidecars
+        .get(&slot)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,32 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::{Bytes, B256};
+use anyhow::Context;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+/// Produces a seal for the given journal that the `RiscZeroMockVerifier` accepts
+pub fn mock_seal(image_id: B256, journal: impl Into<Vec<u8>>) -> anyhow::Result<Bytes> {
+    let receipt = mock_receipt(image_id, journal);
+    let seal = risc0_ethereum_contracts::encode_seal(&receipt).context("encode_seal (mock)")?;
+    Ok(seal.into())
+}
+
+/// Produces a fake receipt of the given image id committing to the given journal
+pub fn mock_receipt(image_id: B256, journal: impl Into<Vec<u8>>) -> Receipt {
+    let journal = journal.into();
+    let claim = ReceiptClaim::ok(Digest::from(image_id.0), journal.clone());
+    Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal)
+}
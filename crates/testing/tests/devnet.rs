@@ -0,0 +1,120 @@
+// Copyright 2024 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloy::primitives::B256;
+use alloy::providers::{Provider, ProviderBuilder};
+use kailua_cli::providers::beacon::BlobProvider;
+use kailua_cli::providers::optimism::OpNodeProvider;
+use kailua_common::journal::ProofJournal;
+use kailua_common::precondition::{precondition_hash, PRECONDITION_VERSION};
+use kailua_contracts::extra_data::GameExtraData;
+use kailua_testing::fixture::{generate, FixtureSpec};
+use kailua_testing::journal::SealedJournalInput;
+use kailua_testing::malicious::{simulate, Tampering};
+use kailua_testing::mock::{MockRecording, MockServers};
+use kailua_testing::{Devnet, DevnetConfig};
+use serde_json::{json, Value};
+
+#[tokio::test]
+#[ignore = "requires anvil"]
+async fn unchallenged_proposal_resolves() -> anyhow::Result<()> {
+    let devnet = Devnet::spawn(DevnetConfig::default()).await?;
+    let block_count = devnet.config.proposal_block_count;
+
+    let io_field_elements = vec![B256::repeat_byte(0x01); block_count as usize - 1];
+    let game = devnet
+        .propose(
+            1,
+            B256::repeat_byte(0x02),
+            GameExtraData::new(block_count, 0),
+            &io_field_elements,
+        )
+        .await?;
+    assert_eq!(devnet.game_at_index(1).await?, game);
+    assert_eq!(devnet.status(game).await?, 0);
+
+    devnet
+        .advance_time(devnet.config.challenge_timeout + 1)
+        .await?;
+    devnet.resolve(2, game).await?;
+    assert_eq!(devnet.status(game).await?, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires anvil"]
+async fn tampered_proofs_are_rejected() -> anyhow::Result<()> {
+    let spec: FixtureSpec = serde_json::from_str(include_str!("../fixtures/divergences.json"))?;
+    let devnet = Devnet::spawn(spec.devnet_config()).await?;
+    let fixture = generate(&devnet, &spec).await?;
+
+    let reports = simulate(&devnet, &fixture, &Tampering::ALL).await?;
+    assert!(reports.iter().all(|report| report.rejected_onchain));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_servers_serve_recording() -> anyhow::Result<()> {
+    let recording = MockRecording {
+        sync_status: Some(json!({"safe_l2": {"number": 5}})),
+        blocks: [(5, json!({"number": "0x5"}))].into(),
+        genesis_time: 1000,
+        seconds_per_slot: 12,
+        ..Default::default()
+    };
+    let servers = MockServers::spawn(recording.clone()).await?;
+
+    let op_node_provider =
+        OpNodeProvider(ProviderBuilder::new().on_http(servers.op_node_url.as_str().try_into()?));
+    assert_eq!(
+        op_node_provider.sync_status().await?,
+        recording.sync_status.unwrap()
+    );
+    assert!(op_node_provider.output_at_block(5).await.is_err());
+    let block: Value = op_node_provider
+        .0
+        .client()
+        .request("eth_getBlockByNumber", ("0x5", false))
+        .await?;
+    assert_eq!(block, recording.blocks[&5]);
+
+    let beacon_provider = BlobProvider::new(&servers.beacon_url, None).await?;
+    assert_eq!(beacon_provider.genesis_time, 1000);
+    assert_eq!(beacon_provider.seconds_per_slot, 12);
+    assert!(beacon_provider.get_blob(1012, B256::ZERO).await.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn encoded_journal_matches_contract_layout() -> anyhow::Result<()> {
+    let input: SealedJournalInput = serde_json::from_str(include_str!("../fixtures/journal.json"))?;
+    let encoded = input.encode()?;
+
+    assert_eq!(encoded.packed.len(), ProofJournal::PACKED_LEN);
+    // the blob hashes committed to by the fault precondition carry the kzg version byte
+    let (mut contender, mut proposal) = (B256::with_last_byte(1), B256::with_last_byte(2));
+    contender.0[0] = 1;
+    proposal.0[0] = 1;
+    assert_eq!(
+        &encoded.packed[..32],
+        precondition_hash(&contender, &proposal, PRECONDITION_VERSION).as_slice()
+    );
+    assert_eq!(&encoded.packed[128..136], 61u64.to_be_bytes().as_slice());
+    assert!(!encoded.seal.is_empty());
+
+    Ok(())
+}